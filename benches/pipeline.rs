@@ -0,0 +1,165 @@
+//! Baselines for filename parsing, directory scanning and duplicate hashing.
+//!
+//! The crate is a binary, so the modules the pipeline needs are compiled in
+//! directly. File counts can be changed with `EBOOK_BENCH_SCAN_FILES` and
+//! `EBOOK_BENCH_HASH_FILES`.
+// Only part of each module is used here, and their test modules are compiled
+// without a test harness under `cargo clippy --all-targets`
+#![allow(dead_code, unused_imports)]
+
+#[path = "../src/authors.rs"]
+mod authors;
+#[path = "../src/cli.rs"]
+mod cli;
+#[path = "../src/collections.rs"]
+mod collections;
+#[path = "../src/dupe_ignore.rs"]
+mod dupe_ignore;
+#[path = "../src/duplicates.rs"]
+mod duplicates;
+#[path = "../src/error.rs"]
+mod error;
+#[path = "../src/guard.rs"]
+mod guard;
+#[path = "../src/io_timeout.rs"]
+mod io_timeout;
+#[path = "../src/known_authors.rs"]
+mod known_authors;
+#[path = "../src/normalizer.rs"]
+mod normalizer;
+#[path = "../src/overrides.rs"]
+mod overrides;
+#[path = "../src/placeholders.rs"]
+mod placeholders;
+#[path = "../src/priority.rs"]
+mod priority;
+#[path = "../src/redownload.rs"]
+mod redownload;
+#[path = "../src/scan_checkpoint.rs"]
+mod scan_checkpoint;
+#[path = "../src/scanner.rs"]
+mod scanner;
+#[path = "../src/search_key.rs"]
+mod search_key;
+#[path = "../src/subjects.rs"]
+mod subjects;
+#[path = "../src/sync_ignore.rs"]
+mod sync_ignore;
+#[path = "../src/template.rs"]
+mod template;
+#[path = "../src/traversal.rs"]
+mod traversal;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use std::collections::HashSet;
+use std::fs;
+use std::hint::black_box;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn file_count(var: &str, default: usize) -> usize {
+    std::env::var(var).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+fn corpus_names() -> Vec<String> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("corpus");
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir).unwrap().flatten() {
+        if entry.path().extension().is_some_and(|ext| ext == "txt") {
+            let content = fs::read_to_string(entry.path()).unwrap();
+            names.extend(
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(|line| line.split(" => ").next().unwrap().to_string()),
+            );
+        }
+    }
+    names
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let names = corpus_names();
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(names.len() as u64));
+    group.bench_function("corpus", |b| {
+        b.iter(|| {
+            for name in &names {
+                black_box(normalizer::parse_filename(black_box(name), ".pdf").unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+// 100 folders of equal size; names look like real downloads
+fn build_tree(root: &Path, files: usize) {
+    let per_dir = files.div_ceil(100);
+    for i in 0..files {
+        let dir = root.join(format!("Shelf {:03}", i / per_dir));
+        if i % per_dir == 0 {
+            fs::create_dir_all(&dir).unwrap();
+        }
+        let name = format!("Author {} - Title {} (Springer, {}) -- libgen.pdf", i % 997, i, 1950 + i % 70);
+        fs::write(dir.join(name), b"").unwrap();
+    }
+}
+
+fn bench_scan(c: &mut Criterion) {
+    let files = file_count("EBOOK_BENCH_SCAN_FILES", 100_000);
+    let tmp_dir = TempDir::new().unwrap();
+    build_tree(tmp_dir.path(), files);
+
+    let mut group = c.benchmark_group("scan");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(files as u64));
+    group.bench_function(format!("{}_files", files), |b| {
+        b.iter(|| {
+            let mut scanner = scanner::Scanner::new(tmp_dir.path(), usize::MAX).unwrap();
+            black_box(scanner.scan().unwrap())
+        })
+    });
+    // What the run does: each file is handed on as it is found
+    group.bench_function(format!("{}_files_streamed", files), |b| {
+        b.iter(|| {
+            let mut scanner = scanner::Scanner::new(tmp_dir.path(), usize::MAX).unwrap();
+            black_box(scanner.scan_each(|file_info| {
+                black_box(file_info);
+                Ok(())
+            }))
+            .unwrap()
+        })
+    });
+    group.finish();
+}
+
+// Every fourth file is a copy of the previous one
+fn bench_hash(c: &mut Criterion) {
+    let files = file_count("EBOOK_BENCH_HASH_FILES", 200);
+    let size = 256 * 1024;
+    let tmp_dir = TempDir::new().unwrap();
+    for i in 0..files {
+        let seed = if i % 4 == 3 { i - 1 } else { i };
+        let content: Vec<u8> = (0..size).map(|j| ((j * 31 + seed * 7) % 251) as u8).collect();
+        fs::write(tmp_dir.path().join(format!("Book {}.pdf", i)), content).unwrap();
+    }
+    let scanned = scanner::Scanner::new(tmp_dir.path(), usize::MAX).unwrap().scan().unwrap();
+    let ignore = dupe_ignore::DupeIgnoreList::default();
+
+    let mut group = c.benchmark_group("hash");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes((files * size) as u64));
+    group.bench_function(format!("{}_files_256k", files), |b| {
+        b.iter_batched(
+            || scanned.clone(),
+            |files| black_box(duplicates::detect_duplicates(files, false, None, &ignore, &HashSet::new()).unwrap()),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse, bench_scan, bench_hash);
+criterion_main!(benches);
@@ -0,0 +1,244 @@
+# Console and TUI messages. Keep zh.ftl in step: every message needs both.
+# todo.md and the JSON output are not translated (see docs/spec.md).
+
+## Startup
+cloud-detected =
+    ⚠️  Detected { $provider } storage. Using metadata-only mode to avoid downloading files.
+    Duplicate detection based on filename similarity (≥85%) + exact size match.
+    This is less accurate than content-based hashing. Review carefully!
+cloud-enabled =
+    ⚠️  Cloud mode enabled: Using metadata-only duplicate detection.
+       Duplicate detection based on filename similarity (≥85%) + exact size match.
+profile-selected = Using profile { $name }
+scan-resumed = Resumed the interrupted scan: { $files } files taken from the checkpoint
+warning-label = ⚠️  Warning:
+fetch-arxiv-not-implemented = --fetch-arxiv is not implemented yet. Files will be processed offline only.
+libgen-found = Found { $count } files on Library Genesis by MD5
+libgen-offline = Library Genesis lookup failed ({ $error }); uncached files keep their parsed names
+recovered-downloads = Recovered { $count } PDFs from download folders
+scanning = Scanning... { $walked } entries ({ $folder })
+skipped-native-docs = Skipped { $count } native document shortcuts (Google Docs, Dropbox Paper)
+file-written = { $path } written
+skipped-io-timeout = Skipped { $count } files that exceeded the IO timeout
+
+## Dry run
+dry-run-banner = ═══ DRY RUN MODE ═══
+label-rename-dir = RENAME DIR:
+label-rename = RENAME:
+label-keep = KEEP:
+label-delete = DELETE:
+label-remove-dir = REMOVE DIR:
+label-duplicate = DUPLICATE:
+files-to-rename = { $count } files to rename
+duplicate-group = 🔍 DUPLICATE GROUP:
+small-files-to-delete = 🗑️  SMALL/CORRUPTED/FAILED FILES TO DELETE:
+empty-dirs-to-prune = 🗂️  EMPTY DIRECTORIES TO PRUNE:
+empty-dirs-pruned = 🗂️  EMPTY DIRECTORIES PRUNED:
+label-quarantine = QUARANTINE:
+label-sample = SAMPLE:
+sample-files = { $count } sample files
+label-merge = MERGE:
+label-parts = PARTS:
+part-sets = { $count } books split into parts
+annotated-duplicates = ✍️  ANNOTATED DUPLICATES (kept, use --delete-annotated-dupes to remove pristine copies):
+label-annotated = ANNOTATED:
+probable-duplicates = 🧬 PROBABLE DUPLICATES (same first pages, different bytes; kept):
+label-same-text = SAME TEXT:
+label-same-pages = SAME PAGES:
+label-copy = COPY:
+label-superseded = SUPERSEDED:
+label-older = OLDER:
+arxiv-versions = 📄 ARXIV VERSIONS:
+label-newest = NEWEST:
+chapter-files = 📚 CHAPTER FILES:
+label-folder = FOLDER:
+chapter-pages = , { $count } pages
+label-part = PART:
+todo-list = 📋 TODO LIST:
+todo-not-written = todo.md not written (read-only mode)
+todo-written-dry-run = todo.md written (dry-run mode)
+dir-plan-counts = ({ $renames } renames, { $duplicates } duplicates, { $deletions } deletions)
+
+## Execution
+not-renamed-permission = Insufficient permission, not renamed: { $name }
+not-renamed = Not renamed, { $reason }: { $name }
+not-deleted = Not deleted, { $reason }: { $path }
+original-not-recorded = Original name not recorded ({ $reason }): { $name }
+delete-effect-permanent = DELETE removes files from disk for good.
+delete-effect-dropbox = On Dropbox, DELETE moves files to its deleted files, restorable on dropbox.com for 30 days or more depending on the plan; only the website deletes them permanently.
+delete-effect-google-drive = On Google Drive, DELETE moves files to the trash, which counts against your storage until it is emptied (after 30 days).
+delete-effect-onedrive = On OneDrive, DELETE moves files to the recycle bin, which counts against your storage until it is emptied (after 30 days for personal accounts).
+delete-effect-archive = DELETE moves files to { $dir } under today's date; nothing is removed.
+delete-effect-archive-synced = DELETE moves files to { $dir } under today's date; that folder is still synced and uses cloud storage until you delete it.
+delete-effect-archive-unsynced = DELETE moves files out of { $provider } to { $dir } under today's date; { $provider } treats them as deleted files.
+deleting-small-files = { $count } small/corrupted/failed files...
+removing-empty-dirs = { $count } empty directories...
+label-deleted = Deleted:
+label-removed = Removed:
+pruned-dirs-restored = Recreated { $count } directories removed by --prune-empty-dirs
+restore-nothing = No recorded original names to restore in { $path }
+restore-planned = { $count } files would get their original names back
+restore-done = { $restored } files restored, { $failed } left alone
+operation-completed = Operation completed successfully!
+
+## Summary
+summary-banner = ═══ SUMMARY ═══
+summary-banner-dry-run = ═══ SUMMARY (dry run) ═══
+summary-processed = Processed
+summary-renamed = Renamed
+summary-duplicates = Duplicates
+summary-small-failed = Small/Failed
+summary-todo-items = Todo items
+summary-reclaimed = Reclaimed
+summary-pruned-dirs = Pruned dirs
+collections-banner = ═══ COLLECTIONS ═══
+summary-collection = Collection
+collection-none = (none)
+collection-stats = { $name }: { $files } files, { $renamed } renamed, { $duplicates } duplicates, { $todo } todo items
+notify-finished = ebook-renamer finished
+notify-finished-dry-run = ebook-renamer finished (dry run)
+notify-failed = ebook-renamer failed
+notify-summary = Renamed { $renamed } · Duplicates { $duplicates } ({ $reclaimed }) · Todo { $todo } · Problems { $problems }
+plan-check-issues = { $count } plan check issues
+files-skipped = { $count } files skipped
+label-skipped = SKIPPED:
+
+## Skip reasons
+skip-unsupported-extension = not a pdf, epub or txt file
+skip-native-document = cloud document shortcut
+skip-failed-download = unfinished download, see todo.md
+skip-too-small = below the minimum size, see todo.md
+skip-chapter-part = chapter file, keeps its name
+skip-read-only-folder = folder is read-only
+skip-io-timeout = IO timeout
+skip-normalize-disabled = --no-normalize
+skip-already-normalized = already normalized
+skip-sync-temporary = sync client is still writing it
+skip-sync-conflict = sync conflict, see todo.md
+skip-in-use = still being written, retried next run
+skip-name-conflict = same name as another file, different content, see todo.md
+skip-rename-conflict = rename would overwrite a file
+skip-html-stub = saved web page instead of the book, see todo.md
+
+## Deletion preview
+deletions-previewed = { $count } deletions previewed, nothing deleted:
+label-pending-delete = PENDING DELETE:
+review-then-confirm = Review them, then run again with { $flag } to delete them.
+no-pending-deletions = No pending deletions (run with --dry-run-deletes first)
+deleted-previewed = Deleted { $deleted } of { $total } previewed items from { $created } ({ $reclaimed } reclaimed)
+
+## Cleanup
+nothing-to-clean = Nothing to clean up
+cleanup-banner = ═══ CLEANUP PLAN ═══
+cleanup-summary = Summary:
+cleanup-total = total
+cleanup-total-files = { $count } files, { $bytes }
+cleanup-confirm = Delete { $count } files?
+cleanup-cancelled = Cleanup cancelled, nothing was deleted
+cleanup-deleted = Deleted { $count } files, freed { $bytes }
+label-failed = FAILED:
+reason-failed-download = failed download
+reason-too-small = too small
+reason-corrupted-pdf = corrupted PDF
+reason-html-stub = saved web page
+
+## Changes and stats
+changes-none = No changes since { $since }
+changes-banner = ═══ CHANGES SINCE { $since } ═══
+changes-summary = { $added } added, { $removed } removed, { $renamed } renamed, { $modified } modified
+stats-no-history = No run history found in { $path }
+stats-banner = ═══ RUN HISTORY ═══
+stats-date = Date
+stats-mode = Mode
+stats-dry-run = dry-run
+stats-live = live
+stats-total = { $count } runs recorded, { $bytes } reclaimed in total
+stats-audit-issues = { $count } audit issues on { $date }
+stats-improving = Improving: { $delta } fewer duplicates/failed files per run
+stats-worsening = Worsening: { $delta } more duplicates/failed files per run
+stats-stable = Stable: no change in duplicates/failed files per run
+
+## Space map
+space-largest-files = Largest files
+space-largest-duplicate-groups = Largest duplicate groups
+space-extra-copies = ({ $count } extra copies)
+space-largest-folders = Largest folders
+space-folder-files = ({ $count } files)
+tui-large-file = Large file ({ $bytes }): { $path }
+tui-large-duplicates = Duplicates ({ $bytes } reclaimable): { $path }
+tui-large-folder = Large folder ({ $bytes }, { $count } files): { $path }
+
+## Learn
+learn-banner = ═══ LEARNED NAMING CONVENTION ═══
+learn-template = Template:
+learn-subtitle-separator = Subtitle separator:
+learn-matches = Matches:
+learn-matched-files = { $matched } of { $total } files
+learn-alternative = Alternative:
+learn-alternative-files = ({ $count } files)
+learn-authors = Authors:
+learn-authors-added = Added { $count } authors to { $path }
+learn-saved = Saved to { $path }
+
+## Commands
+corpus-added = Added { $added } of { $total } names to { $path }
+dupe-ignored = { $a } and { $b } will not be treated as duplicates (saved to { $path })
+override-saved = Override for { $file } saved to { $path }
+override-removed = Override for { $file } removed
+override-missing = No override for { $file }
+overrides-none = No overrides in { $path }
+check-error = ERROR:
+check-warning = WARNING:
+check-info = INFO:
+check-summary = { $files } files checked: { $errors } errors, { $warnings } warnings, { $infos } info
+server-listening = Listening on { $address }
+
+## TUI
+tui-title = Ebook Renamer
+tui-starting = Starting...
+tui-initializing = Initializing
+tui-cloud-mode = ⚠️  Detected { $provider } - using metadata-only mode
+tui-scanning = Scanning... { $walked } entries ({ $folder })
+tui-found-files = Found { $count } files
+tui-normalizing = Normalizing...
+tui-normalized = Normalized { $count } files
+tui-checking-integrity = Checking Integrity...
+tui-integrity-done = Integrity check complete
+tui-detecting-duplicates = Detecting Duplicates...
+tui-duplicate-groups = Detected { $count } duplicate groups
+tui-executing = Executing...
+tui-reviewing = Reviewing { $count } low-confidence parses...
+review-applied = Applied { $count } corrections
+review-saved = Saved the corrections as overrides in { $path }
+tui-dirs-removed = Removed { $count } empty directories
+tui-deletions-previewed = { $count } deletions previewed; run with --confirm-deletes to delete them
+tui-in-use = Still being written, deferred: { $path }
+tui-archive = Archive { $path }: { $summary }
+tui-archive-contents = { $count } ebooks, { $duplicates } already in the library
+tui-sample = Sample ({ $reason }): { $path }
+tui-sync-conflict = Sync conflict with { $base }: { $path }
+tui-name-variant = Same name as { $base }, different content: { $path }
+tui-html-stub = Saved { $kind }: { $path }
+tui-chapter-files = Chapter files for { $book }: { $count } parts
+tui-split-parts = Split into { $count } parts: { $path }
+tui-arxiv-versions = arXiv { $id }: { $count } older versions of { $path }
+tui-annotated-group = Kept duplicate group with annotated copies: { $path }
+tui-probable-duplicates = Probable duplicates (same { $basis }): { $files }
+tui-io-timeout = IO timeout, skipped: { $path }
+tui-plan-check = Plan check ({ $code }): { $message }
+tui-skipped = Skipped ({ $reason }): { $path }
+tui-error = Error
+tui-done = Done!
+tui-completed = Completed
+tui-status = Status
+tui-progress = Progress
+tui-logs = Logs
+tui-review = Review { $count } parses (↑/↓ file, Tab field, Enter apply, Esc skip, Ctrl-S save overrides: { $save })
+tui-plan-by-folder = Plan by folder (↑/↓ select, Enter expand)
+tui-renames = Renames ({ $count })
+tui-field-author = Author
+tui-field-title = Title
+tui-field-year = Year
+tui-on = on
+tui-off = off
+tui-delete = delete { $name }
@@ -0,0 +1,246 @@
+# 控制台和 TUI 文本，与 en.ftl 一一对应。
+
+## Startup
+cloud-detected =
+    ⚠️  检测到 { $provider } 云存储。使用仅元数据模式，避免下载文件。
+    重复检测基于文件名相似度（≥85%）+ 大小完全一致。
+    准确度低于内容哈希，请仔细核对！
+cloud-enabled =
+    ⚠️  已启用云模式：仅用元数据检测重复。
+       重复检测基于文件名相似度（≥85%）+ 大小完全一致。
+profile-selected = 使用配置 { $name }
+scan-resumed = 已从检查点继续中断的扫描：沿用 { $files } 个文件
+warning-label = ⚠️  警告：
+fetch-arxiv-not-implemented = --fetch-arxiv 尚未实现，文件仅离线处理。
+libgen-found = 按 MD5 在 Library Genesis 找到 { $count } 个文件
+libgen-offline = Library Genesis 查询失败（{ $error }）；未缓存的文件按文件名解析
+recovered-downloads = 从下载文件夹恢复了 { $count } 个 PDF
+scanning = 扫描中… { $walked } 项（{ $folder }）
+skipped-native-docs = 跳过 { $count } 个原生文档快捷方式（Google 文档、Dropbox Paper）
+file-written = 已写入 { $path }
+skipped-io-timeout = 跳过 { $count } 个读取超时的文件
+
+## Dry run
+dry-run-banner = ═══ 预览模式 ═══
+label-rename-dir = 重命名目录：
+label-rename = 重命名：
+label-keep = 保留：
+label-delete = 删除：
+label-remove-dir = 删除目录：
+label-duplicate = 重复：
+files-to-rename = { $count } 个文件待重命名
+duplicate-group = 🔍 重复文件组：
+small-files-to-delete = 🗑️  待删除的过小/损坏/下载失败文件：
+empty-dirs-to-prune = 🗂️  待清理的空目录：
+empty-dirs-pruned = 🗂️  已清理的空目录：
+label-quarantine = 隔离：
+label-sample = 试读本：
+sample-files = { $count } 个试读本/预览文件
+label-merge = 合并：
+label-parts = 分卷：
+part-sets = { $count } 本书被拆分成多个部分
+annotated-duplicates = ✍️  带批注的重复文件（已保留，加 --delete-annotated-dupes 删除未批注的副本）：
+label-annotated = 有批注：
+probable-duplicates = 🧬 疑似重复文件（前几页相同但字节不同，已保留）：
+label-same-text = 文字相同：
+label-same-pages = 页面相同：
+label-copy = 副本：
+label-superseded = 已被取代：
+label-older = 旧版本：
+arxiv-versions = 📄 ARXIV 版本：
+label-newest = 最新：
+chapter-files = 📚 分章文件：
+label-folder = 文件夹：
+chapter-pages = ，{ $count } 页
+label-part = 部分：
+todo-list = 📋 待办清单：
+todo-not-written = 未写入 todo.md（只读模式）
+todo-written-dry-run = 已写入 todo.md（预览模式）
+dir-plan-counts = （{ $renames } 个重命名，{ $duplicates } 个重复，{ $deletions } 个删除）
+
+## Execution
+not-renamed-permission = 权限不足，未重命名：{ $name }
+not-renamed = 未重命名，{ $reason }：{ $name }
+not-deleted = 未删除，{ $reason }：{ $path }
+original-not-recorded = 未记录原文件名（{ $reason }）：{ $name }
+delete-effect-permanent = 删除（DELETE）会从磁盘上永久移除文件。
+delete-effect-dropbox = 在 Dropbox 中，删除会把文件移到“已删除文件”，视套餐可在 dropbox.com 上恢复 30 天或更久；只有在网站上才能永久删除。
+delete-effect-google-drive = 在 Google Drive 中，删除会把文件移到回收站，清空前（30 天后自动清空）仍占用存储空间。
+delete-effect-onedrive = 在 OneDrive 中，删除会把文件移到回收站，清空前（个人帐户 30 天后自动清空）仍占用存储空间。
+delete-effect-archive = 删除会把文件移到 { $dir } 下以今天日期命名的文件夹，不会移除任何文件。
+delete-effect-archive-synced = 删除会把文件移到 { $dir } 下以今天日期命名的文件夹；该文件夹仍会同步，删除它之前仍占用云存储空间。
+delete-effect-archive-unsynced = 删除会把文件从 { $provider } 移到 { $dir } 下以今天日期命名的文件夹；{ $provider } 会将其视为已删除的文件。
+deleting-small-files = { $count } 个过小/损坏/下载失败文件…
+removing-empty-dirs = { $count } 个空目录…
+label-deleted = 已删除：
+label-removed = 已删除：
+pruned-dirs-restored = 已重建 { $count } 个被 --prune-empty-dirs 删除的目录
+restore-nothing = { $path } 中没有可恢复的原文件名记录
+restore-planned = { $count } 个文件将恢复原文件名
+restore-done = 已恢复 { $restored } 个文件，{ $failed } 个未改动
+operation-completed = 操作成功完成！
+
+## Summary
+summary-banner = ═══ 摘要 ═══
+summary-banner-dry-run = ═══ 摘要（预览） ═══
+summary-processed = 已处理
+summary-renamed = 已重命名
+summary-duplicates = 重复
+summary-small-failed = 过小/失败
+summary-todo-items = 待办事项
+summary-reclaimed = 释放空间
+summary-pruned-dirs = 清理目录
+collections-banner = ═══ 合集 ═══
+summary-collection = 合集
+collection-none = （未归入）
+collection-stats = { $name }：{ $files } 个文件，重命名 { $renamed }，重复 { $duplicates }，待办 { $todo }
+notify-finished = ebook-renamer 已完成
+notify-finished-dry-run = ebook-renamer 已完成（预览）
+notify-failed = ebook-renamer 运行失败
+notify-summary = 已重命名 { $renamed } · 重复 { $duplicates }（{ $reclaimed }）· 待办 { $todo } · 问题 { $problems }
+plan-check-issues = { $count } 个计划检查问题
+files-skipped = 跳过 { $count } 个文件
+label-skipped = 跳过：
+
+## Skip reasons
+skip-unsupported-extension = 不是 pdf、epub 或 txt 文件
+skip-native-document = 云文档快捷方式
+skip-failed-download = 未完成的下载，见 todo.md
+skip-too-small = 小于最小文件大小，见 todo.md
+skip-chapter-part = 分章文件，保留原名
+skip-read-only-folder = 文件夹只读
+skip-io-timeout = 读取超时
+skip-normalize-disabled = --no-normalize
+skip-already-normalized = 已是规范名称
+skip-sync-temporary = 同步客户端仍在写入
+skip-sync-conflict = 同步冲突，见 todo.md
+skip-in-use = 仍在写入，下次运行重试
+skip-name-conflict = 与另一文件同名但内容不同，见 todo.md
+skip-rename-conflict = 重命名会覆盖已有文件
+skip-html-stub = 保存的是网页而非电子书，见 todo.md
+
+## Deletion preview
+deletions-previewed = 预览了 { $count } 个删除，尚未删除任何文件：
+label-pending-delete = 待删除：
+review-then-confirm = 确认无误后，加 { $flag } 再次运行即可删除。
+no-pending-deletions = 没有待删除项（请先用 --dry-run-deletes 运行）
+deleted-previewed = 已删除 { $created } 预览的 { $total } 项中的 { $deleted } 项（释放 { $reclaimed }）
+
+## Cleanup
+nothing-to-clean = 没有需要清理的文件
+cleanup-banner = ═══ 清理计划 ═══
+cleanup-summary = 摘要：
+cleanup-total = 合计
+cleanup-total-files = { $count } 个文件，{ $bytes }
+cleanup-confirm = 删除 { $count } 个文件？
+cleanup-cancelled = 已取消清理，未删除任何文件
+cleanup-deleted = 已删除 { $count } 个文件，释放 { $bytes }
+label-failed = 失败：
+reason-failed-download = 下载失败
+reason-too-small = 文件过小
+reason-corrupted-pdf = PDF 损坏
+reason-html-stub = 保存的网页
+
+## Changes and stats
+changes-none = 自 { $since } 以来没有变化
+changes-banner = ═══ 自 { $since } 以来的变化 ═══
+changes-summary = 新增 { $added }，移除 { $removed }，重命名 { $renamed }，修改 { $modified }
+stats-no-history = { $path } 中没有运行记录
+stats-banner = ═══ 运行记录 ═══
+stats-date = 日期
+stats-mode = 模式
+stats-dry-run = 预演
+stats-live = 实际
+stats-total = 共记录 { $count } 次运行，累计回收 { $bytes }
+stats-audit-issues = { $date } 有 { $count } 个审计问题
+stats-improving = 好转：每次运行的重复/失败文件减少 { $delta } 个
+stats-worsening = 变差：每次运行的重复/失败文件增加 { $delta } 个
+stats-stable = 稳定：每次运行的重复/失败文件没有变化
+
+## Space map
+space-largest-files = 最大的文件
+space-largest-duplicate-groups = 最大的重复组
+space-extra-copies = （{ $count } 个多余副本）
+space-largest-folders = 最大的文件夹
+space-folder-files = （{ $count } 个文件）
+tui-large-file = 大文件（{ $bytes }）：{ $path }
+tui-large-duplicates = 重复文件（可回收 { $bytes }）：{ $path }
+tui-large-folder = 大文件夹（{ $bytes }，{ $count } 个文件）：{ $path }
+
+## Learn
+learn-banner = ═══ 学到的命名规则 ═══
+learn-template = 模板：
+learn-subtitle-separator = 副标题分隔符：
+learn-matches = 匹配：
+learn-matched-files = { $total } 个文件中的 { $matched } 个
+learn-alternative = 备选：
+learn-alternative-files = （{ $count } 个文件）
+learn-authors = 作者：
+learn-authors-added = 已向 { $path } 添加 { $count } 位作者
+learn-saved = 已保存到 { $path }
+
+## Commands
+corpus-added = 已将 { $total } 个名称中的 { $added } 个添加到 { $path }
+dupe-ignored = { $a } 和 { $b } 不再视为重复文件（已保存到 { $path }）
+override-saved = { $file } 的覆盖项已保存到 { $path }
+override-removed = 已删除 { $file } 的覆盖项
+override-missing = { $file } 没有覆盖项
+overrides-none = { $path } 中没有覆盖项
+check-error = 错误：
+check-warning = 警告：
+check-info = 信息：
+check-summary = 已检查 { $files } 个文件：{ $errors } 个错误，{ $warnings } 个警告，{ $infos } 条信息
+server-listening = 正在监听 { $address }
+
+## TUI
+tui-title = 电子书重命名
+tui-starting = 开始…
+tui-initializing = 初始化
+tui-cloud-mode = ⚠️  检测到 { $provider }，使用仅元数据模式
+tui-scanning = 扫描中… { $walked } 项（{ $folder }）
+tui-found-files = 找到 { $count } 个文件
+tui-normalizing = 规范化文件名…
+tui-normalized = 已规范化 { $count } 个文件
+tui-checking-integrity = 检查完整性…
+tui-integrity-done = 完整性检查完成
+tui-detecting-duplicates = 检测重复…
+tui-duplicate-groups = 检测到 { $count } 组重复
+tui-executing = 执行中…
+tui-reviewing = 复核 { $count } 个低置信度解析…
+review-applied = 已应用 { $count } 处更正
+review-saved = 已将更正保存为覆盖项：{ $path }
+tui-dirs-removed = 已删除 { $count } 个空目录
+tui-deletions-previewed = 预览了 { $count } 个删除；使用 --confirm-deletes 运行以删除它们
+tui-in-use = 仍在写入，推迟处理：{ $path }
+tui-archive = 压缩包 { $path }：{ $summary }
+tui-archive-contents = { $count } 本电子书，{ $duplicates } 本已在库中
+tui-sample = 试读本（{ $reason }）：{ $path }
+tui-sync-conflict = 与 { $base } 同步冲突：{ $path }
+tui-name-variant = 与 { $base } 同名但内容不同：{ $path }
+tui-html-stub = 保存的是{ $kind }：{ $path }
+tui-chapter-files = { $book } 的章节文件：{ $count } 个部分
+tui-split-parts = 分为 { $count } 个部分：{ $path }
+tui-arxiv-versions = arXiv { $id }：{ $path } 有 { $count } 个旧版本
+tui-annotated-group = 已保留含批注副本的重复组：{ $path }
+tui-probable-duplicates = 疑似重复（{ $basis ->
+        [text] 文本相同
+       *[content] 页面内容相同
+    }）：{ $files }
+tui-io-timeout = 读取超时，已跳过：{ $path }
+tui-plan-check = 计划检查（{ $code }）：{ $message }
+tui-skipped = 跳过（{ $reason }）：{ $path }
+tui-error = 错误
+tui-done = 完成！
+tui-completed = 已完成
+tui-status = 状态
+tui-progress = 进度
+tui-logs = 日志
+tui-review = 复核 { $count } 个解析（↑/↓ 文件，Tab 字段，Enter 应用，Esc 跳过，Ctrl-S 保存修正：{ $save }）
+tui-plan-by-folder = 按文件夹查看计划（↑/↓ 选择，Enter 展开）
+tui-renames = 重命名（{ $count }）
+tui-field-author = 作者
+tui-field-title = 书名
+tui-field-year = 年份
+tui-on = 开
+tui-off = 关
+tui-delete = 删除 { $name }
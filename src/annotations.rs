@@ -0,0 +1,134 @@
+use crate::duplicates::DuplicateGroups;
+use crate::placeholders;
+use log::debug;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+// Larger PDFs are only checked near the end, where incremental saves put new annotations
+const MAX_FULL_SCAN_BYTES: u64 = 64 * 1024 * 1024;
+const TAIL_SCAN_BYTES: u64 = 16 * 1024 * 1024;
+
+/// A duplicate group held back because some members carry annotations.
+#[derive(Debug, Clone)]
+pub struct AnnotatedGroup {
+    pub files: Vec<PathBuf>,
+    pub annotated: Vec<PathBuf>,
+}
+
+/// Whether a PDF contains user annotations (highlights, notes, ink, ...).
+/// Link annotations are ignored since almost every PDF has them.
+pub fn has_annotations(path: &Path) -> bool {
+    if !path.extension().is_some_and(|e| e.eq_ignore_ascii_case("pdf")) {
+        return false;
+    }
+    let Ok(bytes) = read_for_scan(path) else {
+        return false;
+    };
+    let re = regex::bytes::Regex::new(
+        r"/Subtype\s*/(?:Highlight|Underline|StrikeOut|Squiggly|Ink|FreeText|Text|Square|Circle|PolyLine|Polygon|Line|Stamp|Caret|FileAttachment)\b",
+    )
+    .unwrap();
+    re.is_match(&bytes)
+}
+
+fn read_for_scan(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut file = fs::File::open(path)?;
+    let size = file.metadata()?.len();
+    if size <= MAX_FULL_SCAN_BYTES {
+        let mut bytes = Vec::with_capacity(size as usize);
+        file.read_to_end(&mut bytes)?;
+        return Ok(bytes);
+    }
+    file.seek(SeekFrom::Start(size - TAIL_SCAN_BYTES))?;
+    let mut bytes = Vec::with_capacity(TAIL_SCAN_BYTES as usize);
+    file.take(TAIL_SCAN_BYTES).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Take groups with annotated members out of the automatic deletions.
+/// With `confirmed`, those groups stay deletable but keep every annotated
+/// copy (the largest, then newest, becomes the kept file) and only delete
+/// the pristine ones.
+pub fn split_annotated_groups(groups: DuplicateGroups, confirmed: bool) -> (DuplicateGroups, Vec<AnnotatedGroup>) {
+    let mut deletable = Vec::new();
+    let mut held = Vec::new();
+
+    for group in groups {
+        // Reading a cloud placeholder would download it
+        let mut annotated: Vec<PathBuf> =
+            group.iter().filter(|p| !placeholders::is_placeholder_path(p) && has_annotations(p)).cloned().collect();
+        if annotated.is_empty() {
+            deletable.push(group);
+            continue;
+        }
+        debug!("Duplicate group with {} annotated copies: {:?}", annotated.len(), annotated);
+
+        if !confirmed {
+            held.push(AnnotatedGroup { files: group, annotated });
+            continue;
+        }
+
+        annotated.sort_by_key(|p| {
+            let metadata = fs::metadata(p).ok();
+            std::cmp::Reverse((metadata.as_ref().map(|m| m.len()), metadata.and_then(|m| m.modified().ok())))
+        });
+        let pristine: Vec<PathBuf> = group.into_iter().filter(|p| !annotated.contains(p)).collect();
+        if !pristine.is_empty() {
+            let mut kept = vec![annotated[0].clone()];
+            kept.extend(pristine);
+            deletable.push(kept);
+        }
+    }
+
+    (deletable, held)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    const PRISTINE: &str = "%PDF-1.4\n1 0 obj << /Type /Annot /Subtype /Link >> endobj\n";
+    const ANNOTATED: &str = "%PDF-1.4\n1 0 obj << /Type /Annot /Subtype /Highlight >> endobj\n";
+
+    #[test]
+    fn test_has_annotations() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let pristine = tmp_dir.path().join("pristine.pdf");
+        let annotated = tmp_dir.path().join("annotated.pdf");
+        fs::write(&pristine, PRISTINE)?;
+        fs::write(&annotated, ANNOTATED)?;
+
+        assert!(!has_annotations(&pristine));
+        assert!(has_annotations(&annotated));
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_annotated_groups() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let path = |name: &str| tmp_dir.path().join(name);
+        fs::write(path("a.pdf"), PRISTINE)?;
+        fs::write(path("b.pdf"), PRISTINE)?;
+        fs::write(path("notes.pdf"), ANNOTATED)?;
+        fs::write(path("clean.pdf"), PRISTINE)?;
+
+        let groups = vec![
+            vec![path("a.pdf"), path("b.pdf")],
+            vec![path("clean.pdf"), path("notes.pdf")],
+        ];
+
+        let (deletable, held) = split_annotated_groups(groups.clone(), false);
+        assert_eq!(deletable, vec![vec![path("a.pdf"), path("b.pdf")]]);
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].annotated, vec![path("notes.pdf")]);
+
+        // Confirmed: the annotated copy is kept, the pristine one goes
+        let (deletable, held) = split_annotated_groups(groups, true);
+        assert!(held.is_empty());
+        assert_eq!(deletable[1], vec![path("notes.pdf"), path("clean.pdf")]);
+        Ok(())
+    }
+}
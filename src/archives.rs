@@ -0,0 +1,286 @@
+use crate::duplicates::compute_md5;
+use crate::error::Error;
+use crate::scanner::FileInfo;
+use anyhow::{anyhow, Result};
+use log::{debug, info};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const ARCHIVE_EXTENSIONS: &[&str] = &[".zip", ".rar"];
+// Text files in archives are almost always readmes and site ads
+const PAYLOAD_EXTENSIONS: &[&str] = &[".pdf", ".epub"];
+
+pub fn is_archive(extension: &str) -> bool {
+    ARCHIVE_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+}
+
+fn is_payload(entry: &str) -> bool {
+    let lower = entry.to_lowercase();
+    let name = lower.rsplit('/').next().unwrap_or(&lower);
+    // macOS resource forks and hidden files are junk
+    !lower.starts_with("__macosx/") && !name.starts_with('.') && PAYLOAD_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
+}
+
+/// An ebook inside an archive.
+#[derive(Debug, Clone)]
+pub struct Payload {
+    /// Path inside the archive
+    pub entry: String,
+    /// Next to the archive, under the entry's file name
+    pub target: PathBuf,
+    /// Library file with the same content
+    pub duplicate_of: Option<PathBuf>,
+}
+
+impl Payload {
+    pub fn file_name(&self) -> String {
+        self.target.file_name().unwrap_or_default().to_string_lossy().to_string()
+    }
+
+    /// `.pdf` or `.epub`, as written in the archive
+    pub fn extension(&self) -> String {
+        self.target.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveReport {
+    pub path: PathBuf,
+    pub payloads: Vec<Payload>,
+    /// Why the archive could not be read
+    pub error: Option<String>,
+}
+
+impl ArchiveReport {
+    /// Every ebook inside is already in the library.
+    pub fn is_redundant(&self) -> bool {
+        !self.payloads.is_empty() && self.payloads.iter().all(|p| p.duplicate_of.is_some())
+    }
+}
+
+/// List the ebooks in every archive among `files` and compare their content
+/// with the rest of the library. `hash` is off in cloud mode, where only the
+/// listing is done.
+pub fn inspect(files: &[FileInfo], hash: bool) -> Vec<ArchiveReport> {
+    let mut library_hashes: HashMap<PathBuf, String> = HashMap::new();
+    let mut reports = Vec::new();
+
+    for archive in files.iter().filter(|f| is_archive(&f.extension)) {
+        let dir = archive.original_path.parent().unwrap_or(Path::new("."));
+        let mut report = ArchiveReport { path: archive.original_path.clone(), payloads: Vec::new(), error: None };
+        let entries = match list_entries(&archive.original_path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                debug!("Cannot read archive {:?}: {}", archive.original_path, e);
+                report.error = Some(e.to_string());
+                reports.push(report);
+                continue;
+            }
+        };
+
+        for entry in entries.into_iter().filter(|e| is_payload(e)) {
+            let name = entry.rsplit('/').next().unwrap_or(&entry).to_string();
+            let duplicate_of = if hash {
+                find_duplicate(&archive.original_path, &entry, files, &mut library_hashes)
+            } else {
+                None
+            };
+            report.payloads.push(Payload { target: dir.join(name), entry, duplicate_of });
+        }
+        reports.push(report);
+    }
+    reports
+}
+
+fn find_duplicate(
+    archive: &Path,
+    entry: &str,
+    files: &[FileInfo],
+    library_hashes: &mut HashMap<PathBuf, String>,
+) -> Option<PathBuf> {
+    let (hash, size) = match hash_entry(archive, entry) {
+        Ok(result) => result,
+        Err(e) => {
+            debug!("Cannot hash {} in {:?}: {}", entry, archive, e);
+            return None;
+        }
+    };
+    for file_info in files.iter().filter(|f| f.size == size && !is_archive(&f.extension)) {
+        let path = &file_info.original_path;
+        if !library_hashes.contains_key(path) {
+            let Ok(file_hash) = compute_md5(path) else { continue };
+            library_hashes.insert(path.clone(), file_hash);
+        }
+        if library_hashes[path] == hash {
+            return Some(path.clone());
+        }
+    }
+    None
+}
+
+fn is_rar(archive: &Path) -> bool {
+    archive.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("rar"))
+}
+
+fn list_entries(archive: &Path) -> Result<Vec<String>> {
+    if is_rar(archive) {
+        let output = unrar(&["lb", "-p-"], archive, None).output().map_err(spawn_error)?;
+        if !output.status.success() {
+            return Err(anyhow!("unrar failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        // Bare listing uses the platform separator
+        return Ok(String::from_utf8_lossy(&output.stdout).lines().map(|l| l.replace('\\', "/")).collect());
+    }
+    let mut zip = zip::ZipArchive::new(File::open(archive)?)?;
+    let mut entries = Vec::new();
+    for i in 0..zip.len() {
+        let file = zip.by_index(i)?;
+        if !file.is_dir() {
+            entries.push(file.name().to_string());
+        }
+    }
+    Ok(entries)
+}
+
+/// MD5 (formatted like `compute_md5`) and size of one entry.
+fn hash_entry(archive: &Path, entry: &str) -> Result<(String, u64)> {
+    let mut context = md5::Context::new();
+    let size = if is_rar(archive) {
+        copy_rar_entry(archive, entry, &mut context)?
+    } else {
+        let mut zip = zip::ZipArchive::new(File::open(archive)?)?;
+        let mut file = zip.by_name(entry)?;
+        io::copy(&mut file, &mut context)?
+    };
+    Ok((format!("{:x}", context.compute()), size))
+}
+
+/// RAR archives are read with the `unrar` command when it is installed.
+fn unrar(args: &[&str], archive: &Path, entry: Option<&str>) -> Command {
+    let mut command = Command::new("unrar");
+    command.args(args).arg(archive).args(entry).stdin(Stdio::null()).stderr(Stdio::piped());
+    command
+}
+
+fn spawn_error(e: io::Error) -> anyhow::Error {
+    if e.kind() == io::ErrorKind::NotFound {
+        Error::MissingTool("unrar").into()
+    } else {
+        e.into()
+    }
+}
+
+/// Stream one RAR entry into `output`; returns the number of bytes.
+fn copy_rar_entry(archive: &Path, entry: &str, output: &mut impl io::Write) -> Result<u64> {
+    let mut child = unrar(&["p", "-inul", "-p-"], archive, Some(entry)).stdout(Stdio::piped()).spawn().map_err(spawn_error)?;
+    let size = io::copy(child.stdout.as_mut().ok_or_else(|| anyhow!("no unrar output"))?, output)?;
+    if !child.wait()?.success() {
+        return Err(anyhow!("unrar could not read {}", entry));
+    }
+    Ok(size)
+}
+
+/// Extract every payload that is not already in the library. Existing files
+/// are never overwritten. Returns the extracted paths and any errors.
+pub fn extract(reports: &[ArchiveReport]) -> (Vec<PathBuf>, Vec<String>) {
+    let mut extracted = Vec::new();
+    let mut errors = Vec::new();
+    for report in reports {
+        for payload in report.payloads.iter().filter(|p| p.duplicate_of.is_none()) {
+            if payload.target.exists() {
+                errors.push(format!("Not extracted from {}: {} already exists", report.path.display(), payload.target.display()));
+                continue;
+            }
+            match extract_entry(&report.path, &payload.entry, &payload.target) {
+                Ok(()) => {
+                    info!("Extracted {} from {:?}", payload.entry, report.path);
+                    extracted.push(payload.target.clone());
+                }
+                Err(e) => {
+                    errors.push(format!("Failed to extract {} from {}: {}", payload.entry, report.path.display(), e));
+                }
+            }
+        }
+    }
+    (extracted, errors)
+}
+
+fn extract_entry(archive: &Path, entry: &str, target: &Path) -> Result<()> {
+    let mut output = File::create_new(target)?;
+    let mut copy = || -> Result<()> {
+        if is_rar(archive) {
+            copy_rar_entry(archive, entry, &mut output)?;
+        } else {
+            let mut zip = zip::ZipArchive::new(File::open(archive)?)?;
+            io::copy(&mut zip.by_name(entry)?, &mut output)?;
+        }
+        Ok(())
+    };
+    let result = copy();
+    // Don't leave a partial file behind
+    if result.is_err() {
+        let _ = fs::remove_file(target);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+    use std::io::Write;
+    use tempfile::TempDir;
+    use zip::write::SimpleFileOptions;
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) -> Result<()> {
+        let mut zip = zip::ZipWriter::new(File::create(path)?);
+        for (name, content) in entries {
+            zip.start_file(*name, SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored))?;
+            zip.write_all(content)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_inspect_and_extract_zip() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        let owned = vec![b'a'; 2048];
+        fs::write(root.join("Owned Book.pdf"), &owned)?;
+        write_zip(&root.join("bundle.zip"), &[
+            ("Bundle/Jane Quux - Topology (2001).epub", &[b'b'; 2048]),
+            ("Bundle/copy.pdf", &owned),
+            ("Bundle/Read me.txt", b"visit our site"),
+            ("__MACOSX/Bundle/._copy.pdf", b"junk"),
+        ])?;
+        write_zip(&root.join("dupes.zip"), &[("owned.pdf", &owned)])?;
+        fs::write(root.join("broken.zip"), "not a zip")?;
+
+        let files = Scanner::new(&root, 1)?.scan()?;
+        let mut reports = inspect(&files, true);
+        reports.sort_by(|a, b| a.path.cmp(&b.path));
+        let [broken, bundle, dupes] = &reports[..] else { panic!("expected three archives") };
+
+        assert!(broken.error.is_some());
+        let names: Vec<String> = bundle.payloads.iter().map(Payload::file_name).collect();
+        assert_eq!(names, vec!["Jane Quux - Topology (2001).epub", "copy.pdf"]);
+        assert_eq!(bundle.payloads[1].duplicate_of, Some(root.join("Owned Book.pdf")));
+        assert!(!bundle.is_redundant());
+        assert!(dupes.is_redundant());
+
+        let (extracted, errors) = extract(&reports);
+        assert!(errors.is_empty());
+        assert_eq!(extracted, vec![root.join("Jane Quux - Topology (2001).epub")]);
+        assert_eq!(fs::read(&extracted[0])?, vec![b'b'; 2048]);
+
+        // A second run leaves the extracted file alone
+        let (extracted, errors) = extract(&reports);
+        assert!(extracted.is_empty());
+        assert_eq!(errors.len(), 1);
+        Ok(())
+    }
+}
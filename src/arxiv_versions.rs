@@ -0,0 +1,116 @@
+use crate::scanner::FileInfo;
+use regex::Regex;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+
+/// Several downloaded versions (`v1`, `v3`, ...) of the same arXiv paper.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionGroup {
+    /// arXiv ID without the version, e.g. `2301.12345`
+    pub id: String,
+    pub newest: PathBuf,
+    /// Older versions, newest first
+    pub superseded: Vec<PathBuf>,
+}
+
+/// arXiv ID and version number, for new-style (`2301.12345v2`) and
+/// old-style (`math/0501234v3`, `math.AG_0501234v3` on disk) IDs.
+pub fn arxiv_version(filename: &str) -> Option<(String, u32)> {
+    let re = Regex::new(r"(?i)(?:^|[^\d.])(\d{4}\.\d{4,5})v(\d+)(?:[^\d]|$)").unwrap();
+    if let Some(caps) = re.captures(filename) {
+        return Some((caps[1].to_string(), caps[2].parse().ok()?));
+    }
+    let re_old = Regex::new(r"(?:^|[^A-Za-z])([a-z-]+(?:\.[A-Z]{2})?)[/_](\d{7})v(\d+)(?:[^\d]|$)").unwrap();
+    let caps = re_old.captures(filename)?;
+    Some((format!("{}/{}", &caps[1], &caps[2]), caps[3].parse().ok()?))
+}
+
+/// Group files that are versions of the same paper (same ID and extension).
+/// Only papers with an older version on disk are returned, sorted by ID.
+pub fn detect_versions(files: &[FileInfo]) -> Vec<VersionGroup> {
+    let mut papers: BTreeMap<(String, String), Vec<(u32, PathBuf)>> = BTreeMap::new();
+    for file in files {
+        if let Some((id, version)) = arxiv_version(&file.original_name) {
+            papers.entry((id, file.extension.to_lowercase())).or_default().push((version, file.original_path.clone()));
+        }
+    }
+
+    let mut groups = Vec::new();
+    for ((id, _), mut versions) in papers {
+        versions.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        let newest_version = versions[0].0;
+        // Two files claiming the newest version are left to duplicate detection
+        if versions.iter().filter(|(v, _)| *v == newest_version).count() > 1 {
+            continue;
+        }
+        let superseded: Vec<PathBuf> = versions[1..].iter().map(|(_, p)| p.clone()).collect();
+        if !superseded.is_empty() {
+            groups.push(VersionGroup { id, newest: versions[0].1.clone(), superseded });
+        }
+    }
+    groups
+}
+
+/// Keep only the newest version: superseded files are no longer renamed and
+/// are added to the duplicate deletions (so `--no-delete` and annotation
+/// checks still apply).
+pub fn supersede(groups: &[VersionGroup], clean_files: &mut Vec<FileInfo>, duplicate_groups: &mut Vec<Vec<PathBuf>>) {
+    let superseded: HashSet<&PathBuf> = groups.iter().flat_map(|g| &g.superseded).collect();
+    clean_files.retain(|f| !superseded.contains(&f.original_path));
+    for group in groups {
+        let mut paths = vec![group.newest.clone()];
+        paths.extend(group.superseded.iter().cloned());
+        duplicate_groups.push(paths);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_arxiv_version() {
+        assert_eq!(arxiv_version("2301.12345v2.pdf"), Some(("2301.12345".to_string(), 2)));
+        assert_eq!(arxiv_version("arXiv-2012.08669v1.tar.gz"), Some(("2012.08669".to_string(), 1)));
+        assert_eq!(arxiv_version("Smith - Paper [1706.03762v5].pdf"), Some(("1706.03762".to_string(), 5)));
+        assert_eq!(arxiv_version("math_0501234v3.pdf"), Some(("math/0501234".to_string(), 3)));
+        assert_eq!(arxiv_version("2301.12345.pdf"), None);
+        assert_eq!(arxiv_version("Lang - Algebra v2.pdf"), None);
+    }
+
+    #[test]
+    fn test_detect_and_supersede() {
+        let file = |name: &str| FileInfo {
+            original_path: PathBuf::from("/papers").join(name),
+            original_name: name.to_string(),
+            extension: if name.ends_with(".tar.gz") { ".tar.gz".to_string() } else { ".pdf".to_string() },
+            size: 2048,
+            modified_time: SystemTime::now(),
+            is_failed_download: false,
+            is_too_small: false,
+            is_virtual: false,
+            new_name: None,
+            new_path: PathBuf::from("/papers").join(name),
+            metadata: None,
+        };
+        let mut files = vec![
+            file("2301.12345v1.pdf"),
+            file("2301.12345v3.pdf"),
+            file("2301.12345v2.pdf"),
+            file("2301.12345v1.tar.gz"),
+            file("1706.03762v5.pdf"),
+        ];
+
+        let groups = detect_versions(&files);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].id, "2301.12345");
+        assert_eq!(groups[0].newest, PathBuf::from("/papers/2301.12345v3.pdf"));
+        assert_eq!(groups[0].superseded, vec![PathBuf::from("/papers/2301.12345v2.pdf"), PathBuf::from("/papers/2301.12345v1.pdf")]);
+
+        let mut duplicate_groups = Vec::new();
+        supersede(&groups, &mut files, &mut duplicate_groups);
+        assert_eq!(files.len(), 3);
+        assert_eq!(duplicate_groups[0].len(), 3);
+    }
+}
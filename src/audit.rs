@@ -0,0 +1,97 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// What a live run did to the disk, checked once everything has executed so
+/// partial failures show up in the output and the run history.
+#[derive(Debug, Default)]
+pub struct Audit {
+    renamed: Vec<(PathBuf, PathBuf)>,
+    /// Deleted file and, for duplicates, the copy that was kept
+    deleted: Vec<(PathBuf, Option<PathBuf>)>,
+    untouched: Vec<PathBuf>,
+    touched: HashSet<PathBuf>,
+}
+
+impl Audit {
+    pub fn renamed(&mut self, from: &Path, to: &Path) {
+        self.touched.insert(from.to_path_buf());
+        self.renamed.push((from.to_path_buf(), to.to_path_buf()));
+    }
+
+    pub fn deleted(&mut self, path: &Path, kept: Option<&Path>) {
+        self.touched.insert(path.to_path_buf());
+        self.deleted.push((path.to_path_buf(), kept.map(Path::to_path_buf)));
+    }
+
+    /// A file the plan left where it was. Ignored if it was renamed or deleted.
+    pub fn untouched(&mut self, path: &Path) {
+        if !self.touched.contains(path) {
+            self.untouched.push(path.to_path_buf());
+        }
+    }
+
+    /// One message per discrepancy; empty when the disk matches the plan.
+    pub fn verify(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        // Kept copies may have been renamed in the same run
+        let final_paths: HashMap<&Path, &Path> =
+            self.renamed.iter().map(|(from, to)| (from.as_path(), to.as_path())).collect();
+
+        for (from, to) in &self.renamed {
+            if !to.exists() {
+                issues.push(format!("Rename target missing: {} (was {})", to.display(), from.display()));
+            }
+        }
+        for (path, kept) in &self.deleted {
+            if path.exists() {
+                issues.push(format!("Deleted file still present: {}", path.display()));
+            }
+            if let Some(kept) = kept {
+                let kept = final_paths.get(kept.as_path()).copied().unwrap_or(kept);
+                if !kept.exists() {
+                    issues.push(format!("Kept copy missing for deleted duplicate {}: {}", path.display(), kept.display()));
+                }
+            }
+        }
+        for path in &self.untouched {
+            if !path.exists() {
+                issues.push(format!("File vanished during the run: {}", path.display()));
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_verify_reports_discrepancies() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path();
+        fs::write(dir.join("Renamed.pdf"), "x")?;
+        fs::write(dir.join("Other.pdf"), "x")?;
+
+        let mut audit = Audit::default();
+        audit.renamed(&dir.join("old.pdf"), &dir.join("Renamed.pdf"));
+        audit.deleted(&dir.join("copy.pdf"), Some(&dir.join("old.pdf")));
+        audit.untouched(&dir.join("Other.pdf"));
+        audit.untouched(&dir.join("old.pdf"));
+        assert!(audit.verify().is_empty());
+
+        audit.renamed(&dir.join("a.pdf"), &dir.join("A.pdf"));
+        audit.deleted(&dir.join("Other.pdf"), Some(&dir.join("gone.pdf")));
+        audit.untouched(&dir.join("lost.pdf"));
+        let issues = audit.verify();
+        assert_eq!(issues.len(), 4);
+        assert!(issues[0].starts_with("Rename target missing"));
+        assert!(issues[1].starts_with("Deleted file still present"));
+        assert!(issues[2].starts_with("Kept copy missing"));
+        assert!(issues[3].starts_with("File vanished"));
+        Ok(())
+    }
+}
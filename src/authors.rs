@@ -0,0 +1,159 @@
+use crate::error::Error;
+use crate::search_key;
+use anyhow::Result;
+use log::debug;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub const ALIASES_FILE_NAME: &str = ".ebook-renamer-authors.txt";
+
+// Canonical spelling followed by the variants seen in the wild
+const BUILTIN_ALIASES: &[(&str, &[&str])] = &[
+    ("Saunders Mac Lane", &["S. Mac Lane", "Saunders MacLane", "S. MacLane", "Mac Lane, Saunders"]),
+    ("Jean-Pierre Serre", &["J.-P. Serre", "J. P. Serre", "Jean Pierre Serre"]),
+    ("Alexander Grothendieck", &["A. Grothendieck"]),
+    ("Israel M. Gelfand", &["I. M. Gelfand", "I. M. Gel'fand", "Israel Gelfand", "I. Gelfand"]),
+    ("Vladimir I. Arnold", &["V. I. Arnold", "V. I. Arnol'd", "Vladimir Arnold", "V. Arnold"]),
+    ("Michael Atiyah", &["M. F. Atiyah", "Michael F. Atiyah", "M. Atiyah"]),
+    ("Pafnuty L. Chebyshev", &["P. L. Chebyshev", "Pafnuty Chebyshev", "P. Tchebychev", "P. L. Tchebychef"]),
+];
+
+/// Maps author spellings to one canonical form. Lookups ignore case,
+/// punctuation and spacing, so "s. mac  lane" matches "S. Mac Lane".
+pub struct AuthorAliases {
+    map: HashMap<String, String>,
+}
+
+impl AuthorAliases {
+    pub fn builtin() -> Self {
+        let mut aliases = AuthorAliases { map: HashMap::new() };
+        for (canonical, variants) in BUILTIN_ALIASES {
+            aliases.add(canonical, variants.iter().copied());
+        }
+        aliases
+    }
+
+    /// Built-in aliases plus `<target-dir>/.ebook-renamer-authors.txt` (or
+    /// `custom_path`) when present. User entries override built-in ones.
+    pub fn load(custom_path: &Option<std::path::PathBuf>, target_dir: &Path) -> Result<Self> {
+        let mut aliases = Self::builtin();
+        let path = custom_path.clone().unwrap_or_else(|| target_dir.join(ALIASES_FILE_NAME));
+        if path.exists() {
+            aliases.merge_file(&path)?;
+        } else if custom_path.is_some() {
+            return Err(Error::NotFound { what: "Author alias file", path }.into());
+        }
+        Ok(aliases)
+    }
+
+    /// Parse lines of the form `Canonical Name = Variant One; Variant Two`.
+    /// Blank lines and lines starting with `#` are ignored.
+    pub fn merge_file(&mut self, path: &Path) -> Result<()> {
+        let content = fs::read_to_string(path)?;
+        for (line_no, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (canonical, variants) = line
+                .split_once('=')
+                .ok_or_else(|| Error::InvalidFile {
+                    path: path.to_path_buf(),
+                    message: format!("line {}: expected `Canonical = Variant; ...`", line_no + 1),
+                })?;
+            self.add(canonical.trim(), variants.split(';').map(str::trim).filter(|v| !v.is_empty()));
+        }
+        debug!("Loaded author aliases from {:?}", path);
+        Ok(())
+    }
+
+    fn add<'a>(&mut self, canonical: &str, variants: impl Iterator<Item = &'a str>) {
+        self.map.insert(alias_key(canonical), canonical.to_string());
+        for variant in variants {
+            self.map.insert(alias_key(variant), canonical.to_string());
+        }
+    }
+
+    /// Canonical spelling for an author string. Multi-author strings are
+    /// resolved per author; unknown names are returned unchanged.
+    pub fn canonicalize(&self, authors: &str) -> String {
+        if let Some(canonical) = self.map.get(&alias_key(authors)) {
+            return canonical.clone();
+        }
+        if !authors.contains(", ") {
+            return authors.to_string();
+        }
+        authors
+            .split(", ")
+            .map(|author| self.map.get(&alias_key(author)).map(String::as_str).unwrap_or(author))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn alias_key(name: &str) -> String {
+    search_key::fold(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_builtin_aliases() {
+        let aliases = AuthorAliases::builtin();
+        assert_eq!(aliases.canonicalize("S. Mac Lane"), "Saunders Mac Lane");
+        assert_eq!(aliases.canonicalize("Saunders MacLane"), "Saunders Mac Lane");
+        assert_eq!(aliases.canonicalize("saunders mac lane"), "Saunders Mac Lane");
+        assert_eq!(aliases.canonicalize("V. I. Arnol'd"), "Vladimir I. Arnold");
+        // Accents and curly apostrophes are ignored too
+        assert_eq!(aliases.canonicalize("V. I. Arnol’d"), "Vladimir I. Arnold");
+        assert_eq!(aliases.canonicalize("J.-P. Sérre"), "Jean-Pierre Serre");
+        assert_eq!(aliases.canonicalize("John Smith"), "John Smith");
+    }
+
+    #[test]
+    fn test_chebyshev_canonical_name() {
+        let aliases = AuthorAliases::builtin();
+        for variant in ["P. L. Chebyshev", "Pafnuty Chebyshev", "P. Tchebychev", "Pafnuty L. Chebyshev"] {
+            assert_eq!(aliases.canonicalize(variant), "Pafnuty L. Chebyshev");
+        }
+    }
+
+    #[test]
+    fn test_multi_author_lists() {
+        let aliases = AuthorAliases::builtin();
+        assert_eq!(
+            aliases.canonicalize("S. Mac Lane, I. Moerdijk, J.-P. Serre"),
+            "Saunders Mac Lane, I. Moerdijk, Jean-Pierre Serre"
+        );
+    }
+
+    #[test]
+    fn test_user_file_overrides_builtin() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        fs::write(
+            tmp_dir.path().join(ALIASES_FILE_NAME),
+            "# my spellings\nS. MacLane = S. Mac Lane\nIeke Moerdijk = I. Moerdijk; I Moerdijk\n",
+        )?;
+
+        let aliases = AuthorAliases::load(&None, tmp_dir.path())?;
+        assert_eq!(aliases.canonicalize("S. Mac Lane"), "S. MacLane");
+        assert_eq!(aliases.canonicalize("I Moerdijk"), "Ieke Moerdijk");
+        assert_eq!(aliases.canonicalize("A. Grothendieck"), "Alexander Grothendieck");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_line_is_an_error() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let path = tmp_dir.path().join("aliases.txt");
+        fs::write(&path, "no separator here\n")?;
+
+        assert!(AuthorAliases::load(&Some(path), tmp_dir.path()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use crate::scanner::{FileInfo, Scanner};
+use anyhow::Result;
+use chrono::Local;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// The library as the last run left it, read back by `changes`.
+pub const CATALOG_FILE_NAME: &str = ".ebook-renamer-catalog.json";
+
+/// What a file looked like on disk; a rename keeps both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Entry {
+    pub size: u64,
+    /// Seconds since the Unix epoch
+    pub modified: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Catalog {
+    pub saved: String,
+    pub max_depth: usize,
+    /// By path relative to the target directory
+    pub files: BTreeMap<String, Entry>,
+}
+
+impl Catalog {
+    pub fn from_files(root: &Path, max_depth: usize, files: &[FileInfo]) -> Self {
+        let files = files
+            .iter()
+            .map(|file| {
+                let relative = file.original_path.strip_prefix(root).unwrap_or(&file.original_path);
+                let modified = file.modified_time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+                (relative.to_string_lossy().replace('\\', "/"), Entry { size: file.size, modified })
+            })
+            .collect();
+        Self { saved: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(), max_depth, files }
+    }
+
+    /// Scan the library as it is now (sizes and dates only, no hashing).
+    pub fn snapshot(path: &Path, max_depth: usize) -> Result<Self> {
+        let root = path.canonicalize()?;
+        let files = Scanner::new(&root, max_depth)?.scan()?;
+        Ok(Self::from_files(&root, max_depth, &files))
+    }
+
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        let path = root.join(CATALOG_FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(&path)?)?))
+    }
+
+    pub fn save(&self, root: &Path) -> Result<()> {
+        // Write then rename, so an interruption never leaves half a catalog
+        let path = root.join(CATALOG_FILE_NAME);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string(self)?)?;
+        fs::rename(&tmp, &path)?;
+        debug!("Saved catalog of {} files to {:?}", self.files.len(), path);
+        Ok(())
+    }
+
+    /// What happened between this catalog and `current`. A file that
+    /// disappeared and one that appeared with the same size and modification
+    /// time count as a rename.
+    pub fn changes(&self, current: &Catalog) -> Changes {
+        let mut added: Vec<&String> = current.files.keys().filter(|p| !self.files.contains_key(*p)).collect();
+        let mut removed = Vec::new();
+        let mut modified = Vec::new();
+        for (path, entry) in &self.files {
+            match current.files.get(path) {
+                None => removed.push(path),
+                Some(now) if now != entry => modified.push(path.clone()),
+                Some(_) => {}
+            }
+        }
+
+        let mut appeared: HashMap<Entry, Vec<&String>> = HashMap::new();
+        for path in added.iter().rev() {
+            appeared.entry(current.files[*path]).or_default().push(path);
+        }
+        let mut renamed = Vec::new();
+        removed.retain(|path| match appeared.get_mut(&self.files[*path]).and_then(Vec::pop) {
+            Some(to) => {
+                renamed.push(Renamed { from: (*path).clone(), to: to.clone() });
+                false
+            }
+            None => true,
+        });
+        added.retain(|path| !renamed.iter().any(|r| &r.to == *path));
+
+        Changes {
+            since: self.saved.clone(),
+            added: added.into_iter().cloned().collect(),
+            removed: removed.into_iter().cloned().collect(),
+            modified,
+            renamed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Renamed {
+    pub from: String,
+    pub to: String,
+}
+
+/// Output of `changes`, every list sorted by path.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Changes {
+    pub since: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+    pub renamed: Vec<Renamed>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty() && self.renamed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn catalog(files: &[(&str, u64, u64)]) -> Catalog {
+        Catalog {
+            saved: "2026-01-01 00:00:00".to_string(),
+            max_depth: usize::MAX,
+            files: files.iter().map(|(p, size, modified)| (p.to_string(), Entry { size: *size, modified: *modified })).collect(),
+        }
+    }
+
+    #[test]
+    fn test_changes() {
+        let before = catalog(&[("a.pdf", 100, 1), ("b.pdf", 200, 2), ("c.pdf", 300, 3), ("old.epub", 400, 4)]);
+        let after = catalog(&[("a.pdf", 100, 1), ("b.pdf", 250, 5), ("Math/c.pdf", 300, 3), ("new.pdf", 500, 6)]);
+        let changes = before.changes(&after);
+        assert_eq!(changes.added, vec!["new.pdf"]);
+        assert_eq!(changes.removed, vec!["old.epub"]);
+        assert_eq!(changes.modified, vec!["b.pdf"]);
+        assert_eq!(changes.renamed, vec![Renamed { from: "c.pdf".to_string(), to: "Math/c.pdf".to_string() }]);
+        assert!(after.changes(&after).is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_save_and_load() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        fs::create_dir(tmp_dir.path().join("Math"))?;
+        fs::write(tmp_dir.path().join("Math/Lang - Algebra.pdf"), vec![b'x'; 2048])?;
+
+        let snapshot = Catalog::snapshot(tmp_dir.path(), usize::MAX)?;
+        assert_eq!(snapshot.files.keys().collect::<Vec<_>>(), vec!["Math/Lang - Algebra.pdf"]);
+        snapshot.save(tmp_dir.path())?;
+        assert_eq!(Catalog::load(tmp_dir.path())?, Some(snapshot));
+        Ok(())
+    }
+}
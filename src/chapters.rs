@@ -0,0 +1,234 @@
+use crate::scanner::FileInfo;
+use log::{debug, info};
+use regex::Regex;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Generic "chapter 3.pdf" names above this page count are probably whole books
+const MAX_CHAPTER_PAGES: usize = 150;
+// Don't read huge files just to count pages
+const MAX_PAGE_COUNT_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PartKind {
+    FrontMatter,
+    Chapter(u32),
+    BackMatter,
+}
+
+impl PartKind {
+    pub fn label(&self) -> String {
+        match self {
+            PartKind::FrontMatter => "front matter".to_string(),
+            PartKind::Chapter(n) => format!("chapter {}", n),
+            PartKind::BackMatter => "back matter".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChapterPart {
+    pub path: PathBuf,
+    pub kind: PartKind,
+    pub pages: Option<usize>,
+}
+
+/// Chapter and book-matter PDFs that belong to the same book.
+#[derive(Debug, Clone)]
+pub struct ChapterGroup {
+    /// ISBN for SpringerLink downloads, otherwise the containing folder name
+    pub book_key: String,
+    pub dir: PathBuf,
+    pub parts: Vec<ChapterPart>,
+}
+
+impl ChapterGroup {
+    /// Folder used by `--chapter-files folder`.
+    pub fn folder(&self) -> PathBuf {
+        self.dir.join(format!("{} (chapters)", self.book_key))
+    }
+}
+
+/// Classify a filename as a chapter or book-matter part. Returns the ISBN
+/// when the name carries one (SpringerLink style) so parts of different
+/// books in the same folder are kept apart.
+pub fn classify(file_name: &str) -> Option<(Option<String>, PartKind)> {
+    let stem = file_name.rsplit_once('.').map(|(s, _)| s).unwrap_or(file_name);
+
+    // SpringerLink: 978-3-030-12345-6_3, 978-3-030-12345-6_BookFrontmatter
+    let re_springer = Regex::new(r"^(97[89][\d-]{10,14})_(\d{1,3}|BookFrontmatter|BookBackmatter)$").unwrap();
+    if let Some(caps) = re_springer.captures(stem) {
+        let isbn = caps[1].to_string();
+        let kind = match &caps[2] {
+            "BookFrontmatter" => PartKind::FrontMatter,
+            "BookBackmatter" => PartKind::BackMatter,
+            n => PartKind::Chapter(n.parse().ok()?),
+        };
+        return Some((Some(isbn), kind));
+    }
+
+    // SpringerLink book matter: bfm%3A978-..., bbm:978-...
+    let re_matter = Regex::new(r"^(bfm|bbm)(?:%3A|[:_-])(97[89][\d-]{10,14})").unwrap();
+    if let Some(caps) = re_matter.captures(stem) {
+        let kind = if &caps[1] == "bfm" { PartKind::FrontMatter } else { PartKind::BackMatter };
+        return Some((Some(caps[2].to_string()), kind));
+    }
+
+    let lower = stem.to_lowercase();
+    if Regex::new(r"^(front|back)[\s_-]?matter$").unwrap().is_match(&lower) {
+        let kind = if lower.starts_with("front") { PartKind::FrontMatter } else { PartKind::BackMatter };
+        return Some((None, kind));
+    }
+
+    let re_chapter = Regex::new(r"^(?:chapter|chap|ch)[\s_.-]*(\d{1,3})$").unwrap();
+    if let Some(caps) = re_chapter.captures(&lower) {
+        return Some((None, PartKind::Chapter(caps[1].parse().ok()?)));
+    }
+
+    None
+}
+
+/// Rough PDF page count: number of `/Type /Page` objects.
+pub fn count_pdf_pages(path: &Path) -> Option<usize> {
+    if fs::metadata(path).ok()?.len() > MAX_PAGE_COUNT_BYTES {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    let re = regex::bytes::Regex::new(r"/Type\s*/Page\b").unwrap();
+    let count = re.find_iter(&bytes).count();
+    (count > 0).then_some(count)
+}
+
+pub fn detect_chapter_files(files: &[FileInfo]) -> Vec<ChapterGroup> {
+    let mut groups: BTreeMap<(PathBuf, String), Vec<ChapterPart>> = BTreeMap::new();
+
+    for file_info in files {
+        if file_info.extension != ".pdf" || file_info.is_failed_download || file_info.is_too_small {
+            continue;
+        }
+        let Some((isbn, kind)) = classify(&file_info.original_name) else {
+            continue;
+        };
+        let pages = count_pdf_pages(&file_info.original_path);
+
+        // Only generic names need the page count as a sanity check
+        if isbn.is_none() && matches!(kind, PartKind::Chapter(_)) && pages.is_some_and(|p| p > MAX_CHAPTER_PAGES) {
+            debug!("{} has {:?} pages, not treating it as a chapter", file_info.original_name, pages);
+            continue;
+        }
+
+        let dir = file_info.original_path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let book_key = isbn.unwrap_or_else(|| {
+            dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "book".to_string())
+        });
+
+        groups.entry((dir, book_key)).or_default().push(ChapterPart {
+            path: file_info.original_path.clone(),
+            kind,
+            pages,
+        });
+    }
+
+    groups
+        .into_iter()
+        .map(|((dir, book_key), mut parts)| {
+            parts.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.path.cmp(&b.path)));
+            ChapterGroup { book_key, dir, parts }
+        })
+        .collect()
+}
+
+/// Clear the planned rename of every detected part so it keeps its name.
+pub fn keep_original_names(files: &mut [FileInfo], groups: &[ChapterGroup]) {
+    let parts: HashSet<&Path> = groups.iter().flat_map(|g| g.parts.iter().map(|p| p.path.as_path())).collect();
+    for file_info in files.iter_mut() {
+        if parts.contains(file_info.original_path.as_path()) {
+            file_info.new_name = None;
+            file_info.new_path = file_info.original_path.clone();
+        }
+    }
+}
+
+/// Move every part into its group's folder. Existing targets are left alone.
+/// Folders are created even if all parts vanished, so run this only when
+/// there is something to move.
+pub fn move_into_folders(groups: &[ChapterGroup]) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    for group in groups {
+        let folder = group.folder();
+        if let Err(e) = fs::create_dir_all(&folder) {
+            errors.push(format!("Failed to create {:?}: {}", folder, e));
+            continue;
+        }
+        for part in &group.parts {
+            // Parts deleted earlier in the run (e.g. as duplicates) are skipped
+            let Some(name) = part.path.file_name().filter(|_| part.path.exists()) else {
+                continue;
+            };
+            let target = folder.join(name);
+            if target.exists() {
+                errors.push(format!("Skipped {:?}: {:?} already exists", part.path, target));
+                continue;
+            }
+            match fs::rename(&part.path, &target) {
+                Ok(_) => info!("Moved chapter file {:?} -> {:?}", part.path, target),
+                Err(e) => errors.push(format!("Failed to move {:?}: {}", part.path, e)),
+            }
+        }
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify("978-3-030-12345-6_3.pdf"),
+            Some((Some("978-3-030-12345-6".to_string()), PartKind::Chapter(3)))
+        );
+        assert_eq!(
+            classify("978-3-030-12345-6_BookBackmatter.pdf"),
+            Some((Some("978-3-030-12345-6".to_string()), PartKind::BackMatter))
+        );
+        assert_eq!(
+            classify("bfm%3A978-1-4419-8853-9%2F1.pdf").map(|(_, kind)| kind),
+            Some(PartKind::FrontMatter)
+        );
+        assert_eq!(classify("Chapter 12.pdf"), Some((None, PartKind::Chapter(12))));
+        assert_eq!(classify("front-matter.pdf"), Some((None, PartKind::FrontMatter)));
+        assert_eq!(classify("978-3-030-12345-6.pdf"), None);
+        assert_eq!(classify("Chapter 3 - Sheaves on Manifolds.pdf"), None);
+    }
+
+    #[test]
+    fn test_detect_and_move_into_folders() -> anyhow::Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let page = "%PDF-1.4\n1 0 obj << /Type /Page >> endobj\n".repeat(40);
+        for name in ["978-3-030-12345-6_1.pdf", "978-3-030-12345-6_BookFrontmatter.pdf", "chapter 2.pdf"] {
+            fs::write(tmp_dir.path().join(name), &page)?;
+        }
+
+        let files = Scanner::new(tmp_dir.path(), 1)?.scan()?;
+        let groups = detect_chapter_files(&files);
+
+        assert_eq!(groups.len(), 2);
+        let springer = groups.iter().find(|g| g.book_key == "978-3-030-12345-6").unwrap();
+        assert_eq!(springer.parts.len(), 2);
+        assert_eq!(springer.parts[0].kind, PartKind::FrontMatter);
+        assert_eq!(springer.parts[1].pages, Some(40));
+
+        assert!(move_into_folders(&groups).is_empty());
+        assert!(springer.folder().join("978-3-030-12345-6_1.pdf").exists());
+        assert!(!tmp_dir.path().join("chapter 2.pdf").exists());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+use crate::delete_mode::Deleter;
+use crate::i18n::t;
+use crate::scanner::FileInfo;
+use crate::stubs;
+use crate::todo::validate_pdf_header;
+use anyhow::Result;
+use log::{debug, info};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CleanupReason {
+    FailedDownload,
+    /// Error or captcha page saved under the book's name
+    HtmlStub,
+    TooSmall,
+    CorruptedPdf,
+}
+
+impl CleanupReason {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CleanupReason::FailedDownload => "failed download",
+            CleanupReason::HtmlStub => "saved web page",
+            CleanupReason::TooSmall => "too small",
+            CleanupReason::CorruptedPdf => "corrupted PDF",
+        }
+    }
+
+    /// `label` in the output language.
+    pub fn localized(&self) -> String {
+        match self {
+            CleanupReason::FailedDownload => t!("reason-failed-download"),
+            CleanupReason::HtmlStub => t!("reason-html-stub"),
+            CleanupReason::TooSmall => t!("reason-too-small"),
+            CleanupReason::CorruptedPdf => t!("reason-corrupted-pdf"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CleanupItem {
+    pub path: PathBuf,
+    pub size: u64,
+    pub reason: CleanupReason,
+}
+
+#[derive(Debug, Default)]
+pub struct CleanupPlan {
+    pub items: Vec<CleanupItem>,
+}
+
+#[derive(Debug, Default)]
+pub struct CleanupResult {
+    pub deleted: Vec<PathBuf>,
+    pub failed: Vec<(PathBuf, String)>,
+    pub bytes_freed: u64,
+}
+
+impl CleanupPlan {
+    /// Build the plan from scanner results plus the PDF integrity check.
+    /// Cloud placeholders are left alone: their content can't be checked.
+    pub fn from_files(files: &[FileInfo]) -> Self {
+        let mut items: Vec<CleanupItem> = files
+            .iter()
+            .filter(|f| !f.is_virtual)
+            .filter_map(|f| {
+                let reason = if f.is_failed_download {
+                    CleanupReason::FailedDownload
+                } else if stubs::check(f).is_some() {
+                    CleanupReason::HtmlStub
+                } else if f.is_too_small {
+                    CleanupReason::TooSmall
+                } else if f.extension.to_lowercase() == ".pdf" && validate_pdf_header(&f.original_path).is_err() {
+                    CleanupReason::CorruptedPdf
+                } else {
+                    return None;
+                };
+                Some(CleanupItem {
+                    path: f.original_path.clone(),
+                    size: f.size,
+                    reason,
+                })
+            })
+            .collect();
+
+        items.sort_by(|a, b| a.reason.cmp(&b.reason).then_with(|| a.path.cmp(&b.path)));
+        debug!("Cleanup plan contains {} files", items.len());
+        CleanupPlan { items }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.items.iter().map(|i| i.size).sum()
+    }
+
+    pub fn count(&self, reason: CleanupReason) -> usize {
+        self.items.iter().filter(|i| i.reason == reason).count()
+    }
+}
+
+/// Ask a yes/no question; anything other than "y"/"yes" counts as no.
+pub fn prompt_confirmation(prompt: &str, input: &mut impl BufRead, output: &mut impl Write) -> Result<bool> {
+    write!(output, "{} [y/N] ", prompt)?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    let answer = answer.trim().to_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+pub fn execute_cleanup(plan: &CleanupPlan, deleter: &Deleter) -> CleanupResult {
+    let mut result = CleanupResult::default();
+
+    for item in &plan.items {
+        match deleter.remove(&item.path) {
+            Ok(_) => {
+                info!("Deleted {} file: {}", item.reason.label(), item.path.display());
+                result.bytes_freed += item.size;
+                result.deleted.push(item.path.clone());
+            }
+            Err(e) => result.failed.push((item.path.clone(), e.to_string())),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::DeleteMode;
+    use std::fs;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    fn file_info(path: PathBuf, size: u64, failed: bool, small: bool) -> FileInfo {
+        FileInfo {
+            original_name: path.file_name().unwrap().to_string_lossy().to_string(),
+            extension: format!(".{}", path.extension().unwrap().to_string_lossy()),
+            original_path: path.clone(),
+            size,
+            modified_time: std::time::SystemTime::now(),
+            is_failed_download: failed,
+            is_too_small: small,
+            is_virtual: false,
+            new_name: None,
+            new_path: path,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_plan_collects_failed_small_and_corrupted() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let failed = tmp_dir.path().join("book.pdf.download");
+        let small = tmp_dir.path().join("tiny.pdf");
+        let corrupt = tmp_dir.path().join("corrupt.pdf");
+        let valid = tmp_dir.path().join("valid.pdf");
+        let stub = tmp_dir.path().join("stub.pdf");
+        fs::write(&failed, "")?;
+        fs::write(&small, "x")?;
+        fs::write(&corrupt, "garbage, not a pdf")?;
+        fs::write(&valid, "%PDF-1.4 content")?;
+        fs::write(&stub, "<html>not a pdf</html>")?;
+
+        let files = vec![
+            file_info(failed, 0, true, false),
+            file_info(small, 1, false, true),
+            file_info(corrupt, 18, false, false),
+            file_info(valid, 16, false, false),
+            file_info(stub, 22, false, false),
+        ];
+        let plan = CleanupPlan::from_files(&files);
+
+        assert_eq!(plan.items.len(), 4);
+        assert_eq!(plan.count(CleanupReason::FailedDownload), 1);
+        assert_eq!(plan.count(CleanupReason::HtmlStub), 1);
+        assert_eq!(plan.count(CleanupReason::TooSmall), 1);
+        assert_eq!(plan.count(CleanupReason::CorruptedPdf), 1);
+        assert_eq!(plan.total_bytes(), 41);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prompt_confirmation() -> Result<()> {
+        let mut out = Vec::new();
+        assert!(prompt_confirmation("Delete?", &mut Cursor::new("y\n"), &mut out)?);
+        assert!(prompt_confirmation("Delete?", &mut Cursor::new("YES\n"), &mut out)?);
+        assert!(!prompt_confirmation("Delete?", &mut Cursor::new("\n"), &mut out)?);
+        assert!(!prompt_confirmation("Delete?", &mut Cursor::new("nope\n"), &mut out)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_cleanup() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let small = tmp_dir.path().join("tiny.pdf");
+        fs::write(&small, "x")?;
+
+        let plan = CleanupPlan {
+            items: vec![
+                CleanupItem { path: small.clone(), size: 1, reason: CleanupReason::TooSmall },
+                CleanupItem { path: tmp_dir.path().join("missing.pdf"), size: 5, reason: CleanupReason::TooSmall },
+            ],
+        };
+        let result = execute_cleanup(&plan, &Deleter::new(DeleteMode::Delete, tmp_dir.path(), None)?);
+
+        assert_eq!(result.deleted, vec![small.clone()]);
+        assert_eq!(result.failed.len(), 1);
+        assert_eq!(result.bytes_freed, 1);
+        assert!(!small.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_cleanup_archives() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        let small = root.join("tiny.pdf");
+        fs::write(&small, "x")?;
+
+        let plan = CleanupPlan {
+            items: vec![CleanupItem { path: small.clone(), size: 1, reason: CleanupReason::TooSmall }],
+        };
+        let archive = root.join("archive");
+        let result = execute_cleanup(&plan, &Deleter::new(DeleteMode::Archive, &root, Some(&archive))?);
+
+        assert_eq!(result.deleted, vec![small.clone()]);
+        assert!(!small.exists());
+        let day = fs::read_dir(&archive)?.next().unwrap()?.path();
+        assert!(day.join("tiny.pdf").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_skips_placeholders() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let placeholder = tmp_dir.path().join("evicted.pdf");
+        fs::write(&placeholder, "")?;
+
+        let mut info = file_info(placeholder, 0, false, true);
+        info.is_virtual = true;
+        assert!(CleanupPlan::from_files(&[info]).is_empty());
+
+        Ok(())
+    }
+}
@@ -1,4 +1,7 @@
-use clap::Parser;
+use clap::{ArgAction, Parser, Subcommand};
+use crate::traversal::Traversal;
+use crate::guard::Limits;
+use crate::scanner::MinValidSize;
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
@@ -8,6 +11,9 @@ use std::path::PathBuf;
     version = "0.1.0"
 )]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Target directory to scan and rename
     #[arg(
         value_name = "PATH",
@@ -78,6 +84,22 @@ pub struct Args {
     )]
     pub preserve_unicode: bool,
 
+    /// Look up names that carry a Library Genesis MD5
+    #[arg(
+        long,
+        help = "Look up files whose name carries a Library Genesis/Anna's Archive MD5 on the libgen API and use its author, title, year and edition; answers are cached in .ebook-renamer-libgen-cache.json, and if the API is unreachable the run goes on with filename parsing. Requires the curl program on PATH"
+    )]
+    pub fetch_libgen_metadata: bool,
+
+    /// Library Genesis JSON API to query
+    #[arg(
+        long,
+        value_name = "URL",
+        requires = "fetch_libgen_metadata",
+        help = "Library Genesis json.php endpoint used by --fetch-libgen-metadata, e.g. a mirror (default: https://libgen.is/json.php)"
+    )]
+    pub libgen_url: Option<String>,
+
     /// Fetch arXiv metadata (placeholder for future implementation)
     #[arg(
         long,
@@ -85,9 +107,30 @@ pub struct Args {
     )]
     pub fetch_arxiv: bool,
 
-    /// Verbose output
-    #[arg(long, short = 'v', help = "Enable verbose logging")]
-    pub verbose: bool,
+    /// Log verbosity, repeatable
+    #[arg(
+        long,
+        short = 'v',
+        action = ArgAction::Count,
+        help = "Increase log verbosity: -v info, -vv debug, -vvv trace (RUST_LOG still takes precedence)"
+    )]
+    pub verbose: u8,
+
+    /// Only report errors
+    #[arg(
+        long,
+        short = 'q',
+        conflicts_with_all = ["verbose", "summary"],
+        help = "Print errors only: no TUI, no per-file output"
+    )]
+    pub quiet: bool,
+
+    /// Only print the final counts
+    #[arg(
+        long,
+        help = "Print only the final counts table instead of the TUI (for cron jobs and scripts)"
+    )]
+    pub summary: bool,
 
     /// Automatically delete small/corrupted files (< 1KB)
     #[arg(
@@ -99,7 +142,7 @@ pub struct Args {
     /// Clean up failed/broken downloads after logging them to todo.md
     #[arg(
         long,
-        help = "Delete failed/broken downloads and small files after logging them to todo.md"
+        help = "Delete failed/broken downloads, saved error/captcha pages and small files after logging them to todo.md"
     )]
     pub clean_failed: bool,
 
@@ -123,9 +166,845 @@ pub struct Args {
         help = "Automatically remove empty .download/.crdownload folders after extracting PDFs"
     )]
     pub cleanup_downloads: bool,
+
+    /// Normalize directory names as well as files
+    #[arg(
+        long,
+        help = "Also normalize directory names (noise removal, series extraction), deepest folders first"
+    )]
+    pub rename_dirs: bool,
+
+    /// Remove directories left empty by this run
+    #[arg(
+        long,
+        help = "Remove directories that became empty during this run (pre-existing empty directories are kept)"
+    )]
+    pub prune_empty_dirs: bool,
+
+    /// Strict audit mode: never modify the filesystem
+    #[arg(
+        long,
+        help = "Read-only report: implies --dry-run and also skips download recovery, todo.md and run history writes"
+    )]
+    pub read_only: bool,
+
+    /// Per-file IO timeout for metadata reads and hashing
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "Skip and report files whose metadata read or hash takes longer than SECS seconds (for hung network mounts)"
+    )]
+    pub io_timeout: Option<u64>,
+
+    /// Normalize filenames only, never delete anything
+    #[arg(
+        long,
+        conflicts_with_all = ["delete_small", "clean_failed", "cleanup_downloads", "prune_empty_dirs", "drop_samples"],
+        help = "Only rename files: implies --no-delete and refuses any flag that deletes files or folders"
+    )]
+    pub rename_only: bool,
+
+    /// Author alias dictionary
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Author alias file, one `Canonical Name = Variant; Variant` per line (default: <target-dir>/.ebook-renamer-authors.txt if present)"
+    )]
+    pub author_aliases: Option<PathBuf>,
+
+    /// Filename template
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        help = "Filename template without extension, e.g. \"{authors} - {title} ({year}, {publisher})\". Fields: {authors} {title} {subtitle} {year} {series} {edition} {volume} {publisher} {kind} {subject} {category}; a / starts a subfolder"
+    )]
+    pub template: Option<String>,
+
+    /// Separator between title and subtitle
+    #[arg(
+        long,
+        value_name = "SEP",
+        default_value = crate::normalizer::DEFAULT_SUBTITLE_SEPARATOR,
+        help = "Separator placed between title and a detected subtitle (\"Algebra_ Chapter 0\" -> \"Algebra - Chapter 0\")"
+    )]
+    pub subtitle_separator: String,
+
+    /// Chapter and book-matter PDF handling
+    #[arg(
+        long,
+        value_name = "MODE",
+        help = "Detect chapter/front-matter/back-matter PDFs and leave them unrenamed: `report` lists them per book, `folder` also moves them into \"<book> (chapters)\""
+    )]
+    pub chapter_files: Option<ChapterMode>,
+
+    /// Record a pair of files that should never be treated as duplicates
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["PATH1", "PATH2"],
+        conflicts_with = "read_only",
+        help = "Remember that PATH1 and PATH2 are not duplicates (e.g. annotated and clean copy) and exit"
+    )]
+    pub ignore_dupe: Option<Vec<PathBuf>>,
+
+    /// Confirm deleting pristine copies of annotated duplicates
+    #[arg(
+        long,
+        help = "Delete duplicates even when a copy has PDF annotations (annotated copies are always kept; without this flag such groups are left alone)"
+    )]
+    pub delete_annotated_dupes: bool,
+
+    /// Organize the dry-run plan by folder
+    #[arg(
+        long,
+        value_name = "MODE",
+        help = "Group the dry-run plan by containing folder with per-folder counts of renames, duplicates and deletions: dir"
+    )]
+    pub group_by: Option<GroupBy>,
+
+    /// Skip recovering PDFs from .download/.crdownload folders
+    #[arg(
+        long,
+        help = "Don't recover PDFs from .download/.crdownload folders"
+    )]
+    pub no_recovery: bool,
+
+    /// Skip reading file headers to find corrupted PDFs
+    #[arg(
+        long,
+        help = "Don't open files to check PDF headers (avoids reads on slow or metered shares)"
+    )]
+    pub no_integrity_check: bool,
+
+    /// Skip duplicate detection
+    #[arg(
+        long,
+        help = "Don't look for duplicates (no hashing, nothing deleted as a duplicate)"
+    )]
+    pub no_dedupe: bool,
+
+    /// Skip filename normalization
+    #[arg(
+        long,
+        help = "Don't rename files; only run the other stages (recovery, integrity check, duplicates)"
+    )]
+    pub no_normalize: bool,
+
+    /// Queue behind a concurrent run instead of refusing to start
+    #[arg(
+        long,
+        help = "If another run holds the lock on this folder, wait for it to finish instead of exiting"
+    )]
+    pub wait_for_lock: bool,
+
+    /// Write redownload.txt for failed/corrupted files with a known source
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        help = "Write redownload.txt (aria2 or wget input) for broken files whose source URL is known, and list them in todo.md"
+    )]
+    pub redownload_list: Option<RedownloadFormat>,
+
+    /// Detect several downloaded versions of the same arXiv paper
+    #[arg(
+        long,
+        value_enum,
+        value_name = "POLICY",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "newest",
+        help = "Treat arXiv versions (v1, v3, ...) of a paper as one work: keep the newest (default) or keep-all and only report them"
+    )]
+    pub arxiv_versions: Option<ArxivVersions>,
+
+    /// Correct low-confidence parses in a TUI form before the plan runs
+    #[arg(
+        long,
+        conflicts_with_all = ["json", "quiet", "summary"],
+        help = "Stop after parsing to correct files with no author or a broken title in the TUI (optionally saved as overrides)"
+    )]
+    pub review: bool,
+
+    /// Map full-width and typographic punctuation to ASCII before parsing
+    #[arg(
+        long,
+        help = "Map full-width brackets, curly quotes, dashes and ideographic spaces to ASCII before parsing"
+    )]
+    pub ascii_punctuation: bool,
+
+    /// Put year and series in front of Arabic/Hebrew titles
+    #[arg(
+        long,
+        help = "For Arabic/Hebrew titles, put series, year and edition before the title instead of after it"
+    )]
+    pub rtl_metadata_first: bool,
+
+    /// List skipped files with the reason
+    #[arg(
+        long,
+        help = "List files that are neither renamed nor deleted, with the reason (JSON: `skipped` array)"
+    )]
+    pub show_skipped: bool,
+
+    /// Look inside zip/rar downloads for ebooks
+    #[arg(
+        long,
+        value_name = "MODE",
+        help = "List the ebooks inside .zip/.rar archives (`report`) or also extract the ones not already in the library next to the archive (`extract`); .rar needs unrar"
+    )]
+    pub archives: Option<ArchiveMode>,
+
+    /// Space map of the largest files, duplicate groups and folders
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Show the N largest files, duplicate groups (by reclaimable bytes) and folders with the summary (JSON: `space_map`)"
+    )]
+    pub top: Option<usize>,
+
+    /// Per-extension size below which a file is a broken download
+    #[arg(
+        long,
+        value_name = "EXT=SIZE,...",
+        help = "Size below which a file counts as too small, per extension, e.g. `pdf=10KB,epub=5KB,txt=0` (default pdf=1KB,epub=1KB); also keeps truncated PDFs in download folders"
+    )]
+    pub min_valid_size: Option<MinValidSize>,
+
+    /// Detect store samples and previews
+    #[arg(
+        long,
+        value_enum,
+        value_name = "ACTION",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "quarantine",
+        help = "Detect Kindle/Play samples and previews and move them to .ebook-renamer-samples/ (quarantine, default), delete them, or only list them (report)"
+    )]
+    pub drop_samples: Option<SampleAction>,
+
+    /// Also report PDFs whose first pages match
+    #[arg(
+        long,
+        value_name = "PAGES",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "10",
+        conflicts_with_all = ["skip_cloud_hash", "no_dedupe"],
+        help = "Report PDFs with different bytes but the same page count and the same text (or, for scans, the same page content) on their first PAGES pages (default 10) as probable duplicates; they are never deleted"
+    )]
+    pub fingerprint_pdfs: Option<usize>,
+
+    /// Merge PDFs split into numbered parts
+    #[arg(
+        long,
+        help = "Merge PDF sets split into parts (`Title part1.pdf`, `Title part2.pdf`, or consecutive `pp. 1-120` page ranges) into one normalized PDF and move the parts to .ebook-renamer-parts/; without it such sets are only reported"
+    )]
+    pub merge_parts: bool,
+
+    /// Pair sync-client conflicted copies with their base file
+    #[arg(
+        long,
+        help = "Recognize Dropbox/OneDrive/Syncthing conflicted copies: delete identical ones as duplicates of their base file, list the others as sync conflicts instead of renaming them"
+    )]
+    pub sync_conflicts: bool,
+
+    /// Leave files that are still being written for the next run
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "Wait SECS after scanning and skip files whose size or modification time changed meanwhile, or that another process has open for writing (Linux)"
+    )]
+    pub settle: Option<u64>,
+
+    /// What deleting a file means
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        default_value = "delete",
+        help = "How duplicates, small/failed files and samples are removed: delete them (on Dropbox, Google Drive and OneDrive they go to the provider's deleted files/trash), or archive them into a dated folder under --archive-dir"
+    )]
+    pub delete_mode: DeleteMode,
+
+    /// Where `--delete-mode archive` moves files
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Folder for --delete-mode archive (default: <target>/.ebook-renamer-archive); files go to DIR/YYYY-MM-DD/ with their folder structure. A folder outside the synced folder takes them out of cloud storage"
+    )]
+    pub archive_dir: Option<PathBuf>,
+
+    /// Two-phase runs: rename now, delete after review
+    #[arg(
+        long,
+        conflicts_with_all = ["dry_run", "read_only", "confirm_deletes"],
+        help = "Perform renames but only preview deletions (duplicates, small files, samples, download folder cleanup); they are saved for --confirm-deletes"
+    )]
+    pub dry_run_deletes: bool,
+
+    /// Run the deletions saved by --dry-run-deletes
+    #[arg(
+        long,
+        conflicts_with_all = ["dry_run", "read_only"],
+        help = "Delete what the last --dry-run-deletes run previewed, skipping files that changed or whose kept copy is gone, then exit"
+    )]
+    pub confirm_deletes: bool,
+
+    /// Rate limit for renames and deletions
+    #[arg(
+        long,
+        value_name = "N_OPS/S",
+        value_parser = parse_rate,
+        help = "Perform at most N renames/deletions per second, e.g. `20/s`, so sync clients (Dropbox, OneDrive, ...) keep up"
+    )]
+    pub throttle: Option<f64>,
+
+    /// Pause between batches of operations
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Pause 10 seconds after every N renames/deletions"
+    )]
+    pub batch_size: Option<usize>,
+
+    /// Low CPU and disk priority
+    #[arg(
+        long,
+        help = "Run at low priority so the machine stays responsive: nice +10, idle I/O class on Linux, background priority on macOS; hashing uses one thread and pauses between files"
+    )]
+    pub nice: bool,
+
+    /// Merge `X (1).pdf`, `X - Copy.pdf`, `X 副本.pdf` into `X.pdf`
+    #[arg(
+        long,
+        help = "Pair re-downloaded variants like \"X (1).pdf\", \"X - Copy.pdf\" and \"X 副本.pdf\" with X.pdf: delete identical ones as duplicates, list the others as same-name conflicts instead of renaming them"
+    )]
+    pub merge_variants: bool,
+
+    /// Use author and series folder names for files without an author
+    #[arg(
+        long,
+        help = "Files without an author in their name take it from the nearest folder named after a known author (Serre/), and a missing series from a series folder (GTM/)"
+    )]
+    pub folder_hints: bool,
+
+    /// Language of console and TUI output
+    #[arg(
+        long,
+        value_enum,
+        value_name = "LANG",
+        help = "Language of console and TUI output (default: from LC_ALL/LC_MESSAGES/LANG, English unless zh*)"
+    )]
+    pub lang: Option<Lang>,
+
+    /// When to color console output
+    #[arg(
+        long,
+        value_enum,
+        value_name = "WHEN",
+        default_value = "auto",
+        help = "Color console output: auto (only on a terminal, off with NO_COLOR), always or never"
+    )]
+    pub color: ColorChoice,
+
+    /// Cloud placeholders to download and verify like local files
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Hash and check cloud placeholders (OneDrive Files On-Demand, evicted macOS CloudStorage files) under PATH like local files; reading them downloads them. Repeatable"
+    )]
+    pub hydrate: Vec<PathBuf>,
+
+    /// Hash file contents even inside a detected cloud-synced folder
+    #[arg(
+        long,
+        conflicts_with = "skip_cloud_hash",
+        help = "Hash file contents even when the folder is inside Dropbox, Google Drive or OneDrive (downloads every file; placeholders are still never read)"
+    )]
+    pub force_hash: bool,
+
+    /// Give files the extension of their actual content
+    #[arg(
+        long,
+        help = "Rename files whose content is another format than their extension says (an EPUB named .pdf) to the right extension; HTML error pages are only listed in todo.md"
+    )]
+    pub fix_extensions: bool,
+
+    /// Per-path profiles
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Profiles file with per-root settings (default: ~/.config/ebook-renamer/profiles.json if present)"
+    )]
+    pub profiles: Option<PathBuf>,
+
+    /// Process these files instead of walking the directory
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with = "rename_dirs",
+        help = "Process the files listed in FILE (one path per line, - for stdin, e.g. from fd or find) instead of walking the target directory; paths outside it are skipped"
+    )]
+    pub files_from: Option<PathBuf>,
+
+    /// Keep each renamed file's original name for `restore-names`
+    #[arg(
+        long,
+        value_enum,
+        value_name = "WHERE",
+        help = "Remember each renamed file's original name, in an extended attribute (xattr, Unix only) or in a hidden .orignames.json per folder (sidecar), so `restore-names` can undo the renames without the run history"
+    )]
+    pub record_original: Option<RecordOriginal>,
+
+    /// Leave out what the sync client does not sync
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Exclusion list exported from the sync client (gitignore-style patterns or paths, e.g. the output of `dropbox exclude list`); .stignore and .sync-exclude.lst in the target directory are always read"
+    )]
+    pub sync_exclusions: Option<PathBuf>,
+
+    /// Continue an interrupted scan from its checkpoint
+    #[arg(
+        long,
+        conflicts_with = "files_from",
+        help = "Skip the folders an interrupted scan of the same directory already completed (saved every minute in .ebook-renamer-scan-checkpoint.json, kept for 24 hours)"
+    )]
+    pub resume_scan: bool,
+
+    /// Scan progress as JSON lines on stderr, read by `serve`
+    #[arg(long, hide = true)]
+    pub progress_json: bool,
+
+    /// Write the plan as shell scripts instead of running it
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["rename_dirs", "dry_run_deletes", "confirm_deletes"],
+        help = "Don't change anything; write the renames and deletions as a POSIX script to FILE, a PowerShell variant next to it (.ps1), and undo.sh/undo.ps1 that reverse the renames"
+    )]
+    pub emit_script: Option<PathBuf>,
+
+    /// Author/title/year corrections from an external tool
+    #[arg(long, value_name = "FILE", help = "JSON object mapping file paths (relative to the target directory or absolute) to {\"authors\", \"title\", \"year\"} corrections that take precedence over parsing")]
+    pub merge_metadata: Option<PathBuf>,
+
+    /// Guard limit on deletions
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 500,
+        help = "Refuse to run if the plan would delete more than N files (duplicates, small/failed files, samples) unless --force is given"
+    )]
+    pub max_deletes: usize,
+
+    /// Guard limit on deletions relative to the library
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        default_value_t = 50,
+        value_parser = clap::value_parser!(u8).range(1..=100),
+        help = "Refuse to run if the plan would delete more than PERCENT% of the scanned files (checked from 11 deletions on) unless --force is given"
+    )]
+    pub max_delete_percent: u8,
+
+    /// Guard limit on renames
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Refuse to run if the plan would rename more than N files unless --force is given"
+    )]
+    pub max_renames: Option<usize>,
+
+    /// Skip the guard limits
+    #[arg(long, help = "Run even if the plan exceeds --max-deletes, --max-delete-percent or --max-renames, or deletes files in a rename-only profile")]
+    pub force: bool,
+
+    /// Desktop notification when the run ends
+    #[arg(long, help = "Show a desktop notification with the run summary (renamed, duplicates, todo items, problems) when the run finishes or fails")]
+    pub notify: bool,
+}
+
+/// `--throttle 20`, `20/s` or `20ops/s`.
+fn parse_rate(s: &str) -> Result<f64, String> {
+    let number = s.trim().strip_suffix("/s").unwrap_or(s.trim()).trim_end_matches("ops").trim();
+    match number.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+        _ => Err(format!("expected operations per second like `20/s`, got '{}'", s)),
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ChapterMode {
+    Report,
+    Folder,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ArchiveMode {
+    Report,
+    Extract,
+}
+
+/// How much the human-readable (non-`--json`) mode prints.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputLevel {
+    Quiet,
+    Summary,
+    Normal,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum RedownloadFormat {
+    Aria2,
+    Wget,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ArxivVersions {
+    Newest,
+    KeepAll,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum SampleAction {
+    Quarantine,
+    Delete,
+    Report,
+}
+
+impl SampleAction {
+    /// As written on the command line and in JSON output.
+    pub fn code(self) -> &'static str {
+        match self {
+            SampleAction::Quarantine => "quarantine",
+            SampleAction::Delete => "delete",
+            SampleAction::Report => "report",
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum DeleteMode {
+    Delete,
+    Archive,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum RecordOriginal {
+    Xattr,
+    Sidecar,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum GroupBy {
+    Dir,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Lang {
+    En,
+    Zh,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+/// Options of the `cleanup` subcommand; deleting works as in a run.
+#[derive(clap::Args, Debug)]
+pub struct CleanupArgs {
+    /// Directory to clean up
+    #[arg(value_name = "PATH", default_value = ".")]
+    pub path: PathBuf,
+
+    /// Skip the confirmation prompt (for non-interactive use)
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+
+    /// Only scan the top-level directory, no recursion
+    #[arg(long)]
+    pub no_recursive: bool,
+
+    /// Per-extension size below which a file is a broken download
+    #[arg(long, value_name = "EXT=SIZE,...", help = "Size below which a file counts as too small, as in a run")]
+    pub min_valid_size: Option<MinValidSize>,
+
+    /// Leave out what the sync client does not sync
+    #[arg(long, value_name = "FILE", help = "Exclusion list exported from the sync client, as in a run")]
+    pub sync_exclusions: Option<PathBuf>,
+
+    /// Treat the cloud placeholders under PATH as local files
+    #[arg(long, value_name = "PATH")]
+    pub hydrate: Vec<PathBuf>,
+
+    /// What deleting a file means
+    #[arg(long, value_enum, value_name = "MODE", default_value = "delete")]
+    pub delete_mode: DeleteMode,
+
+    /// Where `--delete-mode archive` moves files
+    #[arg(long, value_name = "DIR")]
+    pub archive_dir: Option<PathBuf>,
+
+    /// Queue behind a concurrent run instead of refusing to start
+    #[arg(long)]
+    pub wait_for_lock: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Show statistics recorded by previous runs and how they trend over time
+    Stats {
+        /// Library directory whose run history should be shown
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Number of most recent runs to show
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        last: usize,
+    },
+
+    /// Report files added, removed, renamed or modified since the last run
+    Changes {
+        /// Library directory to compare with its catalog
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Print the changes as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Save the current state as the new baseline afterwards
+        #[arg(long)]
+        update: bool,
+    },
+
+    /// Delete failed downloads, too-small files and corrupted PDFs after confirmation
+    Cleanup(CleanupArgs),
+
+    /// Infer the naming convention of an already organized library and save it as its config
+    Learn {
+        /// Well-named library directory to analyze
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Only print the inferred template, don't write .ebook-renamer.json
+        #[arg(long)]
+        print: bool,
+
+        /// Only scan the top-level directory, no recursion
+        #[arg(long)]
+        no_recursive: bool,
+
+        /// Also add the authors of already normalized files to .ebook-renamer-known-authors.txt
+        #[arg(long)]
+        authors: bool,
+    },
+
+    /// Validate every file (PDF structure, EPUB zip, DRM, empty files) and report by severity; exits 1 on errors
+    Check {
+        /// Library directory to check
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Report format
+        #[arg(long, value_enum, default_value = "console")]
+        format: ReportFormat,
+
+        /// Write the report to this file instead of stdout
+        #[arg(long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Only scan the top-level directory, no recursion
+        #[arg(long)]
+        no_recursive: bool,
+    },
+
+    /// Print the normalized name for each filename, without touching the filesystem
+    NormalizeName {
+        /// Filenames to normalize (paths are reduced to their names; - reads names from stdin, one per line)
+        #[arg(value_name = "NAME", required = true)]
+        names: Vec<String>,
+
+        /// Print one JSON object per name with the parsed fields
+        #[arg(long)]
+        json: bool,
+
+        /// Naming template, as for a normal run
+        #[arg(long, value_name = "TEMPLATE")]
+        template: Option<String>,
+
+        /// Separator between title and subtitle
+        #[arg(long, value_name = "SEP", default_value = crate::normalizer::DEFAULT_SUBTITLE_SEPARATOR)]
+        subtitle_separator: String,
+
+        /// Map full-width brackets, curly quotes, dashes and ideographic spaces to ASCII before parsing
+        #[arg(long)]
+        ascii_punctuation: bool,
+    },
+
+    /// Manage hand-corrected metadata that always wins over filename parsing
+    Override {
+        #[command(subcommand)]
+        action: OverrideAction,
+    },
+
+    /// Give files renamed with --record-original their original names back
+    RestoreNames {
+        /// Library directory to restore
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Only print what would be renamed
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Only scan the top-level directory, no recursion
+        #[arg(long)]
+        no_recursive: bool,
+    },
+
+    /// Serve scans, plans, approvals and execution over a local socket (line-delimited JSON-RPC 2.0), for GUI frontends and editor plugins
+    Serve {
+        /// Unix socket to listen on
+        #[arg(long, value_name = "PATH", required_unless_present = "port", conflicts_with = "port")]
+        socket: Option<PathBuf>,
+
+        /// Listen on 127.0.0.1:PORT instead
+        #[arg(long, value_name = "PORT")]
+        port: Option<u16>,
+    },
+
+    /// Development: manage the normalizer test corpus (tests/corpus)
+    #[command(hide = true)]
+    Corpus {
+        #[command(subcommand)]
+        action: CorpusAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CorpusAction {
+    /// Append problematic real filenames (paths are reduced to their names)
+    Add {
+        #[arg(value_name = "FILENAME", required = true)]
+        names: Vec<String>,
+
+        /// Corpus file to append to, without `.txt`
+        #[arg(long, value_name = "NAME", default_value = "misc")]
+        to: String,
+
+        /// Correct output name; without it only the snapshot tracks the case
+        #[arg(long, value_name = "NAME")]
+        expected: Option<String>,
+
+        /// Corpus directory (defaults to tests/corpus of the source checkout)
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum OverrideAction {
+    /// Remember author/title/year for this file's content
+    Set {
+        /// File to correct
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Library directory whose runs should use the override
+        #[arg(long, value_name = "PATH", default_value = ".")]
+        library: PathBuf,
+
+        #[arg(long)]
+        authors: Option<String>,
+
+        #[arg(long)]
+        title: Option<String>,
+
+        #[arg(long)]
+        year: Option<u16>,
+    },
+
+    /// Forget the override for this file's content
+    Unset {
+        /// File whose override should be removed
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Library directory holding the override
+        #[arg(long, value_name = "PATH", default_value = ".")]
+        library: PathBuf,
+    },
+
+    /// Show all overrides of a library
+    List {
+        /// Library directory
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+    },
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    Console,
+    Json,
+    Html,
 }
 
 impl Args {
+    /// Depth limits shared by recovery, directory renames and the scan.
+    pub fn traversal(&self) -> Traversal {
+        Traversal::effective(self.max_depth, self.no_recursive)
+    }
+
+    /// `--max-deletes`, `--max-delete-percent` and `--max-renames`.
+    pub fn limits(&self) -> Limits {
+        Limits {
+            max_deletes: self.max_deletes,
+            max_delete_percent: self.max_delete_percent,
+            max_renames: self.max_renames,
+        }
+    }
+
+    pub fn output_level(&self) -> OutputLevel {
+        if self.quiet {
+            OutputLevel::Quiet
+        } else if self.summary {
+            OutputLevel::Summary
+        } else {
+            OutputLevel::Normal
+        }
+    }
+
+    /// Log filter for env_logger; without -v only errors are logged.
+    pub fn log_level(&self) -> log::LevelFilter {
+        match self.verbose {
+            0 => log::LevelFilter::Error,
+            1 => log::LevelFilter::Info,
+            2 => log::LevelFilter::Debug,
+            _ => log::LevelFilter::Trace,
+        }
+    }
+
+    /// Forced coloring for `--color always/never`; `None` leaves it to the
+    /// terminal check and `NO_COLOR`/`CLICOLOR_FORCE`.
+    pub fn color_override(&self) -> Option<bool> {
+        match self.color {
+            ColorChoice::Auto => None,
+            ColorChoice::Always => Some(true),
+            ColorChoice::Never => Some(false),
+        }
+    }
+
+    pub fn io_timeout_duration(&self) -> Option<std::time::Duration> {
+        self.io_timeout.map(std::time::Duration::from_secs)
+    }
+
     #[allow(dead_code)]
     pub fn get_extensions(&self) -> Vec<String> {
         if let Some(ref exts) = self.extensions {
@@ -149,6 +1028,7 @@ mod tests {
     #[test]
     fn test_default_extensions() {
         let args = Args {
+            command: None,
             path: PathBuf::from("."),
             dry_run: false,
             max_depth: 0,
@@ -159,12 +1039,74 @@ mod tests {
             log_file: None,
             preserve_unicode: false,
             fetch_arxiv: false,
-            verbose: false,
+            fetch_libgen_metadata: false,
+            libgen_url: None,
+            verbose: 0,
+            quiet: false,
+            summary: false,
             delete_small: false,
             clean_failed: false,
             json: false,
             skip_cloud_hash: false,
             cleanup_downloads: false,
+            rename_dirs: false,
+            prune_empty_dirs: false,
+            read_only: false,
+            io_timeout: None,
+            rename_only: false,
+            author_aliases: None,
+            template: None,
+            subtitle_separator: " - ".to_string(),
+            chapter_files: None,
+            ignore_dupe: None,
+            delete_annotated_dupes: false,
+            group_by: None,
+            no_recovery: false,
+            no_integrity_check: false,
+            no_dedupe: false,
+            no_normalize: false,
+            wait_for_lock: false,
+            redownload_list: None,
+            arxiv_versions: None,
+            review: false,
+            ascii_punctuation: false,
+            rtl_metadata_first: false,
+            show_skipped: false,
+            archives: None,
+            top: None,
+            min_valid_size: None,
+            drop_samples: None,
+            sync_conflicts: false,
+            settle: None,
+            dry_run_deletes: false,
+            confirm_deletes: false,
+            throttle: None,
+            batch_size: None,
+            nice: false,
+            merge_variants: false,
+            folder_hints: false,
+            lang: None,
+            color: ColorChoice::Auto,
+            hydrate: Vec::new(),
+            force_hash: false,
+            fix_extensions: false,
+            profiles: None,
+            files_from: None,
+            sync_exclusions: None,
+            record_original: None,
+            delete_mode: DeleteMode::Delete,
+            archive_dir: None,
+            resume_scan: false,
+            progress_json: false,
+            merge_parts: false,
+            fingerprint_pdfs: None,
+            emit_script: None,
+            merge_metadata: None,
+            max_deletes: 500,
+            max_delete_percent: 50,
+            max_renames: None,
+            force: false,
+            notify: false,
         };
 
         let exts = args.get_extensions();
@@ -177,6 +1119,7 @@ mod tests {
     #[test]
     fn test_custom_extensions() {
         let args = Args {
+            command: None,
             path: PathBuf::from("."),
             dry_run: false,
             max_depth: 0,
@@ -187,12 +1130,74 @@ mod tests {
             log_file: None,
             preserve_unicode: false,
             fetch_arxiv: false,
-            verbose: false,
+            fetch_libgen_metadata: false,
+            libgen_url: None,
+            verbose: 0,
+            quiet: false,
+            summary: false,
             delete_small: false,
             clean_failed: false,
             json: false,
             skip_cloud_hash: false,
             cleanup_downloads: false,
+            rename_dirs: false,
+            prune_empty_dirs: false,
+            read_only: false,
+            io_timeout: None,
+            rename_only: false,
+            author_aliases: None,
+            template: None,
+            subtitle_separator: " - ".to_string(),
+            chapter_files: None,
+            ignore_dupe: None,
+            delete_annotated_dupes: false,
+            group_by: None,
+            no_recovery: false,
+            no_integrity_check: false,
+            no_dedupe: false,
+            no_normalize: false,
+            wait_for_lock: false,
+            redownload_list: None,
+            arxiv_versions: None,
+            review: false,
+            ascii_punctuation: false,
+            rtl_metadata_first: false,
+            show_skipped: false,
+            archives: None,
+            top: None,
+            min_valid_size: None,
+            drop_samples: None,
+            sync_conflicts: false,
+            settle: None,
+            dry_run_deletes: false,
+            confirm_deletes: false,
+            throttle: None,
+            batch_size: None,
+            nice: false,
+            merge_variants: false,
+            folder_hints: false,
+            lang: None,
+            color: ColorChoice::Auto,
+            hydrate: Vec::new(),
+            force_hash: false,
+            fix_extensions: false,
+            profiles: None,
+            files_from: None,
+            sync_exclusions: None,
+            record_original: None,
+            delete_mode: DeleteMode::Delete,
+            archive_dir: None,
+            resume_scan: false,
+            progress_json: false,
+            merge_parts: false,
+            fingerprint_pdfs: None,
+            emit_script: None,
+            merge_metadata: None,
+            max_deletes: 500,
+            max_delete_percent: 50,
+            max_renames: None,
+            force: false,
+            notify: false,
         };
 
         let exts = args.get_extensions();
@@ -204,6 +1209,7 @@ mod tests {
     #[test]
     fn test_custom_extensions_with_dots() {
         let args = Args {
+            command: None,
             path: PathBuf::from("."),
             dry_run: false,
             max_depth: 0,
@@ -214,12 +1220,74 @@ mod tests {
             log_file: None,
             preserve_unicode: false,
             fetch_arxiv: false,
-            verbose: false,
+            fetch_libgen_metadata: false,
+            libgen_url: None,
+            verbose: 0,
+            quiet: false,
+            summary: false,
             delete_small: false,
             clean_failed: false,
             json: false,
             skip_cloud_hash: false,
             cleanup_downloads: false,
+            rename_dirs: false,
+            prune_empty_dirs: false,
+            read_only: false,
+            io_timeout: None,
+            rename_only: false,
+            author_aliases: None,
+            template: None,
+            subtitle_separator: " - ".to_string(),
+            chapter_files: None,
+            ignore_dupe: None,
+            delete_annotated_dupes: false,
+            group_by: None,
+            no_recovery: false,
+            no_integrity_check: false,
+            no_dedupe: false,
+            no_normalize: false,
+            wait_for_lock: false,
+            redownload_list: None,
+            arxiv_versions: None,
+            review: false,
+            ascii_punctuation: false,
+            rtl_metadata_first: false,
+            show_skipped: false,
+            archives: None,
+            top: None,
+            min_valid_size: None,
+            drop_samples: None,
+            sync_conflicts: false,
+            settle: None,
+            dry_run_deletes: false,
+            confirm_deletes: false,
+            throttle: None,
+            batch_size: None,
+            nice: false,
+            merge_variants: false,
+            folder_hints: false,
+            lang: None,
+            color: ColorChoice::Auto,
+            hydrate: Vec::new(),
+            force_hash: false,
+            fix_extensions: false,
+            profiles: None,
+            files_from: None,
+            sync_exclusions: None,
+            record_original: None,
+            delete_mode: DeleteMode::Delete,
+            archive_dir: None,
+            resume_scan: false,
+            progress_json: false,
+            merge_parts: false,
+            fingerprint_pdfs: None,
+            emit_script: None,
+            merge_metadata: None,
+            max_deletes: 500,
+            max_delete_percent: 50,
+            max_renames: None,
+            force: false,
+            notify: false,
         };
 
         let exts = args.get_extensions();
@@ -227,4 +1295,68 @@ mod tests {
         assert!(exts.contains(&".mobi".to_string()));
         assert!(exts.contains(&".azw3".to_string()));
     }
+
+    #[test]
+    fn test_verbosity_flags() {
+        let args = Args::try_parse_from(["ebook-renamer", "-vv"]).unwrap();
+        assert_eq!(args.log_level(), log::LevelFilter::Debug);
+        assert_eq!(args.output_level(), OutputLevel::Normal);
+
+        let args = Args::try_parse_from(["ebook-renamer", "--summary", "-v"]).unwrap();
+        assert_eq!(args.log_level(), log::LevelFilter::Info);
+        assert_eq!(args.output_level(), OutputLevel::Summary);
+
+        let args = Args::try_parse_from(["ebook-renamer", "--quiet"]).unwrap();
+        assert_eq!(args.log_level(), log::LevelFilter::Error);
+        assert_eq!(args.output_level(), OutputLevel::Quiet);
+
+        assert!(Args::try_parse_from(["ebook-renamer", "--quiet", "-v"]).is_err());
+        assert!(Args::try_parse_from(["ebook-renamer", "--quiet", "--summary"]).is_err());
+    }
+
+    #[test]
+    fn test_color_choice() {
+        assert_eq!(Args::try_parse_from(["ebook-renamer"]).unwrap().color_override(), None);
+        assert_eq!(Args::try_parse_from(["ebook-renamer", "--color", "never"]).unwrap().color_override(), Some(false));
+        assert_eq!(Args::try_parse_from(["ebook-renamer", "--color=always"]).unwrap().color_override(), Some(true));
+        assert!(Args::try_parse_from(["ebook-renamer", "--color", "sometimes"]).is_err());
+    }
+
+    #[test]
+    fn test_throttle_rate() {
+        let args = Args::try_parse_from(["ebook-renamer", "--throttle", "20ops/s"]).unwrap();
+        assert_eq!(args.throttle, Some(20.0));
+        assert_eq!(parse_rate("0.5/s"), Ok(0.5));
+        assert!(Args::try_parse_from(["ebook-renamer", "--throttle", "0"]).is_err());
+        assert!(Args::try_parse_from(["ebook-renamer", "--throttle", "fast"]).is_err());
+    }
+
+    #[test]
+    fn test_rename_only_rejects_deleting_flags() {
+        assert!(Args::try_parse_from(["ebook-renamer", "--rename-only"]).is_ok());
+        assert!(Args::try_parse_from(["ebook-renamer", "--rename-only", "--delete-small"]).is_err());
+        assert!(Args::try_parse_from(["ebook-renamer", "--rename-only", "--clean-failed"]).is_err());
+        assert!(Args::try_parse_from(["ebook-renamer", "--rename-only", "--prune-empty-dirs"]).is_err());
+        assert!(Args::try_parse_from(["ebook-renamer", "--rename-only", "--drop-samples"]).is_err());
+    }
+
+    #[test]
+    fn test_read_only_rejects_ignore_dupe() {
+        assert!(Args::try_parse_from(["ebook-renamer", "--ignore-dupe", "a.pdf", "b.pdf"]).is_ok());
+        assert!(Args::try_parse_from(["ebook-renamer", "--read-only", "--ignore-dupe", "a.pdf", "b.pdf"]).is_err());
+    }
+
+    #[test]
+    fn test_override_subcommand() {
+        let args = Args::try_parse_from(["ebook-renamer", "override", "set", "scan.pdf", "--authors", "Serge Lang", "--year", "2002"]).unwrap();
+        match args.command {
+            Some(Command::Override { action: OverrideAction::Set { file, library, authors, title, year } }) => {
+                assert_eq!(file, PathBuf::from("scan.pdf"));
+                assert_eq!(library, PathBuf::from("."));
+                assert_eq!((authors.as_deref(), title, year), (Some("Serge Lang"), None, Some(2002)));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert!(Args::try_parse_from(["ebook-renamer", "override", "set", "scan.pdf", "--year", "twenty"]).is_err());
+    }
 }
@@ -1,5 +1,7 @@
+use crate::cli::Args;
+use crate::i18n::t;
 use std::path::Path;
-use log::debug;
+use log::{debug, info};
 
 /// Detects if a path is within a cloud storage directory
 pub fn is_cloud_storage_path(path: &Path) -> Option<CloudProvider> {
@@ -57,18 +59,69 @@ impl CloudProvider {
     }
 }
 
+// Shortcut files sync clients create for native documents: Google
+// Docs/Sheets/Slides/... and Dropbox Paper
+const NATIVE_DOC_EXTENSIONS: &[&str] = &[
+    ".gdoc", ".gsheet", ".gslides", ".gdraw", ".gform", ".gmap", ".gsite", ".gjam", ".gtable", ".gscript",
+    ".paper",
+];
+
+/// Whether a synced file is only a shortcut to a provider-native document.
+/// These have no real content or size, and renaming them renames the
+/// cloud document, so they are left out of the pipeline.
+pub fn is_native_doc(extension: &str) -> bool {
+    NATIVE_DOC_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+}
+
 pub fn cloud_mode_warning(provider: CloudProvider) -> String {
-    format!(
-        "⚠️  Detected {} storage. Using metadata-only mode to avoid downloading files.\n\
-         Duplicate detection based on filename similarity (≥85%) + exact size match.\n\
-         This is less accurate than content-based hashing. Review carefully!",
-        provider.name()
-    )
+    t!("cloud-detected", provider = provider.name())
+}
+
+/// Whether duplicates are found by content hash or by name and size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashMode {
+    Hash,
+    /// `--skip-cloud-hash`
+    MetadataOnly,
+    /// The folder is inside a synced folder and `--force-hash` is not given
+    Detected(CloudProvider),
+}
+
+impl HashMode {
+    /// Startup message, if any.
+    pub fn message(self) -> Option<String> {
+        match self {
+            HashMode::Hash => None,
+            HashMode::MetadataOnly => Some(t!("cloud-enabled")),
+            HashMode::Detected(provider) => Some(cloud_mode_warning(provider)),
+        }
+    }
+}
+
+/// Decide the hash mode once for every frontend and set `skip_cloud_hash`
+/// to match. Hashing a synced folder downloads every file in it.
+pub fn resolve_hash_mode(args: &mut Args) -> HashMode {
+    if args.skip_cloud_hash {
+        return HashMode::MetadataOnly;
+    }
+    match is_cloud_storage_path(&args.path) {
+        Some(provider) if args.force_hash => {
+            info!("{} storage detected, hashing anyway (--force-hash)", provider.name());
+            HashMode::Hash
+        }
+        Some(provider) => {
+            args.skip_cloud_hash = true;
+            info!("Auto-enabled cloud mode for {} storage", provider.name());
+            HashMode::Detected(provider)
+        }
+        None => HashMode::Hash,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clap::Parser;
     use std::path::PathBuf;
 
     #[test]
@@ -95,6 +148,25 @@ mod tests {
         assert_eq!(is_cloud_storage_path(&path), Some(CloudProvider::GoogleDrive));
     }
 
+    #[test]
+    fn test_native_doc_shortcuts() {
+        assert!(is_native_doc(".gdoc"));
+        assert!(is_native_doc(".GSHEET"));
+        assert!(is_native_doc(".paper"));
+        assert!(!is_native_doc(".pdf"));
+    }
+
+    #[test]
+    fn test_resolve_hash_mode() {
+        let mut args = Args::try_parse_from(["ebook-renamer", "/Users/user/Dropbox/Books"]).unwrap();
+        assert_eq!(resolve_hash_mode(&mut args), HashMode::Detected(CloudProvider::Dropbox));
+        assert!(args.skip_cloud_hash);
+
+        let mut args = Args::try_parse_from(["ebook-renamer", "--force-hash", "/Users/user/Dropbox/Books"]).unwrap();
+        assert_eq!(resolve_hash_mode(&mut args), HashMode::Hash);
+        assert!(!args.skip_cloud_hash);
+    }
+
     #[test]
     fn test_not_cloud_storage() {
         let path = PathBuf::from("/Users/user/Documents/Books");
@@ -0,0 +1,231 @@
+use crate::error::Error;
+use crate::scanner::FileInfo;
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// One glob or a list of globs, as written in the config.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Globs {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Globs {
+    fn patterns(&self) -> &[String] {
+        match self {
+            Globs::One(glob) => std::slice::from_ref(glob),
+            Globs::Many(globs) => globs,
+        }
+    }
+}
+
+/// Named sub-libraries from the `collections` config entry, e.g.
+/// `{"textbooks": "Math/**", "papers": ["arXiv/**", "Preprints/**"]}`.
+/// Globs are matched against paths relative to the target directory; a file
+/// belongs to the first collection (in name order) that matches it.
+#[derive(Debug, Clone, Default)]
+pub struct Collections {
+    collections: Vec<(String, Vec<Regex>)>,
+}
+
+impl Collections {
+    pub fn new(config: &BTreeMap<String, Globs>) -> Result<Self> {
+        let mut collections = Vec::new();
+        for (name, globs) in config {
+            let patterns = globs
+                .patterns()
+                .iter()
+                .map(|glob| {
+                    Regex::new(&glob_to_regex(glob))
+                        .map_err(|e| Error::Usage(format!("Invalid glob {:?} in collection {}: {}", glob, name, e)))
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            collections.push((name.clone(), patterns));
+        }
+        Ok(Self { collections })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.collections.is_empty()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.collections.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// The collection of a path relative to the target directory.
+    pub fn of(&self, relative: &Path) -> Option<&str> {
+        let path = relative.to_string_lossy().replace('\\', "/");
+        self.collections
+            .iter()
+            .find(|(_, patterns)| patterns.iter().any(|re| re.is_match(&path)))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// `**` spans folders, `*` and `?` stay within one path component.
+pub fn glob_to_regex(glob: &str) -> String {
+    let mut re = String::from("^");
+    let mut chars = glob.trim_start_matches("./").chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    re.push_str("(?:.*/)?");
+                } else {
+                    re.push_str(".*");
+                }
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    re
+}
+
+/// What one run plans for a collection.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CollectionStats {
+    pub name: String,
+    pub files: usize,
+    pub renamed: usize,
+    pub duplicates: usize,
+    pub small_or_failed: usize,
+    pub todo_items: usize,
+}
+
+/// Counts a run's plan per collection. Files go in before duplicate
+/// detection consumes them, renames, duplicates and todo items after.
+pub struct CollectionTally<'a> {
+    collections: &'a Collections,
+    root: PathBuf,
+    by_name: BTreeMap<String, CollectionStats>,
+}
+
+impl<'a> CollectionTally<'a> {
+    pub fn new(collections: &'a Collections, root: &Path) -> Self {
+        Self { collections, root: root.to_path_buf(), by_name: BTreeMap::new() }
+    }
+
+    fn entry(&mut self, relative: &Path) -> &mut CollectionStats {
+        let name = self.collections.of(relative).unwrap_or_default();
+        self.by_name
+            .entry(name.to_string())
+            .or_insert_with(|| CollectionStats { name: name.to_string(), ..Default::default() })
+    }
+
+    fn entry_for(&mut self, path: &Path) -> &mut CollectionStats {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path).to_path_buf();
+        self.entry(&relative)
+    }
+
+    pub fn add_files(&mut self, files: &[FileInfo]) {
+        for file in files {
+            let stats = self.entry_for(&file.original_path);
+            stats.files += 1;
+            if file.is_failed_download || file.is_too_small {
+                stats.small_or_failed += 1;
+            }
+        }
+    }
+
+    pub fn add_renames(&mut self, clean_files: &[FileInfo]) {
+        for file in clean_files.iter().filter(|f| f.new_name.is_some() && f.new_path != f.original_path) {
+            self.entry_for(&file.original_path).renamed += 1;
+        }
+    }
+
+    /// The copies to delete, not the kept ones.
+    pub fn add_duplicates(&mut self, duplicate_groups: &[Vec<PathBuf>]) {
+        for path in duplicate_groups.iter().flat_map(|group| group.iter().skip(1)) {
+            self.entry_for(path).duplicates += 1;
+        }
+    }
+
+    /// Paths relative to the target directory, like todo entries.
+    pub fn add_todo_items(&mut self, paths: &[&str]) {
+        for path in paths {
+            self.entry(Path::new(path)).todo_items += 1;
+        }
+    }
+
+    /// In collection order, followed by the files outside every collection
+    /// (named `""`) when there are any.
+    pub fn finish(mut self) -> Vec<CollectionStats> {
+        let mut ordered: Vec<CollectionStats> =
+            self.collections.names().filter_map(|name| self.by_name.remove(name)).collect();
+        ordered.extend(self.by_name.remove(""));
+        ordered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collections() -> Collections {
+        let config: BTreeMap<String, Globs> = serde_json::from_str(
+            r#"{"textbooks": "Math/**", "papers": ["arXiv/*.pdf", "Preprints/**/*.pdf"], "notes": "**/Notes?/**"}"#,
+        )
+        .unwrap();
+        Collections::new(&config).unwrap()
+    }
+
+    #[test]
+    fn test_glob_matching() {
+        let collections = collections();
+        assert_eq!(collections.of(Path::new("Math/Algebra/Lang - Algebra.pdf")), Some("textbooks"));
+        assert_eq!(collections.of(Path::new("arXiv/2301.12345.pdf")), Some("papers"));
+        assert_eq!(collections.of(Path::new("arXiv/old/2301.12345.pdf")), None);
+        assert_eq!(collections.of(Path::new("Preprints/2023/a.pdf")), Some("papers"));
+        assert_eq!(collections.of(Path::new("Preprints/a.pdf")), Some("papers"));
+        assert_eq!(collections.of(Path::new("Physics/Notes1/qm.pdf")), Some("notes"));
+        // Name order decides between overlapping collections
+        assert_eq!(collections.of(Path::new("Math/Notes2/x.pdf")), Some("notes"));
+        assert_eq!(collections.of(Path::new("Mathematics/x.pdf")), None);
+    }
+
+    #[test]
+    fn test_stats_per_collection() {
+        let root = Path::new("/lib");
+        let file = |path: &str, new_name: Option<&str>| FileInfo {
+            original_path: root.join(path),
+            original_name: path.rsplit('/').next().unwrap().to_string(),
+            extension: ".pdf".to_string(),
+            size: 2048,
+            modified_time: std::time::SystemTime::UNIX_EPOCH,
+            is_failed_download: false,
+            is_too_small: false,
+            is_virtual: false,
+            new_name: new_name.map(str::to_string),
+            new_path: root.join(path).with_file_name(new_name.unwrap_or(path.rsplit('/').next().unwrap())),
+            metadata: None,
+        };
+        let files = vec![
+            file("Math/lang.pdf", Some("Lang - Algebra.pdf")),
+            file("Math/Lang - Algebra.pdf", None),
+            file("arXiv/2301.12345.pdf", None),
+            file("misc.pdf", None),
+        ];
+        let duplicates = vec![vec![root.join("Math/Lang - Algebra.pdf"), root.join("misc.pdf")]];
+
+        let collections = collections();
+        let mut tally = CollectionTally::new(&collections, root);
+        tally.add_files(&files);
+        tally.add_renames(&files);
+        tally.add_duplicates(&duplicates);
+        tally.add_todo_items(&["arXiv/2301.12345.pdf"]);
+        let stats = tally.finish();
+        let summary: Vec<(&str, usize, usize, usize, usize)> =
+            stats.iter().map(|s| (s.name.as_str(), s.files, s.renamed, s.duplicates, s.todo_items)).collect();
+        assert_eq!(summary, vec![("papers", 1, 0, 0, 1), ("textbooks", 2, 1, 0, 0), ("", 1, 0, 1, 0)]);
+    }
+}
@@ -0,0 +1,71 @@
+use crate::collections::Globs;
+use crate::error::Error;
+use anyhow::Result;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_FILE_NAME: &str = ".ebook-renamer.json";
+
+/// Per-library naming settings, written by `learn` and picked up from the
+/// target directory. Command-line flags take precedence.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitle_separator: Option<String>,
+    /// Collection name to globs relative to the target directory
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub collections: BTreeMap<String, Globs>,
+}
+
+pub fn config_path(target_dir: &Path) -> PathBuf {
+    target_dir.join(CONFIG_FILE_NAME)
+}
+
+impl Config {
+    /// `<target-dir>/.ebook-renamer.json`, or the default config if there is none.
+    pub fn load(target_dir: &Path) -> Result<Self> {
+        let path = config_path(target_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        let config = serde_json::from_str(&content).map_err(|e| Error::InvalidFile { path: path.clone(), message: format!("invalid config: {}", e) })?;
+        debug!("Loaded config from {:?}", path);
+        Ok(config)
+    }
+
+    pub fn save(&self, target_dir: &Path) -> Result<PathBuf> {
+        let path = config_path(target_dir);
+        fs::write(&path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_and_load_roundtrip() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        assert_eq!(Config::load(tmp_dir.path())?, Config::default());
+
+        let config = Config {
+            template: Some("{title} - {authors}".to_string()),
+            subtitle_separator: Some(". ".to_string()),
+            collections: BTreeMap::from([("papers".to_string(), Globs::One("arXiv/**".to_string()))]),
+        };
+        config.save(tmp_dir.path())?;
+        assert_eq!(Config::load(tmp_dir.path())?, config);
+
+        fs::write(config_path(tmp_dir.path()), "not json")?;
+        assert!(Config::load(tmp_dir.path()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use crate::error::Error;
+use crate::normalizer::{self, DEFAULT_SUBTITLE_SEPARATOR};
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `tests/corpus` of the source checkout this binary was built from.
+pub fn default_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("corpus")
+}
+
+/// One corpus line: `input => expected`, or just `input` when the output is
+/// only tracked by the snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorpusCase {
+    pub input: String,
+    pub expected: Option<String>,
+}
+
+/// Blank lines and lines starting with `#` are ignored.
+pub fn parse_corpus(content: &str) -> Vec<CorpusCase> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.split_once(" => ") {
+            Some((input, expected)) => CorpusCase { input: input.to_string(), expected: Some(expected.to_string()) },
+            None => CorpusCase { input: line.to_string(), expected: None },
+        })
+        .collect()
+}
+
+/// The extension the scanner would see, including `.tar.gz`.
+pub fn extension_of(name: &str) -> &str {
+    if name.ends_with(".tar.gz") {
+        return ".tar.gz";
+    }
+    name.rfind('.').map(|i| &name[i..]).unwrap_or("")
+}
+
+/// The name the default naming scheme gives `input`.
+pub fn normalize(input: &str) -> Result<String> {
+    let extension = extension_of(input);
+    let metadata = normalizer::parse_filename(input, extension)?;
+    Ok(normalizer::generate_new_filename(&metadata, extension, DEFAULT_SUBTITLE_SEPARATOR))
+}
+
+/// Append filenames to `<dir>/<file>.txt`, skipping ones already in it.
+/// Returns how many were added.
+pub fn add(dir: &Path, file: &str, names: &[String], expected: Option<&str>) -> Result<usize> {
+    if expected.is_some() && names.len() != 1 {
+        return Err(Error::Usage("--expected needs exactly one filename".to_string()).into());
+    }
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.txt", file));
+    let mut content = if path.exists() { fs::read_to_string(&path)? } else { String::new() };
+    let existing: Vec<String> = parse_corpus(&content).into_iter().map(|c| c.input).collect();
+
+    let mut added = 0;
+    for name in names {
+        // Paths are accepted so `corpus add ~/Downloads/*.pdf` works
+        let name = Path::new(name).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| name.clone());
+        if name.contains(" => ") || existing.contains(&name) {
+            continue;
+        }
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        match expected {
+            Some(expected) => content.push_str(&format!("{} => {}\n", name, expected)),
+            None => content.push_str(&format!("{}\n", name)),
+        }
+        added += 1;
+    }
+    fs::write(&path, content)?;
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Every parsed field and the resulting name, for snapshot review.
+    fn render(input: &str) -> Result<(String, String)> {
+        let extension = extension_of(input);
+        let m = normalizer::parse_filename(input, extension)?;
+        let fields = [
+            ("authors", m.authors.clone()),
+            ("title", Some(m.title.clone())),
+            ("subtitle", m.subtitle.clone()),
+            ("year", m.year.map(|y| y.to_string())),
+            ("series", m.series.clone()),
+            ("edition", m.edition.clone()),
+            ("volume", m.volume.clone()),
+            ("publisher", m.publisher.clone()),
+            ("kind", m.kind.clone()),
+            ("order", m.ambiguous_order.then(|| "ambiguous".to_string())),
+        ];
+        let parsed: Vec<String> = fields
+            .iter()
+            .filter_map(|(name, value)| value.as_ref().map(|v| format!("{}: {}", name, v)))
+            .collect();
+        let name = normalizer::generate_new_filename(&m, extension, DEFAULT_SUBTITLE_SEPARATOR);
+        Ok((format!("{}\n  {}\n  => {}\n", input, parsed.join(" | "), name), name))
+    }
+
+    /// Every `tests/corpus/*.txt`: expected names must match, and the full
+    /// parse is compared against `tests/corpus/snapshots/<file>.snap`.
+    #[test]
+    fn test_corpus() -> Result<()> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(default_dir())?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "txt"))
+            .collect();
+        paths.sort();
+        assert!(!paths.is_empty());
+
+        let mut settings = insta::Settings::clone_current();
+        settings.set_snapshot_path(default_dir().join("snapshots"));
+        settings.set_prepend_module_to_snapshot(false);
+        for path in paths {
+            let cases = parse_corpus(&fs::read_to_string(&path)?);
+            let mut snapshot = String::new();
+            let mut failures = Vec::new();
+            for case in &cases {
+                let (block, got) = render(&case.input)?;
+                snapshot.push_str(&block);
+                if let Some(expected) = &case.expected
+                    && &got != expected {
+                    failures.push(format!("{}\n  expected {}\n  got      {}", case.input, expected, got));
+                }
+            }
+            assert!(failures.is_empty(), "{:?}: {} of {} cases failed:\n{}", path, failures.len(), cases.len(), failures.join("\n"));
+
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+            settings.bind(|| insta::assert_snapshot!(name, snapshot));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_add() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let names = vec!["/downloads/Odd Name (1).pdf".to_string(), "Other.pdf".to_string()];
+        assert_eq!(add(tmp_dir.path(), "misc", &names, None)?, 2);
+        assert_eq!(add(tmp_dir.path(), "misc", &names[..1], None)?, 0);
+        assert_eq!(add(tmp_dir.path(), "misc", &["New.pdf".to_string()], Some("New.pdf"))?, 1);
+        assert!(add(tmp_dir.path(), "misc", &names, Some("x.pdf")).is_err());
+
+        let cases = parse_corpus(&fs::read_to_string(tmp_dir.path().join("misc.txt"))?);
+        assert_eq!(cases[0], CorpusCase { input: "Odd Name (1).pdf".to_string(), expected: None });
+        assert_eq!(cases[2].expected.as_deref(), Some("New.pdf"));
+        Ok(())
+    }
+}
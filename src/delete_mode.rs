@@ -0,0 +1,165 @@
+use crate::cli::DeleteMode;
+use crate::cloud::{self, CloudProvider};
+use crate::i18n::t;
+use anyhow::Result;
+use chrono::Local;
+use log::info;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Default `--archive-dir`, hidden so later scans skip it.
+pub const ARCHIVE_DIR_NAME: &str = ".ebook-renamer-archive";
+
+/// How a run gets rid of duplicates, small files and samples: removed from
+/// disk, or with `--delete-mode archive` moved under a folder named after
+/// the day, keeping their folder structure.
+pub struct Deleter {
+    root: PathBuf,
+    /// `<archive-dir>/<YYYY-MM-DD>`
+    archive: Option<PathBuf>,
+}
+
+impl Deleter {
+    pub fn new(mode: DeleteMode, root: &Path, archive_dir: Option<&Path>) -> Result<Self> {
+        let root = root.canonicalize()?;
+        let archive = match mode {
+            DeleteMode::Delete => None,
+            DeleteMode::Archive => {
+                Some(archive_root(&root, archive_dir).join(Local::now().format("%Y-%m-%d").to_string()))
+            }
+        };
+        Ok(Self { root, archive })
+    }
+
+    /// Delete or archive one file. Archived files never replace another:
+    /// a taken name gets a ` (2)`, ` (3)`... suffix.
+    pub fn remove(&self, path: &Path) -> io::Result<()> {
+        let Some(archive) = &self.archive else {
+            return fs::remove_file(path);
+        };
+        let relative = path.strip_prefix(&self.root).unwrap_or_else(|_| Path::new(path.file_name().unwrap_or_default()));
+        let target = free_name(&archive.join(relative));
+        let moved = target.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| match fs::rename(path, &target) {
+            // An archive on another disk
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                fs::copy(path, &target)?;
+                fs::remove_file(path)
+            }
+            result => result,
+        });
+        // The bare error would not say that the file was being archived, or where
+        moved.map_err(|e| io::Error::new(e.kind(), format!("not archived to {}: {}", target.display(), e)))?;
+        info!("Archived {:?} -> {:?}", path, target);
+        Ok(())
+    }
+}
+
+fn archive_root(root: &Path, archive_dir: Option<&Path>) -> PathBuf {
+    match archive_dir {
+        Some(dir) => std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf()),
+        None => root.join(ARCHIVE_DIR_NAME),
+    }
+}
+
+fn free_name(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded range")
+}
+
+/// What deleting actually does in this library, shown with the plan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeleteEffect {
+    /// `delete` or `archive`
+    pub mode: &'static str,
+    /// `permanent`, `provider_trash` or `archive`
+    pub effect: &'static str,
+    /// Sync client of the target directory
+    pub provider: Option<&'static str>,
+    pub archive_dir: Option<PathBuf>,
+    /// Whether archived files still take up space with the provider
+    pub synced: bool,
+}
+
+impl DeleteEffect {
+    pub fn new(mode: DeleteMode, root: &Path, archive_dir: Option<&Path>) -> Self {
+        let provider = cloud::is_cloud_storage_path(root);
+        match mode {
+            DeleteMode::Delete => Self {
+                mode: "delete",
+                effect: if provider.is_some() { "provider_trash" } else { "permanent" },
+                provider: provider.map(|p| p.name()),
+                archive_dir: None,
+                synced: false,
+            },
+            DeleteMode::Archive => {
+                let dir = archive_root(root, archive_dir);
+                let synced = cloud::is_cloud_storage_path(&dir).is_some();
+                Self { mode: "archive", effect: "archive", provider: provider.map(|p| p.name()), archive_dir: Some(dir), synced }
+            }
+        }
+    }
+
+    /// One sentence for the console and the TUI.
+    pub fn describe(&self) -> String {
+        let provider = self.provider.unwrap_or_default();
+        match (&self.archive_dir, provider) {
+            (Some(dir), _) if self.synced => t!("delete-effect-archive-synced", dir = dir.display().to_string()),
+            (Some(dir), "") => t!("delete-effect-archive", dir = dir.display().to_string()),
+            (Some(dir), provider) => t!("delete-effect-archive-unsynced", dir = dir.display().to_string(), provider = provider),
+            (None, "") => t!("delete-effect-permanent"),
+            (None, p) if p == CloudProvider::Dropbox.name() => t!("delete-effect-dropbox"),
+            (None, p) if p == CloudProvider::GoogleDrive.name() => t!("delete-effect-google-drive"),
+            (None, _) => t!("delete-effect-onedrive"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_archive_keeps_structure_and_never_replaces() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        fs::create_dir(root.join("Math"))?;
+        fs::write(root.join("Math/dupe.pdf"), "first")?;
+
+        let deleter = Deleter::new(DeleteMode::Archive, &root, None)?;
+        deleter.remove(&root.join("Math/dupe.pdf"))?;
+        fs::write(root.join("Math/dupe.pdf"), "second")?;
+        deleter.remove(&root.join("Math/dupe.pdf"))?;
+
+        let day = root.join(ARCHIVE_DIR_NAME).join(Local::now().format("%Y-%m-%d").to_string());
+        assert_eq!(fs::read_to_string(day.join("Math/dupe.pdf"))?, "first");
+        assert_eq!(fs::read_to_string(day.join("Math/dupe (2).pdf"))?, "second");
+        assert!(!root.join("Math/dupe.pdf").exists());
+
+        fs::write(root.join("gone.pdf"), "x")?;
+        Deleter::new(DeleteMode::Delete, &root, None)?.remove(&root.join("gone.pdf"))?;
+        assert!(!root.join("gone.pdf").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_effect() {
+        let dropbox = Path::new("/home/me/Dropbox/Books");
+        let effect = DeleteEffect::new(DeleteMode::Delete, dropbox, None);
+        assert_eq!((effect.effect, effect.provider), ("provider_trash", Some("Dropbox")));
+        assert_eq!(DeleteEffect::new(DeleteMode::Delete, Path::new("/home/me/Books"), None).effect, "permanent");
+
+        let inside = DeleteEffect::new(DeleteMode::Archive, dropbox, None);
+        assert!(inside.synced);
+        let outside = DeleteEffect::new(DeleteMode::Archive, dropbox, Some(Path::new("/home/me/book-archive")));
+        assert!(!outside.synced);
+    }
+}
@@ -0,0 +1,83 @@
+use crate::scanner::FileInfo;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Planned operations for one folder, for `--group-by dir`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DirPlan {
+    pub dir: PathBuf,
+    /// Original name, new name
+    pub renames: Vec<(String, String)>,
+    /// Duplicate copies that will be removed (the kept copy counts for its own folder)
+    pub duplicates: Vec<PathBuf>,
+    /// Failed downloads and small/corrupted files to delete
+    pub deletions: Vec<PathBuf>,
+}
+
+/// Group the plan by the folder each file lives in, sorted by folder.
+pub fn group_by_dir(clean_files: &[FileInfo], duplicate_groups: &[Vec<PathBuf>], files_to_delete: &[PathBuf]) -> Vec<DirPlan> {
+    let mut plans: BTreeMap<PathBuf, DirPlan> = BTreeMap::new();
+
+    for file_info in clean_files {
+        if let Some(new_name) = &file_info.new_name
+            && file_info.new_path != file_info.original_path
+        {
+            plan_for(&mut plans, &file_info.original_path).renames.push((file_info.original_name.clone(), new_name.clone()));
+        }
+    }
+    for path in duplicate_groups.iter().flat_map(|group| group.iter().skip(1)) {
+        plan_for(&mut plans, path).duplicates.push(path.clone());
+    }
+    for path in files_to_delete {
+        plan_for(&mut plans, path).deletions.push(path.clone());
+    }
+
+    plans.into_values().collect()
+}
+
+fn plan_for<'a>(plans: &'a mut BTreeMap<PathBuf, DirPlan>, path: &Path) -> &'a mut DirPlan {
+    let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+    plans.entry(dir.clone()).or_insert_with(|| DirPlan { dir, ..Default::default() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_group_by_dir() {
+        let root = PathBuf::from("/library");
+        let file = |dir: &str, name: &str, new_name: &str| FileInfo {
+            original_path: root.join(dir).join(name),
+            original_name: name.to_string(),
+            extension: ".pdf".to_string(),
+            size: 2048,
+            modified_time: SystemTime::now(),
+            is_failed_download: false,
+            is_too_small: false,
+            is_virtual: false,
+            new_name: Some(new_name.to_string()),
+            new_path: root.join(dir).join(new_name),
+            metadata: None,
+        };
+        let clean_files = vec![
+            file("Topology", "munkres.pdf", "Munkres - Topology.pdf"),
+            file("Algebra", "lang.pdf", "Lang - Algebra.pdf"),
+            file("Algebra", "Already Named.pdf", "Already Named.pdf"),
+        ];
+        let duplicates = vec![vec![root.join("Algebra/lang.pdf"), root.join("Topology/lang copy.pdf")]];
+        let deletions = vec![root.join("Algebra/broken.pdf")];
+
+        let plans = group_by_dir(&clean_files, &duplicates, &deletions);
+
+        assert_eq!(plans.len(), 2);
+        assert_eq!(plans[0].dir, root.join("Algebra"));
+        assert_eq!(plans[0].renames, vec![("lang.pdf".to_string(), "Lang - Algebra.pdf".to_string())]);
+        assert!(plans[0].duplicates.is_empty());
+        assert_eq!(plans[0].deletions.len(), 1);
+        assert_eq!(plans[1].dir, root.join("Topology"));
+        assert_eq!(plans[1].renames.len(), 1);
+        assert_eq!(plans[1].duplicates, vec![root.join("Topology/lang copy.pdf")]);
+    }
+}
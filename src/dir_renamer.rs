@@ -0,0 +1,169 @@
+use crate::error::Error;
+use crate::normalizer::normalize_dir_name;
+use crate::sync_ignore::SyncIgnore;
+use crate::traversal::{self, Traversal};
+use anyhow::Result;
+use log::{debug, info};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone)]
+pub struct DirRename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+pub struct DirRenamer {
+    root_path: PathBuf,
+    traversal: Traversal,
+    sync_ignore: SyncIgnore,
+}
+
+impl DirRenamer {
+    pub fn new(path: &Path, max_depth: usize) -> Result<Self> {
+        let root_path = path.canonicalize()?;
+        if !root_path.is_dir() {
+            return Err(Error::NotADirectory(path.to_path_buf()).into());
+        }
+        Ok(DirRenamer {
+            root_path,
+            traversal: Traversal::new(max_depth),
+            sync_ignore: SyncIgnore::default(),
+        })
+    }
+
+    /// Folders the sync client leaves out are neither renamed nor walked.
+    pub fn with_sync_ignore(mut self, sync_ignore: SyncIgnore) -> Self {
+        self.sync_ignore = sync_ignore;
+        self
+    }
+
+    /// Build the rename plan bottom-up: children always come before their
+    /// parents, so executing the plan in order never invalidates a later path.
+    pub fn plan(&self) -> Result<Vec<DirRename>> {
+        let mut renames = Vec::new();
+
+        let walker = self.traversal.walker(&self.root_path)
+            .min_depth(1)
+            .contents_first(true)
+            .into_iter()
+            .filter_entry(|e| {
+                !e.file_type().is_dir()
+                    || !(traversal::is_excluded_dir(e.path())
+                        || e.path().strip_prefix(&self.root_path).is_ok_and(|p| self.sync_ignore.is_excluded(p, true)))
+            });
+
+        for entry in walker.filter_map(|e| e.ok()) {
+            if !entry.file_type().is_dir() {
+                continue;
+            }
+
+            let path = entry.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+
+            let new_name = normalize_dir_name(name);
+            if new_name.is_empty() || new_name == name {
+                continue;
+            }
+
+            let to = path.with_file_name(&new_name);
+            debug!("Directory rename planned: {} -> {}", name, new_name);
+            renames.push(DirRename {
+                from: path.to_path_buf(),
+                to,
+            });
+        }
+
+        Ok(renames)
+    }
+
+    /// Execute a plan produced by `plan()`. Targets that already exist are
+    /// left untouched and reported as errors instead of being merged.
+    pub fn execute(&self, renames: &[DirRename]) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        for rename in renames {
+            if rename.to.exists() {
+                errors.push(format!(
+                    "Skipped directory rename {:?} -> {:?}: target already exists",
+                    rename.from, rename.to
+                ));
+                continue;
+            }
+            match fs::rename(&rename.from, &rename.to) {
+                Ok(_) => info!("Renamed directory: {:?} -> {:?}", rename.from, rename.to),
+                Err(e) => errors.push(format!("Failed to rename {:?}: {}", rename.from, e)),
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_plan_is_bottom_up() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let parent = tmp_dir.path().join("Algebra (libgen)");
+        let child = parent.join("Notes [z-lib]");
+        fs::create_dir_all(&child)?;
+
+        let renamer = DirRenamer::new(tmp_dir.path(), usize::MAX)?;
+        let plan = renamer.plan()?;
+
+        assert_eq!(plan.len(), 2);
+        assert!(plan[0].from.ends_with("Notes [z-lib]"));
+        assert!(plan[1].from.ends_with("Algebra (libgen)"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_renames_nested_dirs() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        fs::create_dir_all(tmp_dir.path().join("Algebra (libgen)").join("Notes [z-lib]"))?;
+
+        let renamer = DirRenamer::new(tmp_dir.path(), usize::MAX)?;
+        let plan = renamer.plan()?;
+        let errors = renamer.execute(&plan);
+
+        assert!(errors.is_empty());
+        assert!(tmp_dir.path().join("Algebra").join("Notes").is_dir());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_skips_hidden_and_download_dirs() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        fs::create_dir_all(tmp_dir.path().join(".cache (libgen)"))?;
+        fs::create_dir_all(tmp_dir.path().join("book (libgen).pdf.download"))?;
+
+        let renamer = DirRenamer::new(tmp_dir.path(), usize::MAX)?;
+        assert!(renamer.plan()?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_skips_existing_target() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        fs::create_dir_all(tmp_dir.path().join("Topology (libgen)"))?;
+        fs::create_dir_all(tmp_dir.path().join("Topology"))?;
+
+        let renamer = DirRenamer::new(tmp_dir.path(), usize::MAX)?;
+        let plan = renamer.plan()?;
+        let errors = renamer.execute(&plan);
+
+        assert_eq!(errors.len(), 1);
+        assert!(tmp_dir.path().join("Topology (libgen)").is_dir());
+
+        Ok(())
+    }
+}
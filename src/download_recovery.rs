@@ -1,3 +1,6 @@
+use crate::error::Error;
+use crate::scanner::MinValidSize;
+use crate::traversal::Traversal;
 use anyhow::Result;
 use log::{debug, info};
 use std::fs;
@@ -6,6 +9,9 @@ use std::path::{Path, PathBuf};
 pub struct DownloadRecovery {
     target_dir: PathBuf,
     auto_cleanup: bool,
+    traversal: Traversal,
+    /// Only set with `--min-valid-size`; otherwise every PDF is recovered
+    min_valid_size: Option<MinValidSize>,
 }
 
 #[derive(Debug)]
@@ -20,9 +26,24 @@ impl DownloadRecovery {
         Self {
             target_dir: target_dir.to_path_buf(),
             auto_cleanup,
+            traversal: Traversal::new(1),
+            min_valid_size: None,
         }
     }
 
+    /// Also look for download folders in subdirectories, down to the scan depth.
+    /// Recovered PDFs are placed next to the download folder they came from.
+    pub fn with_traversal(mut self, traversal: Traversal) -> Self {
+        self.traversal = traversal;
+        self
+    }
+
+    /// PDFs below the threshold are truncated and stay in the download folder.
+    pub fn with_min_valid_size(mut self, min_valid_size: Option<MinValidSize>) -> Self {
+        self.min_valid_size = min_valid_size;
+        self
+    }
+
     pub fn recover_downloads(&self) -> Result<RecoveryResult> {
         let mut result = RecoveryResult {
             extracted_files: Vec::new(),
@@ -32,24 +53,19 @@ impl DownloadRecovery {
 
         info!("Scanning for download folders in {:?}", self.target_dir);
         
-        // Find all .download and .crdownload directories
-        for entry in fs::read_dir(&self.target_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            
-            if path.is_dir() {
-                if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                    if filename.ends_with(".download") || filename.ends_with(".crdownload") {
-                        debug!("Processing download folder: {:?}", path);
-                        match self.process_download_folder(&path, &mut result) {
-                            Ok(_) => info!("Successfully processed: {:?}", filename),
-                            Err(e) => {
-                                let error_msg = format!("Failed to process {:?}: {}", path, e);
-                                debug!("{}", error_msg);
-                                result.errors.push(error_msg);
-                            }
-                        }
-                    }
+        if !self.target_dir.is_dir() {
+            return Err(Error::NotADirectory(self.target_dir.clone()).into());
+        }
+
+        // Find all .download and .crdownload directories within the scan depth
+        for path in self.traversal.download_folders(&self.target_dir) {
+            debug!("Processing download folder: {:?}", path);
+            match self.process_download_folder(&path, &mut result) {
+                Ok(_) => info!("Successfully processed: {:?}", path.file_name().unwrap_or_default()),
+                Err(e) => {
+                    let error_msg = format!("Failed to process {:?}: {}", path, e);
+                    debug!("{}", error_msg);
+                    result.errors.push(error_msg);
                 }
             }
         }
@@ -72,12 +88,10 @@ impl DownloadRecovery {
             let entry = entry?;
             let path = entry.path();
             
-            if path.is_file() {
-                if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
-                    if extension.to_lowercase() == "pdf" {
-                        pdf_files.push(path);
-                    }
-                }
+            if path.is_file()
+                && let Some(extension) = path.extension().and_then(|e| e.to_str())
+                && extension.to_lowercase() == "pdf" {
+                pdf_files.push(path);
             }
         }
 
@@ -88,8 +102,13 @@ impl DownloadRecovery {
 
         // Extract each PDF file
         for pdf_file in pdf_files {
+            let size = fs::metadata(&pdf_file)?.len();
+            if self.min_valid_size.as_ref().is_some_and(|sizes| sizes.is_too_small(".pdf", size)) {
+                result.errors.push(format!("Not recovered, only {} bytes: {:?}", size, pdf_file));
+                continue;
+            }
             let new_name = self.clean_filename(pdf_file.file_name().unwrap().to_str().unwrap());
-            let new_path = self.target_dir.join(&new_name);
+            let new_path = available_path(download_folder.parent().unwrap_or(&self.target_dir), &new_name);
             
             // Move PDF to target directory
             fs::rename(&pdf_file, &new_path)?;
@@ -148,6 +167,25 @@ impl DownloadRecovery {
     }
 }
 
+/// `dir/name`, or `Name (recovered).pdf` (then `(recovered 2)`, ...) when a
+/// completed copy is already there, so duplicate detection sees both files
+/// instead of the payload silently replacing the existing one.
+fn available_path(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+    // clean_filename always leaves a 4-byte .pdf extension
+    let stem = &name[..name.len() - 4];
+    (1..)
+        .map(|n| match n {
+            1 => dir.join(format!("{} (recovered).pdf", stem)),
+            n => dir.join(format!("{} (recovered {}).pdf", stem, n)),
+        })
+        .find(|p| !p.exists())
+        .expect("unbounded range")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -240,6 +278,42 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_recover_downloads_respects_depth() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let subject = tmp_dir.path().join("Algebra");
+        let download_folder = subject.join("lang.pdf.download");
+        fs::create_dir_all(&download_folder)?;
+        fs::write(download_folder.join("Lang - Algebra (Z-Library).pdf"), "dummy pdf content")?;
+
+        // Default (and --no-recursive): only the top-level directory
+        let result = DownloadRecovery::new(tmp_dir.path(), false).recover_downloads()?;
+        assert!(result.extracted_files.is_empty());
+
+        let result = DownloadRecovery::new(tmp_dir.path(), false)
+            .with_traversal(Traversal::new(usize::MAX))
+            .recover_downloads()?;
+        assert_eq!(result.extracted_files, vec![subject.join("Lang - Algebra.pdf")]);
+        assert!(subject.join("Lang - Algebra.pdf").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_downloads_keeps_existing_copy() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        fs::write(tmp_dir.path().join("Test Book.pdf"), "completed")?;
+        let download_folder = tmp_dir.path().join("Test Book.pdf.download");
+        fs::create_dir(&download_folder)?;
+        fs::write(download_folder.join("Test Book.pdf"), "payload")?;
+
+        let result = DownloadRecovery::new(tmp_dir.path(), true).recover_downloads()?;
+        assert_eq!(result.extracted_files, vec![tmp_dir.path().join("Test Book (recovered).pdf")]);
+        assert_eq!(fs::read_to_string(tmp_dir.path().join("Test Book.pdf"))?, "completed");
+        assert_eq!(fs::read_to_string(&result.extracted_files[0])?, "payload");
+        Ok(())
+    }
+
     #[test]
     fn test_recover_downloads_with_crdownload() -> Result<()> {
         let tmp_dir = TempDir::new()?;
@@ -259,4 +333,20 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_recover_downloads_leaves_truncated_pdf() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let download_folder = tmp_dir.path().join("test.pdf.crdownload");
+        fs::create_dir(&download_folder)?;
+        fs::write(download_folder.join("Test Book.pdf"), "truncated")?;
+
+        let recovery = DownloadRecovery::new(tmp_dir.path(), true).with_min_valid_size(Some("pdf=1KB".parse().unwrap()));
+        let result = recovery.recover_downloads()?;
+
+        assert!(result.extracted_files.is_empty());
+        assert_eq!(result.errors.len(), 1);
+        assert!(download_folder.join("Test Book.pdf").exists());
+        Ok(())
+    }
 }
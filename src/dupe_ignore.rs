@@ -0,0 +1,138 @@
+use crate::error::Error;
+use anyhow::Result;
+use log::debug;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const IGNORE_FILE_NAME: &str = ".ebook-renamer-dupe-ignore.txt";
+
+/// One side of an ignored pair: a content hash survives renames, a path is
+/// used when hashing is not possible (cloud placeholders).
+#[derive(Debug, Clone, PartialEq)]
+pub enum IgnoreEntry {
+    Md5(String),
+    Path(PathBuf),
+}
+
+impl IgnoreEntry {
+    fn parse(s: &str, target_dir: &Path) -> Self {
+        match s.strip_prefix("md5:") {
+            Some(hash) => IgnoreEntry::Md5(hash.to_lowercase()),
+            None => IgnoreEntry::Path(target_dir.join(s)),
+        }
+    }
+
+    fn matches(&self, path: &Path, hash: Option<&str>) -> bool {
+        match self {
+            IgnoreEntry::Md5(h) => hash == Some(h.as_str()),
+            IgnoreEntry::Path(p) => p == path,
+        }
+    }
+}
+
+/// File pairs the user wants to keep even though they look like duplicates.
+/// Stored in `<target-dir>/.ebook-renamer-dupe-ignore.txt`, one tab-separated
+/// pair per line (`md5:<hash>` or a path relative to the target directory).
+#[derive(Debug, Default)]
+pub struct DupeIgnoreList {
+    pairs: Vec<(IgnoreEntry, IgnoreEntry)>,
+}
+
+impl DupeIgnoreList {
+    pub fn load(target_dir: &Path) -> Result<Self> {
+        let path = target_dir.join(IGNORE_FILE_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        // Scanned paths are canonical, so resolve relative entries against the canonical root
+        let root = target_dir.canonicalize().unwrap_or_else(|_| target_dir.to_path_buf());
+        let mut pairs = Vec::new();
+        for (line_no, line) in fs::read_to_string(&path)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (a, b) = line
+                .split_once('\t')
+                .ok_or_else(|| Error::InvalidFile {
+                    path: path.clone(),
+                    message: format!("line {}: expected two tab-separated entries", line_no + 1),
+                })?;
+            pairs.push((IgnoreEntry::parse(a.trim(), &root), IgnoreEntry::parse(b.trim(), &root)));
+        }
+        debug!("Loaded {} ignored duplicate pairs from {:?}", pairs.len(), path);
+        Ok(Self { pairs })
+    }
+
+    /// Append a pair to the ignore file.
+    pub fn record(target_dir: &Path, a: &str, b: &str) -> Result<PathBuf> {
+        let path = target_dir.join(IGNORE_FILE_NAME);
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(file, "{}\t{}", a, b)?;
+        Ok(path)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Members of a duplicate group that some ignored pair protects. Both
+    /// sides must match different files of the group, except for a hash
+    /// paired with itself, which protects every copy of that content.
+    pub fn protected(&self, members: &[(&Path, Option<&str>)]) -> Vec<usize> {
+        let mut protected = Vec::new();
+        for (a, b) in &self.pairs {
+            let matching = |entry: &IgnoreEntry| -> Vec<usize> {
+                (0..members.len()).filter(|&i| entry.matches(members[i].0, members[i].1)).collect()
+            };
+            let (side_a, side_b) = (matching(a), matching(b));
+            let mut both: Vec<usize> = side_a.iter().chain(&side_b).copied().collect();
+            both.sort_unstable();
+            both.dedup();
+            if !side_a.is_empty() && !side_b.is_empty() && both.len() >= 2 {
+                protected.extend(both);
+            }
+        }
+        protected.sort_unstable();
+        protected.dedup();
+        protected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_load_and_protect() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path();
+        DupeIgnoreList::record(root, "books/a.pdf", "books/a (annotated).pdf")?;
+        DupeIgnoreList::record(root, "md5:ABC", "md5:abc")?;
+
+        let ignore = DupeIgnoreList::load(root)?;
+        let root = root.canonicalize()?;
+        let root = root.as_path();
+        let a = root.join("books/a.pdf");
+        let annotated = root.join("books/a (annotated).pdf");
+        let other = root.join("other.pdf");
+
+        // Path pair: both annotated and clean copy are kept, the third file is not
+        let members = [(a.as_path(), None), (other.as_path(), None), (annotated.as_path(), None)];
+        assert_eq!(ignore.protected(&members), vec![0, 2]);
+
+        // One side alone protects nothing
+        assert!(ignore.protected(&[(a.as_path(), None), (other.as_path(), None)]).is_empty());
+
+        // Same hash on both sides keeps every copy of that content
+        let members = [(a.as_path(), Some("abc")), (other.as_path(), Some("abc"))];
+        assert_eq!(ignore.protected(&members), vec![0, 1]);
+
+        fs::write(root.join(IGNORE_FILE_NAME), "one entry only\n")?;
+        assert!(DupeIgnoreList::load(root).is_err());
+        Ok(())
+    }
+}
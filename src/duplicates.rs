@@ -1,134 +1,117 @@
+use crate::dupe_ignore::DupeIgnoreList;
+use crate::io_timeout::{self, run_with_timeout};
+use crate::priority;
 use crate::scanner::FileInfo;
+use crate::search_key;
 use anyhow::Result;
-use log::debug;
-use std::collections::HashMap;
+use log::{debug, warn};
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use strsim::jaro_winkler;
 
 // Allowed formats to keep
 const ALLOWED_EXTENSIONS: &[&str] = &[".pdf", ".epub", ".txt"];
 
-pub fn detect_duplicates(files: Vec<FileInfo>, skip_hash: bool) -> Result<(Vec<Vec<PathBuf>>, Vec<FileInfo>)> {
+// Keys of groups matched by name and size rather than content
+const FUZZY_KEY_PREFIX: &str = "fuzzy_group_";
+
+pub fn is_allowed_extension(extension: &str) -> bool {
+    ALLOWED_EXTENSIONS.contains(&extension)
+}
+
+/// Each group lists the kept file first, followed by the files to delete.
+pub type DuplicateGroups = Vec<Vec<PathBuf>>;
+
+/// Returns the duplicate groups, the remaining files, and
+/// the files that were skipped because hashing exceeded `io_timeout`.
+/// Files protected by a pair in `ignore` are never grouped. A file that was
+/// already in the library is kept over a copy in `recovered` (payloads just
+/// moved out of download folders).
+pub fn detect_duplicates(
+    files: Vec<FileInfo>,
+    skip_hash: bool,
+    io_timeout: Option<Duration>,
+    ignore: &DupeIgnoreList,
+    recovered: &HashSet<PathBuf>,
+) -> Result<(DuplicateGroups, Vec<FileInfo>, Vec<PathBuf>)> {
     // Filter to only allowed formats first
     let filtered_files: Vec<FileInfo> = files
         .into_iter()
-        .filter(|f| ALLOWED_EXTENSIONS.contains(&f.extension.as_str()))
+        .filter(|f| is_allowed_extension(&f.extension))
         .collect();
     
     debug!("Filtered to {} files with allowed extensions", filtered_files.len());
     
-    // Build hash map: key -> list of file infos
-    // Key is either MD5 hash or normalized filename depending on skip_hash
-    let mut hash_map: HashMap<String, Vec<FileInfo>> = HashMap::new();
-
-    if skip_hash {
-        debug!("Skipping MD5 hash computation, using fuzzy filename matching + size comparison");
-
-        // Group by size first (exact size match required)
-        let mut size_groups: HashMap<u64, Vec<FileInfo>> = HashMap::new();
-        for file_info in &filtered_files {
-            if !file_info.is_failed_download && !file_info.is_too_small {
-                size_groups
-                    .entry(file_info.size)
-                    .or_insert_with(Vec::new)
-                    .push(file_info.clone());
-            }
+    // Build hash map: key -> indices into filtered_files
+    // Key is either MD5 hash or normalized filename depending on skip_hash.
+    // Indices instead of FileInfo clones keep memory flat on very large libraries.
+    let mut hash_map: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    let mut timed_out: Vec<PathBuf> = Vec::new();
+
+    // Group by size first (exact size match required in both modes); ordered maps
+    // keep hashing, logs and fuzzy group keys the same from run to run
+    let mut size_groups: BTreeMap<u64, Vec<usize>> = BTreeMap::new();
+    for (idx, file_info) in filtered_files.iter().enumerate() {
+        if !file_info.is_failed_download && !file_info.is_too_small {
+            size_groups
+                .entry(file_info.size)
+                .or_default()
+                .push(idx);
         }
+    }
 
-        // Within each size group, use fuzzy matching
-        const SIMILARITY_THRESHOLD: f64 = 0.85;
-        let mut group_id = 0;
-
-        for (_size, files_with_same_size) in size_groups {
-            if files_with_same_size.len() < 2 {
-                // Only one file with this size, cannot be duplicate
-                continue;
-            }
-
-            // Compare all pairs within this size group
-            let mut already_grouped: Vec<usize> = Vec::new();
-
-            for i in 0..files_with_same_size.len() {
-                if already_grouped.contains(&i) {
-                    continue;
-                }
-
-                let mut current_group = vec![i];
-                let name_i = files_with_same_size[i].new_name.clone()
-                    .unwrap_or_else(|| files_with_same_size[i].original_name.clone());
-
-                for j in (i + 1)..files_with_same_size.len() {
-                    if already_grouped.contains(&j) {
-                        continue;
-                    }
-
-                    let name_j = files_with_same_size[j].new_name.clone()
-                        .unwrap_or_else(|| files_with_same_size[j].original_name.clone());
-
-                    let similarity = jaro_winkler(&name_i, &name_j);
-
-                    if similarity >= SIMILARITY_THRESHOLD {
-                        current_group.push(j);
-                        already_grouped.push(j);
-                        debug!("Fuzzy match: '{}' ~ '{}' (similarity: {:.2})", name_i, name_j, similarity);
-                    }
-                }
+    debug!("Grouped {} files into {} size groups", filtered_files.len(), size_groups.len());
 
-                // If we found matches, add to hash_map
-                if current_group.len() > 1 {
-                    let group_key = format!("fuzzy_group_{}", group_id);
-                    group_id += 1;
-
-                    for idx in current_group {
-                        hash_map
-                            .entry(group_key.clone())
-                            .or_insert_with(Vec::new)
-                            .push(files_with_same_size[idx].clone());
-                    }
-                }
+    let mut fuzzy_groups = 0;
+    let mut add_fuzzy_groups = |hash_map: &mut BTreeMap<String, Vec<usize>>, indices: &[usize]| {
+        for group in fuzzy_match(&filtered_files, indices) {
+            hash_map.insert(format!("{}{}", FUZZY_KEY_PREFIX, fuzzy_groups), group);
+            fuzzy_groups += 1;
+        }
+    };
 
-                already_grouped.push(i);
-            }
+    if skip_hash {
+        debug!("Skipping MD5 hash computation, using fuzzy filename matching + size comparison");
+        for indices in size_groups.values() {
+            add_fuzzy_groups(&mut hash_map, indices);
         }
     } else {
-        // Optimization: Group by size first
-        // Only compute MD5 for files that have the same size
-        let mut size_map: HashMap<u64, Vec<&FileInfo>> = HashMap::new();
-        
-        for file_info in &filtered_files {
-            if !file_info.is_failed_download && !file_info.is_too_small {
-                size_map
-                    .entry(file_info.size)
-                    .or_insert_with(Vec::new)
-                    .push(file_info);
+        // Only compute MD5 for files that share a size with another file
+        for (size, indices) in size_groups {
+            // If only one file has this size, it cannot be a duplicate (unless size is 0, but we filter those)
+            if indices.len() == 1 {
+                continue;
             }
-        }
-
-        debug!("Grouped {} files into {} size groups", filtered_files.len(), size_map.len());
 
-        for (size, files) in size_map {
-            // If only one file has this size, it cannot be a duplicate (unless size is 0, but we filter those)
-            if files.len() == 1 {
+            // Hashing a cloud placeholder downloads it; its size group is matched by name instead
+            if indices.iter().any(|&idx| filtered_files[idx].is_virtual) {
+                debug!("Size {} includes cloud placeholders, using fuzzy filename matching", size);
+                add_fuzzy_groups(&mut hash_map, &indices);
                 continue;
             }
 
-            debug!("Size {} has {} potential duplicates, computing hashes...", size, files.len());
-            
-            for file_info in files {
-                match compute_md5(&file_info.original_path) {
-                    Ok(hash) => {
-                        hash_map
-                            .entry(hash)
-                            .or_insert_with(Vec::new)
-                            .push(file_info.clone());
-                    },
-                    Err(e) => {
-                        debug!("Failed to compute hash for {}: {}", file_info.original_path.display(), e);
-                        // Treat as unique if we can't read it? Or just skip? 
-                        // Skipping might be safer to avoid false negatives, or maybe we should warn.
-                        // For now, let's just log and skip adding to duplicate map (so it stays "clean")
-                    }
+            debug!("Size {} has {} potential duplicates, computing hashes...", size, indices.len());
+
+            // Phase 1: hash only the head and tail of large files. Small files
+            // would be read in full anyway, so they go straight to phase 2.
+            let candidates: Vec<Vec<usize>> = if size > 2 * PARTIAL_HASH_BYTES {
+                hash_indices(&filtered_files, indices, io_timeout, &mut timed_out, compute_partial_md5)
+                    .into_values()
+                    .collect()
+            } else {
+                vec![indices]
+            };
+
+            // Phase 2: full hash confirms every partial collision before anything is deleted
+            for candidate in candidates {
+                if candidate.len() < 2 {
+                    continue;
+                }
+                for (hash, group) in hash_indices(&filtered_files, candidate, io_timeout, &mut timed_out, compute_md5) {
+                    hash_map.entry(hash).or_default().extend(group);
                 }
             }
         }
@@ -136,39 +119,173 @@ pub fn detect_duplicates(files: Vec<FileInfo>, skip_hash: bool) -> Result<(Vec<V
 
     // Group duplicates by hash and apply retention strategy
     let mut duplicate_groups: Vec<Vec<PathBuf>> = Vec::new();
-    let mut duplicate_paths = std::collections::HashSet::new();
+    let mut is_duplicate = vec![false; filtered_files.len()];
+
+    for (hash, mut indices) in hash_map {
+        if !ignore.is_empty() {
+            // Fuzzy groups have no content hash, only path entries apply there
+            let hash = (!hash.starts_with(FUZZY_KEY_PREFIX)).then_some(hash.as_str());
+            let members: Vec<(&Path, Option<&str>)> =
+                indices.iter().map(|&idx| (filtered_files[idx].original_path.as_path(), hash)).collect();
+            let protected = ignore.protected(&members);
+            if !protected.is_empty() {
+                debug!("Keeping {} files listed in the duplicate ignore list", protected.len());
+                indices = indices
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(i, _)| !protected.contains(i))
+                    .map(|(_, idx)| idx)
+                    .collect();
+            }
+        }
 
-    for (_hash, file_infos) in hash_map {
-        if file_infos.len() > 1 {
+        if indices.len() > 1 {
             // Multiple files with same hash - apply retention strategy
-            let kept_file = select_file_to_keep(&file_infos);
+            let group: Vec<&FileInfo> = indices.iter().map(|&idx| &filtered_files[idx]).collect();
+            let kept_file = select_file_to_keep(&group, recovered);
             
             let mut group_paths: Vec<PathBuf> = Vec::new();
             group_paths.push(kept_file.original_path.clone());
             
-            for file_info in &file_infos {
+            for &idx in &indices {
+                let file_info = &filtered_files[idx];
                 if file_info.original_path != kept_file.original_path {
-                    duplicate_paths.insert(file_info.original_path.clone());
+                    is_duplicate[idx] = true;
                     group_paths.push(file_info.original_path.clone());
                 }
             }
 
+            debug!("Found duplicate group with {} files, keeping: {}", indices.len(), kept_file.original_name);
             duplicate_groups.push(group_paths);
-            debug!("Found duplicate group with {} files, keeping: {}", file_infos.len(), kept_file.original_name);
         }
     }
 
     // Return only non-duplicate files (including filtered out formats)
     let clean_files: Vec<FileInfo> = filtered_files
         .into_iter()
-        .filter(|f| !duplicate_paths.contains(&f.original_path))
+        .zip(is_duplicate)
+        .filter(|(_, duplicate)| !duplicate)
+        .map(|(f, _)| f)
         .collect();
 
-    Ok((duplicate_groups, clean_files))
+    sort_groups(&mut duplicate_groups);
+    timed_out.sort();
+    Ok((duplicate_groups, clean_files, timed_out))
+}
+
+/// Stable order for plans and their diffs: each group keeps its kept file
+/// first and lists the others by path, groups are ordered by kept file.
+/// Call again after adding groups (sync conflicts, variants, arXiv versions).
+pub fn sort_groups(groups: &mut DuplicateGroups) {
+    for group in groups.iter_mut() {
+        if let Some(rest) = group.get_mut(1..) {
+            rest.sort();
+        }
+    }
+    groups.sort();
+}
+
+/// Groups of same-size files whose names are at least 85% similar.
+fn fuzzy_match(files: &[FileInfo], files_with_same_size: &[usize]) -> Vec<Vec<usize>> {
+    const SIMILARITY_THRESHOLD: f64 = 0.85;
+    let mut groups = Vec::new();
+    if files_with_same_size.len() < 2 {
+        // Only one file with this size, cannot be duplicate
+        return groups;
+    }
+
+    // Compare all pairs within this size group
+    let mut already_grouped: Vec<usize> = Vec::new();
+
+    for (i, &idx_i) in files_with_same_size.iter().enumerate() {
+        if already_grouped.contains(&i) {
+            continue;
+        }
+
+        let mut current_group = vec![idx_i];
+        let name_i = display_name(&files[idx_i]);
+        // Accents, apostrophes and punctuation don't make two names different
+        let key_i = search_key::fold(name_i);
+
+        for (j, &idx_j) in files_with_same_size.iter().enumerate().skip(i + 1) {
+            if already_grouped.contains(&j) {
+                continue;
+            }
+
+            // The English and Chinese editions of a book are not copies of each other
+            if language(&files[idx_i]) != language(&files[idx_j]) {
+                continue;
+            }
+
+            let name_j = display_name(&files[idx_j]);
+            let similarity = jaro_winkler(&key_i, &search_key::fold(name_j));
+
+            if similarity >= SIMILARITY_THRESHOLD {
+                current_group.push(idx_j);
+                already_grouped.push(j);
+                debug!("Fuzzy match: '{}' ~ '{}' (similarity: {:.2})", name_i, name_j, similarity);
+            }
+        }
+
+        if current_group.len() > 1 {
+            groups.push(current_group);
+        }
+
+        already_grouped.push(i);
+    }
+    groups
 }
 
-// Select file to keep based on priority: normalized > shortest path > newest
-fn select_file_to_keep(files: &[FileInfo]) -> &FileInfo {
+/// The files `detect_duplicates` would return as clean if it found nothing,
+/// for runs with `--no-dedupe`.
+pub fn skip_detection(files: Vec<FileInfo>) -> Vec<FileInfo> {
+    files.into_iter().filter(|f| is_allowed_extension(&f.extension)).collect()
+}
+
+/// Hash each file in `indices` and group them by digest. Files that time out
+/// are recorded in `timed_out`; unreadable files are left out (so they stay "clean").
+fn hash_indices(
+    files: &[FileInfo],
+    indices: Vec<usize>,
+    io_timeout: Option<Duration>,
+    timed_out: &mut Vec<PathBuf>,
+    hasher: fn(&Path) -> Result<String>,
+) -> BTreeMap<String, Vec<usize>> {
+    let mut groups: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+
+    for idx in indices {
+        let file_info = &files[idx];
+        let path = file_info.original_path.clone();
+        match run_with_timeout(io_timeout, move || hasher(&path)) {
+            Ok(hash) => groups.entry(hash).or_default().push(idx),
+            Err(e) if io_timeout::is_timeout(&e) => {
+                warn!("Skipping {}: {}", file_info.original_path.display(), e);
+                timed_out.push(file_info.original_path.clone());
+            }
+            Err(e) => debug!("Failed to compute hash for {}: {}", file_info.original_path.display(), e),
+        }
+        priority::pause();
+    }
+
+    groups
+}
+
+fn display_name(file_info: &FileInfo) -> &str {
+    file_info.new_name.as_deref().unwrap_or(&file_info.original_name)
+}
+
+fn language(file_info: &FileInfo) -> Option<&str> {
+    file_info.metadata.as_ref().and_then(|m| m.language.as_deref())
+}
+
+// Select file to keep based on priority: completed > normalized > shortest path > newest
+fn select_file_to_keep<'a, F: Borrow<FileInfo>>(files: &'a [F], recovered: &HashSet<PathBuf>) -> &'a FileInfo {
+    let files: Vec<&FileInfo> = files.iter().map(|f| f.borrow()).collect();
+
+    // Priority 0: a copy that was complete before this run over a recovered download payload
+    let completed: Vec<&FileInfo> = files.iter().copied().filter(|f| !recovered.contains(&f.original_path)).collect();
+    let files = if completed.is_empty() { files } else { completed };
+
     // Priority 1: Already normalized files (have new_name set)
     let normalized_indices: Vec<usize> = files
         .iter()
@@ -217,7 +334,7 @@ fn select_file_to_keep(files: &[FileInfo]) -> &FileInfo {
             0
         });
     
-    &files[best_index]
+    files[best_index]
 }
 
 #[allow(dead_code)]
@@ -228,19 +345,20 @@ pub fn detect_name_variants(files: &[FileInfo]) -> Result<Vec<Vec<usize>>> {
     for (idx, file_info) in files.iter().enumerate() {
         if let Some(ref new_name) = file_info.new_name {
             // Strip off (1), (2), etc. to find base name
-            let base_name = strip_variant_suffix(new_name);
+            let base_name = search_key::fold(&strip_variant_suffix(new_name));
             name_groups
                 .entry(base_name)
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(idx);
         }
     }
 
     // Keep only groups with duplicates
-    let variants: Vec<Vec<usize>> = name_groups
+    let mut variants: Vec<Vec<usize>> = name_groups
         .into_values()
         .filter(|group| group.len() > 1)
         .collect();
+    variants.sort();
 
     Ok(variants)
 }
@@ -260,12 +378,45 @@ fn strip_variant_suffix(filename: &str) -> String {
     }
 }
 
-fn compute_md5(path: &std::path::Path) -> Result<String> {
+// Bytes read from each end of a file for the cheap first-pass hash
+const PARTIAL_HASH_BYTES: u64 = 128 * 1024;
+
+// Files at or above this size are hashed with large buffers on a reader thread
+const LARGE_FILE_THRESHOLD: u64 = 64 * 1024 * 1024;
+const LARGE_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+
+/// MD5 of the first and last `PARTIAL_HASH_BYTES` of a file. Only used to
+/// rule out non-duplicates; equal partial hashes still need a full hash.
+fn compute_partial_md5(path: &Path) -> Result<String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = fs::File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut hasher = md5::Context::new();
+    let mut buffer = Vec::with_capacity(PARTIAL_HASH_BYTES as usize);
+
+    (&mut file).take(PARTIAL_HASH_BYTES).read_to_end(&mut buffer)?;
+    hasher.consume(&buffer);
+
+    buffer.clear();
+    file.seek(SeekFrom::Start(size.saturating_sub(PARTIAL_HASH_BYTES)))?;
+    file.take(PARTIAL_HASH_BYTES).read_to_end(&mut buffer)?;
+    hasher.consume(&buffer);
+
+    Ok(format!("{:x}", hasher.compute()))
+}
+
+pub fn compute_md5(path: &Path) -> Result<String> {
     use std::io::Read;
 
     const BUFFER_SIZE: usize = 8192;
 
     let mut file = fs::File::open(path)?;
+    // --nice keeps hashing on one thread
+    if file.metadata()?.len() >= LARGE_FILE_THRESHOLD && !priority::is_low_impact() {
+        return md5_double_buffered(file, LARGE_BUFFER_SIZE);
+    }
+
     let mut hasher = md5::Context::new();
     let mut buffer = [0u8; BUFFER_SIZE];
 
@@ -280,6 +431,46 @@ fn compute_md5(path: &std::path::Path) -> Result<String> {
     Ok(format!("{:x}", hasher.compute()))
 }
 
+/// Hash with two rotating buffers: a reader thread fills one while this
+/// thread hashes the other, so disk/NAS reads overlap with MD5 work.
+fn md5_double_buffered<R: std::io::Read + Send + 'static>(mut reader: R, buffer_size: usize) -> Result<String> {
+    use std::sync::mpsc;
+
+    let (full_tx, full_rx) = mpsc::sync_channel::<Vec<u8>>(1);
+    let (empty_tx, empty_rx) = mpsc::channel::<Vec<u8>>();
+    for _ in 0..2 {
+        empty_tx.send(vec![0u8; buffer_size])?;
+    }
+
+    let reader_thread = std::thread::spawn(move || -> std::io::Result<()> {
+        while let Ok(mut buffer) = empty_rx.recv() {
+            buffer.resize(buffer_size, 0);
+            let bytes_read = reader.read(&mut buffer)?;
+            if bytes_read == 0 {
+                break;
+            }
+            buffer.truncate(bytes_read);
+            if full_tx.send(buffer).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    });
+
+    let mut hasher = md5::Context::new();
+    for buffer in full_rx {
+        hasher.consume(&buffer);
+        // The reader may already be done; a closed channel is fine here
+        let _ = empty_tx.send(buffer);
+    }
+
+    reader_thread
+        .join()
+        .map_err(|_| anyhow::anyhow!("Hash reader thread panicked"))??;
+
+    Ok(format!("{:x}", hasher.compute()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -305,8 +496,10 @@ mod tests {
                 modified_time: std::time::SystemTime::now(),
                 is_failed_download: false,
                 is_too_small: false,
+                is_virtual: false,
                 new_name: Some("Book 1.pdf".to_string()),
                 new_path: tmp_dir.path().join("Book 1.pdf"),
+                metadata: None,
             },
             FileInfo {
                 original_path: file2.clone(),
@@ -316,12 +509,14 @@ mod tests {
                 modified_time: std::time::SystemTime::now(),
                 is_failed_download: false,
                 is_too_small: false,
+                is_virtual: false,
                 new_name: Some("Book 2.pdf".to_string()),
                 new_path: tmp_dir.path().join("Book 2.pdf"),
+                metadata: None,
             },
         ];
 
-        let (dup_groups, clean_files) = detect_duplicates(files, false)?;
+        let (dup_groups, clean_files, _) = detect_duplicates(files, false, None, &DupeIgnoreList::default(), &HashSet::new())?;
 
         assert_eq!(dup_groups.len(), 1);
         assert_eq!(dup_groups[0].len(), 2);
@@ -330,6 +525,172 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_placeholders_are_matched_by_name() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path();
+        fs::write(dir.join("Hatcher - Algebraic Topology.pdf"), vec![b'a'; 2048])?;
+        fs::write(dir.join("Hatcher - Algebraic Topology (1).pdf"), vec![b'b'; 2048])?;
+        // Identical content, but a placeholder is never hashed
+        fs::write(dir.join("Munkres.pdf"), vec![b'c'; 3000])?;
+        fs::write(dir.join("Rudin.pdf"), vec![b'c'; 3000])?;
+
+        let mut files = crate::scanner::Scanner::new(dir, 1)?.scan()?;
+        for file_info in &mut files {
+            file_info.is_virtual = matches!(file_info.original_name.as_str(), "Hatcher - Algebraic Topology.pdf" | "Munkres.pdf");
+        }
+        let (dup_groups, _, _) = detect_duplicates(files, false, None, &DupeIgnoreList::default(), &HashSet::new())?;
+        assert_eq!(dup_groups.len(), 1);
+        assert!(dup_groups[0].iter().all(|p| p.to_string_lossy().contains("Hatcher")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_skip_detection_keeps_identical_files() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        fs::write(tmp_dir.path().join("a.pdf"), "identical content")?;
+        fs::write(tmp_dir.path().join("b.pdf"), "identical content")?;
+        fs::write(tmp_dir.path().join("notes.md"), "identical content")?;
+
+        let files = crate::scanner::Scanner::new(tmp_dir.path(), 1)?.scan()?;
+        let clean_files = skip_detection(files);
+
+        // Same set detect_duplicates works on, but nothing is grouped
+        assert_eq!(clean_files.len(), 2);
+        assert!(clean_files.iter().all(|f| f.extension == ".pdf"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_double_buffered_md5_matches_simple_read() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let path = tmp_dir.path().join("scan.pdf");
+        let content: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        fs::write(&path, &content)?;
+
+        // Small buffer size forces many buffer rotations
+        let pipelined = md5_double_buffered(fs::File::open(&path)?, 4096)?;
+        assert_eq!(pipelined, compute_md5(&path)?);
+        assert_eq!(pipelined, format!("{:x}", md5::compute(&content)));
+
+        Ok(())
+    }
+
+    fn pdf_info(path: PathBuf, size: u64) -> FileInfo {
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        FileInfo {
+            original_path: path.clone(),
+            original_name: name,
+            extension: ".pdf".to_string(),
+            size,
+            modified_time: std::time::SystemTime::now(),
+            is_failed_download: false,
+            is_too_small: false,
+            is_virtual: false,
+            new_name: None,
+            new_path: path,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_repeated_runs_give_identical_plans() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let target_dir = tmp_dir.path().to_path_buf();
+        let mut files = Vec::new();
+        // Distinct sizes spread the groups over many hash buckets
+        for group in 0..12u8 {
+            for copy in ["a", "b", "c"] {
+                let path = target_dir.join(format!("{}-{}.pdf", copy, group));
+                fs::write(&path, vec![group; 2000 + group as usize * 37])?;
+                let mut info = pdf_info(path.clone(), fs::metadata(&path)?.len());
+                info.modified_time = std::time::UNIX_EPOCH;
+                files.push(info);
+            }
+        }
+
+        let plan = || -> Result<String> {
+            let (groups, clean_files, _) = detect_duplicates(files.clone(), false, None, &DupeIgnoreList::default(), &HashSet::new())?;
+            assert!(groups.is_sorted_by_key(|group| group[0].clone()));
+            let output = crate::json_output::OperationsOutput::from_results(clean_files, groups, vec![], vec![], &target_dir, false)?;
+            output.to_json()
+        };
+        let first = plan()?;
+        for _ in 0..5 {
+            assert_eq!(plan()?, first);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_partial_hash_collision_confirmed_by_full_hash() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let size = 4 * PARTIAL_HASH_BYTES as usize;
+        let original = vec![7u8; size];
+        // Same size, head and tail as the original; only the middle differs
+        let mut edited = original.clone();
+        edited[size / 2] = 8;
+
+        let paths: Vec<PathBuf> = ["a.pdf", "b.pdf", "c.pdf"]
+            .iter()
+            .map(|name| tmp_dir.path().join(name))
+            .collect();
+        fs::write(&paths[0], &original)?;
+        fs::write(&paths[1], &original)?;
+        fs::write(&paths[2], &edited)?;
+
+        assert_eq!(compute_partial_md5(&paths[0])?, compute_partial_md5(&paths[2])?);
+
+        let files = paths.iter().map(|p| pdf_info(p.clone(), size as u64)).collect();
+        let (dup_groups, clean_files, _) = detect_duplicates(files, false, None, &DupeIgnoreList::default(), &HashSet::new())?;
+
+        assert_eq!(dup_groups.len(), 1);
+        assert_eq!(dup_groups[0].len(), 2);
+        assert!(!dup_groups[0].contains(&paths[2]));
+        assert_eq!(clean_files.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keeps_completed_copy_over_recovered_payload() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let existing = tmp_dir.path().join("Book.pdf");
+        let recovered = tmp_dir.path().join("Book (recovered).pdf");
+        fs::write(&existing, "same bytes")?;
+        fs::write(&recovered, "same bytes")?;
+
+        let mut older = pdf_info(existing.clone(), 10);
+        older.modified_time = std::time::SystemTime::UNIX_EPOCH;
+        let files = vec![older, pdf_info(recovered.clone(), 10)];
+        let (dup_groups, _, _) = detect_duplicates(files, false, None, &DupeIgnoreList::default(), &HashSet::from([recovered.clone()]))?;
+        assert_eq!(dup_groups, vec![vec![existing, recovered]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ignored_pair_is_not_grouped() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        for name in ["clean.pdf", "annotated.pdf", "copy.pdf"] {
+            fs::write(root.join(name), "same bytes")?;
+        }
+        DupeIgnoreList::record(&root, "clean.pdf", "annotated.pdf")?;
+        let ignore = DupeIgnoreList::load(&root)?;
+
+        let files = ["clean.pdf", "annotated.pdf"].iter().map(|n| pdf_info(root.join(n), 10)).collect();
+        let (dup_groups, clean_files, _) = detect_duplicates(files, false, None, &ignore, &HashSet::new())?;
+        assert!(dup_groups.is_empty());
+        assert_eq!(clean_files.len(), 2);
+
+        // A third copy still has no partner to be deleted in favor of
+        let files = ["clean.pdf", "annotated.pdf", "copy.pdf"].iter().map(|n| pdf_info(root.join(n), 10)).collect();
+        let (dup_groups, _, _) = detect_duplicates(files, false, None, &ignore, &HashSet::new())?;
+        assert!(dup_groups.is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn test_strip_variant_suffix() {
         assert_eq!(
@@ -360,8 +721,10 @@ mod tests {
             modified_time: now,
             is_failed_download: false,
             is_too_small: false,
+            is_virtual: false,
             new_name: None,
             new_path: tmp_dir.path().join("original.pdf"),
+            metadata: None,
         };
 
         // File 2: Normalized
@@ -373,12 +736,14 @@ mod tests {
             modified_time: now,
             is_failed_download: false,
             is_too_small: false,
+            is_virtual: false,
             new_name: Some("Normalized Title.pdf".to_string()),
             new_path: tmp_dir.path().join("Normalized Title.pdf"),
+            metadata: None,
         };
 
         let files = vec![f1, f2];
-        let kept = select_file_to_keep(&files);
+        let kept = select_file_to_keep(&files, &HashSet::new());
 
         // Should keep f2 because it's normalized
         assert!(kept.new_name.is_some());
@@ -399,8 +764,10 @@ mod tests {
             modified_time: now,
             is_failed_download: false,
             is_too_small: false,
+            is_virtual: false,
             new_name: None,
             new_path: tmp_dir.path().join("a").join("b").join("deep.pdf"),
+            metadata: None,
         };
 
         // File 2: Shallow path
@@ -412,12 +779,14 @@ mod tests {
             modified_time: now,
             is_failed_download: false,
             is_too_small: false,
+            is_virtual: false,
             new_name: None,
             new_path: tmp_dir.path().join("shallow.pdf"),
+            metadata: None,
         };
 
         let files = vec![f1, f2];
-        let kept = select_file_to_keep(&files);
+        let kept = select_file_to_keep(&files, &HashSet::new());
 
         // Should keep f2 because it has fewer path components
         assert_eq!(kept.original_name, "shallow.pdf");
@@ -438,8 +807,10 @@ mod tests {
             modified_time: older,
             is_failed_download: false,
             is_too_small: false,
+            is_virtual: false,
             new_name: None,
             new_path: tmp_dir.path().join("file1.pdf"),
+            metadata: None,
         };
 
         // File 2: Newer
@@ -451,12 +822,14 @@ mod tests {
             modified_time: now,
             is_failed_download: false,
             is_too_small: false,
+            is_virtual: false,
             new_name: None,
             new_path: tmp_dir.path().join("file2.pdf"),
+            metadata: None,
         };
 
         let files = vec![f1, f2];
-        let kept = select_file_to_keep(&files);
+        let kept = select_file_to_keep(&files, &HashSet::new());
 
         // Should keep f2 because it's newer (both have same depth and normalization status)
         assert_eq!(kept.original_name, "file2.pdf");
@@ -475,18 +848,36 @@ mod tests {
                 modified_time: std::time::SystemTime::now(),
                 is_failed_download: false,
                 is_too_small: false,
+                is_virtual: false,
                 new_name: None,
                 new_path: tmp_dir.path().join("file1.pdf"),
+                metadata: None,
             }
         ];
 
         // Even if files are present, skip_hash=true should return empty duplicate groups
-        let (dup_groups, clean_files) = detect_duplicates(files.clone(), true).unwrap();
+        let (dup_groups, clean_files, _) = detect_duplicates(files.clone(), true, None, &DupeIgnoreList::default(), &HashSet::new()).unwrap();
 
         assert!(dup_groups.is_empty());
         assert_eq!(clean_files.len(), 1);
     }
 
+    #[test]
+    fn test_fuzzy_match_keeps_translations_apart() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut files: Vec<FileInfo> = ["Lang - Algebra (2002).pdf", "Lang - Algebra (2002) [zh].pdf", "Lang - Algebra (2002) (1).pdf"]
+            .iter()
+            .map(|name| pdf_info(tmp_dir.path().join(name), 5000))
+            .collect();
+        for file in &mut files {
+            file.metadata = Some(crate::normalizer::parse_filename(&file.original_name, ".pdf").unwrap());
+        }
+
+        let (dup_groups, _, _) = detect_duplicates(files, true, None, &DupeIgnoreList::default(), &HashSet::new()).unwrap();
+        assert_eq!(dup_groups.len(), 1);
+        assert!(!dup_groups[0].iter().any(|p| p.to_string_lossy().contains("[zh]")));
+    }
+
     #[test]
     fn test_detect_name_variants() {
         let tmp_dir = TempDir::new().unwrap();
@@ -500,8 +891,10 @@ mod tests {
             modified_time: now,
             is_failed_download: false,
             is_too_small: false,
+            is_virtual: false,
             new_name: Some("Book.pdf".to_string()),
             new_path: tmp_dir.path().join("Book.pdf"),
+            metadata: None,
         };
 
         let f2 = FileInfo {
@@ -512,8 +905,10 @@ mod tests {
             modified_time: now,
             is_failed_download: false,
             is_too_small: false,
+            is_virtual: false,
             new_name: Some("Book (1).pdf".to_string()),
             new_path: tmp_dir.path().join("Book (1).pdf"),
+            metadata: None,
         };
 
         let files = vec![f1, f2];
@@ -539,8 +934,10 @@ mod tests {
             modified_time: now,
             is_failed_download: false,
             is_too_small: false,
+            is_virtual: false,
             new_name: Some("Final Name.pdf".to_string()),
             new_path: tmp_dir.path().join("Final Name.pdf"),
+            metadata: None,
         };
 
         let f2 = FileInfo {
@@ -551,14 +948,16 @@ mod tests {
             modified_time: now,
             is_failed_download: false,
             is_too_small: false,
+            is_virtual: false,
             new_name: Some("Final Name.pdf".to_string()),
             new_path: tmp_dir.path().join("Final Name.pdf"),
+            metadata: None,
         };
 
         let files = vec![f1, f2];
 
         // When skip_hash is true, we expect it to find duplicates based on new_name
-        let (dup_groups, clean_files) = detect_duplicates(files, true).unwrap();
+        let (dup_groups, clean_files, _) = detect_duplicates(files, true, None, &DupeIgnoreList::default(), &HashSet::new()).unwrap();
 
         assert_eq!(dup_groups.len(), 1, "Should find 1 duplicate group");
         assert_eq!(dup_groups[0].len(), 2, "Group should have 2 files");
@@ -0,0 +1,133 @@
+use crate::io_timeout::IoTimeout;
+use std::io;
+use std::path::PathBuf;
+
+/// Failures the tool raises itself. They travel inside `anyhow::Error`, like
+/// `IoTimeout`, and `Category::of` finds them again for JSON output and the
+/// exit code.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Bad flags or template
+    #[error("{0}")]
+    Usage(String),
+    #[error("Path is not a directory: {0:?}")]
+    NotADirectory(PathBuf),
+    #[error("{what} not found: {path:?}")]
+    NotFound { what: &'static str, path: PathBuf },
+    /// A config, override or list file that does not parse
+    #[error("{path:?}: {message}")]
+    InvalidFile { path: PathBuf, message: String },
+    /// Another run holds the folder's lock
+    #[error("{0}")]
+    Locked(String),
+    #[error("{0} is not installed")]
+    MissingTool(&'static str),
+    /// The plan exceeds a `--max-*` guard limit and `--force` was not given
+    #[error("{0}")]
+    LimitExceeded(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Usage,
+    NotFound,
+    Permission,
+    InvalidData,
+    Timeout,
+    Network,
+    Locked,
+    MissingTool,
+    Io,
+    Other,
+}
+
+impl Category {
+    /// The first cause in the chain that can be classified.
+    pub fn of(error: &anyhow::Error) -> Self {
+        error.chain().find_map(classify).unwrap_or(Category::Other)
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Category::Usage => "usage",
+            Category::NotFound => "not_found",
+            Category::Permission => "permission",
+            Category::InvalidData => "invalid_data",
+            Category::Timeout => "timeout",
+            Category::Network => "network",
+            Category::Locked => "locked",
+            Category::MissingTool => "missing_tool",
+            Category::Io => "io",
+            Category::Other => "other",
+        }
+    }
+
+    /// Exit status from BSD `sysexits.h`; errors that cannot be classified
+    /// keep exiting with 1.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Category::Usage => 64,
+            Category::InvalidData => 65,
+            Category::NotFound => 66,
+            Category::MissingTool => 69,
+            Category::Io => 74,
+            Category::Timeout | Category::Network | Category::Locked => 75,
+            Category::Permission => 77,
+            Category::Other => 1,
+        }
+    }
+}
+
+fn classify(cause: &(dyn std::error::Error + 'static)) -> Option<Category> {
+    if let Some(error) = cause.downcast_ref::<Error>() {
+        return Some(match error {
+            Error::Usage(_) | Error::LimitExceeded(_) => Category::Usage,
+            Error::NotADirectory(_) | Error::NotFound { .. } => Category::NotFound,
+            Error::InvalidFile { .. } => Category::InvalidData,
+            Error::Locked(_) => Category::Locked,
+            Error::MissingTool(_) => Category::MissingTool,
+        });
+    }
+    if cause.is::<IoTimeout>() {
+        return Some(Category::Timeout);
+    }
+    if cause.is::<serde_json::Error>() || cause.is::<zip::result::ZipError>() {
+        return Some(Category::InvalidData);
+    }
+    let error = cause.downcast_ref::<io::Error>()?;
+    Some(match error.kind() {
+        io::ErrorKind::NotFound => Category::NotFound,
+        io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem => Category::Permission,
+        io::ErrorKind::InvalidData | io::ErrorKind::InvalidFilename | io::ErrorKind::UnexpectedEof => Category::InvalidData,
+        io::ErrorKind::ConnectionRefused
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::NotConnected
+        | io::ErrorKind::HostUnreachable
+        | io::ErrorKind::NetworkUnreachable
+        | io::ErrorKind::NetworkDown
+        | io::ErrorKind::StaleNetworkFileHandle
+        | io::ErrorKind::TimedOut => Category::Network,
+        _ => Category::Io,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use std::time::Duration;
+
+    #[test]
+    fn test_category_of() {
+        let permission: anyhow::Result<()> = Err(io::Error::from(io::ErrorKind::PermissionDenied)).context("Renaming a.pdf");
+        assert_eq!(Category::of(&permission.unwrap_err()), Category::Permission);
+        assert_eq!(Category::of(&Error::NotADirectory(PathBuf::from("x")).into()), Category::NotFound);
+        assert_eq!(Category::of(&IoTimeout(Duration::from_secs(1)).into()), Category::Timeout);
+        assert_eq!(Category::of(&io::Error::from(io::ErrorKind::StaleNetworkFileHandle).into()), Category::Network);
+        let parse = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        assert_eq!(Category::of(&parse.into()), Category::InvalidData);
+        assert_eq!(Category::of(&anyhow::anyhow!("something else")), Category::Other);
+        assert_eq!(Category::Other.exit_code(), 1);
+    }
+}
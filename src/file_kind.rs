@@ -0,0 +1,208 @@
+use crate::scanner::FileInfo;
+use log::{debug, info};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+// Enough for the EPUB mimetype entry and the MOBI header
+pub const SNIFF_BYTES: u64 = 128;
+
+/// What a file is by its first bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Pdf,
+    Epub,
+    Djvu,
+    Mobi,
+    Chm,
+    Zip,
+    Rar,
+    SevenZip,
+    Gzip,
+    Html,
+}
+
+impl FileKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            FileKind::Pdf => "PDF",
+            FileKind::Epub => "EPUB",
+            FileKind::Djvu => "DjVu",
+            FileKind::Mobi => "MOBI",
+            FileKind::Chm => "CHM",
+            FileKind::Zip => "ZIP",
+            FileKind::Rar => "RAR",
+            FileKind::SevenZip => "7z",
+            FileKind::Gzip => "gzip",
+            FileKind::Html => "HTML",
+        }
+    }
+
+    /// Extensions this content may have; the first is used by `--fix-extensions`.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            FileKind::Pdf => &[".pdf"],
+            FileKind::Epub => &[".epub", ".kepub"],
+            FileKind::Djvu => &[".djvu", ".djv"],
+            FileKind::Mobi => &[".mobi", ".azw3", ".azw", ".prc", ".pdb"],
+            FileKind::Chm => &[".chm"],
+            // EPUBs that don't store their mimetype first are plain zips
+            FileKind::Zip => &[".zip", ".cbz", ".epub", ".kepub"],
+            FileKind::Rar => &[".rar", ".cbr"],
+            FileKind::SevenZip => &[".7z", ".cb7"],
+            FileKind::Gzip => &[".gz", ".tar.gz", ".tgz"],
+            FileKind::Html => &[".html", ".htm"],
+        }
+    }
+
+    /// A saved error page is a failed download, not a book to rename.
+    pub fn is_fixable(self) -> bool {
+        self != FileKind::Html
+    }
+}
+
+const ALL_KINDS: &[FileKind] = &[
+    FileKind::Pdf,
+    FileKind::Epub,
+    FileKind::Djvu,
+    FileKind::Mobi,
+    FileKind::Chm,
+    FileKind::Zip,
+    FileKind::Rar,
+    FileKind::SevenZip,
+    FileKind::Gzip,
+    FileKind::Html,
+];
+
+/// Whether the extension belongs to one of the formats `sniff` knows.
+pub fn is_known_extension(extension: &str) -> bool {
+    let extension = extension.to_lowercase();
+    ALL_KINDS.iter().any(|kind| kind.extensions().contains(&extension.as_str()))
+}
+
+/// The kind of the file's content, or `None` if it is none of the known formats.
+pub fn sniff(path: &Path) -> io::Result<Option<FileKind>> {
+    let mut head = Vec::new();
+    File::open(path)?.take(SNIFF_BYTES).read_to_end(&mut head)?;
+    Ok(sniff_bytes(&head))
+}
+
+pub fn sniff_bytes(head: &[u8]) -> Option<FileKind> {
+    if head.starts_with(b"%PDF-") {
+        return Some(FileKind::Pdf);
+    }
+    if head.starts_with(b"PK\x03\x04") {
+        // The EPUB container stores "mimetype" uncompressed as its first entry
+        let is_epub = head.get(30..58) == Some(b"mimetypeapplication/epub+zip".as_slice());
+        return Some(if is_epub { FileKind::Epub } else { FileKind::Zip });
+    }
+    if head.starts_with(b"AT&TFORM") {
+        return Some(FileKind::Djvu);
+    }
+    if matches!(head.get(60..68), Some(b"BOOKMOBI" | b"TEXtREAd")) {
+        return Some(FileKind::Mobi);
+    }
+    if head.starts_with(b"ITSF") {
+        return Some(FileKind::Chm);
+    }
+    if head.starts_with(b"Rar!\x1a\x07") {
+        return Some(FileKind::Rar);
+    }
+    if head.starts_with(b"7z\xbc\xaf\x27\x1c") {
+        return Some(FileKind::SevenZip);
+    }
+    if head.starts_with(b"\x1f\x8b") {
+        return Some(FileKind::Gzip);
+    }
+    let text = head.strip_prefix(b"\xef\xbb\xbf").unwrap_or(head);
+    let start = text.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(text.len());
+    let text = text[start..].to_ascii_lowercase();
+    if text.starts_with(b"<!doctype html") || text.starts_with(b"<html") || text.starts_with(b"<head") {
+        return Some(FileKind::Html);
+    }
+    None
+}
+
+/// A file whose content is another format than its extension says.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub path: PathBuf,
+    pub kind: FileKind,
+}
+
+/// Sniff every file with a known extension. Placeholders and failed
+/// downloads are not read.
+pub fn detect_mismatches(files: &[FileInfo]) -> Vec<Mismatch> {
+    files
+        .iter()
+        .filter(|f| !f.is_virtual && !f.is_failed_download)
+        .filter_map(|f| {
+            if !is_known_extension(&f.extension) {
+                return None;
+            }
+            let extension = f.extension.to_lowercase();
+            let kind = sniff(&f.original_path).map_err(|e| debug!("Could not sniff {:?}: {}", f.original_path, e)).ok()??;
+            if kind.extensions().contains(&extension.as_str()) {
+                return None;
+            }
+            info!("{:?} is {} content, not {}", f.original_path, kind.name(), f.extension);
+            Some(Mismatch { path: f.original_path.clone(), kind })
+        })
+        .collect()
+}
+
+/// Give fixable mismatches the extension of their content in the rename
+/// plan. Returns the mismatches left as they are.
+pub fn fix_extensions(files: &mut [FileInfo], mismatches: Vec<Mismatch>) -> Vec<Mismatch> {
+    let (fixable, rest): (Vec<Mismatch>, Vec<Mismatch>) = mismatches.into_iter().partition(|m| m.kind.is_fixable());
+    for mismatch in fixable {
+        let Some(file_info) = files.iter_mut().find(|f| f.original_path == mismatch.path) else {
+            continue;
+        };
+        let name = file_info.new_name.as_deref().unwrap_or(&file_info.original_name);
+        let stem = name.strip_suffix(file_info.extension.as_str()).unwrap_or(name);
+        let extension = mismatch.kind.extensions()[0];
+        let new_name = format!("{}{}", stem, extension);
+        file_info.new_path = file_info.original_path.with_file_name(&new_name);
+        file_info.new_name = Some(new_name);
+        file_info.extension = extension.to_string();
+    }
+    rest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+    use anyhow::Result;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detect_and_fix() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path();
+        let mut epub = b"PK\x03\x04".to_vec();
+        epub.extend([0; 26]);
+        epub.extend(b"mimetypeapplication/epub+zip");
+        fs::write(dir.join("Real.pdf"), b"%PDF-1.7\n")?;
+        fs::write(dir.join("Book.pdf"), &epub)?;
+        fs::write(dir.join("Error.pdf"), b"\n  <!DOCTYPE html><html><body>404</body></html>")?;
+        fs::write(dir.join("notes.txt"), b"<html>")?;
+
+        let mut files = Scanner::new(dir, 1)?.scan()?;
+        files.sort_by(|a, b| a.original_name.cmp(&b.original_name));
+        let mismatches = detect_mismatches(&files);
+        let kinds: Vec<(&str, FileKind)> =
+            mismatches.iter().map(|m| (m.path.file_name().unwrap().to_str().unwrap(), m.kind)).collect();
+        assert_eq!(kinds, vec![("Book.pdf", FileKind::Epub), ("Error.pdf", FileKind::Html)]);
+
+        // The error page is left for the todo list
+        let rest = fix_extensions(&mut files, mismatches);
+        assert_eq!(rest.len(), 1);
+        let book = files.iter().find(|f| f.original_name == "Book.pdf").unwrap();
+        assert_eq!(book.new_name.as_deref(), Some("Book.epub"));
+        assert_eq!(book.extension, ".epub");
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use crate::priority;
+use crate::scanner::FileInfo;
+use log::debug;
+use lopdf::Document;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// Less text than this on the first pages means a scan or an image-only
+// PDF, whose drawing instructions are compared instead
+const MIN_TEXT_CHARS: usize = 100;
+// Whole documents are parsed, so huge files are left out
+const MAX_FINGERPRINT_BYTES: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Basis {
+    /// Extracted text, letters and digits only
+    Text,
+    /// Decompressed page content streams
+    Content,
+}
+
+impl Basis {
+    pub fn code(self) -> &'static str {
+        match self {
+            Basis::Text => "text",
+            Basis::Content => "content",
+        }
+    }
+}
+
+/// PDFs with different bytes but the same page count and the same content
+/// on their first pages: re-downloads from another mirror, re-linearized or
+/// with rewritten metadata. Reported, never deleted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbableGroup {
+    /// Sorted
+    pub files: Vec<PathBuf>,
+    pub basis: Basis,
+}
+
+/// The page count and a hash of the first `pages` pages' text, or of their
+/// content streams when there is too little text. `None` for files that
+/// cannot be parsed or are encrypted.
+pub fn fingerprint(path: &Path, pages: usize) -> Option<(Basis, String)> {
+    let doc = Document::load(path).map_err(|e| debug!("No fingerprint for {:?}: {}", path, e)).ok()?;
+    if doc.is_encrypted() {
+        return None;
+    }
+    let page_ids = doc.get_pages();
+    let first: Vec<u32> = page_ids.keys().copied().take(pages).collect();
+
+    let text: String = doc
+        .extract_text(&first)
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let (basis, digest) = if text.chars().count() >= MIN_TEXT_CHARS {
+        (Basis::Text, md5::compute(text.as_bytes()))
+    } else {
+        let mut context = md5::Context::new();
+        for number in &first {
+            context.consume(doc.get_page_content(page_ids[number]).ok()?);
+        }
+        (Basis::Content, context.compute())
+    };
+    Some((basis, format!("{}:{:x}", page_ids.len(), digest)))
+}
+
+/// Groups of `files` (PDFs only) with the same fingerprint.
+pub fn detect(files: &[FileInfo], pages: usize) -> Vec<ProbableGroup> {
+    let mut by_fingerprint: BTreeMap<(Basis, String), Vec<PathBuf>> = BTreeMap::new();
+    for file in files {
+        if !file.extension.eq_ignore_ascii_case(".pdf")
+            || file.is_failed_download
+            || file.is_too_small
+            || file.is_virtual
+            || file.size > MAX_FINGERPRINT_BYTES
+        {
+            continue;
+        }
+        if let Some(key) = fingerprint(&file.original_path, pages) {
+            by_fingerprint.entry(key).or_default().push(file.original_path.clone());
+        }
+        priority::pause();
+    }
+    let mut groups: Vec<ProbableGroup> = by_fingerprint
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .map(|((basis, _), mut files)| {
+            files.sort();
+            ProbableGroup { files, basis }
+        })
+        .collect();
+    groups.sort_by(|a, b| a.files.cmp(&b.files));
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+    use lopdf::{dictionary, Object, Stream};
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// One page per text; `producer` only changes the metadata.
+    fn pdf(texts: &[&str], producer: &str) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Courier" });
+        let kids: Vec<Object> = texts
+            .iter()
+            .map(|text| {
+                let content = format!("BT /F1 12 Tf 72 720 Td ({}) Tj ET", text);
+                let content_id = doc.add_object(Stream::new(dictionary! {}, content.into_bytes()));
+                doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "Contents" => content_id,
+                    "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+                    "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+                })
+                .into()
+            })
+            .collect();
+        let count = kids.len() as i64;
+        doc.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => count }));
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+        let info_id = doc.add_object(dictionary! { "Producer" => Object::string_literal(producer) });
+        doc.trailer.set("Info", info_id);
+        doc.add_object(Stream::new(dictionary! {}, vec![b' '; 2048]));
+        let mut content = Vec::new();
+        doc.save_to(&mut content).unwrap();
+        content
+    }
+
+    #[test]
+    fn test_detect_probable_duplicates() -> anyhow::Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        let page = "The quick brown fox jumps over the lazy dog, again and again, until the end of the chapter.";
+        fs::write(root.join("mirror1.pdf"), pdf(&[page, page], "Mirror One"))?;
+        fs::write(root.join("mirror2.pdf"), pdf(&[page, page], "Mirror Two, re-linearized"))?;
+        fs::write(root.join("other.pdf"), pdf(&[page, "A different second page entirely, with its own words."], "x"))?;
+        // Too little text: the content streams decide
+        fs::write(root.join("scan1.pdf"), pdf(&["x"], "a"))?;
+        fs::write(root.join("scan2.pdf"), pdf(&["x"], "b"))?;
+
+        let files = Scanner::new(&root, usize::MAX)?.scan()?;
+        let groups = detect(&files, 10);
+        assert_eq!(
+            groups,
+            vec![
+                ProbableGroup { files: vec![root.join("mirror1.pdf"), root.join("mirror2.pdf")], basis: Basis::Text },
+                ProbableGroup { files: vec![root.join("scan1.pdf"), root.join("scan2.pdf")], basis: Basis::Content },
+            ]
+        );
+        // Only the first page compared: "other" matches too
+        assert_eq!(detect(&files, 1)[0].files.len(), 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use crate::error::Error;
+
+/// Below this many deletions the percentage limit is not checked, so small
+/// folders with a few duplicates still run without `--force`.
+pub const PERCENT_MIN_DELETES: usize = 10;
+
+/// Upper bounds on what one live run may change, so a bad template, regex or
+/// profile can't wipe a library. `--force` skips the check.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub max_deletes: usize,
+    pub max_delete_percent: u8,
+    pub max_renames: Option<usize>,
+}
+
+impl Limits {
+    /// `files` is the number of ebooks the scan found.
+    pub fn check(&self, renames: usize, deletes: usize, files: usize) -> Result<(), Error> {
+        let mut exceeded = Vec::new();
+        if deletes > self.max_deletes {
+            exceeded.push(format!("delete {} files (limit {}, --max-deletes)", deletes, self.max_deletes));
+        }
+        if deletes > PERCENT_MIN_DELETES && files > 0 && deletes * 100 > files * self.max_delete_percent as usize {
+            exceeded.push(format!(
+                "delete {}% of the {} files (limit {}%, --max-delete-percent)",
+                deletes * 100 / files,
+                files,
+                self.max_delete_percent
+            ));
+        }
+        if let Some(max_renames) = self.max_renames
+            && renames > max_renames {
+            exceeded.push(format!("rename {} files (limit {}, --max-renames)", renames, max_renames));
+        }
+        if exceeded.is_empty() {
+            return Ok(());
+        }
+        Err(Error::LimitExceeded(format!(
+            "Refusing to run: the plan would {}. Preview it with --dry-run, then raise the limit or pass --force",
+            exceeded.join(" and ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        let limits = Limits { max_deletes: 50, max_delete_percent: 25, max_renames: Some(100) };
+        assert!(limits.check(100, 50, 1000).is_ok());
+        // Few deletions pass the percentage limit even in a small folder
+        assert!(limits.check(0, PERCENT_MIN_DELETES, 12).is_ok());
+
+        let error = limits.check(0, 51, 1000).unwrap_err().to_string();
+        assert!(error.contains("delete 51 files (limit 50"), "{}", error);
+        let error = limits.check(0, 30, 100).unwrap_err().to_string();
+        assert!(error.contains("delete 30% of the 100 files"), "{}", error);
+        let error = limits.check(101, 60, 100).unwrap_err().to_string();
+        assert!(error.contains("--max-deletes") && error.contains("--max-delete-percent") && error.contains("rename 101"), "{}", error);
+
+        let unlimited_renames = Limits { max_renames: None, ..limits };
+        assert!(unlimited_renames.check(1_000_000, 0, 10).is_ok());
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::Result;
+use chrono::Local;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const HISTORY_FILE_NAME: &str = ".ebook-renamer-history.jsonl";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunStats {
+    pub timestamp: String,
+    pub dry_run: bool,
+    pub files_processed: usize,
+    pub renamed: usize,
+    pub duplicates: usize,
+    pub small_or_failed: usize,
+    pub bytes_reclaimed: u64,
+    /// Directories removed (or, in a dry run, predicted) by `--prune-empty-dirs`
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub pruned_dirs: usize,
+    /// Discrepancies found after a live run (see `audit.rs`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audit_issues: Vec<String>,
+}
+
+impl RunStats {
+    pub fn new(dry_run: bool) -> Self {
+        Self {
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            dry_run,
+            ..Default::default()
+        }
+    }
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+pub fn history_path(target_dir: &Path) -> PathBuf {
+    target_dir.join(HISTORY_FILE_NAME)
+}
+
+/// Append one run to the history file (one JSON object per line).
+pub fn record_run(target_dir: &Path, stats: &RunStats) -> Result<()> {
+    let path = history_path(target_dir);
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(stats)?)?;
+    debug!("Recorded run statistics to {:?}", path);
+    Ok(())
+}
+
+pub fn load_history(target_dir: &Path) -> Result<Vec<RunStats>> {
+    let path = history_path(target_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    // Skip malformed lines instead of failing the whole report
+    let content = fs::read_to_string(&path)?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Compare the average duplicates + failed files per live run in the older
+/// and newer half of the history. Negative means fewer problems per run
+/// lately. Dry runs (recorded by older versions) don't count.
+pub fn problem_trend(runs: &[RunStats]) -> Option<f64> {
+    let runs: Vec<RunStats> = runs.iter().filter(|r| !r.dry_run).cloned().collect();
+    if runs.len() < 2 {
+        return None;
+    }
+    let mid = runs.len() / 2;
+    let avg = |slice: &[RunStats]| {
+        slice.iter().map(|r| (r.duplicates + r.small_or_failed) as f64).sum::<f64>() / slice.len() as f64
+    };
+    Some(avg(&runs[mid..]) - avg(&runs[..mid]))
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn run(duplicates: usize, small_or_failed: usize) -> RunStats {
+        RunStats {
+            duplicates,
+            small_or_failed,
+            ..RunStats::new(false)
+        }
+    }
+
+    #[test]
+    fn test_record_and_load_history() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        record_run(tmp_dir.path(), &run(3, 1))?;
+        record_run(tmp_dir.path(), &run(1, 0))?;
+
+        let runs = load_history(tmp_dir.path())?;
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].duplicates, 3);
+        assert_eq!(runs[1].duplicates, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_history_skips_bad_lines() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        record_run(tmp_dir.path(), &run(2, 0))?;
+        let mut file = OpenOptions::new().append(true).open(history_path(tmp_dir.path()))?;
+        writeln!(file, "not json")?;
+
+        assert_eq!(load_history(tmp_dir.path())?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_problem_trend() {
+        assert_eq!(problem_trend(&[run(5, 0)]), None);
+        let improving = [run(6, 2), run(4, 2), run(1, 0), run(1, 1)];
+        assert!(problem_trend(&improving).unwrap() < 0.0);
+    }
+
+    #[test]
+    fn test_problem_trend_ignores_dry_runs() {
+        let dry = RunStats { duplicates: 40, ..RunStats::new(true) };
+        assert_eq!(problem_trend(&[run(5, 0), dry.clone()]), None);
+        assert_eq!(problem_trend(&[run(5, 0), dry.clone(), run(5, 0), dry]), Some(0.0));
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MB");
+    }
+}
@@ -0,0 +1,103 @@
+use crate::cli::Lang;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use std::env;
+use std::sync::{LazyLock, OnceLock};
+
+const EN: &str = include_str!("../locales/en.ftl");
+const ZH: &str = include_str!("../locales/zh.ftl");
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+static BUNDLES: LazyLock<[FluentBundle<FluentResource>; 2]> = LazyLock::new(|| [bundle("en", EN), bundle("zh", ZH)]);
+
+/// `t!("id")` or `t!("id", count = n, path = p.display().to_string())`.
+macro_rules! t {
+    ($id:expr) => {
+        $crate::i18n::message($id, None)
+    };
+    ($id:expr, $($key:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.set(stringify!($key), $value);)+
+        $crate::i18n::message($id, Some(&args))
+    }};
+}
+pub(crate) use t;
+
+/// Pick the output language once, before anything is printed. Without
+/// `--lang`, a `zh*` locale selects Chinese.
+pub fn init(lang: Option<Lang>) {
+    let lang = lang.unwrap_or_else(|| {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"].iter().find_map(|var| env::var(var).ok().filter(|v| !v.is_empty()));
+        match locale {
+            Some(locale) if locale.to_lowercase().starts_with("zh") => Lang::Zh,
+            _ => Lang::En,
+        }
+    });
+    let _ = LANG.set(lang);
+}
+
+/// English until `init` ran, so tests don't depend on the locale.
+pub fn lang() -> Lang {
+    LANG.get().copied().unwrap_or(Lang::En)
+}
+
+/// The message in the current language, falling back to English, then the id.
+pub fn message(id: &str, args: Option<&FluentArgs>) -> String {
+    let [en, zh] = &*BUNDLES;
+    let bundles = match lang() {
+        Lang::Zh => [zh, en],
+        Lang::En => [en, en],
+    };
+    for bundle in bundles {
+        if let Some(pattern) = bundle.get_message(id).and_then(|m| m.value()) {
+            let mut errors = Vec::new();
+            return bundle.format_pattern(pattern, args, &mut errors).into_owned();
+        }
+    }
+    id.to_string()
+}
+
+fn bundle(locale: &str, source: &str) -> FluentBundle<FluentResource> {
+    let mut bundle = FluentBundle::new_concurrent(vec![locale.parse().expect("valid locale")]);
+    // Bidi isolation marks would end up in terminals and log files
+    bundle.set_use_isolating(false);
+    let resource = FluentResource::try_new(source.to_string()).expect("valid FTL");
+    bundle.add_resource(resource).expect("no duplicate messages");
+    bundle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn ids(source: &str) -> BTreeSet<String> {
+        source
+            .lines()
+            .filter_map(|line| line.split_once(" =").map(|(id, _)| id))
+            .filter(|id| !id.is_empty() && !id.starts_with([' ', '#', '.']))
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_bundles() {
+        // Both languages have every message
+        assert_eq!(ids(EN), ids(ZH));
+        assert_eq!(t!("files-to-rename", count = 3), "3 files to rename");
+        assert_eq!(t!("scanning", walked = 10, folder = "Math"), "Scanning... 10 entries (Math)");
+        assert_eq!(t!("no-such-message"), "no-such-message");
+    }
+
+    #[test]
+    fn test_probable_duplicates_basis() {
+        assert_eq!(t!("tui-probable-duplicates", basis = "text", files = "a.pdf, b.pdf"), "Probable duplicates (same text): a.pdf, b.pdf");
+        // Chinese names the basis through a select expression
+        let zh = &BUNDLES[1];
+        let pattern = zh.get_message("tui-probable-duplicates").and_then(|m| m.value()).unwrap();
+        let mut args = FluentArgs::new();
+        args.set("basis", "content");
+        args.set("files", "a.pdf");
+        assert_eq!(zh.format_pattern(pattern, Some(&args), &mut Vec::new()), "疑似重复（页面内容相同）：a.pdf");
+    }
+}
@@ -0,0 +1,109 @@
+use crate::scanner::FileInfo;
+use log::debug;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+/// Files that are still being written: their size or modification time
+/// changed within `settle` of the scan, they vanished, or another process
+/// has them open for writing. They are left alone until a later run.
+pub fn find_in_use(files: &[FileInfo], settle: Duration) -> Vec<PathBuf> {
+    thread::sleep(settle);
+    let open_for_writing = open_for_writing();
+    files
+        .iter()
+        .filter(|f| {
+            let changed = match fs::metadata(&f.original_path) {
+                Ok(metadata) => metadata.len() != f.size || metadata.modified().ok() != Some(f.modified_time),
+                Err(_) => true,
+            };
+            changed || open_for_writing.contains(&f.original_path)
+        })
+        .inspect(|f| debug!("In use, deferred: {:?}", f.original_path))
+        .map(|f| f.original_path.clone())
+        .collect()
+}
+
+/// Files other processes have open with write access. Only Linux exposes
+/// this without extra tools; elsewhere the settle check has to do.
+#[cfg(target_os = "linux")]
+fn open_for_writing() -> HashSet<PathBuf> {
+    let own_pid = std::process::id().to_string();
+    let mut paths = HashSet::new();
+    let Ok(processes) = fs::read_dir("/proc") else {
+        return paths;
+    };
+    for process in processes.flatten() {
+        let pid = process.file_name().to_string_lossy().to_string();
+        if pid == own_pid || !pid.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        // Other users' processes are not readable, which is fine
+        let Ok(fds) = fs::read_dir(process.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            let info = process.path().join("fdinfo").join(fd.file_name());
+            if fs::read_to_string(info).is_ok_and(|info| is_write_mode(&info)) {
+                paths.insert(target);
+            }
+        }
+    }
+    paths
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_for_writing() -> HashSet<PathBuf> {
+    HashSet::new()
+}
+
+/// `flags:` in `/proc/<pid>/fdinfo/<fd>` is octal; O_WRONLY or O_RDWR.
+#[cfg(target_os = "linux")]
+fn is_write_mode(fdinfo: &str) -> bool {
+    fdinfo
+        .lines()
+        .find_map(|line| line.strip_prefix("flags:"))
+        .and_then(|flags| u32::from_str_radix(flags.trim(), 8).ok())
+        .is_some_and(|flags| flags & 0o3 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+    use anyhow::Result;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_in_use() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        fs::write(tmp_dir.path().join("Done.pdf"), vec![b'x'; 2048])?;
+        fs::write(tmp_dir.path().join("Growing.pdf"), vec![b'x'; 2048])?;
+        fs::write(tmp_dir.path().join("Vanishing.pdf"), vec![b'x'; 2048])?;
+        let files = Scanner::new(tmp_dir.path(), 1)?.scan()?;
+
+        fs::OpenOptions::new().append(true).open(tmp_dir.path().join("Growing.pdf"))?.write_all(b"more")?;
+        fs::remove_file(tmp_dir.path().join("Vanishing.pdf"))?;
+        let mut in_use: Vec<String> = find_in_use(&files, Duration::ZERO)
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        in_use.sort();
+        assert_eq!(in_use, vec!["Growing.pdf", "Vanishing.pdf"]);
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_is_write_mode() {
+        assert!(is_write_mode("pos:\t0\nflags:\t0100001\nmnt_id:\t25\n"));
+        assert!(is_write_mode("pos:\t0\nflags:\t02\n"));
+        assert!(!is_write_mode("pos:\t0\nflags:\t0100000\n"));
+    }
+}
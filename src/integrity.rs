@@ -0,0 +1,264 @@
+use crate::file_kind;
+use crate::scanner::FileInfo;
+use anyhow::Result;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+// How much of the end of a file is searched for trailers and the zip directory
+const TAIL_BYTES: u64 = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub path: String,
+    pub severity: Severity,
+    /// Which validator reported it, e.g. `pdf` or `drm`
+    pub check: &'static str,
+    pub message: String,
+}
+
+/// Result of the `check` subcommand, most severe findings first.
+#[derive(Debug, Default, Serialize)]
+pub struct IntegrityReport {
+    pub files_checked: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    pub infos: usize,
+    pub findings: Vec<Finding>,
+}
+
+impl IntegrityReport {
+    /// Run every validator on the scanned files. Paths are shown relative to `root`.
+    pub fn from_files(files: &[FileInfo], root: &Path) -> Self {
+        let mut findings = Vec::new();
+        for file in files {
+            let path = file.original_path.strip_prefix(root).unwrap_or(&file.original_path).to_string_lossy().to_string();
+            for (severity, check, message) in check_file(file) {
+                findings.push(Finding { path: path.clone(), severity, check, message });
+            }
+        }
+        findings.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.path.cmp(&b.path)));
+
+        let count = |severity| findings.iter().filter(|f| f.severity == severity).count();
+        IntegrityReport {
+            files_checked: files.len(),
+            errors: count(Severity::Error),
+            warnings: count(Severity::Warning),
+            infos: count(Severity::Info),
+            findings,
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors > 0
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>ebook-renamer integrity report</title>\n\
+             <style>body{font-family:sans-serif}td,th{padding:2px 8px;text-align:left}\
+             .error{color:#b00}.warning{color:#a60}.info{color:#666}</style>\n</head>\n<body>\n",
+        );
+        html.push_str("<h1>Integrity report</h1>\n");
+        html.push_str(&format!(
+            "<p>{} files checked: {} errors, {} warnings, {} info</p>\n",
+            self.files_checked, self.errors, self.warnings, self.infos
+        ));
+        html.push_str("<table>\n<tr><th>Severity</th><th>Check</th><th>File</th><th>Issue</th></tr>\n");
+        for finding in &self.findings {
+            html.push_str(&format!(
+                "<tr class=\"{0}\"><td>{0}</td><td>{1}</td><td>{2}</td><td>{3}</td></tr>\n",
+                finding.severity.label(),
+                finding.check,
+                escape_html(&finding.path),
+                escape_html(&finding.message)
+            ));
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn check_file(file: &FileInfo) -> Vec<(Severity, &'static str, String)> {
+    if file.is_failed_download {
+        return vec![(Severity::Error, "download", "Incomplete download".to_string())];
+    }
+    if file.size == 0 {
+        return vec![(Severity::Error, "zero-byte", "File is empty".to_string())];
+    }
+
+    let mut findings = Vec::new();
+    if file.is_too_small {
+        findings.push((Severity::Warning, "size", format!("Only {} bytes", file.size)));
+    }
+    // The format checks below would only repeat that the header is wrong
+    if file_kind::is_known_extension(&file.extension)
+        && let Ok(Some(kind)) = file_kind::sniff(&file.original_path)
+        && !kind.extensions().contains(&file.extension.to_lowercase().as_str())
+    {
+        findings.push((Severity::Error, "kind", format!("Content is {}, not {}", kind.name(), file.extension)));
+        return findings;
+    }
+    let checked = match file.extension.to_lowercase().as_str() {
+        ".pdf" => check_pdf(&file.original_path, &mut findings),
+        ".epub" => check_epub(&file.original_path, &mut findings),
+        _ => Ok(()),
+    };
+    if let Err(e) = checked {
+        findings.push((Severity::Error, "read", format!("Could not read file: {}", e)));
+    }
+    findings
+}
+
+/// Header, `%%EOF` trailer (missing means truncated) and encryption dictionary.
+fn check_pdf(path: &Path, findings: &mut Vec<(Severity, &'static str, String)>) -> Result<()> {
+    let (head, tail) = read_head_and_tail(path, 8)?;
+    if !head.starts_with(b"%PDF-") {
+        findings.push((Severity::Error, "pdf", "Missing %PDF- header".to_string()));
+        return Ok(());
+    }
+    if !contains(&tail, b"%%EOF") {
+        findings.push((Severity::Error, "pdf", "No %%EOF trailer, file is probably truncated".to_string()));
+    }
+    if contains(&tail, b"/Encrypt") {
+        findings.push((Severity::Info, "drm", "PDF is encrypted".to_string()));
+    }
+    Ok(())
+}
+
+/// Zip signature, `mimetype` entry, end of central directory (missing means
+/// truncated) and DRM markers in the central directory.
+fn check_epub(path: &Path, findings: &mut Vec<(Severity, &'static str, String)>) -> Result<()> {
+    let (head, tail) = read_head_and_tail(path, 58)?;
+    if !head.starts_with(b"PK\x03\x04") {
+        findings.push((Severity::Error, "epub", "Not a zip archive".to_string()));
+        return Ok(());
+    }
+    if !contains(&tail, b"PK\x05\x06") {
+        findings.push((Severity::Error, "epub", "Zip central directory missing, file is probably truncated".to_string()));
+        return Ok(());
+    }
+    // The first entry should be an uncompressed `mimetype` file
+    if !(head.len() >= 58 && &head[30..38] == b"mimetype" && &head[38..58] == b"application/epub+zip") {
+        findings.push((Severity::Warning, "epub", "First entry is not the EPUB mimetype".to_string()));
+    }
+    if contains(&tail, b"META-INF/rights.xml") {
+        findings.push((Severity::Warning, "drm", "EPUB is DRM protected (META-INF/rights.xml)".to_string()));
+    } else if contains(&tail, b"META-INF/encryption.xml") {
+        findings.push((Severity::Info, "drm", "EPUB has encrypted resources (fonts or DRM)".to_string()));
+    }
+    Ok(())
+}
+
+fn read_head_and_tail(path: &Path, head_len: usize) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut file = File::open(path)?;
+    let mut head = Vec::with_capacity(head_len);
+    file.by_ref().take(head_len as u64).read_to_end(&mut head)?;
+
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(TAIL_BYTES)))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+    Ok((head, tail))
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+/// Where `check --output` writes, or stdout.
+pub fn write_report(content: &str, output: Option<&PathBuf>) -> Result<()> {
+    match output {
+        Some(path) => std::fs::write(path, content)?,
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    fn file_info(path: PathBuf) -> FileInfo {
+        let size = fs::metadata(&path).unwrap().len();
+        let extension = format!(".{}", path.extension().unwrap().to_str().unwrap());
+        FileInfo {
+            original_name: path.file_name().unwrap().to_string_lossy().to_string(),
+            original_path: path.clone(),
+            is_too_small: size < 1024,
+            is_virtual: false,
+            extension,
+            size,
+            modified_time: SystemTime::now(),
+            is_failed_download: false,
+            new_name: None,
+            new_path: path,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_report_grades_findings() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path();
+        let mut good = b"%PDF-1.7\n".to_vec();
+        good.resize(2048, b' ');
+        good.extend_from_slice(b"\n%%EOF\n");
+        fs::write(root.join("good.pdf"), &good)?;
+        let mut truncated = b"%PDF-1.4\n".to_vec();
+        truncated.resize(2048, b' ');
+        fs::write(root.join("truncated.pdf"), &truncated)?;
+        fs::write(root.join("empty.epub"), "")?;
+        let mut epub = b"PK\x03\x04".to_vec();
+        epub.resize(30, 0);
+        epub.extend_from_slice(b"mimetypeapplication/epub+zip");
+        epub.resize(2048, 0);
+        epub.extend_from_slice(b"META-INF/encryption.xmlMETA-INF/rights.xmlPK\x05\x06");
+        fs::write(root.join("drm.epub"), &epub)?;
+
+        let files: Vec<FileInfo> = ["good.pdf", "truncated.pdf", "empty.epub", "drm.epub"]
+            .iter()
+            .map(|name| file_info(root.join(name)))
+            .collect();
+        let report = IntegrityReport::from_files(&files, root);
+
+        assert_eq!(report.files_checked, 4);
+        assert_eq!((report.errors, report.warnings, report.infos), (2, 1, 0));
+        assert!(report.has_errors());
+        assert_eq!(report.findings[0].path, "empty.epub");
+        assert_eq!(report.findings[1].path, "truncated.pdf");
+        assert_eq!((report.findings[2].check, report.findings[2].path.as_str()), ("drm", "drm.epub"));
+        assert!(report.to_html().contains("<tr class=\"error\"><td>error</td><td>zero-byte</td><td>empty.epub</td>"));
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Returned when a file operation did not finish within the configured limit.
+#[derive(Debug, thiserror::Error)]
+#[error("IO operation timed out after {0:?}")]
+pub struct IoTimeout(pub Duration);
+
+/// Run `op` on a helper thread and give up after `timeout`.
+///
+/// A hung read on a stale network mount cannot be cancelled, so the helper
+/// thread is left behind; the run carries on with the next file.
+pub fn run_with_timeout<T, F>(timeout: Option<Duration>, op: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let Some(timeout) = timeout else {
+        return op();
+    };
+
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(op());
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(IoTimeout(timeout).into()),
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(anyhow::anyhow!("IO worker thread panicked")),
+    }
+}
+
+pub fn is_timeout(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<IoTimeout>().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completes_within_timeout() -> Result<()> {
+        let value = run_with_timeout(Some(Duration::from_secs(5)), || Ok(42))?;
+        assert_eq!(value, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hung_operation_times_out() {
+        let result: Result<()> = run_with_timeout(Some(Duration::from_millis(20)), || {
+            thread::sleep(Duration::from_secs(2));
+            Ok(())
+        });
+        assert!(is_timeout(&result.unwrap_err()));
+    }
+
+    #[test]
+    fn test_no_timeout_runs_inline() -> Result<()> {
+        let value = run_with_timeout(None, || Ok("inline"))?;
+        assert_eq!(value, "inline");
+        Ok(())
+    }
+}
@@ -1,13 +1,39 @@
+use crate::annotations::AnnotatedGroup;
+use crate::archives::{ArchiveReport, Payload};
+use crate::arxiv_versions::VersionGroup;
+use crate::chapters::ChapterGroup;
+use crate::collections::CollectionStats;
+use crate::delete_mode::DeleteEffect;
+use crate::dir_groups::DirPlan;
+use crate::dir_renamer::DirRename;
+use crate::fingerprint::ProbableGroup;
+use crate::normalizer::ParsedMetadata;
 use crate::scanner::FileInfo;
+use crate::samples::Sample;
+use crate::skipped::SkipList;
+use crate::simulation::Issue;
+use crate::space_map::SpaceMap;
+use crate::split_parts::PartSet;
+use crate::variants::PairedCopy;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RenameOperation {
     pub from: String,
     pub to: String,
     pub reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// ISO 639-1 code of a translated copy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Author and title could be swapped; worth a manual look
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ambiguous_order: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,12 +55,240 @@ pub struct TodoItem {
     pub message: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProbableDuplicateGroup {
+    pub files: Vec<String>,
+    /// `text` or `content`
+    pub basis: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnnotatedDuplicateGroup {
+    pub files: Vec<String>,
+    pub annotated: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChapterGroupOutput {
+    pub book: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    pub parts: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DirectorySummary {
+    pub dir: String,
+    pub renames: usize,
+    pub duplicates: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CollectionSummary {
+    /// Empty for files outside every collection
+    pub collection: String,
+    pub files: usize,
+    pub renames: usize,
+    pub duplicates: usize,
+    pub small_or_failed: usize,
+    pub todo_items: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArxivVersionGroup {
+    pub id: String,
+    pub keep: String,
+    pub superseded: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivePayloadOutput {
+    /// Path inside the archive
+    pub entry: String,
+    /// Normalized name it gets once extracted
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveOutput {
+    pub path: String,
+    pub payloads: Vec<ArchivePayloadOutput>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SampleOutput {
+    pub path: String,
+    pub reason: String,
+    /// `quarantine`, `delete` or `report`
+    pub action: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PartSetOutput {
+    /// In reading order
+    pub parts: Vec<String>,
+    pub merged: String,
+    /// `merge` or `report`
+    pub action: String,
+}
+
+/// What the deletions in the plan will do, see `DeleteEffect`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteEffectOutput {
+    pub mode: String,
+    pub effect: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_dir: Option<String>,
+    pub synced: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PairedCopyOutput {
+    pub path: String,
+    pub base: String,
+    /// `null` when the content could not be compared (cloud mode)
+    pub identical: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanIssueOutput {
+    /// `overwrite`, `missing_source`, `kept_copy_missing` or `orphaned_sidecar`
+    pub kind: String,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub other: Option<String>,
+}
+
+/// Printed instead of the report when the run fails in `--json` mode.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorOutput {
+    pub error: String,
+    /// See `error::Category::code`
+    pub category: String,
+    pub exit_code: u8,
+}
+
+/// One line of `normalize-name --json`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NormalizedNameOutput {
+    pub input: String,
+    pub name: String,
+    pub authors: Option<String>,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub year: Option<u16>,
+    pub series: Option<String>,
+    pub edition: Option<String>,
+    pub volume: Option<String>,
+    pub publisher: Option<String>,
+    pub kind: Option<String>,
+    pub language: Option<String>,
+    /// `ISBN 9780262046305` or `ASIN B0F5TFL6ZQ`
+    pub identifier: Option<String>,
+}
+
+impl NormalizedNameOutput {
+    pub fn new(input: &str, name: String, metadata: ParsedMetadata) -> Self {
+        Self {
+            input: input.to_string(),
+            name,
+            authors: metadata.authors,
+            title: metadata.title,
+            subtitle: metadata.subtitle,
+            year: metadata.year,
+            series: metadata.series,
+            edition: metadata.edition,
+            volume: metadata.volume,
+            publisher: metadata.publisher,
+            kind: metadata.kind,
+            language: metadata.language,
+            identifier: metadata.identifier,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SizedPath {
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateSpaceOutput {
+    pub keep: String,
+    pub copies: usize,
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DirectorySpaceOutput {
+    pub path: String,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpaceMapOutput {
+    pub largest_files: Vec<SizedPath>,
+    pub duplicate_groups: Vec<DuplicateSpaceOutput>,
+    pub directories: Vec<DirectorySpaceOutput>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OperationsOutput {
     pub renames: Vec<RenameOperation>,
     pub duplicate_deletes: Vec<DuplicateGroup>,
     pub small_or_corrupted_deletes: Vec<DeleteOperation>,
     pub todo_items: Vec<TodoItem>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dir_renames: Vec<RenameOperation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pruned_dirs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapter_groups: Vec<ChapterGroupOutput>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotated_duplicates: Vec<AnnotatedDuplicateGroup>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub probable_duplicates: Vec<ProbableDuplicateGroup>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub native_docs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub directories: Vec<DirectorySummary>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arxiv_versions: Vec<ArxivVersionGroup>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedFile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub archives: Vec<ArchiveOutput>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub space_map: Option<SpaceMapOutput>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub samples: Vec<SampleOutput>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub split_parts: Vec<PartSetOutput>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sync_conflicts: Vec<PairedCopyOutput>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<PairedCopyOutput>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plan_issues: Vec<PlanIssueOutput>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collections: Vec<CollectionSummary>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delete_effect: Option<DeleteEffectOutput>,
 }
 
 impl OperationsOutput {
@@ -44,6 +298,24 @@ impl OperationsOutput {
             duplicate_deletes: Vec::new(),
             small_or_corrupted_deletes: Vec::new(),
             todo_items: Vec::new(),
+            dir_renames: Vec::new(),
+            pruned_dirs: Vec::new(),
+            chapter_groups: Vec::new(),
+            annotated_duplicates: Vec::new(),
+            probable_duplicates: Vec::new(),
+            native_docs: Vec::new(),
+            directories: Vec::new(),
+            arxiv_versions: Vec::new(),
+            skipped: Vec::new(),
+            archives: Vec::new(),
+            space_map: None,
+            samples: Vec::new(),
+            split_parts: Vec::new(),
+            sync_conflicts: Vec::new(),
+            variants: Vec::new(),
+            plan_issues: Vec::new(),
+            collections: Vec::new(),
+            delete_effect: None,
         }
     }
 
@@ -53,6 +325,7 @@ impl OperationsOutput {
         files_to_delete: Vec<PathBuf>,
         todo_items: Vec<(String, String, String)>, // (category, file, message)
         target_dir: &PathBuf,
+        include_metadata: bool, // extracted fields such as publisher; off by default for cross-language parity
     ) -> Result<Self> {
         let mut output = Self::new();
 
@@ -69,10 +342,17 @@ impl OperationsOutput {
                     .to_string_lossy()
                     .to_string();
                 
+                let metadata = file_info.metadata.as_ref().filter(|_| include_metadata);
+                let publisher = metadata.and_then(|m| m.publisher.clone());
+                let kind = metadata.and_then(|m| m.kind.clone());
                 renames.push(RenameOperation {
                     from: from_path,
                     to: to_path,
                     reason: "normalized".to_string(),
+                    publisher,
+                    kind,
+                    language: file_info.metadata.as_ref().and_then(|m| m.language.clone()),
+                    ambiguous_order: file_info.metadata.as_ref().is_some_and(|m| m.ambiguous_order),
                 });
             }
         }
@@ -139,15 +419,257 @@ impl OperationsOutput {
         Ok(output)
     }
 
+    pub fn set_dir_renames(&mut self, dir_renames: &[DirRename], target_dir: &Path) {
+        let mut renames: Vec<RenameOperation> = dir_renames
+            .iter()
+            .map(|r| RenameOperation {
+                from: r.from.strip_prefix(target_dir).unwrap_or(&r.from).to_string_lossy().to_string(),
+                to: r.to.strip_prefix(target_dir).unwrap_or(&r.to).to_string_lossy().to_string(),
+                reason: "normalized_dir".to_string(),
+                publisher: None,
+                kind: None,
+                language: None,
+                ambiguous_order: false,
+            })
+            .collect();
+        renames.sort_by(|a, b| a.from.cmp(&b.from));
+        self.dir_renames = renames;
+    }
+
+    pub fn set_pruned_dirs(&mut self, dirs: &[PathBuf], target_dir: &Path) {
+        let mut pruned: Vec<String> = dirs
+            .iter()
+            .map(|d| d.strip_prefix(target_dir).unwrap_or(d).to_string_lossy().to_string())
+            .collect();
+        pruned.sort();
+        self.pruned_dirs = pruned;
+    }
+
+    /// `folder` is only filled in when the parts will be moved there.
+    pub fn set_chapter_groups(&mut self, groups: &[ChapterGroup], target_dir: &Path, move_to_folder: bool) {
+        let relative = |p: &Path| p.strip_prefix(target_dir).unwrap_or(p).to_string_lossy().to_string();
+        self.chapter_groups = groups
+            .iter()
+            .map(|g| ChapterGroupOutput {
+                book: g.book_key.clone(),
+                folder: move_to_folder.then(|| relative(&g.folder())),
+                parts: g.parts.iter().map(|p| relative(&p.path)).collect(),
+            })
+            .collect();
+    }
+
+    pub fn set_probable_groups(&mut self, groups: &[ProbableGroup], target_dir: &Path) {
+        self.probable_duplicates = groups
+            .iter()
+            .map(|g| ProbableDuplicateGroup {
+                files: g.files.iter().map(|p| p.strip_prefix(target_dir).unwrap_or(p).to_string_lossy().to_string()).collect(),
+                basis: g.basis.code().to_string(),
+            })
+            .collect();
+    }
+
+    pub fn set_annotated_groups(&mut self, groups: &[AnnotatedGroup], target_dir: &Path) {
+        let relative = |paths: &[PathBuf]| -> Vec<String> {
+            let mut paths: Vec<String> = paths
+                .iter()
+                .map(|p| p.strip_prefix(target_dir).unwrap_or(p).to_string_lossy().to_string())
+                .collect();
+            paths.sort();
+            paths
+        };
+        let mut annotated: Vec<AnnotatedDuplicateGroup> = groups
+            .iter()
+            .map(|g| AnnotatedDuplicateGroup { files: relative(&g.files), annotated: relative(&g.annotated) })
+            .collect();
+        annotated.sort_by(|a, b| a.files.cmp(&b.files));
+        self.annotated_duplicates = annotated;
+    }
+
+    pub fn set_native_docs(&mut self, docs: &[PathBuf], target_dir: &Path) {
+        let mut native: Vec<String> = docs
+            .iter()
+            .map(|path| path.strip_prefix(target_dir).unwrap_or(path).to_string_lossy().to_string())
+            .collect();
+        native.sort();
+        self.native_docs = native;
+    }
+
+    /// Per-folder counts for `--group-by dir`; the target directory itself is `.`.
+    pub fn set_directories(&mut self, plans: &[DirPlan], target_dir: &Path) {
+        self.directories = plans
+            .iter()
+            .map(|plan| {
+                let dir = plan.dir.strip_prefix(target_dir).unwrap_or(&plan.dir).to_string_lossy().to_string();
+                DirectorySummary {
+                    dir: if dir.is_empty() { ".".to_string() } else { dir },
+                    renames: plan.renames.len(),
+                    duplicates: plan.duplicates.len(),
+                    deletions: plan.deletions.len(),
+                }
+            })
+            .collect();
+        self.directories.sort_by(|a, b| a.dir.cmp(&b.dir));
+    }
+
+    pub fn set_arxiv_versions(&mut self, groups: &[VersionGroup], target_dir: &Path) {
+        let relative = |p: &PathBuf| p.strip_prefix(target_dir).unwrap_or(p).to_string_lossy().to_string();
+        self.arxiv_versions = groups
+            .iter()
+            .map(|g| ArxivVersionGroup {
+                id: g.id.clone(),
+                keep: relative(&g.newest),
+                superseded: g.superseded.iter().map(relative).collect(),
+            })
+            .collect();
+    }
+
+    /// Only set with `--show-skipped`; sorted by path.
+    pub fn set_skipped(&mut self, skipped: &SkipList, target_dir: &Path) {
+        self.skipped = skipped
+            .iter()
+            .map(|(path, reason)| SkippedFile {
+                path: path.strip_prefix(target_dir).unwrap_or(path).to_string_lossy().to_string(),
+                reason: reason.code().to_string(),
+            })
+            .collect();
+    }
+
+    /// `name` gives the normalized name of each payload.
+    pub fn set_archives(&mut self, reports: &[ArchiveReport], target_dir: &Path, name: impl Fn(&Payload) -> String) {
+        let relative = |p: &Path| p.strip_prefix(target_dir).unwrap_or(p).to_string_lossy().to_string();
+        self.archives = reports
+            .iter()
+            .map(|report| ArchiveOutput {
+                path: relative(&report.path),
+                payloads: report
+                    .payloads
+                    .iter()
+                    .map(|p| ArchivePayloadOutput {
+                        entry: p.entry.clone(),
+                        name: name(p),
+                        duplicate_of: p.duplicate_of.as_deref().map(relative),
+                    })
+                    .collect(),
+                error: report.error.clone(),
+            })
+            .collect();
+        self.archives.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    /// Only set with `--top`.
+    pub fn set_space_map(&mut self, map: &SpaceMap, target_dir: &Path) {
+        let relative = |p: &Path| p.strip_prefix(target_dir).unwrap_or(p).to_string_lossy().to_string();
+        self.space_map = Some(SpaceMapOutput {
+            largest_files: map
+                .largest_files
+                .iter()
+                .map(|(path, bytes)| SizedPath { path: relative(path), bytes: *bytes })
+                .collect(),
+            duplicate_groups: map
+                .duplicate_groups
+                .iter()
+                .map(|g| DuplicateSpaceOutput { keep: relative(&g.keep), copies: g.copies, reclaimable_bytes: g.reclaimable })
+                .collect(),
+            directories: map
+                .directories
+                .iter()
+                .map(|d| DirectorySpaceOutput { path: relative(&d.path), files: d.files, bytes: d.bytes })
+                .collect(),
+        });
+    }
+
+    pub fn set_samples(&mut self, samples: &[Sample], target_dir: &Path, action: &str) {
+        self.samples = samples
+            .iter()
+            .map(|sample| SampleOutput {
+                path: sample.path.strip_prefix(target_dir).unwrap_or(&sample.path).to_string_lossy().to_string(),
+                reason: sample.reason.clone(),
+                action: action.to_string(),
+            })
+            .collect();
+        self.samples.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    pub fn set_split_parts(&mut self, sets: &[PartSet], target_dir: &Path, merge: bool) {
+        let relative = |path: &Path| path.strip_prefix(target_dir).unwrap_or(path).to_string_lossy().to_string();
+        self.split_parts = sets
+            .iter()
+            .map(|set| PartSetOutput {
+                parts: set.parts.iter().map(|p| relative(p)).collect(),
+                merged: relative(&set.merged_path()),
+                action: if merge { "merge" } else { "report" }.to_string(),
+            })
+            .collect();
+        self.split_parts.sort_by(|a, b| a.merged.cmp(&b.merged));
+    }
+
+    /// Only for plans that delete something.
+    pub fn set_delete_effect(&mut self, effect: &DeleteEffect) {
+        if self.duplicate_deletes.is_empty() && self.small_or_corrupted_deletes.is_empty() {
+            return;
+        }
+        self.delete_effect = Some(DeleteEffectOutput {
+            mode: effect.mode.to_string(),
+            effect: effect.effect.to_string(),
+            provider: effect.provider.map(str::to_string),
+            archive_dir: effect.archive_dir.as_ref().map(|dir| dir.to_string_lossy().to_string()),
+            synced: effect.synced,
+        });
+    }
+
+    /// Identical copies also appear in `duplicate_deletes`.
+    pub fn set_sync_conflicts(&mut self, conflicts: &[PairedCopy], target_dir: &Path) {
+        self.sync_conflicts = paired_outputs(conflicts, target_dir);
+    }
+
+    /// Identical variants also appear in `duplicate_deletes`.
+    pub fn set_variants(&mut self, variants: &[PairedCopy], target_dir: &Path) {
+        self.variants = paired_outputs(variants, target_dir);
+    }
+
+    pub fn set_collections(&mut self, stats: &[CollectionStats]) {
+        self.collections = stats
+            .iter()
+            .map(|s| CollectionSummary {
+                collection: s.name.clone(),
+                files: s.files,
+                renames: s.renamed,
+                duplicates: s.duplicates,
+                small_or_failed: s.small_or_failed,
+                todo_items: s.todo_items,
+            })
+            .collect();
+    }
+
+    /// Operations the plan check dropped or flagged.
+    pub fn set_plan_issues(&mut self, issues: &[Issue], target_dir: &Path) {
+        let relative = |p: &Path| p.strip_prefix(target_dir).unwrap_or(p).to_string_lossy().to_string();
+        self.plan_issues = issues
+            .iter()
+            .map(|issue| PlanIssueOutput {
+                kind: issue.kind.code().to_string(),
+                path: relative(&issue.path),
+                other: issue.other.as_deref().map(relative),
+            })
+            .collect();
+    }
+
     pub fn to_json(&self) -> Result<String> {
         Ok(serde_json::to_string_pretty(self)?)
     }
 }
 
+fn paired_outputs(copies: &[PairedCopy], target_dir: &Path) -> Vec<PairedCopyOutput> {
+    let relative = |p: &Path| p.strip_prefix(target_dir).unwrap_or(p).to_string_lossy().to_string();
+    copies
+        .iter()
+        .map(|c| PairedCopyOutput { path: relative(&c.path), base: relative(&c.base), identical: c.identical })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::Path;
     use std::time::SystemTime;
 
     #[test]
@@ -157,6 +679,10 @@ mod tests {
                 from: "old.pdf".to_string(),
                 to: "new.pdf".to_string(),
                 reason: "test".to_string(),
+                publisher: None,
+                kind: None,
+                language: None,
+                ambiguous_order: false,
             }],
             duplicate_deletes: vec![DuplicateGroup {
                 keep: "keep.pdf".to_string(),
@@ -171,6 +697,24 @@ mod tests {
                 file: "file.pdf".to_string(),
                 message: "message".to_string(),
             }],
+            dir_renames: Vec::new(),
+            pruned_dirs: Vec::new(),
+            chapter_groups: Vec::new(),
+            annotated_duplicates: Vec::new(),
+            probable_duplicates: Vec::new(),
+            native_docs: Vec::new(),
+            directories: Vec::new(),
+            arxiv_versions: Vec::new(),
+            skipped: Vec::new(),
+            archives: Vec::new(),
+            space_map: None,
+            samples: Vec::new(),
+            split_parts: Vec::new(),
+            sync_conflicts: Vec::new(),
+            variants: Vec::new(),
+            plan_issues: Vec::new(),
+            collections: Vec::new(),
+            delete_effect: None,
         };
 
         let json = output.to_json().unwrap();
@@ -197,8 +741,10 @@ mod tests {
             modified_time: SystemTime::now(),
             is_failed_download: false,
             is_too_small: false,
+            is_virtual: false,
             new_name: Some("renamed.pdf".to_string()),
             new_path: target_dir.join("renamed.pdf"),
+            metadata: None,
         };
 
         let duplicate_group = vec![
@@ -218,6 +764,7 @@ mod tests {
             files_to_delete,
             todo_items,
             &target_dir,
+            false,
         ).unwrap();
 
         assert_eq!(output.renames.len(), 1);
@@ -250,8 +797,10 @@ mod tests {
             modified_time: SystemTime::now(),
             is_failed_download: false,
             is_too_small: false,
+            is_virtual: false,
             new_name: Some("new.pdf".to_string()),
             new_path: target_dir.join("subdir").join("new.pdf"),
+            metadata: None,
         };
 
         let output = OperationsOutput::from_results(
@@ -260,10 +809,24 @@ mod tests {
             vec![],
             vec![],
             &target_dir,
+            false,
         ).unwrap();
 
         // Paths should be relative to target_dir
         #[cfg(not(windows))]
         assert_eq!(output.renames[0].from, "subdir/file.pdf");
     }
+
+    #[test]
+    fn test_normalized_name_output() {
+        let input = "Lang S. - Algebra (2002).pdf";
+        let metadata = crate::normalizer::parse_filename(input, ".pdf").unwrap();
+        let output = NormalizedNameOutput::new(input, "Serge Lang - Algebra (2002).pdf".to_string(), metadata);
+        let json: serde_json::Value = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["input"], input);
+        assert_eq!(json["title"], "Algebra");
+        assert_eq!(json["year"], 2002);
+        // Missing fields are null rather than absent, so scripts can index them
+        assert!(json["series"].is_null() && json.as_object().unwrap().contains_key("series"));
+    }
 }
@@ -0,0 +1,168 @@
+use crate::normalizer;
+use crate::search_key;
+use crate::scanner::FileInfo;
+use anyhow::Result;
+use log::debug;
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const KNOWN_AUTHORS_FILE_NAME: &str = ".ebook-renamer-known-authors.txt";
+
+// Surnames common on academic shelves. Words that also show up at the end
+// of titles ("Cover", "May", "Lane") are left out.
+const BUILTIN_SURNAMES: &[&str] = &[
+    "Adams", "Ahlfors", "Aho", "Apostol", "Arnold", "Arora", "Artin", "Ashcroft", "Atiyah", "Awodey",
+    "Axler", "Barak", "Bengio", "Billingsley", "Bishop", "Borceux", "Borel", "Bott", "Bourbaki", "Boyd",
+    "Bredon", "Brezis", "Bump", "Callen", "Carmo", "Carroll", "Cartan", "Cassels", "Chebyshev", "Chern",
+    "Conway", "Cormen", "Courant", "Davenport", "Dieudonné", "Dummit", "Durrett", "Eisenbud", "Euler",
+    "Evans", "Feller", "Feynman", "Folland", "Foote", "Fulton", "Gauss", "Gelfand", "Gilbarg", "Goldstein",
+    "Golub", "Goodfellow", "Griffiths", "Grothendieck", "Halliday", "Halmos", "Hardy", "Harris",
+    "Hartshorne", "Hastie", "Hatcher", "Helgason", "Herstein", "Hilbert", "Hopcroft", "Hörmander",
+    "Humphreys", "Hungerford", "Huybrechts", "Ireland", "Iwaniec", "Jackson", "Jacobson", "Johnstone",
+    "Jost", "Kac", "Kallenberg", "Karatzas", "Kashiwara", "Katznelson", "Kittel", "Kleinberg", "Kleppner",
+    "Knapp", "Knuth", "Koblitz", "Kobayashi", "Kolmogorov", "Kowalski", "Kreyszig", "Landau", "Lang",
+    "Lax", "Lee", "Leinster", "Lifshitz", "Macdonald", "Manin", "Marion", "Massey", "Matsumura", "Mermin",
+    "Milnor", "Misner", "Montgomery", "Mumford", "Munkres", "Murphy", "Needham", "Neukirch", "Nocedal",
+    "Noether", "Nomizu", "Øksendal", "Papadimitriou", "Pathria", "Peskin", "Petersen", "Poincaré",
+    "Protter", "Purcell", "Quillen", "Ravenel", "Reif", "Resnick", "Revuz", "Riehl", "Riemann", "Rosen",
+    "Royden", "Rudin", "Sakurai", "Schapira", "Sedgewick", "Serre", "Shakarchi", "Shankar", "Shannon",
+    "Shreve", "Silverman", "Sipser", "Skiena", "Spanier", "Spivak", "Srednicki", "Stein", "Strang",
+    "Switzer", "Tao", "Tardos", "Tate", "Thorne", "Tibshirani", "Titchmarsh", "Trefethen", "Trudinger",
+    "Tu", "Ullman", "Vakil", "Vandenberghe", "Vaughan", "Wald", "Warner", "Weibel", "Weil", "Weinberg",
+    "Wheeler", "Whittaker", "Zee", "Zygmund",
+];
+
+/// Author surnames that make one side of `A - B` more likely to be the
+/// author. Matching ignores case and looks at the last word of each name.
+#[derive(Debug, Clone, Default)]
+pub struct KnownAuthors {
+    surnames: HashSet<String>,
+}
+
+impl KnownAuthors {
+    pub fn builtin() -> Self {
+        let mut known = Self::default();
+        for surname in BUILTIN_SURNAMES {
+            known.add(surname);
+        }
+        known
+    }
+
+    /// Built-in surnames plus `<target-dir>/.ebook-renamer-known-authors.txt`
+    /// when present.
+    pub fn load(target_dir: &Path) -> Result<Self> {
+        let mut known = Self::builtin();
+        let path = target_dir.join(KNOWN_AUTHORS_FILE_NAME);
+        if path.exists() {
+            for name in read_names(&path)? {
+                known.add(&name);
+            }
+            debug!("Loaded known authors from {:?}", path);
+        }
+        Ok(known)
+    }
+
+    fn add(&mut self, name: &str) {
+        if let Some(surname) = surname_key(name) {
+            self.surnames.insert(surname);
+        }
+    }
+
+    /// Whether any of the comma- or `&`-separated names in `s` ends in a
+    /// known surname.
+    pub fn contains(&self, s: &str) -> bool {
+        s.split([',', '&']).filter_map(surname_key).any(|surname| self.surnames.contains(&surname))
+    }
+}
+
+fn surname_key(name: &str) -> Option<String> {
+    let last = name.split_whitespace().last()?;
+    let key: String = search_key::fold(last).chars().filter(|c| c.is_alphabetic()).collect();
+    // Initials say nothing about the name
+    (key.chars().count() >= 2).then_some(key)
+}
+
+/// Names listed one per line; blank lines and `#` comments are ignored.
+fn read_names(path: &Path) -> Result<Vec<String>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Authors of files that are already named the way this tool would name
+/// them, one per name in multi-author lists.
+pub fn authors_from_library(files: &[FileInfo]) -> Result<BTreeSet<String>> {
+    let mut authors = BTreeSet::new();
+    for file_info in files {
+        if file_info.is_failed_download || file_info.is_too_small {
+            continue;
+        }
+        let metadata = normalizer::parse_filename(&file_info.original_name, &file_info.extension)?;
+        let normalized = normalizer::generate_new_filename(&metadata, &file_info.extension, normalizer::DEFAULT_SUBTITLE_SEPARATOR);
+        if normalized != file_info.original_name || metadata.ambiguous_order {
+            continue;
+        }
+        if let Some(names) = &metadata.authors {
+            authors.extend(names.split(", ").map(str::trim).filter(|n| n.contains(' ')).map(str::to_string));
+        }
+    }
+    Ok(authors)
+}
+
+/// Merge `authors` into the library's known-authors file; returns its path
+/// and how many names were new.
+pub fn save_authors(target_dir: &Path, authors: &BTreeSet<String>) -> Result<(PathBuf, usize)> {
+    let path = target_dir.join(KNOWN_AUTHORS_FILE_NAME);
+    let existing: Vec<String> = if path.exists() { read_names(&path)? } else { Vec::new() };
+    let mut merged: BTreeSet<String> = existing.iter().cloned().collect();
+    let before = merged.len();
+    merged.extend(authors.iter().cloned());
+    let added = merged.len() - before;
+
+    let mut content = String::from("# One author per line; the last word is matched as a surname\n");
+    for name in &merged {
+        content.push_str(name);
+        content.push('\n');
+    }
+    fs::write(&path, content)?;
+    Ok((path, added))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_contains() {
+        let known = KnownAuthors::builtin();
+        assert!(known.contains("Walter Rudin"));
+        assert!(known.contains("rudin"));
+        assert!(known.contains("David S. Dummit, Richard M. Foote"));
+        assert!(!known.contains("Real and Complex Analysis"));
+        assert!(!known.contains("W."));
+    }
+
+    #[test]
+    fn test_learn_from_library() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        for name in ["Jane Quux - Algebra (2002).pdf", "some_download_v2.pdf", "Zed Corge, Ann Grault - Topology (1999).pdf"] {
+            fs::write(tmp_dir.path().join(name), vec![b'x'; 2048])?;
+        }
+        let files = Scanner::new(tmp_dir.path(), usize::MAX)?.scan()?;
+        let authors = authors_from_library(&files)?;
+        assert_eq!(authors.into_iter().collect::<Vec<_>>(), vec!["Ann Grault", "Jane Quux", "Zed Corge"]);
+
+        let (path, added) = save_authors(tmp_dir.path(), &BTreeSet::from(["Jane Quux".to_string()]))?;
+        assert_eq!(added, 1);
+        let known = KnownAuthors::load(tmp_dir.path())?;
+        assert!(known.contains("J. Quux"));
+        assert!(fs::read_to_string(path)?.ends_with("Jane Quux\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use crate::normalizer::{self, ParsedMetadata, DEFAULT_SUBTITLE_SEPARATOR};
+use crate::scanner::FileInfo;
+use crate::template;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// Naming convention inferred from an already organized library.
+#[derive(Debug)]
+pub struct LearnedConvention {
+    pub template: String,
+    pub subtitle_separator: String,
+    /// Files the template reproduces exactly
+    pub matched: usize,
+    /// Files that were analyzed
+    pub total: usize,
+    /// Other templates seen, with how many files they reproduce
+    pub alternatives: Vec<(String, usize)>,
+}
+
+/// Infer the dominant filename template. Each name is parsed, its field
+/// values are replaced by placeholders, and the candidate that reproduces
+/// the most names (missing fields degrade like `--template` does) wins.
+pub fn learn_convention(files: &[FileInfo]) -> Result<LearnedConvention> {
+    let mut samples = Vec::new();
+    for file_info in files {
+        if file_info.is_failed_download || file_info.is_too_small {
+            continue;
+        }
+        let stem = file_info.original_name.strip_suffix(&file_info.extension).unwrap_or(&file_info.original_name);
+        let metadata = normalizer::parse_filename(&file_info.original_name, &file_info.extension)?;
+        samples.push((stem.to_string(), metadata));
+    }
+
+    // The parser reads "A - B" as author first; if more files have the name
+    // on the B side, the library is organized title first
+    let order_bias: i32 = samples
+        .iter()
+        .filter_map(|(_, m)| m.authors.as_ref().map(|a| (normalizer::name_score(a) - normalizer::name_score(&m.title)).signum()))
+        .sum();
+    if order_bias < 0 {
+        for (stem, metadata) in &mut samples {
+            normalizer::swap_for_title_first(metadata, stem);
+        }
+    }
+
+    let mut candidates: HashMap<String, usize> = HashMap::new();
+    let mut separators: HashMap<String, usize> = HashMap::new();
+    for (stem, metadata) in &samples {
+        if let Some((template, separator)) = infer_template(stem, metadata) {
+            *candidates.entry(template).or_default() += 1;
+            if let Some(separator) = separator {
+                *separators.entry(separator).or_default() += 1;
+            }
+        }
+    }
+
+    let subtitle_separator = most_common(&separators).unwrap_or_else(|| DEFAULT_SUBTITLE_SEPARATOR.to_string());
+    let mut scored: Vec<(String, usize, usize)> = candidates
+        .into_iter()
+        .map(|(template, inferred)| {
+            let matched = samples
+                .iter()
+                .filter(|(stem, metadata)| template::render(&template, metadata, &subtitle_separator) == *stem)
+                .count();
+            (template, matched, inferred)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)).then(a.0.cmp(&b.0)));
+
+    let mut scored = scored.into_iter();
+    let (template, matched, _) = scored
+        .next()
+        .ok_or_else(|| anyhow!("No consistently named files found to learn from"))?;
+
+    Ok(LearnedConvention {
+        template,
+        subtitle_separator,
+        matched,
+        total: samples.len(),
+        alternatives: scored.map(|(template, matched, _)| (template, matched)).collect(),
+    })
+}
+
+/// Turn one filename stem into a template by replacing the parsed field
+/// values with placeholders. Returns `None` if anything but separators and
+/// brackets is left over. The second value is the subtitle separator used.
+fn infer_template(stem: &str, metadata: &ParsedMetadata) -> Option<(String, Option<String>)> {
+    let mut result = stem.to_string();
+    let mut separator = None;
+
+    // Title (with its subtitle) first, since the other fields may occur inside it
+    let title_start = result.find(&metadata.title)?;
+    let mut title_end = title_start + metadata.title.len();
+    if let Some(subtitle) = &metadata.subtitle {
+        let subtitle_start = title_end + result[title_end..].find(subtitle.as_str())?;
+        let between = &result[title_end..subtitle_start];
+        if between.is_empty() || between.chars().any(char::is_alphanumeric) {
+            return None;
+        }
+        separator = Some(between.to_string());
+        title_end = subtitle_start + subtitle.len();
+    }
+    result.replace_range(title_start..title_end, "{title}");
+
+    let year = metadata.year.map(|y| y.to_string());
+    let fields = [
+        ("authors", &metadata.authors),
+        ("series", &metadata.series),
+        ("publisher", &metadata.publisher),
+        ("kind", &metadata.kind),
+        ("edition", &metadata.edition),
+        ("year", &year),
+    ];
+    for (field, value) in fields {
+        if let Some(value) = value
+            && let Some(pos) = result.find(value.as_str())
+        {
+            result.replace_range(pos..pos + value.len(), &format!("{{{}}}", field));
+        }
+    }
+
+    let leftover: String = result.split('{').map(|part| part.split_once('}').map_or(part, |(_, rest)| rest)).collect();
+    if leftover.chars().any(char::is_alphanumeric) || template::validate(&result).is_err() {
+        return None;
+    }
+    Some((result, separator))
+}
+
+fn most_common(counts: &HashMap<String, usize>) -> Option<String> {
+    counts
+        .iter()
+        .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+        .map(|(value, _)| value.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_infer_template() {
+        let metadata = normalizer::parse_filename("Serge Lang - Algebra (2002).pdf", ".pdf").unwrap();
+        assert_eq!(
+            infer_template("Serge Lang - Algebra (2002)", &metadata),
+            Some(("{authors} - {title} ({year})".to_string(), None))
+        );
+
+        let metadata = normalizer::parse_filename("Algebra (Serge Lang, 2002).pdf", ".pdf").unwrap();
+        assert_eq!(infer_template("Algebra (Serge Lang, 2002) extra words", &metadata), None);
+    }
+
+    #[test]
+    fn test_learn_dominant_convention() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        for name in [
+            "Algebra - Serge Lang (2002).pdf",
+            "Topology - James Munkres (2000).pdf",
+            "Real Analysis - Halsey Royden (1988).pdf",
+            "Introduction to Smooth Manifolds - John M. Lee (2012).pdf",
+            "Walter Rudin - Principles of Mathematical Analysis (1976).pdf",
+        ] {
+            fs::write(tmp_dir.path().join(name), "%PDF-1.4 ".repeat(200))?;
+        }
+
+        let files = Scanner::new(tmp_dir.path(), 1)?.scan()?;
+        let learned = learn_convention(&files)?;
+
+        assert_eq!(learned.template, "{title} - {authors} ({year})");
+        assert_eq!(learned.total, 5);
+        // The author-first Rudin file is the odd one out
+        assert_eq!(learned.matched, 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+use crate::error::Error;
+use crate::normalizer;
+use crate::overrides::{MergedMetadata, Override};
+use crate::scanner::FileInfo;
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::LazyLock;
+
+/// Answers of earlier lookups, including "not found", by MD5.
+pub const CACHE_FILE_NAME: &str = ".ebook-renamer-libgen-cache.json";
+
+pub const DEFAULT_API_URL: &str = "https://libgen.is/json.php";
+const FIELDS: &str = "author,title,year,edition";
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+
+// A 32-digit hex token on its own: "Title -- 0f1e...e1f0 -- Anna's Archive.pdf", "0f1e...e1f0.pdf"
+static RE_MD5: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)(?:^|[^0-9a-z])([0-9a-f]{32})(?:[^0-9a-z]|$)").unwrap());
+static RE_EDITION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\D{0,20}?(\d{1,2})(?:\D|$)").unwrap());
+
+/// The Library Genesis MD5 a downloaded file still carries in its name.
+pub fn md5_in_name(name: &str) -> Option<String> {
+    RE_MD5.captures(name).map(|caps| caps[1].to_lowercase())
+}
+
+/// The fields of a Library Genesis record that go into a name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authors: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<u16>,
+    /// As the normalizer writes it, e.g. `2nd ed`; first editions are left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edition: Option<String>,
+}
+
+impl Record {
+    /// The first entry of a `json.php` answer; `None` for an empty list.
+    fn from_api(answer: &Value) -> Option<Self> {
+        let entry = answer.as_array()?.first()?;
+        let field = |key: &str| entry[key].as_str().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+        let edition = field("edition")
+            .and_then(|e| RE_EDITION.captures(&e).map(|caps| caps[1].to_string()))
+            .filter(|n| n != "1" && n != "0")
+            .map(|n| normalizer::edition_label(&n));
+        Some(Self {
+            authors: field("author"),
+            title: field("title"),
+            year: field("year").and_then(|y| y.get(..4)?.parse().ok()).filter(|y| (1450..=2100).contains(y)),
+            edition,
+        })
+    }
+
+    fn to_override(&self) -> Override {
+        Override {
+            authors: self.authors.clone(),
+            title: self.title.clone(),
+            year: self.year,
+            edition: self.edition.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Lookups for one run. After the first failed request the run goes on
+/// offline: only cached answers are used and filenames are parsed as usual.
+pub struct Lookup {
+    root: PathBuf,
+    api_url: String,
+    cache: BTreeMap<String, Option<Record>>,
+    changed: bool,
+    /// Why lookups stopped, once they did
+    pub offline: Option<String>,
+    pub found: usize,
+}
+
+impl Lookup {
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(CACHE_FILE_NAME);
+        let cache = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)
+                .map_err(|e| Error::InvalidFile { path: path.clone(), message: format!("invalid libgen cache: {}", e) })?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { root: root.to_path_buf(), api_url: DEFAULT_API_URL.to_string(), cache, changed: false, offline: None, found: 0 })
+    }
+
+    /// Query `api_url` instead of [`DEFAULT_API_URL`], e.g. a mirror.
+    pub fn with_api_url(mut self, api_url: Option<&str>) -> Self {
+        if let Some(url) = api_url {
+            self.api_url = url.to_string();
+        }
+        self
+    }
+
+    /// Add what Library Genesis knows about a file with an MD5 in its
+    /// name to `merged`.
+    pub fn apply(&mut self, file: &FileInfo, merged: &mut MergedMetadata) {
+        if file.is_failed_download || file.is_too_small {
+            return;
+        }
+        let Some(md5) = md5_in_name(&file.original_name) else {
+            return;
+        };
+        if let Some(record) = self.get(&md5) {
+            debug!("Library Genesis record for {}: {:?}", file.original_name, record);
+            merged.add_missing(&file.original_path, record.to_override());
+            self.found += 1;
+        }
+    }
+
+    fn get(&mut self, md5: &str) -> Option<Record> {
+        if let Some(cached) = self.cache.get(md5) {
+            return cached.clone();
+        }
+        if self.offline.is_some() {
+            return None;
+        }
+        match fetch(&self.api_url, md5) {
+            Ok(record) => {
+                self.cache.insert(md5.to_string(), record.clone());
+                self.changed = true;
+                record
+            }
+            Err(e) => {
+                warn!("Library Genesis lookup failed, using filenames only: {:#}", e);
+                self.offline = Some(format!("{:#}", e));
+                None
+            }
+        }
+    }
+
+    /// Write the cache if this run added to it.
+    pub fn save(&self) -> Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+        let path = self.root.join(CACHE_FILE_NAME);
+        fs::write(&path, serde_json::to_string_pretty(&self.cache)? + "\n")?;
+        info!("Saved {} Library Genesis lookups to {:?}", self.cache.len(), path);
+        Ok(())
+    }
+}
+
+fn fetch(api_url: &str, md5: &str) -> Result<Option<Record>> {
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location", "--max-time", &REQUEST_TIMEOUT_SECS.to_string()])
+        .args(["--get", api_url, "--data-urlencode", &format!("md5={}", md5), "--data-urlencode", &format!("fields={}", FIELDS)])
+        .output()
+        .map_err(|e| if e.kind() == io::ErrorKind::NotFound { Error::MissingTool("curl").into() } else { anyhow::Error::from(e) })?;
+    if !output.status.success() {
+        return Err(anyhow!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let answer: Value = serde_json::from_slice(&output.stdout).map_err(|e| anyhow!("unexpected answer from {}: {}", api_url, e))?;
+    Ok(Record::from_api(&answer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_md5_in_name() {
+        let md5 = "b3ab25f14db594eb0188171e0dd81250";
+        assert_eq!(
+            md5_in_name(&format!("Kashiwara - Systems -- 9780817631383 -- {} -- Anna's Archive.pdf", md5)).as_deref(),
+            Some(md5)
+        );
+        assert_eq!(md5_in_name(&format!("{}.pdf", md5.to_uppercase())).as_deref(), Some(md5));
+        assert_eq!(md5_in_name(&format!("x{}.pdf", md5)), None);
+        assert_eq!(md5_in_name("Lang - Algebra.pdf"), None);
+    }
+
+    #[test]
+    fn test_record_from_api() {
+        let answer = json!([{"author": "Serge Lang", "title": "Algebra", "year": "2002", "edition": "3rd ed."}]);
+        let record = Record::from_api(&answer).unwrap();
+        assert_eq!(record.authors.as_deref(), Some("Serge Lang"));
+        assert_eq!(record.year, Some(2002));
+        assert_eq!(record.edition.as_deref(), Some("3rd ed"));
+        let first = json!([{"title": "Algebra", "year": "", "edition": "1"}]);
+        assert_eq!(Record::from_api(&first).unwrap(), Record { title: Some("Algebra".to_string()), ..Default::default() });
+        assert_eq!(Record::from_api(&json!([])), None);
+    }
+
+    #[test]
+    fn test_edition_suffixes() {
+        for (edition, expected) in [("4", "4th ed"), ("11th", "11th ed"), ("12", "12th ed"), ("13", "13th ed"), ("21", "21st ed"), ("22nd ed.", "22nd ed"), ("23", "23rd ed")] {
+            let record = Record::from_api(&json!([{"edition": edition}])).unwrap();
+            assert_eq!(record.edition.as_deref(), Some(expected), "edition {}", edition);
+        }
+    }
+
+    #[test]
+    fn test_cached_and_offline_lookups() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path();
+        let cached = "b3ab25f14db594eb0188171e0dd81250";
+        let record = Record { authors: Some("Masaki Kashiwara".to_string()), title: Some("Systems of Microdifferential Equations".to_string()), year: Some(1983), edition: None };
+        fs::write(root.join(CACHE_FILE_NAME), serde_json::to_string(&BTreeMap::from([(cached, Some(record))]))?)?;
+
+        let file = |name: String| FileInfo {
+            original_path: root.join(&name),
+            original_name: name,
+            extension: ".pdf".to_string(),
+            size: 2048,
+            modified_time: SystemTime::now(),
+            is_failed_download: false,
+            is_too_small: false,
+            is_virtual: false,
+            new_name: None,
+            new_path: PathBuf::new(),
+            metadata: None,
+        };
+        let files = vec![file(format!("{}.pdf", cached)), file("0f1e2d3c4b5a69788796a5b4c3d2e1f0.pdf".to_string())];
+        let mut lookup = Lookup::load(root)?;
+        // Never reaches the network in tests
+        lookup.offline = Some("test".to_string());
+        let mut merged = MergedMetadata::default();
+        for file in &files {
+            lookup.apply(file, &mut merged);
+        }
+        assert_eq!(lookup.found, 1);
+        assert_eq!(merged.lookup(&files[0]).and_then(|o| o.year), Some(1983));
+        assert!(merged.lookup(&files[1]).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lookup_uses_configured_api_url() -> Result<()> {
+        if Command::new("curl").arg("--version").output().is_err() {
+            return Ok(());
+        }
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path();
+        // curl ignores the query string of a file: URL, so every MD5 gets this answer
+        let answer = root.join("json.php");
+        fs::write(&answer, json!([{"author": "Serge Lang", "title": "Algebra", "year": "2002"}]).to_string())?;
+        let url = format!("file://{}", answer.display());
+        let md5 = "0f1e2d3c4b5a69788796a5b4c3d2e1f0";
+
+        let mut lookup = Lookup::load(root)?.with_api_url(Some(&url));
+        let record = lookup.get(md5);
+        assert_eq!(lookup.offline, None);
+        assert_eq!(record.and_then(|r| r.year), Some(2002));
+        lookup.save()?;
+        assert!(Lookup::load(root)?.cache.contains_key(md5));
+
+        let mut unreachable = Lookup::load(&root.join("missing"))?.with_api_url(Some("file:///nonexistent/json.php"));
+        assert_eq!(unreachable.get("b3ab25f14db594eb0188171e0dd81250"), None);
+        assert!(unreachable.offline.is_some());
+        Ok(())
+    }
+}
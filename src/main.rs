@@ -7,204 +7,386 @@ mod json_output;
 mod download_recovery;
 mod tui;
 mod cloud;
+mod dir_renamer;
+mod prune;
+mod history;
+mod cleanup;
+mod io_timeout;
+mod authors;
+mod template;
+mod chapters;
+mod config;
+mod learn;
+mod dupe_ignore;
+mod annotations;
+mod permissions;
+mod name_diff;
+mod dir_groups;
+mod traversal;
+mod run_lock;
+mod integrity;
+mod redownload;
+mod arxiv_versions;
+mod overrides;
+mod known_authors;
+mod corpus;
+mod skipped;
+mod audit;
+mod archives;
+mod space_map;
+mod samples;
+mod sync_conflicts;
+mod in_use;
+mod pending_deletes;
+mod throttle;
+mod variants;
+mod subjects;
+mod simulation;
+mod rename_order;
+mod error;
+mod i18n;
+mod placeholders;
+mod file_kind;
+mod stubs;
+mod profiles;
+mod script;
+mod guard;
+mod notify;
+mod search_key;
+mod priority;
+mod scan_checkpoint;
+mod collections;
+mod server;
+mod catalog;
+mod split_parts;
+mod fingerprint;
+mod libgen;
+mod sync_ignore;
+mod original_names;
+mod delete_mode;
+mod pipeline;
 
 use anyhow::Result;
 use clap::Parser;
-use cli::Args;
+use cli::{Args, Command, OutputLevel};
 use log::info;
-use download_recovery::DownloadRecovery;
+use i18n::t;
 use colored::*;
+use std::process::ExitCode;
 
-fn main() -> Result<()> {
-    env_logger::Builder::from_default_env()
-        .format_timestamp_millis()
-        .init();
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let json = args.json;
+    let notify = args.notify;
+    match run(args) {
+        Ok(code) => code,
+        Err(e) => {
+            if notify {
+                notify::run_failed(&e);
+            }
+            let category = error::Category::of(&e);
+            if json {
+                let output = json_output::ErrorOutput {
+                    error: format!("{:#}", e),
+                    category: category.code().to_string(),
+                    exit_code: category.exit_code(),
+                };
+                println!("{}", serde_json::to_string_pretty(&output).unwrap_or_default());
+            } else {
+                eprintln!("Error: {:?}", e);
+            }
+            ExitCode::from(category.exit_code())
+        }
+    }
+}
 
-    let mut args = Args::parse();
+/// Fails for errors; `check` finding broken files is a failure exit code
+/// without an error.
+fn run(args: Args) -> Result<ExitCode> {
+    init_logging(&args)?;
+    i18n::init(args.lang);
+    // Without an override, `colored` checks for a terminal and NO_COLOR itself
+    if let Some(color) = args.color_override() {
+        colored::control::set_override(color);
+    }
     info!("Starting ebook renamer with args: {:?}", args);
+    // Before the TUI or hashing spawns threads, which inherit the priority
+    if args.nice {
+        priority::lower();
+    }
+
+    // One run per library at a time, taken before anything reads the library
+    let _run_lock = match lock_target(&args) {
+        Some((path, wait)) => Some(run_lock::RunLock::acquire(path, wait)?),
+        None => None,
+    };
 
-    // Auto-detect cloud storage and enable skip_cloud_hash if not explicitly set
-    if !args.skip_cloud_hash {
-        if let Some(provider) = cloud::is_cloud_storage_path(&args.path) {
-            if !args.json {
-                println!("{}", cloud::cloud_mode_warning(provider).yellow());
+    match &args.command {
+        Some(Command::Stats { path, last }) => print_stats(path, *last)?,
+        Some(Command::Changes { path, json, update }) => print_changes(path, *json, *update)?,
+        Some(Command::Cleanup(cleanup_args)) => run_cleanup(cleanup_args)?,
+        Some(Command::Learn { path, print, no_recursive, authors }) => {
+            run_learn(path, *print, *no_recursive, *authors)?;
+        }
+        Some(Command::Override { action }) => run_override(action)?,
+        Some(Command::NormalizeName { names, json, template, subtitle_separator, ascii_punctuation }) => {
+            run_normalize_name(names, *json, template.clone(), subtitle_separator.clone(), *ascii_punctuation)?;
+        }
+        Some(Command::Corpus { action: cli::CorpusAction::Add { names, to, expected, dir } }) => {
+            let dir = dir.clone().unwrap_or_else(corpus::default_dir);
+            let added = corpus::add(&dir, to, names, expected.as_deref())?;
+            for name in names {
+                let name = std::path::Path::new(name).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                println!("{}\n  => {}", name, corpus::normalize(&name)?.bright_black());
             }
-            args.skip_cloud_hash = true;
-            info!("Auto-enabled cloud mode for {} storage", provider.name());
+            let path = dir.join(format!("{}.txt", to)).display().to_string();
+            println!("{} {}", "✓".green().bold(), t!("corpus-added", added = added, total = names.len(), path = path));
         }
-    } else {
-        // User explicitly enabled cloud mode
-        if !args.json {
-            println!("{}", "⚠️  Cloud mode enabled: Using metadata-only duplicate detection.".yellow());
-            println!("{}", "   Duplicate detection based on filename similarity (≥85%) + exact size match.".yellow());
+        Some(Command::RestoreNames { path, dry_run, no_recursive }) => {
+            run_restore_names(path, *dry_run, *no_recursive)?;
+        }
+        Some(Command::Serve { socket, port }) => server::run(socket.as_deref(), *port)?,
+        Some(Command::Check { path, format, output, no_recursive }) => {
+            if !run_check(path, *format, output.as_ref(), *no_recursive)? {
+                return Ok(ExitCode::FAILURE);
+            }
         }
+        None => process_library(args)?,
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The main run over `args.path`, under the lock `run` took.
+fn process_library(mut args: Args) -> Result<()> {
+    // Read-only is a stricter dry-run
+    if args.read_only {
+        args.dry_run = true;
+    }
+    // --emit-script writes the plan for the user to run instead
+    if args.emit_script.is_some() {
+        args.dry_run = true;
+    }
+
+    // Learned/saved settings apply unless given on the command line
+    let config = config::Config::load(&args.path)?;
+    if args.template.is_none() {
+        args.template = config.template;
+    }
+    if args.subtitle_separator == normalizer::DEFAULT_SUBTITLE_SEPARATOR
+        && let Some(separator) = config.subtitle_separator {
+        args.subtitle_separator = separator;
+    }
+
+    // Then the profile of the configured root the target is under
+    let profiles = profiles::Profiles::load(args.profiles.as_deref())?;
+    let profile = profiles.select(&args.path).cloned();
+    if let Some(profile) = &profile {
+        info!("Using profile {}", profile.label());
+        profile.apply(&mut args)?;
+    }
+
+    // Rename-only keeps duplicates in place (other deleting flags are rejected by clap)
+    if args.rename_only {
+        args.no_delete = true;
+    }
+
+    // Fail before scanning if the template has typos
+    if let Some(template) = &args.template {
+        template::validate(template)?;
+    }
+
+    // Per-file console output; --quiet and --summary run without the TUI
+    let details = !args.json && args.output_level() == OutputLevel::Normal;
+
+    if details && let Some(profile) = &profile {
+        println!("{} {}", "ℹ".bright_blue(), t!("profile-selected", name = profile.label()));
+    }
+
+    // Synced folders get metadata-only duplicate detection in every frontend
+    let hash_mode = cloud::resolve_hash_mode(&mut args);
+    if details && let Some(message) = hash_mode.message() {
+        println!("{}", message.yellow());
+    }
+
+    if let Some(pair) = &args.ignore_dupe {
+        return record_ignored_dupe(&args.path, &pair[0], &pair[1], args.skip_cloud_hash);
     }
 
     // Handle --fetch-arxiv placeholder
-    if args.fetch_arxiv {
+    if args.fetch_arxiv && args.output_level() != OutputLevel::Quiet {
         println!("{} {}",
-            "⚠️  Warning:".yellow().bold(),
-            "--fetch-arxiv is not implemented yet. Files will be processed offline only.".yellow()
+            t!("warning-label").yellow().bold(),
+            t!("fetch-arxiv-not-implemented").yellow()
         );
     }
 
-    if !args.json {
-        return tui::run(args).map_err(|e| anyhow::anyhow!(e));
+    if args.confirm_deletes {
+        return run_confirm_deletes(&args);
     }
 
-    // Step 1: Recover downloads from .download/.crdownload folders
-    let recovery = DownloadRecovery::new(&args.path, args.cleanup_downloads);
-    let recovery_result = recovery.recover_downloads()?;
-    
-    if !recovery_result.extracted_files.is_empty() {
-        info!("Recovered {} PDFs from download folders", recovery_result.extracted_files.len());
-        if args.dry_run && !args.json {
-            println!("{} Recovered {} PDFs from download folders", 
-                "✓".green().bold(),
-                recovery_result.extracted_files.len().to_string().cyan()
-            );
-        }
+    if details && args.emit_script.is_none() {
+        return tui::run(args, hash_mode);
     }
-    
-    if !recovery_result.errors.is_empty() {
-        info!("Encountered {} errors during download recovery", recovery_result.errors.len());
-        if args.dry_run && !args.json {
-            for error in &recovery_result.errors {
-                println!("{}  {}", "⚠️".yellow(), error.yellow());
-            }
-        }
+
+    let mut reporter = ConsoleReporter::new(&args);
+    let mut plan = pipeline::plan(&args, &mut reporter)?;
+    if !plan.timed_out.is_empty() && details {
+        println!("{} {}",
+            "⚠️".yellow(),
+            t!("skipped-io-timeout", count = plan.timed_out.len().to_string().yellow().to_string())
+        );
     }
 
-    // Handle --no-recursive by setting max_depth to 1
-    let effective_max_depth = if args.no_recursive { 1 } else { args.max_depth };
-    
-    let mut scanner = scanner::Scanner::new(&args.path, effective_max_depth)?;
-    let files = scanner.scan()?;
-    info!("Found {} files to process", files.len());
-
-    // Parse and normalize filenames
-    let normalized = normalizer::normalize_files(files)?;
-    info!("Normalized {} files", normalized.len());
-
-    // Handle failed downloads and small files
-    let mut todo_list = todo::TodoList::new(&args.todo_file, &args.path)?;
-    let mut files_to_delete = Vec::new();
-    let mut todo_items = Vec::new();
-    
-    for file_info in &normalized {
-        // Add existing failed/too small files
-        if file_info.is_failed_download || file_info.is_too_small {
-            if args.delete_small {
-                files_to_delete.push(file_info.original_path.clone());
-                // Remove this file from todo list since we're deleting it
-                todo_list.remove_file_from_todo(&file_info.original_name);
-            } else if args.clean_failed {
-                // Log AND Delete
-                todo_list.add_failed_download(file_info)?;
-                files_to_delete.push(file_info.original_path.clone());
-
-                // Collect todo item for JSON output
-                let category = if file_info.is_failed_download { "failed_download" } else { "too_small" };
-                let message = if file_info.is_failed_download {
-                    format!("Redownload: {} (Unfinished download)", file_info.original_name)
-                } else {
-                    format!("Check and redownload: {} (File too small, only {} bytes)", file_info.original_name, file_info.size)
-                };
-                todo_items.push((category.to_string(), file_info.original_name.clone(), message));
-            } else {
-                todo_list.add_failed_download(file_info)?;
-                // Collect todo item for JSON output
-                let category = if file_info.is_failed_download { "failed_download" } else { "too_small" };
-                let message = if file_info.is_failed_download {
-                    format!("Redownload: {} (Unfinished download)", file_info.original_name)
-                } else {
-                    format!("Check and redownload: {} (File too small, only {} bytes)", file_info.original_name, file_info.size)
-                };
-                todo_items.push((category.to_string(), file_info.original_name.clone(), message));
+    if let Some(script_path) = &args.emit_script {
+        for path in script::write(script_path, &plan.renames(), &plan.deletions(&args))? {
+            if details {
+                println!("{} {}", "✓".green().bold(), t!("file-written", path = path.display().to_string()));
             }
-        } else {
-            // Analyze file integrity for all other files
-            todo_list.analyze_file_integrity(file_info)?;
         }
     }
 
-    // Detect duplicates (skip if cloud storage mode)
-    let (duplicate_groups, clean_files) = duplicates::detect_duplicates(normalized, args.skip_cloud_hash)?;
-    if args.skip_cloud_hash {
-        info!("Skipped duplicate detection (cloud storage mode)");
-    } else {
-        info!("Detected {} duplicate groups", duplicate_groups.len());
-    }
-
     // Show or execute renames
     if args.dry_run {
+        let delete_effect = delete_mode::DeleteEffect::new(args.delete_mode, &args.path.canonicalize()?, args.archive_dir.as_deref());
+        let dir_plans = match args.group_by {
+            Some(cli::GroupBy::Dir) => dir_groups::group_by_dir(&plan.clean_files, &plan.duplicate_groups, &plan.files_to_delete),
+            None => Vec::new(),
+        };
+
         if args.json {
-            // Output JSON format
-            let operations = json_output::OperationsOutput::from_results(
-                clean_files,
-                duplicate_groups,
-                files_to_delete,
-                todo_items,
+            // Output JSON format; the plan's file lists are not needed after this
+            let mut operations = json_output::OperationsOutput::from_results(
+                std::mem::take(&mut plan.clean_files),
+                std::mem::take(&mut plan.duplicate_groups),
+                std::mem::take(&mut plan.files_to_delete),
+                std::mem::take(&mut plan.todo_items),
                 &args.path,
+                args.template.is_some(),
             )?;
+            operations.set_dir_renames(&plan.dir_renames, &args.path);
+            operations.set_pruned_dirs(&plan.pruned_dirs, &args.path);
+            operations.set_chapter_groups(&plan.chapter_groups, &args.path, args.chapter_files == Some(cli::ChapterMode::Folder));
+            operations.set_annotated_groups(&plan.annotated_groups, &args.path);
+            operations.set_probable_groups(&plan.probable_groups, &args.path.canonicalize()?);
+            operations.set_native_docs(&plan.native_docs, &args.path);
+            operations.set_directories(&dir_plans, &args.path);
+            operations.set_arxiv_versions(&plan.version_groups, &args.path);
+            if args.show_skipped {
+                operations.set_skipped(&plan.skipped, &args.path);
+            }
+            operations.set_archives(&plan.archive_reports, &args.path, |payload| {
+                let name = payload.file_name();
+                normalizer::normalized_name(&name, &payload.extension(), &plan.normalize_options).unwrap_or(name)
+            });
+            if let Some(map) = &plan.space_map {
+                operations.set_space_map(map, &args.path.canonicalize()?);
+            }
+            operations.set_sync_conflicts(&plan.sync_conflicts, &args.path.canonicalize()?);
+            operations.set_variants(&plan.name_variants, &args.path.canonicalize()?);
+            operations.set_plan_issues(&plan.plan_issues, &args.path.canonicalize()?);
+            operations.set_collections(&plan.collection_stats);
+            if let Some(action) = args.drop_samples {
+                operations.set_samples(&plan.sample_files, &args.path.canonicalize()?, action.code());
+            }
+            operations.set_split_parts(&plan.part_sets, &args.path.canonicalize()?, args.merge_parts);
+            if !args.no_delete {
+                operations.set_delete_effect(&delete_effect);
+            }
             println!("{}", operations.to_json()?);
-        } else {
+        } else if details {
             // Human-readable output with rich text
-            println!("\n{}", "═══ DRY RUN MODE ═══".bold().bright_blue());
-            
-            if !clean_files.is_empty() {
-                let mut rename_count = 0;
-                for file_info in &clean_files {
-                    if let Some(ref new_name) = file_info.new_name {
-                        println!("{} {} {} {}", 
-                            "RENAME:".green().bold(),
-                            file_info.original_name.bright_white(),
-                            "→".bright_blue().bold(),
-                            new_name.bright_cyan()
+            println!("\n{}", t!("dry-run-banner").bold().bright_blue());
+
+            for dir_rename in &plan.dir_renames {
+                println!("{} {} {} {}",
+                    t!("label-rename-dir").green().bold(),
+                    dir_rename.from.display().to_string().bright_white(),
+                    "→".bright_blue().bold(),
+                    dir_rename.to.file_name().unwrap_or_default().to_string_lossy().bright_cyan()
+                );
+            }
+
+            if args.group_by == Some(cli::GroupBy::Dir) {
+                print_dir_plans(&dir_plans, &args.path);
+                print_annotated_groups(&plan.annotated_groups);
+                print_probable_groups(&plan.probable_groups);
+            } else {
+                if !plan.clean_files.is_empty() {
+                    let mut rename_count = 0;
+                    for file_info in &plan.clean_files {
+                        if let Some(ref new_name) = file_info.new_name {
+                            let (old_segments, new_segments) = name_diff::diff_names(&file_info.original_name, new_name);
+                            println!("{} {} {} {}", 
+                                t!("label-rename").green().bold(),
+                                name_diff::colorize(&old_segments, Color::BrightWhite),
+                                "→".bright_blue().bold(),
+                                name_diff::colorize(&new_segments, Color::BrightCyan)
+                            );
+                            rename_count += 1;
+                        }
+                    }
+                    if rename_count > 0 {
+                        println!("\n{} {}",
+                            "📝".bright_white(),
+                            t!("files-to-rename", count = rename_count.to_string().bright_cyan().bold().to_string())
                         );
-                        rename_count += 1;
                     }
                 }
-                if rename_count > 0 {
-                    println!("\n{} {} files to rename", 
-                        "📝".bright_white(),
-                        rename_count.to_string().bright_cyan().bold()
-                    );
-                }
-            }
             
-            for group in &duplicate_groups {
-                if group.len() > 1 {
-                    println!("\n{}", "🔍 DUPLICATE GROUP:".yellow().bold());
-                    for (idx, path) in group.iter().enumerate() {
-                        if idx == 0 {
-                            println!("  {} {}", 
-                                "KEEP:".bright_blue().bold(),
-                                path.display().to_string().bright_white()
-                            );
-                        } else {
-                            println!("  {} {}", 
-                                "DELETE:".red().bold(),
-                                path.display().to_string().bright_black()
-                            );
+                for group in &plan.duplicate_groups {
+                    if group.len() > 1 {
+                        println!("\n{}", t!("duplicate-group").yellow().bold());
+                        for (idx, path) in group.iter().enumerate() {
+                            if idx == 0 {
+                                println!("  {} {}", 
+                                    t!("label-keep").bright_blue().bold(),
+                                    path.display().to_string().bright_white()
+                                );
+                            } else {
+                                println!("  {} {}", 
+                                    t!("label-delete").red().bold(),
+                                    path.display().to_string().bright_black()
+                                );
+                            }
                         }
                     }
                 }
+
+                print_annotated_groups(&plan.annotated_groups);
+                print_probable_groups(&plan.probable_groups);
+                print_version_groups(&plan.version_groups, args.arxiv_versions);
+
+                if !plan.files_to_delete.is_empty() {
+                    println!("\n{}", t!("small-files-to-delete").red().bold());
+                    for path in &plan.files_to_delete {
+                        println!("  {} {}", 
+                            t!("label-delete").red().bold(),
+                            path.display().to_string().bright_black()
+                        );
+                    }
+                }
             }
 
-            if !files_to_delete.is_empty() {
-                println!("\n{}", "🗑️  SMALL/CORRUPTED/FAILED FILES TO DELETE:".red().bold());
-                for path in &files_to_delete {
-                    println!("  {} {}", 
-                        "DELETE:".red().bold(),
-                        path.display().to_string().bright_black()
+            print_chapter_groups(&plan.chapter_groups, args.chapter_files);
+
+            let deletes = plan.duplicate_groups.iter().filter(|g| g.len() > 1).count() + plan.files_to_delete.len();
+            if deletes > 0 && !args.no_delete {
+                println!("\n{} {}", "ℹ".bright_blue(), delete_effect.describe());
+            }
+
+            if !plan.pruned_dirs.is_empty() {
+                println!("\n{}", t!("empty-dirs-to-prune").red().bold());
+                for dir in &plan.pruned_dirs {
+                    println!("  {} {}",
+                        t!("label-remove-dir").red().bold(),
+                        dir.display().to_string().bright_black()
                     );
                 }
             }
-            
-            if !todo_list.items.is_empty() {
-                println!("\n{}", "📋 TODO LIST:".yellow().bold());
-                for item in &todo_list.items {
+
+            if !plan.todo_list.items.is_empty() {
+                println!("\n{}", t!("todo-list").yellow().bold());
+                for item in &plan.todo_list.items {
                     println!("  {} {}", 
                         "- [ ]".bright_yellow(),
                         item.bright_white()
@@ -212,63 +394,811 @@ fn main() -> Result<()> {
                 }
             }
         }
-        
-        // Write todo.md even in dry-run mode (as requested)
-        todo_list.write()?;
-        if !args.json {
-            println!("\n{} todo.md written (dry-run mode)", "✓".green().bold());
-        }
     } else {
-        // Execute renames
-        for file_info in &clean_files {
-            if let Some(ref new_name) = file_info.new_name {
-                std::fs::rename(&file_info.original_path, &file_info.new_path)?;
-                info!("Renamed: {} -> {}", file_info.original_name, new_name);
+        pipeline::execute(&args, &mut plan, &mut reporter)?;
+        if let Some(pending) = &plan.pending
+            && !args.json
+            && args.output_level() != OutputLevel::Quiet
+        {
+            print_pending_deletes(pending, &args.path.canonicalize()?);
+        }
+        // The TUI logs them; a console run with per-file output lists them here
+        if details && !plan.pruned_dirs.is_empty() {
+            println!("\n{}", t!("empty-dirs-pruned").red().bold());
+            for dir in &plan.pruned_dirs {
+                println!("  {} {}",
+                    t!("label-remove-dir").red().bold(),
+                    dir.display().to_string().bright_black()
+                );
             }
         }
+    }
 
-        // Delete duplicates
-        if !args.no_delete {
-            for group in &duplicate_groups {
-                if group.len() > 1 {
-                    for (idx, path) in group.iter().enumerate() {
-                        if idx > 0 {
-                            std::fs::remove_file(path)?;
-                            info!("Deleted duplicate: {}", path.display());
-                        }
-                    }
-                }
-            }
+    // todo.md is written even in dry-run mode (as requested), but never in read-only mode
+    pipeline::finish(&args, &plan)?;
+    if args.dry_run && details {
+        if args.read_only {
+            println!("\n{} {}", "ℹ".bright_blue(), t!("todo-not-written"));
+        } else {
+            println!("\n{} {}", "✓".green().bold(), t!("todo-written-dry-run"));
+        }
+    }
+
+    if args.show_skipped && !args.json {
+        print_skipped(&plan.skipped, &args.path);
+    }
+
+    if details {
+        print_plan_issues(&plan.plan_issues, &args.path.canonicalize()?);
+        print_part_sets(&plan.part_sets, &args.path.canonicalize()?, args.merge_parts);
+        print_collection_stats(&plan.collection_stats);
+        println!("\n{} {}", 
+            "✓".green().bold(),
+            t!("operation-completed").bright_green().bold()
+        );
+    } else if !args.json && args.output_level() == OutputLevel::Summary {
+        print_run_summary(&plan.run_stats, plan.todo_list.items.len());
+        print_collection_stats(&plan.collection_stats);
+        if let Some(map) = &plan.space_map {
+            print_space_map(map, &args.path.canonicalize()?);
+        }
+        if let Some(action) = args.drop_samples {
+            print_samples(&plan.sample_files, &args.path.canonicalize()?, action);
         }
+        print_part_sets(&plan.part_sets, &args.path.canonicalize()?, args.merge_parts);
+        print_plan_issues(&plan.plan_issues, &args.path.canonicalize()?);
+    }
+    Ok(())
+}
+
+/// Progress and notes of a console run: the scan's progress line on stderr,
+/// notes only with per-file output, and warnings unless quiet or JSON.
+struct ConsoleReporter {
+    details: bool,
+    warnings: bool,
+    progress_json: bool,
+    show_progress: bool,
+}
+
+impl ConsoleReporter {
+    fn new(args: &Args) -> Self {
+        Self {
+            details: !args.json && args.output_level() == OutputLevel::Normal,
+            warnings: !args.json && args.output_level() != OutputLevel::Quiet,
+            progress_json: args.progress_json,
+            // stdout carries the JSON, so progress goes to stderr and only when someone is watching
+            show_progress: std::io::IsTerminal::is_terminal(&std::io::stderr()) && !args.progress_json,
+        }
+    }
+}
+
+impl pipeline::Reporter for ConsoleReporter {
+    fn scan_progress(&mut self) -> Option<scanner::ScanProgress> {
+        if self.progress_json {
+            // One JSON object per line for `serve`, which passes them on to its client
+            Some(Box::new(|walked, folder| {
+                eprintln!("{}", serde_json::json!({ "walked": walked, "folder": folder }));
+            }))
+        } else if self.show_progress {
+            Some(Box::new(|walked, folder| {
+                eprint!("\r\x1b[2K{} {}", "⏳".bright_white(), t!("scanning", walked = walked, folder = folder.display().to_string()));
+            }))
+        } else {
+            None
+        }
+    }
+
+    fn stage(&mut self, stage: pipeline::Stage) -> Result<()> {
+        if self.show_progress && matches!(stage, pipeline::Stage::Scanned(_)) {
+            eprint!("\r\x1b[2K");
+        }
+        Ok(())
+    }
+
+    fn info(&mut self, message: String) -> Result<()> {
+        if self.details {
+            println!("{} {}", "ℹ".bright_blue(), message);
+        }
+        Ok(())
+    }
 
-        // Delete small/corrupted/failed files if requested
-        if (args.delete_small || args.clean_failed) && !files_to_delete.is_empty() {
-            println!("\n{} {} small/corrupted/failed files...",
-                "🗑️".bright_white(),
-                files_to_delete.len().to_string().red().bold()
+    fn warn(&mut self, message: String) -> Result<()> {
+        if self.warnings {
+            eprintln!("{}  {}", "⚠️".yellow(), message.yellow());
+        }
+        Ok(())
+    }
+}
+
+/// Log to stderr, or to `--log-file` (appending) so logs don't draw over the TUI.
+fn init_logging(args: &Args) -> Result<()> {
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(args.log_level()).parse_default_env().format_timestamp_millis();
+    if let Some(color) = args.color_override() {
+        builder.write_style(if color { env_logger::WriteStyle::Always } else { env_logger::WriteStyle::Never });
+    }
+    if let Some(path) = &args.log_file {
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(path)
+            .map_err(|e| anyhow::anyhow!("Cannot open log file {}: {}", path.display(), e))?;
+        builder.target(env_logger::Target::Pipe(Box::new(file)));
+    }
+    builder.init();
+    Ok(())
+}
+
+fn print_run_summary(stats: &history::RunStats, todo_count: usize) {
+    println!("{}", t!(if stats.dry_run { "summary-banner-dry-run" } else { "summary-banner" }).bold().bright_blue());
+    let mut rows = vec![
+        (t!("summary-processed"), stats.files_processed.to_string()),
+        (t!("summary-renamed"), stats.renamed.to_string()),
+        (t!("summary-duplicates"), stats.duplicates.to_string()),
+        (t!("summary-small-failed"), stats.small_or_failed.to_string()),
+        (t!("summary-todo-items"), todo_count.to_string()),
+        (t!("summary-reclaimed"), history::format_bytes(stats.bytes_reclaimed)),
+    ];
+    if stats.pruned_dirs > 0 {
+        rows.push((t!("summary-pruned-dirs"), stats.pruned_dirs.to_string()));
+    }
+    for (label, value) in rows {
+        println!("  {:<14} {:>10}", label, value.bright_cyan());
+    }
+}
+
+fn print_collection_stats(stats: &[collections::CollectionStats]) {
+    if stats.is_empty() {
+        return;
+    }
+    println!("{}", t!("collections-banner").bold().bright_blue());
+    println!("  {:<20} {:>10} {:>10} {:>10} {:>12} {:>10}",
+        t!("summary-collection"), t!("summary-processed"), t!("summary-renamed"),
+        t!("summary-duplicates"), t!("summary-small-failed"), t!("summary-todo-items"));
+    for s in stats {
+        let name = if s.name.is_empty() { t!("collection-none") } else { s.name.clone() };
+        println!("  {:<20} {:>10} {:>10} {:>10} {:>12} {:>10}",
+            name.bright_white(), s.files, s.renamed, s.duplicates, s.small_or_failed, s.todo_items);
+    }
+}
+
+fn print_pending_deletes(pending: &pending_deletes::PendingDeletes, target_dir: &std::path::Path) {
+    if pending.deletes.is_empty() {
+        return;
+    }
+    println!("\n{} {}", "🗑️".bright_white(), t!("deletions-previewed", count = pending.deletes.len().to_string().red().bold().to_string()));
+    for delete in &pending.deletes {
+        println!("  {} {}",
+            t!("label-pending-delete").red().bold(),
+            delete.path.strip_prefix(target_dir).unwrap_or(&delete.path).display().to_string().bright_black()
+        );
+    }
+    println!("{}", t!("review-then-confirm", flag = "--confirm-deletes".bright_cyan().to_string()));
+}
+
+/// Second phase of `--dry-run-deletes`.
+fn run_confirm_deletes(args: &Args) -> Result<()> {
+    let Some(pending) = pending_deletes::PendingDeletes::load(&args.path)? else {
+        if args.output_level() != OutputLevel::Quiet {
+            println!("{} {}", "ℹ".bright_blue(), t!("no-pending-deletions"));
+        }
+        return Ok(());
+    };
+    let deleter = delete_mode::Deleter::new(args.delete_mode, &args.path, args.archive_dir.as_deref())?;
+    let (deleted, errors) = pending.execute(&args.path, &deleter)?;
+
+    let mut run_stats = history::RunStats::new(false);
+    run_stats.duplicates = deleted.iter().filter(|d| d.kind == pending_deletes::DeleteKind::Duplicate).count();
+    run_stats.bytes_reclaimed = deleted.iter().map(|d| d.size).sum();
+    run_stats.audit_issues = errors.clone();
+    if let Err(e) = history::record_run(&args.path, &run_stats) {
+        info!("Failed to record run statistics: {}", e);
+    }
+
+    for error in &errors {
+        log::warn!("{}", error);
+        eprintln!("{}  {}", "⚠️".yellow(), error.yellow());
+    }
+    if args.output_level() != OutputLevel::Quiet {
+        println!("{} {}",
+            "✓".green().bold(),
+            t!("deleted-previewed",
+                deleted = deleted.len().to_string().cyan().to_string(),
+                total = pending.deletes.len(),
+                created = pending.created.clone(),
+                reclaimed = history::format_bytes(run_stats.bytes_reclaimed)
+            )
+        );
+    }
+    Ok(())
+}
+
+fn print_samples(samples: &[samples::Sample], target_dir: &std::path::Path, action: cli::SampleAction) {
+    if samples.is_empty() {
+        return;
+    }
+    let label = match action {
+        cli::SampleAction::Quarantine => t!("label-quarantine"),
+        cli::SampleAction::Delete => t!("label-delete"),
+        cli::SampleAction::Report => t!("label-sample"),
+    };
+    println!("\n{} {}", "🧪".bright_white(), t!("sample-files", count = samples.len().to_string().cyan().to_string()));
+    for sample in samples {
+        println!("  {} {} {}",
+            label.yellow().bold(),
+            sample.path.strip_prefix(target_dir).unwrap_or(&sample.path).display().to_string().bright_white(),
+            format!("({})", sample.reason).bright_black()
+        );
+    }
+}
+
+fn print_part_sets(sets: &[split_parts::PartSet], target_dir: &std::path::Path, merge: bool) {
+    if sets.is_empty() {
+        return;
+    }
+    let relative = |path: &std::path::Path| path.strip_prefix(target_dir).unwrap_or(path).display().to_string();
+    let label = if merge { t!("label-merge") } else { t!("label-parts") };
+    println!("\n{} {}", "📑".bright_white(), t!("part-sets", count = sets.len().to_string().cyan().to_string()));
+    for set in sets {
+        println!("  {} {}", label.yellow().bold(), relative(&set.merged_path()).bright_white());
+        for part in &set.parts {
+            println!("    {}", relative(part).bright_black());
+        }
+    }
+}
+
+fn print_plan_issues(issues: &[simulation::Issue], target_dir: &std::path::Path) {
+    if issues.is_empty() {
+        return;
+    }
+    let relative = |path: &std::path::Path| path.strip_prefix(target_dir).unwrap_or(path).display().to_string();
+    println!("\n{} {}", "⚠️".yellow(), t!("plan-check-issues", count = issues.len().to_string().yellow().to_string()));
+    for issue in issues {
+        let other = issue.other.as_deref().map(|p| format!(" ({})", relative(p))).unwrap_or_default();
+        println!("  {} {}{}",
+            format!("{}:", issue.kind.code().to_uppercase()).yellow().bold(),
+            relative(&issue.path).bright_white(),
+            other.bright_black()
+        );
+    }
+}
+
+fn print_space_map(map: &space_map::SpaceMap, target_dir: &std::path::Path) {
+    let relative = |path: &std::path::Path| {
+        let path = path.strip_prefix(target_dir).unwrap_or(path).display().to_string();
+        if path.is_empty() { ".".to_string() } else { path }
+    };
+    println!("\n{}", t!("space-largest-files").bold());
+    for (path, bytes) in &map.largest_files {
+        println!("  {:>10}  {}", history::format_bytes(*bytes).bright_cyan(), relative(path));
+    }
+    if !map.duplicate_groups.is_empty() {
+        println!("\n{}", t!("space-largest-duplicate-groups").bold());
+        for group in &map.duplicate_groups {
+            println!("  {:>10}  {} {}",
+                history::format_bytes(group.reclaimable).bright_cyan(),
+                relative(&group.keep),
+                t!("space-extra-copies", count = group.copies).bright_black()
             );
-            for path in &files_to_delete {
-                if !args.dry_run {
-                    std::fs::remove_file(path)?;
-                    info!("Deleted small/corrupted/failed file: {}", path.display());
-                    println!("  {} {}",
-                        "Deleted:".red().bold(),
-                        path.display().to_string().bright_black()
-                    );
+        }
+    }
+    println!("\n{}", t!("space-largest-folders").bold());
+    for dir in &map.directories {
+        println!("  {:>10}  {} {}",
+            history::format_bytes(dir.bytes).bright_cyan(),
+            relative(&dir.path),
+            t!("space-folder-files", count = dir.files).bright_black()
+        );
+    }
+}
+
+fn print_skipped(skipped: &skipped::SkipList, target_dir: &std::path::Path) {
+    println!("\n{} {}", "⏭".bright_blue(), t!("files-skipped", count = skipped.len().to_string().cyan().to_string()));
+    for (path, reason) in skipped.iter() {
+        println!("  {} {} {}",
+            t!("label-skipped").bright_black().bold(),
+            path.strip_prefix(target_dir).unwrap_or(path).display().to_string().bright_white(),
+            format!("({})", reason.description()).bright_black()
+        );
+    }
+}
+
+fn print_dir_plans(plans: &[dir_groups::DirPlan], target_dir: &std::path::Path) {
+    for plan in plans {
+        let dir = plan.dir.strip_prefix(target_dir).unwrap_or(&plan.dir).display().to_string();
+        println!("\n{} {} {}",
+            "📁".bright_white(),
+            if dir.is_empty() { ".".to_string() } else { dir }.bright_white().bold(),
+            t!("dir-plan-counts", renames = plan.renames.len(), duplicates = plan.duplicates.len(), deletions = plan.deletions.len()).bright_black()
+        );
+        for (old_name, new_name) in &plan.renames {
+            let (old_segments, new_segments) = name_diff::diff_names(old_name, new_name);
+            println!("  {} {} {} {}",
+                t!("label-rename").green().bold(),
+                name_diff::colorize(&old_segments, Color::BrightWhite),
+                "→".bright_blue().bold(),
+                name_diff::colorize(&new_segments, Color::BrightCyan)
+            );
+        }
+        for path in &plan.duplicates {
+            println!("  {} {}", t!("label-duplicate").yellow().bold(), path.file_name().unwrap_or_default().to_string_lossy().bright_black());
+        }
+        for path in &plan.deletions {
+            println!("  {} {}", t!("label-delete").red().bold(), path.file_name().unwrap_or_default().to_string_lossy().bright_black());
+        }
+    }
+}
+
+fn print_annotated_groups(groups: &[annotations::AnnotatedGroup]) {
+    for group in groups {
+        println!("\n{}", t!("annotated-duplicates").yellow().bold());
+        for path in &group.files {
+            let label = if group.annotated.contains(path) { t!("label-annotated") } else { t!("label-copy") };
+            println!("  {} {}", label.yellow().bold(), path.display().to_string().bright_white());
+        }
+    }
+}
+
+fn print_probable_groups(groups: &[fingerprint::ProbableGroup]) {
+    for group in groups {
+        println!("\n{}", t!("probable-duplicates").yellow().bold());
+        let label = match group.basis {
+            fingerprint::Basis::Text => t!("label-same-text"),
+            fingerprint::Basis::Content => t!("label-same-pages"),
+        };
+        for path in &group.files {
+            println!("  {} {}", label.yellow().bold(), path.display().to_string().bright_white());
+        }
+    }
+}
+
+fn print_version_groups(groups: &[arxiv_versions::VersionGroup], policy: Option<cli::ArxivVersions>) {
+    let label = t!(if policy == Some(cli::ArxivVersions::Newest) { "label-superseded" } else { "label-older" });
+    for group in groups {
+        println!("\n{} {}", t!("arxiv-versions").bright_magenta().bold(), group.id.bright_white());
+        println!("  {} {}", t!("label-newest").bright_blue().bold(), group.newest.display().to_string().bright_white());
+        for path in &group.superseded {
+            println!("  {} {}", label.bright_magenta().bold(), path.display().to_string().bright_black());
+        }
+    }
+}
+
+fn print_chapter_groups(groups: &[chapters::ChapterGroup], mode: Option<cli::ChapterMode>) {
+    for group in groups {
+        println!("\n{} {}", t!("chapter-files").bright_magenta().bold(), group.book_key.bright_white());
+        if mode == Some(cli::ChapterMode::Folder) {
+            println!("  {} {}", t!("label-folder").bright_blue().bold(), group.folder().display().to_string().bright_cyan());
+        }
+        for part in &group.parts {
+            let pages = part.pages.map(|p| t!("chapter-pages", count = p)).unwrap_or_default();
+            println!("  {} {} {}",
+                t!("label-part").bright_magenta().bold(),
+                part.path.file_name().unwrap_or_default().to_string_lossy().bright_white(),
+                format!("({}{})", part.kind.label(), pages).bright_black()
+            );
+        }
+    }
+}
+
+fn print_changes(path: &std::path::Path, json: bool, update: bool) -> Result<()> {
+    let Some(previous) = catalog::Catalog::load(path)? else {
+        return Err(error::Error::NotFound { what: "Catalog", path: path.join(catalog::CATALOG_FILE_NAME) }.into());
+    };
+    let current = catalog::Catalog::snapshot(path, previous.max_depth)?;
+    let changes = previous.changes(&current);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+    } else if changes.is_empty() {
+        println!("{} {}", "✓".green().bold(), t!("changes-none", since = changes.since.clone()));
+    } else {
+        println!("\n{}", t!("changes-banner", since = changes.since.clone()).bold().bright_blue());
+        for file in &changes.added {
+            println!("  {} {}", "+".green().bold(), file);
+        }
+        for file in &changes.removed {
+            println!("  {} {}", "-".red().bold(), file);
+        }
+        for rename in &changes.renamed {
+            println!("  {} {} {} {}", "→".cyan().bold(), rename.from, "=>".bright_black(), rename.to);
+        }
+        for file in &changes.modified {
+            println!("  {} {}", "~".yellow().bold(), file);
+        }
+        println!("\n{} {}",
+            "📊".bright_white(),
+            t!("changes-summary",
+                added = changes.added.len().to_string().green().bold().to_string(),
+                removed = changes.removed.len().to_string().red().bold().to_string(),
+                renamed = changes.renamed.len().to_string().cyan().bold().to_string(),
+                modified = changes.modified.len().to_string().yellow().bold().to_string()
+            )
+        );
+    }
+
+    if update {
+        current.save(path)?;
+    }
+    Ok(())
+}
+
+fn print_stats(path: &std::path::Path, last: usize) -> Result<()> {
+    let runs = history::load_history(path)?;
+    if runs.is_empty() {
+        println!("{} {}", "ℹ".bright_blue(), t!("stats-no-history", path = path.display().to_string()));
+        return Ok(());
+    }
+
+    let recent = &runs[runs.len().saturating_sub(last)..];
+    println!("\n{}", t!("stats-banner").bold().bright_blue());
+    println!("{:<16} {:<7} {:>9} {:>8} {:>11} {:>13} {:>10}",
+        t!("stats-date"),
+        t!("stats-mode"),
+        t!("summary-processed"),
+        t!("summary-renamed"),
+        t!("summary-duplicates"),
+        t!("summary-small-failed"),
+        t!("summary-reclaimed")
+    );
+    for run in recent {
+        println!("{:<16} {:<7} {:>9} {:>8} {:>11} {:>13} {:>10}",
+            &run.timestamp[..run.timestamp.len().min(16)],
+            if run.dry_run { t!("stats-dry-run") } else { t!("stats-live") },
+            run.files_processed,
+            run.renamed,
+            run.duplicates,
+            run.small_or_failed,
+            history::format_bytes(run.bytes_reclaimed)
+        );
+    }
+
+    let total_reclaimed: u64 = runs.iter().filter(|r| !r.dry_run).map(|r| r.bytes_reclaimed).sum();
+    println!("\n{} {}",
+        "📊".bright_white(),
+        t!("stats-total",
+            count = runs.len().to_string().bright_cyan().bold().to_string(),
+            bytes = history::format_bytes(total_reclaimed).bright_cyan().bold().to_string()
+        )
+    );
+
+    // Live runs whose result did not match the plan
+    for run in recent.iter().filter(|r| !r.audit_issues.is_empty()) {
+        println!("{} {}",
+            "⚠️".yellow(),
+            t!("stats-audit-issues", count = run.audit_issues.len().to_string().yellow().bold().to_string(), date = run.timestamp.clone())
+        );
+        for issue in &run.audit_issues {
+            println!("  {}", issue.bright_black());
+        }
+    }
+
+    match history::problem_trend(&runs) {
+        Some(delta) if delta < 0.0 => println!("{} {}",
+            "↘".green().bold(), t!("stats-improving", delta = format!("{:.1}", -delta))),
+        Some(delta) if delta > 0.0 => println!("{} {}",
+            "↗".red().bold(), t!("stats-worsening", delta = format!("{:.1}", delta))),
+        Some(_) => println!("{} {}", "→".yellow().bold(), t!("stats-stable")),
+        None => {}
+    }
+
+    Ok(())
+}
+
+/// Store the pair by content hash so it survives renames; cloud mode can't
+/// read placeholders, so it stores paths relative to the library instead.
+fn record_ignored_dupe(target_dir: &std::path::Path, a: &std::path::Path, b: &std::path::Path, by_path: bool) -> Result<()> {
+    let root = target_dir.canonicalize()?;
+    let entry = |path: &std::path::Path| -> Result<String> {
+        let path = path.canonicalize().map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+        if by_path {
+            Ok(path.strip_prefix(&root).unwrap_or(&path).to_string_lossy().to_string())
+        } else {
+            Ok(format!("md5:{}", duplicates::compute_md5(&path)?))
+        }
+    };
+    let ignore_file = dupe_ignore::DupeIgnoreList::record(target_dir, &entry(a)?, &entry(b)?)?;
+
+    println!("{} {}",
+        "✓".green().bold(),
+        t!("dupe-ignored",
+            a = a.display().to_string().bright_white().to_string(),
+            b = b.display().to_string().bright_white().to_string(),
+            path = ignore_file.display().to_string()
+        )
+    );
+    Ok(())
+}
+
+fn run_override(action: &cli::OverrideAction) -> Result<()> {
+    match action {
+        cli::OverrideAction::Set { file, library, authors, title, year } => {
+            let correction = overrides::Override { authors: authors.clone(), title: title.clone(), year: *year, ..Default::default() };
+            if correction.is_empty() {
+                return Err(error::Error::Usage("Nothing to set: pass --authors, --title and/or --year".to_string()).into());
+            }
+            let mut store = overrides::Overrides::load(library)?;
+            store.set(file, &correction)?;
+            let path = store.save(library)?;
+            println!("{} {}", "✓".green().bold(), t!("override-saved", file = file.display().to_string(), path = path.display().to_string()));
+        }
+        cli::OverrideAction::Unset { file, library } => {
+            let mut store = overrides::Overrides::load(library)?;
+            if store.unset(file)? {
+                store.save(library)?;
+                println!("{} {}", "✓".green().bold(), t!("override-removed", file = file.display().to_string()));
+            } else {
+                println!("{} {}", "ℹ".bright_blue(), t!("override-missing", file = file.display().to_string()));
+            }
+        }
+        cli::OverrideAction::List { path } => {
+            let store = overrides::Overrides::load(path)?;
+            let mut count = 0;
+            for (hash, entry) in store.iter() {
+                let mut fields = Vec::new();
+                if let Some(authors) = &entry.authors {
+                    fields.push(format!("authors={:?}", authors));
+                }
+                if let Some(title) = &entry.title {
+                    fields.push(format!("title={:?}", title));
+                }
+                if let Some(year) = entry.year {
+                    fields.push(format!("year={}", year));
                 }
+                println!("  {} {} {}", hash[..8].bright_black(), entry.name.bright_white(), fields.join(" ").bright_cyan());
+                count += 1;
+            }
+            if count == 0 {
+                println!("{} {}", "ℹ".bright_blue(), t!("overrides-none", path = path.display().to_string()));
             }
         }
+    }
+    Ok(())
+}
 
-        // Write todo.md
-        todo_list.write()?;
-        info!("Wrote todo.md");
+/// `normalize-name`: built-in aliases and known authors only, nothing is read
+/// from a library.
+fn run_normalize_name(names: &[String], json: bool, template: Option<String>, subtitle_separator: String, ascii_punctuation: bool) -> Result<()> {
+    if let Some(template) = &template {
+        template::validate(template)?;
     }
+    let options = normalizer::NormalizeOptions {
+        aliases: authors::AuthorAliases::builtin(),
+        template,
+        subtitle_separator,
+        overrides: overrides::Overrides::default(),
+        merged: overrides::MergedMetadata::default(),
+        ascii_punctuation,
+        rtl_metadata_first: false,
+        known_authors: known_authors::KnownAuthors::builtin(),
+        folder_hints: None,
+    };
+    let names: Vec<String> = if names == ["-"] {
+        std::io::stdin().lines().collect::<std::io::Result<Vec<_>>>()?
+    } else {
+        names.to_vec()
+    };
+    for input in names.iter().map(|n| n.trim_end_matches('\r')).filter(|n| !n.trim().is_empty()) {
+        let name = std::path::Path::new(input).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| input.to_string());
+        let (metadata, new_name) = normalizer::parse_name(&name, corpus::extension_of(&name), &options)?;
+        if json {
+            println!("{}", serde_json::to_string(&json_output::NormalizedNameOutput::new(input, new_name, metadata))?);
+        } else {
+            println!("{}", new_name);
+        }
+    }
+    Ok(())
+}
 
-    if !args.json {
-        println!("\n{} {}", 
-            "✓".green().bold(),
-            "Operation completed successfully!".bright_green().bold()
+fn run_learn(path: &std::path::Path, print: bool, no_recursive: bool, authors: bool) -> Result<()> {
+    let max_depth = traversal::Traversal::effective(usize::MAX, no_recursive).max_depth;
+    let mut scanner = scanner::Scanner::new(path, max_depth)?;
+    let files = scanner.scan()?;
+    let learned = learn::learn_convention(&files)?;
+
+    println!("\n{}", t!("learn-banner").bold().bright_blue());
+    println!("  {:<20} {}", t!("learn-template"), learned.template.bright_cyan().bold());
+    println!("  {:<20} {:?}", t!("learn-subtitle-separator"), learned.subtitle_separator);
+    println!("  {:<20} {}",
+        t!("learn-matches"),
+        t!("learn-matched-files", matched = learned.matched.to_string().bright_cyan().to_string(), total = learned.total)
+    );
+    for (template, matched) in learned.alternatives.iter().take(3) {
+        println!("  {:<20} {} {}", t!("learn-alternative"), template.bright_white(), t!("learn-alternative-files", count = *matched).bright_black());
+    }
+    let library_authors = if authors { known_authors::authors_from_library(&files)? } else { Default::default() };
+    if authors {
+        println!("  {:<20} {}", t!("learn-authors"), library_authors.len().to_string().bright_cyan());
+    }
+
+    if print {
+        return Ok(());
+    }
+
+    if authors {
+        let (authors_path, added) = known_authors::save_authors(path, &library_authors)?;
+        println!("\n{} {}", "✓".green().bold(), t!("learn-authors-added", count = added, path = authors_path.display().to_string()));
+    }
+
+    let mut config = config::Config::load(path)?;
+    config.template = Some(learned.template);
+    config.subtitle_separator = Some(learned.subtitle_separator);
+    let config_path = config.save(path)?;
+    println!("\n{} {}", "✓".green().bold(), t!("learn-saved", path = config_path.display().to_string()));
+
+    Ok(())
+}
+
+/// Returns false if any error-level finding was reported.
+fn run_check(path: &std::path::Path, format: cli::ReportFormat, output: Option<&std::path::PathBuf>, no_recursive: bool) -> Result<bool> {
+    let max_depth = traversal::Traversal::effective(usize::MAX, no_recursive).max_depth;
+    let mut scanner = scanner::Scanner::new(path, max_depth)?;
+    let files = scanner.scan()?;
+    let root = path.canonicalize()?;
+    let report = integrity::IntegrityReport::from_files(&files, &root);
+
+    match format {
+        cli::ReportFormat::Json => integrity::write_report(&format!("{}\n", report.to_json()?), output)?,
+        cli::ReportFormat::Html => integrity::write_report(&report.to_html(), output)?,
+        cli::ReportFormat::Console => {
+            if output.is_some() {
+                colored::control::set_override(false);
+            }
+            let mut text = String::new();
+            for finding in &report.findings {
+                let label = match finding.severity {
+                    integrity::Severity::Error => t!("check-error").red().bold(),
+                    integrity::Severity::Warning => t!("check-warning").yellow().bold(),
+                    integrity::Severity::Info => t!("check-info").bright_black().bold(),
+                };
+                text.push_str(&format!("  {:<9} {} {}\n",
+                    label,
+                    finding.path.bright_white(),
+                    format!("[{}] {}", finding.check, finding.message).bright_black()
+                ));
+            }
+            text.push_str(&format!("\n{}\n",
+                t!("check-summary",
+                    files = report.files_checked,
+                    errors = report.errors.to_string().red().bold().to_string(),
+                    warnings = report.warnings.to_string().yellow().bold().to_string(),
+                    infos = report.infos
+                )
+            ));
+            integrity::write_report(&text, output)?;
+        }
+    }
+
+    Ok(!report.has_errors())
+}
+
+fn run_restore_names(path: &std::path::Path, dry_run: bool, no_recursive: bool) -> Result<()> {
+    let max_depth = traversal::Traversal::effective(usize::MAX, no_recursive).max_depth;
+    let restores = original_names::plan(path, max_depth)?;
+    let pruned_dirs = prune::pruned_dirs(path)?;
+    if restores.is_empty() && pruned_dirs.is_empty() {
+        println!("{} {}", "ℹ".bright_blue(), t!("restore-nothing", path = path.display().to_string()));
+        return Ok(());
+    }
+    let root = path.canonicalize()?;
+    for dir in &pruned_dirs {
+        println!("  {} {}/", "+".green().bold(), dir.strip_prefix(&root).unwrap_or(dir).display());
+    }
+    for restore in &restores {
+        let from = restore.from.strip_prefix(&root).unwrap_or(&restore.from);
+        let to = restore.to.file_name().unwrap_or_default().to_string_lossy();
+        println!("  {} {} {} {}", "→".cyan().bold(), from.display(), "=>".bright_black(), to);
+    }
+    if dry_run {
+        println!("\n{} {}", "📊".bright_white(), t!("restore-planned", count = restores.len().to_string().cyan().bold().to_string()));
+        return Ok(());
+    }
+
+    let restored_dirs = prune::restore_pruned(path)?;
+    if !restored_dirs.is_empty() {
+        println!("{} {}", "✓".green().bold(), t!("pruned-dirs-restored", count = restored_dirs.len()));
+    }
+    let errors = original_names::restore(&restores)?;
+    for error in &errors {
+        println!("{}  {}", "⚠️".yellow(), error.yellow());
+    }
+    println!("\n{} {}",
+        "📊".bright_white(),
+        t!("restore-done",
+            restored = restores.len().saturating_sub(errors.len()).to_string().green().bold().to_string(),
+            failed = errors.len().to_string().yellow().bold().to_string()
+        )
+    );
+    Ok(())
+}
+
+/// The library a command changes files in, with whether to wait for its
+/// lock. Commands that only read (stats, check, read-only runs...) don't lock.
+fn lock_target(args: &Args) -> Option<(&std::path::Path, bool)> {
+    let wait = args.wait_for_lock;
+    match &args.command {
+        None if args.read_only => None,
+        None => Some((&args.path, wait)),
+        Some(Command::Cleanup(cleanup_args)) => Some((&cleanup_args.path, wait || cleanup_args.wait_for_lock)),
+        Some(Command::Changes { path, update: true, .. }) => Some((path, wait)),
+        Some(Command::Learn { path, print: false, .. }) => Some((path, wait)),
+        Some(Command::Override { action: cli::OverrideAction::Set { library, .. } | cli::OverrideAction::Unset { library, .. } }) => {
+            Some((library, wait))
+        }
+        Some(Command::RestoreNames { path, dry_run: false, .. }) => Some((path, wait)),
+        // The server locks each plan it executes
+        Some(_) => None,
+    }
+}
+
+fn run_cleanup(cleanup_args: &cli::CleanupArgs) -> Result<()> {
+    let path = cleanup_args.path.as_path();
+    let max_depth = traversal::Traversal::effective(usize::MAX, cleanup_args.no_recursive).max_depth;
+    let mut scanner = scanner::Scanner::new(path, max_depth)?
+        .with_min_valid_size(cleanup_args.min_valid_size.clone().unwrap_or_default())
+        .with_sync_ignore(sync_ignore::SyncIgnore::load(path, cleanup_args.sync_exclusions.as_deref())?);
+    let mut files = scanner.scan()?;
+    placeholders::hydrate(&mut files, &cleanup_args.hydrate);
+    let plan = cleanup::CleanupPlan::from_files(&files);
+
+    if plan.is_empty() {
+        println!("{} {}", "✓".green().bold(), t!("nothing-to-clean"));
+        return Ok(());
+    }
+
+    println!("\n{}", t!("cleanup-banner").bold().bright_blue());
+    for item in &plan.items {
+        println!("  {} {} {}",
+            t!("label-delete").red().bold(),
+            item.path.display().to_string().bright_white(),
+            format!("({}, {})", item.reason.localized(), history::format_bytes(item.size)).bright_black()
         );
     }
+
+    println!("\n{}", t!("cleanup-summary").bold());
+    for reason in [
+        cleanup::CleanupReason::FailedDownload,
+        cleanup::CleanupReason::HtmlStub,
+        cleanup::CleanupReason::TooSmall,
+        cleanup::CleanupReason::CorruptedPdf,
+    ] {
+        let count = plan.count(reason);
+        if count > 0 {
+            println!("  {:<16} {}", reason.localized(), count.to_string().bright_cyan());
+        }
+    }
+    println!("  {:<16} {}",
+        t!("cleanup-total"),
+        t!("cleanup-total-files",
+            count = plan.items.len().to_string().bright_cyan().bold().to_string(),
+            bytes = history::format_bytes(plan.total_bytes()).bright_cyan().bold().to_string()
+        )
+    );
+
+    let delete_effect = delete_mode::DeleteEffect::new(cleanup_args.delete_mode, &path.canonicalize()?, cleanup_args.archive_dir.as_deref());
+    println!("\n{} {}", "ℹ".bright_blue(), delete_effect.describe());
+
+    if !cleanup_args.yes {
+        let stdin = std::io::stdin();
+        let confirmed = cleanup::prompt_confirmation(
+            &format!("\n{}", t!("cleanup-confirm", count = plan.items.len())),
+            &mut stdin.lock(),
+            &mut std::io::stdout(),
+        )?;
+        if !confirmed {
+            println!("{} {}", "✗".yellow().bold(), t!("cleanup-cancelled"));
+            return Ok(());
+        }
+    }
+
+    let deleter = delete_mode::Deleter::new(cleanup_args.delete_mode, path, cleanup_args.archive_dir.as_deref())?;
+    let result = cleanup::execute_cleanup(&plan, &deleter);
+    println!("\n{} {}",
+        "✓".green().bold(),
+        t!("cleanup-deleted",
+            count = result.deleted.len().to_string().bright_cyan().bold().to_string(),
+            bytes = history::format_bytes(result.bytes_freed).bright_cyan().bold().to_string()
+        )
+    );
+    for (path, error) in &result.failed {
+        println!("  {} {}: {}", t!("label-failed").red().bold(), path.display(), error);
+    }
+
     Ok(())
 }
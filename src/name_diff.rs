@@ -0,0 +1,122 @@
+use colored::*;
+
+/// Whether a piece of text appears in both names or only in one of them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Same,
+    Removed,
+    Added,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub change: Change,
+    pub text: String,
+}
+
+/// Split a name into words, whitespace runs and single punctuation marks,
+/// so the diff lines up on whole words like `git diff --word-diff`.
+fn tokenize(name: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut chars = name.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let same_class = |next: char| {
+            (c.is_alphanumeric() && next.is_alphanumeric()) || (c.is_whitespace() && next.is_whitespace())
+        };
+        match chars.peek() {
+            Some(&(_, next)) if same_class(next) => {}
+            Some(&(j, _)) => {
+                tokens.push(&name[start..j]);
+                start = j;
+            }
+            None => tokens.push(&name[start..i + c.len_utf8()]),
+        }
+    }
+    tokens
+}
+
+/// Word-level diff of an old and a new filename. Returns the segments of the
+/// old name (`Same`/`Removed`) and of the new name (`Same`/`Added`).
+pub fn diff_names(old: &str, new: &str) -> (Vec<Segment>, Vec<Segment>) {
+    let a = tokenize(old);
+    let b = tokenize(new);
+
+    // Longest common subsequence table, filled from the end
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut old_segments = Vec::new();
+    let mut new_segments = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            push(&mut old_segments, Change::Same, a[i]);
+            push(&mut new_segments, Change::Same, b[j]);
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            push(&mut new_segments, Change::Added, b[j]);
+            j += 1;
+        } else {
+            push(&mut old_segments, Change::Removed, a[i]);
+            i += 1;
+        }
+    }
+    (old_segments, new_segments)
+}
+
+fn push(segments: &mut Vec<Segment>, change: Change, text: &str) {
+    match segments.last_mut() {
+        Some(last) if last.change == change => last.text.push_str(text),
+        _ => segments.push(Segment { change, text: text.to_string() }),
+    }
+}
+
+/// Console rendering: removed noise in red, added metadata in green.
+pub fn colorize(segments: &[Segment], unchanged: Color) -> String {
+    segments
+        .iter()
+        .map(|segment| match segment.change {
+            Change::Same => segment.text.color(unchanged).to_string(),
+            Change::Removed => segment.text.red().strikethrough().to_string(),
+            Change::Added => segment.text.green().bold().to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(segments: &[Segment], change: Change) -> Vec<&str> {
+        segments.iter().filter(|s| s.change == change).map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_diff_names() {
+        let (old, new) = diff_names(
+            "Lang S. - Algebra (z-library.org).pdf",
+            "Serge Lang - Algebra (2002).pdf",
+        );
+        assert_eq!(texts(&old, Change::Removed), vec!["S. ", "z-library.org"]);
+        assert_eq!(texts(&new, Change::Added), vec!["Serge ", "2002"]);
+
+        // Reassembling each side gives back the original names
+        let joined = |segments: &[Segment]| segments.iter().map(|s| s.text.as_str()).collect::<String>();
+        assert_eq!(joined(&old), "Lang S. - Algebra (z-library.org).pdf");
+        assert_eq!(joined(&new), "Serge Lang - Algebra (2002).pdf");
+
+        let (old, new) = diff_names("Same.pdf", "Same.pdf");
+        assert_eq!(old, new);
+        assert_eq!(old.len(), 1);
+    }
+}
@@ -1,65 +1,343 @@
+use crate::authors::AuthorAliases;
+use crate::known_authors::KnownAuthors;
+use crate::overrides::{MergedMetadata, Overrides};
 use crate::scanner::FileInfo;
+use crate::subjects;
+use crate::template;
 use anyhow::Result;
 use log::debug;
 use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
 
+#[derive(Debug, Clone, Default)]
 pub struct ParsedMetadata {
     pub authors: Option<String>,
     pub title: String,
+    pub subtitle: Option<String>,    // e.g., "Chapter 0" from "Algebra_ Chapter 0"
     pub year: Option<u16>,
     pub series: Option<String>,      // e.g., "GTM 52"
     pub edition: Option<String>,     // e.g., "2nd ed"
-    #[allow(dead_code)]
     pub volume: Option<String>,      // e.g., "Vol 2" (volume info is kept in title)
+    pub publisher: Option<String>,   // e.g., "Springer", "CUP" (only used by templates)
+    pub kind: Option<String>,        // e.g., "Lecture notes", "PhD thesis" (only used by templates)
+    pub subject: Option<String>,     // e.g., "Commutative algebra", from the file's keywords (only used by templates)
+    pub category: Option<String>,    // e.g., "math.AG", the arXiv category (only used by templates)
+    pub language: Option<String>,    // e.g., "zh", ISO 639-1 code of a translated copy ("中文版", "Russian edition")
+    pub identifier: Option<String>,  // e.g., "ISBN 9780262046305", "ASIN B0F5TFL6ZQ" (only used by templates)
+    pub ambiguous_order: bool,       // "A - B" where either side could be the author
 }
 
-pub fn normalize_files(mut files: Vec<FileInfo>) -> Result<Vec<FileInfo>> {
-    for file_info in &mut files {
-        if file_info.is_failed_download || file_info.is_too_small {
-            // Skip normalization for failed/damaged files
-            continue;
+impl ParsedMetadata {
+    /// Title with the subtitle appended after `separator`, if there is one.
+    pub fn full_title(&self, separator: &str) -> String {
+        match &self.subtitle {
+            Some(subtitle) => format!("{}{}{}", self.title, separator, subtitle),
+            None => self.title.clone(),
         }
+    }
+}
 
-        let metadata = parse_filename(&file_info.original_name, &file_info.extension)?;
-        let new_name = generate_new_filename(&metadata, &file_info.extension);
+pub const DEFAULT_SUBTITLE_SEPARATOR: &str = " - ";
+
+/// Settings that shape the generated filename.
+pub struct NormalizeOptions {
+    pub aliases: AuthorAliases,
+    /// Custom `--template`; `None` keeps the default naming scheme.
+    pub template: Option<String>,
+    /// Placed between title and subtitle (`:` is not allowed on every filesystem)
+    pub subtitle_separator: String,
+    /// Hand-corrected metadata, applied after parsing
+    pub overrides: Overrides,
+    /// `--merge-metadata`, applied after the overrides
+    pub merged: MergedMetadata,
+    /// `--ascii-punctuation`
+    pub ascii_punctuation: bool,
+    /// `--rtl-metadata-first`
+    pub rtl_metadata_first: bool,
+    /// Surnames that tip `A - B` toward the side they appear on
+    pub known_authors: KnownAuthors,
+    /// `--folder-hints`: the library root, above which folders are not used
+    pub folder_hints: Option<PathBuf>,
+}
 
-        file_info.new_name = Some(new_name.clone());
-        
-        let mut new_path = file_info.original_path.clone();
-        new_path.set_file_name(&new_name);
-        file_info.new_path = new_path;
+/// Parse one file's name and plan its new name, e.g. as the scan finds it.
+pub fn normalize_file(file_info: &mut FileInfo, options: &NormalizeOptions) -> Result<()> {
+    if file_info.is_failed_download || file_info.is_too_small {
+        // Skip normalization for failed/damaged files
+        return Ok(());
+    }
 
-        debug!(
-            "Normalized: {} -> {}",
-            file_info.original_name, new_name
-        );
+    let mut metadata = parse_with_options(&file_info.original_name, &file_info.extension, options)?;
+    if options.template.as_deref().is_some_and(template::uses_subjects) && !file_info.is_virtual {
+        (metadata.subject, metadata.category) = subjects::read_subjects(&file_info.original_path, &file_info.extension);
+    }
+    if let Some(root) = &options.folder_hints {
+        apply_folder_hints(&mut metadata, &file_info.original_path, root, &options.known_authors);
+        metadata.authors = metadata.authors.map(|authors| options.aliases.canonicalize(&authors));
+    }
+    if let Some(correction) = options.overrides.lookup(file_info) {
+        correction.apply(&mut metadata);
+    }
+    if let Some(correction) = options.merged.lookup(file_info) {
+        correction.apply(&mut metadata);
+    }
+    rename_with(file_info, metadata, options);
+    Ok(())
+}
+
+fn parse_with_options(original_name: &str, extension: &str, options: &NormalizeOptions) -> Result<ParsedMetadata> {
+    let name = if options.ascii_punctuation {
+        ascii_punctuation(original_name)
+    } else {
+        original_name.to_string()
+    };
+    let mut metadata = parse_filename_with(&name, extension, &options.known_authors)?;
+    if options.template.as_deref().is_some_and(template::is_title_first) {
+        swap_for_title_first(&mut metadata, &name);
+    }
+    metadata.authors = metadata.authors.map(|authors| options.aliases.canonicalize(&authors));
+    Ok(metadata)
+}
+
+fn name_with(metadata: &ParsedMetadata, extension: &str, options: &NormalizeOptions) -> String {
+    if let Some(template) = &options.template {
+        // Only the template's own slashes make folders; field values had theirs replaced
+        let stem = template::render(template, metadata, &options.subtitle_separator);
+        if !stem.is_empty() {
+            return format!("{}{}", stem, extension);
+        }
+    }
+    let new_name = if options.template.is_none() && options.rtl_metadata_first && is_rtl(&metadata.title) {
+        generate_metadata_first_filename(metadata, extension, &options.subtitle_separator)
+    } else {
+        generate_new_filename(metadata, extension, &options.subtitle_separator)
+    };
+    // Overrides can bring in a slash ("AC/DC")
+    new_name.replace('/', "-")
+}
+
+/// Files without an author in their name take it, and a missing series,
+/// from the nearest folders below `root` named after a known author
+/// (`Serre/`) or a series (`GTM/`, `Graduate Texts in Mathematics/`).
+fn apply_folder_hints(metadata: &mut ParsedMetadata, path: &Path, root: &Path, known_authors: &KnownAuthors) {
+    if metadata.authors.is_some() {
+        return;
+    }
+    let Some(relative_dir) = path.parent().and_then(|dir| dir.strip_prefix(root).ok()) else {
+        return;
+    };
+    for dir in relative_dir.iter().rev().map(|name| name.to_string_lossy()) {
+        let dir = dir.trim();
+        if metadata.series.is_none()
+            && let Some((_, abbr)) = SERIES_MAPPINGS
+                .iter()
+                .find(|(name, abbr)| dir.eq_ignore_ascii_case(name) || dir.eq_ignore_ascii_case(abbr))
+        {
+            debug!("Series {} from folder {:?}", abbr, dir);
+            metadata.series = Some(abbr.to_string());
+        } else if metadata.authors.is_none() && author_likeness(dir, known_authors) >= 2 {
+            debug!("Author from folder {:?}", dir);
+            metadata.authors = Some(clean_author_name(dir));
+            metadata.ambiguous_order = false;
+        }
+    }
+}
+
+/// The name `normalize_file` would give a file called `name`, leaving out
+/// overrides (they are looked up by content).
+pub fn normalized_name(name: &str, extension: &str, options: &NormalizeOptions) -> Result<String> {
+    Ok(parse_name(name, extension, options)?.1)
+}
+
+/// Parsed fields and new name for a bare filename, as `normalized_name`.
+pub fn parse_name(name: &str, extension: &str, options: &NormalizeOptions) -> Result<(ParsedMetadata, String)> {
+    let metadata = parse_with_options(name, extension, options)?;
+    let new_name = name_with(&metadata, extension, options);
+    Ok((metadata, new_name))
+}
+
+/// Generate the new name from (possibly corrected) metadata.
+pub fn rename_with(file_info: &mut FileInfo, metadata: ParsedMetadata, options: &NormalizeOptions) {
+    let new_name = name_with(&metadata, &file_info.extension, options);
+
+    file_info.new_name = Some(new_name.clone());
+
+    let mut new_path = file_info.original_path.clone();
+    new_path.set_file_name(&new_name);
+    file_info.new_path = new_path;
+    file_info.metadata = Some(metadata);
+
+    debug!(
+        "Normalized: {} -> {}",
+        file_info.original_name, new_name
+    );
+}
+
+/// Parses that need a human look: no author, or a title without real words.
+pub fn is_low_confidence(metadata: &ParsedMetadata) -> bool {
+    metadata.authors.is_none() || metadata.ambiguous_order || metadata.title.chars().filter(|c| c.is_alphabetic()).count() < 3
+}
+
+/// Normalize a directory name using the subset of filename rules that make
+/// sense for folders: series extraction, noise removal and publisher/series
+/// parentheticals. No author/title split, year or extension handling.
+pub fn normalize_dir_name(name: &str) -> String {
+    let base = name.trim();
+
+    let (mut series_info, mut base) = extract_series_info(base);
+    if series_info.is_none()
+        && let Some((series, rest)) = extract_unnumbered_series(&base)
+    {
+        series_info = Some(series);
+        base = rest;
+    }
+
+    // Drop bracketed mirror tags like [z-lib] or [libgen], keep other brackets
+    let re_bracket = Regex::new(r"\s*\[([^\]]*)\]").unwrap();
+    base = re_bracket.replace_all(&base, |caps: &regex::Captures| {
+        let content = caps.get(1).map(|m| m.as_str()).unwrap_or("");
+        if is_mirror_tag(content) {
+            String::new()
+        } else {
+            caps.get(0).map(|m| m.as_str()).unwrap_or("").to_string()
+        }
+    }).to_string();
+
+    base = clean_noise_sources(&base);
+    base = clean_parentheticals(&base, None);
+    base = clean_orphaned_brackets(&base);
+    base = base.trim_matches(|c: char| c == '-' || c == ':' || c == ',' || c == ';' || c == '.' || c.is_whitespace()).to_string();
+
+    if let Some(series) = series_info {
+        if base.is_empty() {
+            return series;
+        }
+        base = format!("{} [{}]", base, series);
     }
 
-    Ok(files)
+    base
+}
+
+/// A folder-level series without a volume: `( Springer GTM )`, `[Graduate
+/// Texts in Mathematics]` or the `[GTM]` written by `normalize_dir_name`.
+/// The publisher and other words in the group are dropped with it.
+fn extract_unnumbered_series(s: &str) -> Option<(String, String)> {
+    let re_group = Regex::new(r"\s*[(\[]\s*([^()\[\]]+?)\s*[)\]]\s*").unwrap();
+    re_group.captures_iter(s).find_map(|caps| {
+        let group = caps[1].to_lowercase();
+        let words: Vec<&str> = group.split_whitespace().collect();
+        let (_, abbr) = SERIES_MAPPINGS.iter().find(|(name, abbr)| {
+            group.contains(&name.to_lowercase()) || words.contains(&abbr.to_lowercase().as_str())
+        })?;
+        let whole = caps.get(0).unwrap();
+        let rest = format!("{} {}", &s[..whole.start()], &s[whole.end()..]);
+        Some((abbr.to_string(), rest.trim().to_string()))
+    })
+}
+
+fn is_mirror_tag(s: &str) -> bool {
+    let lower = s.to_lowercase();
+    ["z-lib", "zlib", "z-library", "libgen", "library genesis", "anna's archive", "annas archive"]
+        .iter()
+        .any(|tag| lower.contains(tag))
+}
+
+/// Map Unicode punctuation variants (mostly from CJK sources) to their ASCII
+/// equivalents so the parsing patterns match. Full-width letters and digits
+/// become ASCII too; other CJK text is left alone.
+pub fn ascii_punctuation(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '，' | '、' => result.push_str(", "),
+            '：' => result.push_str(": "),
+            '；' => result.push_str("; "),
+            '【' | '〔' | '〖' => result.push('['),
+            '】' | '〕' | '〗' => result.push(']'),
+            '“' | '”' | '„' | '「' | '」' | '『' | '』' => result.push('"'),
+            '‘' | '’' | '‚' | '′' => result.push('\''),
+            '‐' | '‑' | '‒' | '–' | '—' | '―' | '−' => result.push('-'),
+            '…' => result.push_str("..."),
+            '\u{3000}' | '\u{00A0}' | '\u{2002}'..='\u{200A}' | '\u{202F}' => result.push(' '),
+            // Full-width forms block: "（２０２０）" -> "(2020)"
+            '\u{FF01}'..='\u{FF5E}' => result.push(char::from_u32(c as u32 - 0xFEE0).unwrap_or(c)),
+            _ => result.push(c),
+        }
+    }
+    // Added spaces may double up with existing ones
+    let result = Regex::new(r" {2,}").unwrap().replace_all(&result, " ").to_string();
+    Regex::new(r" +([)\],;:])").unwrap().replace_all(&result, "$1").trim().to_string()
+}
+
+/// Hebrew and Arabic-script letters, including presentation forms.
+fn is_rtl_char(c: char) -> bool {
+    matches!(c, '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}')
+}
+
+/// Whether most letters in `s` are right-to-left.
+pub fn is_rtl(s: &str) -> bool {
+    let letters = s.chars().filter(|c| c.is_alphabetic()).count();
+    let rtl = s.chars().filter(|c| c.is_alphabetic() && is_rtl_char(*c)).count();
+    rtl > 0 && rtl * 2 > letters
+}
+
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{200E}' | '\u{200F}' | '\u{061C}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Four-digit runs of Arabic-Indic or Persian digits ("١٩٥٦") as ASCII years.
+fn ascii_year_digits(s: &str) -> String {
+    let re = Regex::new(r"[\x{0660}-\x{0669}]{4}|[\x{06F0}-\x{06F9}]{4}").unwrap();
+    re.replace_all(s, |caps: &regex::Captures| {
+        caps[0]
+            .chars()
+            .map(|c| match c {
+                '\u{0660}'..='\u{0669}' => char::from_u32(c as u32 - 0x0660 + '0' as u32).unwrap_or(c),
+                _ => char::from_u32(c as u32 - 0x06F0 + '0' as u32).unwrap_or(c),
+            })
+            .collect::<String>()
+    })
+    .to_string()
+}
+
+static BUILTIN_KNOWN_AUTHORS: LazyLock<KnownAuthors> = LazyLock::new(KnownAuthors::builtin);
+
+pub fn parse_filename(filename: &str, extension: &str) -> Result<ParsedMetadata> {
+    parse_filename_with(filename, extension, &BUILTIN_KNOWN_AUTHORS)
 }
 
-fn parse_filename(filename: &str, extension: &str) -> Result<ParsedMetadata> {
+/// `parse_filename` with a library's own known-authors list.
+pub fn parse_filename_with(filename: &str, extension: &str, known_authors: &KnownAuthors) -> Result<ParsedMetadata> {
     // Step 1: Remove extension
     let mut base = filename.strip_suffix(extension).unwrap_or(filename);
     base = base.strip_suffix(".download").unwrap_or(base);
     let mut base = base.trim().to_string();
+    let original_stem = base.clone();
+    // Read before noise removal strips ISBNs and ASINs from the name
+    let identifier = extract_identifier(&base);
+    // Invisible bidi marks break the anchored patterns; Arabic-Indic years
+    // would not be recognized
+    base = base.chars().filter(|c| !is_bidi_control(*c)).collect();
+    base = ascii_year_digits(&base);
+
+    // Step 1b: Translation markers ("中文版", "(Russian Edition)", "translated by ...", "[zh]")
+    let (language, translated, base_after_language) = extract_language(&base);
+    base = base_after_language;
 
     // Step 2: Extract series information (before removal)
     let (series_info, base_after_series) = extract_series_info(&base);
     base = base_after_series;
 
-    // Step 3: Remove ALL bracketed annotations [Lecture notes], [masters thesis], etc.
-    // BUT preserve series info that was already extracted
+    // Step 3: Remember the document kind, then remove ALL bracketed annotations
+    // [Lecture notes], [masters thesis], etc. BUT preserve series info that was already extracted
+    let kind = extract_kind(&base);
     base = Regex::new(r"\s*\[[^\]]*\]").unwrap().replace_all(&base, "").to_string();
 
-    // Step 4: Clean noise sources (Z-Library, libgen, Anna's Archive, hashes)
+    // Steps 4-5: Clean noise sources (Z-Library, libgen, Anna's Archive, hashes)
+    // and duplicate markers (-2, (1), ...) until nothing changes
     base = clean_noise_sources(&base);
 
-    // Step 5: Remove duplicate markers: -2, -3, (1), (2), etc.
-    base = Regex::new(r"[-\s]*\(\d{1,2}\)\s*$").unwrap().replace(&base, "").to_string();
-    base = Regex::new(r"-\d{1,2}\s*$").unwrap().replace(&base, "").to_string();
-    base = Regex::new(r"-\d{1,2}\s+\(").unwrap().replace(&base, " (").to_string();
-
     // Step 6: Extract edition information
     let (edition_info, base_after_edition) = extract_edition(&base);
     base = base_after_edition;
@@ -67,7 +345,8 @@ fn parse_filename(filename: &str, extension: &str) -> Result<ParsedMetadata> {
     // Step 7: Extract year
     let year = extract_year(&base);
 
-    // Step 8: Remove parentheticals with year/publisher info
+    // Step 8: Capture the publisher, then remove parentheticals with year/publisher info
+    let publisher = extract_publisher(&base, year);
     base = clean_parentheticals(&base, year);
 
     // Step 9: Extract volume information from title
@@ -75,58 +354,171 @@ fn parse_filename(filename: &str, extension: &str) -> Result<ParsedMetadata> {
     base = base_after_volume;
 
     // Step 10: Parse author and title
-    let (authors, title) = smart_parse_author_title(&base);
+    let (authors, raw_title, ambiguous_order) = smart_parse_author_title(&base, known_authors);
+
+    // Step 11: Split off a subtitle ("Algebra_ Chapter 0", "Topology; a first course")
+    let (title, subtitle) = split_subtitle(&raw_title);
+
+    // Nothing readable left ("(.pdf", "-3.pdf"): keep the name as it is
+    if !title.chars().any(char::is_alphanumeric) {
+        return Ok(ParsedMetadata { title: original_stem, ..Default::default() });
+    }
+
+    // A translator credit without a language: the translation is in the title's script
+    let language = language.or_else(|| if translated { script_language(&title) } else { None });
 
     Ok(ParsedMetadata {
         authors,
         title,
+        subtitle,
         year,
         series: series_info,
         edition: edition_info,
         volume: volume_info,
+        publisher,
+        kind,
+        subject: None,
+        category: None,
+        language,
+        identifier,
+        ambiguous_order,
     })
 }
 
-fn extract_series_info(s: &str) -> (Option<String>, String) {
-    // Series abbreviation mappings
-    let series_mappings = [
-        ("Graduate Texts in Mathematics", "GTM"),
-        ("Cambridge Studies in Advanced Mathematics", "CSAM"),
-        ("London Mathematical Society Lecture Note Series", "LMSLN"),
-        ("Progress in Mathematics", "PM"),
-        ("Springer Undergraduate Mathematics Series", "SUMS"),
-        ("Graduate Studies in Mathematics", "GSM"),
-        ("AMS Mathematical Surveys and Monographs", "AMS-MSM"),
-        ("Oxford Graduate Texts in Mathematics", "OGTM"),
-        ("Springer Monographs in Mathematics", "SMM"),
-    ];
+/// Language names in translation markers and their ISO 639-1 codes.
+const LANGUAGES: &[(&str, &str)] = &[
+    ("Chinese", "zh"), ("English", "en"), ("Japanese", "ja"), ("Korean", "ko"), ("Russian", "ru"),
+    ("German", "de"), ("French", "fr"), ("Spanish", "es"), ("Italian", "it"), ("Portuguese", "pt"),
+    ("Polish", "pl"), ("Dutch", "nl"), ("Turkish", "tr"), ("Arabic", "ar"), ("Persian", "fa"),
+    ("Hebrew", "he"), ("Vietnamese", "vi"),
+];
+
+/// Chinese-language edition markers: "中文版", "英文版", "日本語版", ...
+const CJK_LANGUAGE_MARKERS: &[(&str, &str)] = &[
+    ("简体中文版", "zh"), ("繁體中文版", "zh"), ("繁体中文版", "zh"), ("中文翻译版", "zh"), ("中文版", "zh"),
+    ("中译本", "zh"), ("中譯本", "zh"), ("英文版", "en"), ("英文原版", "en"), ("日文版", "ja"),
+    ("日本語版", "ja"), ("韩文版", "ko"), ("俄文版", "ru"), ("德文版", "de"), ("法文版", "fr"),
+];
+
+/// The language of a translated copy, removed from the name: an edition
+/// marker ("中文版", "(Russian Edition)", "(in German)"), or the `[zh]` this
+/// tool writes. The flag is set for translator credits ("translated by X",
+/// "(张三 译)"), which leave the language to the title's script.
+fn extract_language(s: &str) -> (Option<String>, bool, String) {
+    let names = LANGUAGES.iter().map(|(name, _)| *name).collect::<Vec<_>>().join("|");
+    let codes = LANGUAGES.iter().map(|(_, code)| *code).collect::<Vec<_>>().join("|");
+    let code_of = |name: &str| {
+        LANGUAGES.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, code)| code.to_string())
+    };
+    let mut result = s.to_string();
+    let mut language = None;
+
+    let re_tag = Regex::new(&format!(r"\s*\[({})\]", codes)).unwrap();
+    if let Some(caps) = re_tag.captures(&result) {
+        language = Some(caps[1].to_string());
+        result = re_tag.replace(&result, "").to_string();
+    }
+
+    let markers = CJK_LANGUAGE_MARKERS.iter().map(|(marker, _)| *marker).collect::<Vec<_>>().join("|");
+    let re_cjk = Regex::new(&format!(r"\s*[(\[（【]?\s*({})\s*[)\]）】]?", markers)).unwrap();
+    if let Some(caps) = re_cjk.captures(&result) {
+        language = language.or_else(|| CJK_LANGUAGE_MARKERS.iter().find(|(m, _)| *m == &caps[1]).map(|(_, code)| code.to_string()));
+        result = re_cjk.replace(&result, "").to_string();
+    }
+
+    let re_edition = Regex::new(&format!(
+        r"(?i)\s*(?:\(\s*({names})\s+(?:edition|translation|version)\s*\)|\(\s*in\s+({names})\s*\)|\b({names})\s+edition\b)",
+        names = names
+    ))
+    .unwrap();
+    if let Some(caps) = re_edition.captures(&result) {
+        let name = caps.get(1).or_else(|| caps.get(2)).or_else(|| caps.get(3)).map(|m| m.as_str()).unwrap_or_default();
+        language = language.or_else(|| code_of(name));
+        result = re_edition.replace(&result, "").to_string();
+    }
+
+    // Credits are only recognized in brackets or as a trailing " - " part, so
+    // titles about translation ("翻译理论") are left alone
+    let re_credit = Regex::new(
+        r"(?i)\s*(?:[(\[]\s*(?:translated|transl?\.|tr\.)\s+by\s+[^)\]]+[)\]]|\s-\s+(?:translated|transl?\.)\s+by\s+[^-]+$|[(\[（【]\s*[^()（）\[\]【】]{1,30}?\s*(?:译|譯|翻译|翻譯)(?:者|注)?\s*[)\]）】]|\s-\s+[^-]{1,30}?\s*(?:译|譯)\s*$)",
+    )
+    .unwrap();
+    let translated = re_credit.is_match(&result);
+    if translated {
+        result = re_credit.replace(&result, "").to_string();
+        if Regex::new(r"[\p{Han}]\s*(?:译|譯)").unwrap().is_match(s) {
+            language = language.or_else(|| Some("zh".to_string()));
+        }
+    }
+
+    // "Title - Russian Edition" leaves a dangling dash
+    let result = result.trim().trim_end_matches(|c: char| c == '-' || c.is_whitespace()).to_string();
+    (language, translated, result)
+}
+
+/// Language code for the script most letters of `s` are in: Han, kana,
+/// Hangul and Cyrillic are told apart, Latin counts as English.
+fn script_language(s: &str) -> Option<String> {
+    let letters: Vec<char> = s.chars().filter(|c| c.is_alphabetic()).collect();
+    let count = |range: fn(char) -> bool| letters.iter().filter(|c| range(**c)).count();
+    let kana = count(|c| matches!(c, '\u{3040}'..='\u{30FF}'));
+    let han = count(|c| matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}'));
+    let hangul = count(|c| matches!(c, '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}'));
+    let cyrillic = count(|c| matches!(c, '\u{0400}'..='\u{04FF}'));
+    let latin = count(|c| c.is_ascii_alphabetic());
+    let code = if kana > 0 {
+        "ja"
+    } else if hangul * 2 > letters.len() {
+        "ko"
+    } else if han * 2 > letters.len() {
+        "zh"
+    } else if cyrillic * 2 > letters.len() {
+        "ru"
+    } else if latin * 2 > letters.len() {
+        "en"
+    } else {
+        return None;
+    };
+    Some(code.to_string())
+}
 
+// Series abbreviation mappings
+const SERIES_MAPPINGS: &[(&str, &str)] = &[
+    ("Graduate Texts in Mathematics", "GTM"),
+    ("Cambridge Studies in Advanced Mathematics", "CSAM"),
+    ("London Mathematical Society Lecture Note Series", "LMSLN"),
+    ("Progress in Mathematics", "PM"),
+    ("Springer Undergraduate Mathematics Series", "SUMS"),
+    ("Graduate Studies in Mathematics", "GSM"),
+    ("AMS Mathematical Surveys and Monographs", "AMS-MSM"),
+    ("Oxford Graduate Texts in Mathematics", "OGTM"),
+    ("Springer Monographs in Mathematics", "SMM"),
+];
+
+fn extract_series_info(s: &str) -> (Option<String>, String) {
     let mut result = s.to_string();
     let mut series_info = None;
 
     // Pattern 1: "Series Name Volume - Author - Title"
-    for (series_name, abbr) in &series_mappings {
+    for (series_name, abbr) in SERIES_MAPPINGS {
         let pattern = format!(r"^{}\s*(\d+)\s*[-\s]", regex::escape(series_name));
-        if let Ok(re) = Regex::new(&pattern) {
-            if let Some(caps) = re.captures(&result) {
-                if let Some(vol) = caps.get(1) {
-                    series_info = Some(format!("{} {}", abbr, vol.as_str()));
-                    result = re.replace(&result, "").to_string();
-                    return (series_info, result.trim().to_string());
-                }
-            }
+        if let Ok(re) = Regex::new(&pattern)
+            && let Some(caps) = re.captures(&result)
+            && let Some(vol) = caps.get(1) {
+            series_info = Some(format!("{} {}", abbr, vol.as_str()));
+            result = re.replace(&result, "").to_string();
+            return (series_info, result.trim().to_string());
         }
     }
 
     // Pattern 2: "Series Name - Author - Title" (no volume number)
     // Remove series name but don't set series_info
-    for (series_name, _abbr) in &series_mappings {
+    for (series_name, _abbr) in SERIES_MAPPINGS {
         let pattern = format!(r"^{}\s*-\s*", regex::escape(series_name));
-        if let Ok(re) = Regex::new(&pattern) {
-            if re.is_match(&result) {
-                result = re.replace(&result, "").to_string();
-                return (None, result.trim().to_string());
-            }
+        if let Ok(re) = Regex::new(&pattern)
+            && re.is_match(&result) {
+            result = re.replace(&result, "").to_string();
+            return (None, result.trim().to_string());
         }
     }
 
@@ -137,7 +529,7 @@ fn extract_series_info(s: &str) -> (Option<String>, String) {
         let volume_part = caps.get(2).map(|m| m.as_str()).unwrap_or("");
 
         // Check if series_part matches known series
-        for (series_name, abbr) in &series_mappings {
+        for (series_name, abbr) in SERIES_MAPPINGS {
             if series_part.to_lowercase().contains(&series_name.to_lowercase()) {
                 series_info = Some(format!("{} {}", abbr, volume_part));
                 result = re_paren_series.replace(&result, "").to_string();
@@ -152,7 +544,7 @@ fn extract_series_info(s: &str) -> (Option<String>, String) {
         let series_part = caps.get(1).map(|m| m.as_str()).unwrap_or("");
         let volume_part = caps.get(2).map(|m| m.as_str()).unwrap_or("");
 
-        for (series_name, abbr) in &series_mappings {
+        for (series_name, abbr) in SERIES_MAPPINGS {
             if series_part.to_lowercase().contains(&series_name.to_lowercase()) {
                 series_info = Some(format!("{} {}", abbr, volume_part));
                 result = re_bracket_series.replace(&result, "").to_string();
@@ -173,27 +565,36 @@ fn extract_edition(s: &str) -> (Option<String>, String) {
     ];
 
     let mut result = s.to_string();
+    let mut edition_info = None;
 
     for pattern in &edition_patterns {
-        if let Ok(re) = Regex::new(pattern) {
-            if let Some(caps) = re.captures(&result) {
-                if let Some(num) = caps.get(1) {
-                    let num_str = num.as_str();
-                    let suffix = match num_str {
-                        "1" => "st",
-                        "2" => "nd",
-                        "3" => "rd",
-                        _ => "th",
-                    };
-                    let edition_info = format!("{}{} ed", num_str, suffix);
-                    result = re.replace(&result, "").to_string();
-                    return (Some(edition_info), result.trim().to_string());
-                }
-            }
+        if let Ok(re) = Regex::new(pattern)
+            && let Some(caps) = re.captures(&result)
+            && let Some(num) = caps.get(1) {
+            edition_info = Some(edition_label(num.as_str()));
+            result = re.replace(&result, "").to_string();
+            break;
         }
     }
 
-    (None, result.trim().to_string())
+    if edition_info.is_some() {
+        // "(2nd ed)" leaves empty parentheses behind
+        result = Regex::new(r"\s*\(\s*[,;]?\s*\)").unwrap().replace_all(&result, "").to_string();
+    }
+    (edition_info, result.trim().to_string())
+}
+
+/// `21` becomes `21st ed`; 11 to 13 take `th` like other teens.
+pub(crate) fn edition_label(num: &str) -> String {
+    let n: u64 = num.parse().unwrap_or(0);
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{} ed", num, suffix)
 }
 
 fn extract_volume(s: &str) -> (Option<String>, String) {
@@ -205,19 +606,17 @@ fn extract_volume(s: &str) -> (Option<String>, String) {
     ];
 
     for (pattern, already_normalized) in &volume_patterns {
-        if let Ok(re) = Regex::new(pattern) {
-            if let Some(caps) = re.captures(s) {
-                if let Some(num) = caps.get(1) {
-                    let volume_info = format!("Vol {}", num.as_str());
-                    let normalized_text = if !already_normalized {
-                        // Replace "Volume N" or "Part N" with "Vol N"
-                        re.replace(s, &volume_info).to_string()
-                    } else {
-                        s.to_string()
-                    };
-                    return (Some(volume_info), normalized_text);
-                }
-            }
+        if let Ok(re) = Regex::new(pattern)
+            && let Some(caps) = re.captures(s)
+            && let Some(num) = caps.get(1) {
+            let volume_info = format!("Vol {}", num.as_str());
+            let normalized_text = if !already_normalized {
+                // Replace "Volume N" or "Part N" with "Vol N"
+                re.replace(s, &volume_info).to_string()
+            } else {
+                s.to_string()
+            };
+            return (Some(volume_info), normalized_text);
         }
     }
 
@@ -226,54 +625,80 @@ fn extract_volume(s: &str) -> (Option<String>, String) {
 
 // Deprecated: remove_series_prefixes is now handled by extract_series_info
 
+/// One rewrite of the noise cleaner; rules with a lower priority are tried first.
+struct NoiseRule {
+    priority: u8,
+    pattern: &'static str,
+    replacement: &'static str,
+}
+
+const NOISE_RULES: &[NoiseRule] = &[
+    // `--`-delimited IDs: MD5/SHA hashes, ISBNs (also hyphenated), long IDs, shorter hex hashes
+    NoiseRule { priority: 10, pattern: r"\s*--\s*[a-f0-9]{32}\s*(?:--)?", replacement: "" },
+    NoiseRule { priority: 10, pattern: r"\s*--\s*(?:97[89][-\s]?)?\d[\d-]{8,15}[\dXx]\s*(?:--)?", replacement: "" },
+    NoiseRule { priority: 10, pattern: r"\s*--\s*\d{10,13}\s*(?:--)?", replacement: "" },
+    NoiseRule { priority: 10, pattern: r"\s*--\s*[A-Za-z0-9]{16,}\s*(?:--)?", replacement: "" },
+    NoiseRule { priority: 10, pattern: r"\s*--\s*[a-f0-9]{8,}\s*(?:--)?", replacement: "" },
+    // Z-Library variants
+    NoiseRule { priority: 20, pattern: r"\s*[-\(]?\s*[zZ]-?Library\s*[)\.]?", replacement: "" },
+    NoiseRule { priority: 20, pattern: r"\s*\([zZ]-?Library\)", replacement: "" },
+    NoiseRule { priority: 20, pattern: r"\s*-\s*[zZ]-?Library", replacement: "" },
+    // libgen variants
+    NoiseRule { priority: 20, pattern: r"\s*[-\(]?\s*libgen(?:\.li)?\s*[)\.]?", replacement: "" },
+    NoiseRule { priority: 20, pattern: r"\s*\(libgen(?:\.li)?\)", replacement: "" },
+    NoiseRule { priority: 20, pattern: r"\s*-\s*libgen(?:\.li)?", replacement: "" },
+    // Anna's Archive variants (including stuck to other words and curly apostrophes)
+    NoiseRule { priority: 20, pattern: r"Anna['’]?s?\s*Archive", replacement: "" },
+    NoiseRule { priority: 20, pattern: r"\s*[-\(]?\s*Anna['’]?s?\s+Archive\s*[)\.]?", replacement: "" },
+    NoiseRule { priority: 20, pattern: r"\s*\(Anna['’]?s?\s+Archive\)", replacement: "" },
+    NoiseRule { priority: 20, pattern: r"\s*-\s*Anna['’]?s?\s+Archive", replacement: "" },
+    // "Uploaded by", "Via ..."
+    NoiseRule { priority: 20, pattern: r"\s*[-\(]?\s*[Uu]ploaded by\s+[^)\-]+[)\.]?", replacement: "" },
+    NoiseRule { priority: 20, pattern: r"\s*-\s*[Uu]ploaded by\s+[^)\-]+", replacement: "" },
+    NoiseRule { priority: 20, pattern: r"\s*[-\(]?\s*[Vv]ia\s+[^)\-]+[)\.]?", replacement: "" },
+    // Website URLs
+    NoiseRule { priority: 20, pattern: r"\s*[-\(]?\s*w{3}\.[a-zA-Z0-9-]+\.[a-z]{2,}\s*[)\.]?", replacement: "" },
+    NoiseRule { priority: 20, pattern: r"\s*[-\(]?\s*[a-zA-Z0-9-]+\.(?:com|org|net|edu|io)\s*[)\.]?", replacement: "" },
+    // Printing notes, often nested in the publisher: "(Springer (2nd printing))"
+    NoiseRule { priority: 30, pattern: r"(?i)\s*\(\s*(?:\d+(?:st|nd|rd|th)|corrected)\s+(?:printing|print)\s*\)", replacement: "" },
+    NoiseRule { priority: 30, pattern: r"(?i)\s*,\s*(?:\d+(?:st|nd|rd|th)|corrected)\s+(?:printing|print)\b", replacement: "" },
+    // What removals leave behind: empty brackets, repeated or dangling separators
+    NoiseRule { priority: 40, pattern: r"\s*\(\s*[-,;.]*\s*\)", replacement: "" },
+    NoiseRule { priority: 40, pattern: r"\s*\[\s*[-,;.]*\s*\]", replacement: "" },
+    NoiseRule { priority: 40, pattern: r"\s*--(?:\s*--)+", replacement: " --" },
+    NoiseRule { priority: 40, pattern: r"(?:\s*-+)+\s*$", replacement: "" },
+    // Duplicate markers: -2, -3, (1), (2), etc.
+    NoiseRule { priority: 50, pattern: r"[-\s]*\(\d{1,2}\)\s*$", replacement: "" },
+    NoiseRule { priority: 50, pattern: r"-\d{1,2}\s*$", replacement: "" },
+    NoiseRule { priority: 50, pattern: r"-\d{1,2}\s+\(", replacement: " (" },
+];
+
+// Safety net against rules that keep rewriting each other's output
+const MAX_NOISE_PASSES: usize = 100;
+
+static COMPILED_NOISE_RULES: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    let mut rules: Vec<&NoiseRule> = NOISE_RULES.iter().collect();
+    rules.sort_by_key(|rule| rule.priority);
+    rules.iter().map(|rule| (Regex::new(rule.pattern).unwrap(), rule.replacement)).collect()
+});
+
+/// Remove source markers (Z-Library, libgen, Anna's Archive), hashes, ISBNs
+/// and duplicate markers. Applies the first rule (by priority) that changes
+/// the name and starts over until nothing changes, since removing one piece
+/// often exposes the next: `(Springer (2nd printing)) -- libgen -- AnnasArchive (1)-3`.
 fn clean_noise_sources(s: &str) -> String {
-    // Remove trailing/embedded source markers comprehensively
-    // Includes: Z-Library, libgen, Anna's Archive, hashes, and ISBN-like patterns
-    let patterns = [
-        // Z-Library variants
-        r"\s*[-\(]?\s*[zZ]-?Library\s*[)\.]?",
-        r"\s*\([zZ]-?Library\)",
-        r"\s*-\s*[zZ]-?Library",
-        // libgen variants
-        r"\s*[-\(]?\s*libgen(?:\.li)?\s*[)\.]?",
-        r"\s*\(libgen(?:\.li)?\)",
-        r"\s*-\s*libgen(?:\.li)?",
-        // Anna's Archive variants (including stuck to other words)
-        r"Anna'?s?\s*Archive",  // Catches "Anna's Archive" or "AnnasArchive" or "AnnaArchive"
-        r"\s*[-\(]?\s*Anna'?s?\s+Archive\s*[)\.]?",
-        r"\s*\(Anna'?s?\s+Archive\)",
-        r"\s*-\s*Anna'?s?\s+Archive",
-        // Hash patterns (32 hex chars - MD5/SHA hashes)
-        r"\s*--\s*[a-f0-9]{32}\s*(?:--)?",
-        // ISBN-like patterns (10-13 digits)
-        r"\s*--\s*\d{10,13}\s*(?:--)?",
-        // Long alphanumeric IDs (16+ chars)
-        r"\s*--\s*[A-Za-z0-9]{16,}\s*(?:--)?",
-        // Shorter hash patterns (8+ hex chars)
-        r"\s*--\s*[a-f0-9]{8,}\s*(?:--)?",
-        // "Uploaded by"
-        r"\s*[-\(]?\s*[Uu]ploaded by\s+[^)\-]+[)\.]?",
-        r"\s*-\s*[Uu]ploaded by\s+[^)\-]+",
-        // "Via ..."
-        r"\s*[-\(]?\s*[Vv]ia\s+[^)\-]+[)\.]?",
-        // Website URLs
-        r"\s*[-\(]?\s*w{3}\.[a-zA-Z0-9-]+\.[a-z]{2,}\s*[)\.]?",
-        r"\s*[-\(]?\s*[a-zA-Z0-9-]+\.(?:com|org|net|edu|io)\s*[)\.]?",
-    ];
-    
     let mut result = s.to_string();
-    // Apply patterns multiple times to handle consecutive patterns
-    for _ in 0..3 {
-        let before = result.clone();
-        for pattern in &patterns {
-            let re = Regex::new(pattern).unwrap();
-            result = re.replace_all(&result, "").to_string();
-        }
-        if result == before {
-            break;
+    for _ in 0..MAX_NOISE_PASSES {
+        let rewritten = COMPILED_NOISE_RULES.iter().find_map(|(re, replacement)| {
+            let next = re.replace_all(&result, *replacement);
+            (next != result).then(|| next.into_owned())
+        });
+        match rewritten {
+            Some(next) => result = next,
+            None => break,
         }
     }
-    
+
     result.trim().to_string()
 }
 
@@ -285,6 +710,135 @@ fn extract_year(s: &str) -> Option<u16> {
         .last()
 }
 
+// Lowercase fragment -> canonical publisher name. Checked in order, so
+// specific entries must come before broader ones.
+const KNOWN_PUBLISHERS: &[(&str, &str)] = &[
+    ("cambridge university press", "CUP"),
+    ("cambridge univ", "CUP"),
+    ("oxford university press", "OUP"),
+    ("princeton university press", "PUP"),
+    ("american mathematical society", "AMS"),
+    ("mit press", "MIT Press"),
+    ("academic press", "Academic Press"),
+    ("crc press", "CRC Press"),
+    ("springer", "Springer"),
+    ("birkhäuser", "Birkhäuser"),
+    ("birkhauser", "Birkhäuser"),
+    ("wiley", "Wiley"),
+    ("elsevier", "Elsevier"),
+    ("world scientific", "World Scientific"),
+    ("dover", "Dover"),
+    ("de gruyter", "De Gruyter"),
+    ("addison-wesley", "Addison-Wesley"),
+    ("mcgraw-hill", "McGraw-Hill"),
+    ("pearson", "Pearson"),
+    ("prentice hall", "Prentice Hall"),
+    ("cengage", "Cengage"),
+    ("westview", "Westview Press"),
+    ("publish or perish", "Publish or Perish"),
+    ("hindustan book agency", "Hindustan Book Agency"),
+];
+
+fn canonical_publisher(s: &str) -> Option<String> {
+    let lower = s.trim().to_lowercase();
+    if let Some(abbr) = ["cup", "oup", "ams", "pup", "siam"].iter().find(|a| **a == lower) {
+        return Some(abbr.to_uppercase());
+    }
+    KNOWN_PUBLISHERS
+        .iter()
+        .find(|(fragment, _)| lower.contains(fragment))
+        .map(|(_, name)| name.to_string())
+}
+
+/// Publisher from `(YYYY, Publisher)` or a `(Publisher)` parenthetical.
+/// Unknown names after a year are kept only if they look like a publisher.
+fn extract_publisher(s: &str, year: Option<u16>) -> Option<String> {
+    if let Some(y) = year {
+        let re = Regex::new(&format!(r"\(\s*{}\s*,\s*([^)]+?)\s*\)", y)).unwrap();
+        if let Some(caps) = re.captures(s) {
+            let candidate = caps[1].trim();
+            if let Some(name) = canonical_publisher(candidate) {
+                return Some(name);
+            }
+            if ["Press", "Publishing", "Publishers", "Verlag"].iter().any(|k| candidate.contains(k)) {
+                return Some(candidate.to_string());
+            }
+        }
+    }
+
+    let re = Regex::new(r"\(([^()]+)\)").unwrap();
+    re.captures_iter(s).find_map(|caps| canonical_publisher(&caps[1]))
+}
+
+// A 10- or 13-digit ISBN, possibly hyphenated and after an `ISBN` label
+static RE_ISBN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)(?:^|[^0-9a-z]|isbn)((?:97[89]-?)?\d[\d-]{7,14}[\dx])(?:[^0-9a-z]|$)").unwrap());
+// Amazon ASINs of Kindle books all start with B0
+static RE_ASIN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:^|[^0-9A-Za-z])(B0[0-9A-Z]{8})(?:[^0-9A-Za-z]|$)").unwrap());
+
+/// The book's ISBN or Amazon ASIN, as `ISBN 9780262046305` or
+/// `ASIN B0F5TFL6ZQ`. ISBNs must have a valid check digit, so years and
+/// other numbers are not mistaken for one; an ISBN wins over an ASIN.
+fn extract_identifier(s: &str) -> Option<String> {
+    let isbn = RE_ISBN.captures_iter(s).find_map(|caps| {
+        let digits: String = caps[1].chars().filter(|c| *c != '-').collect::<String>().to_uppercase();
+        is_valid_isbn(&digits).then(|| format!("ISBN {}", digits))
+    });
+    isbn.or_else(|| RE_ASIN.captures(s).map(|caps| format!("ASIN {}", &caps[1])))
+}
+
+fn is_valid_isbn(digits: &str) -> bool {
+    // `X` (ten) is only allowed as the check digit of an ISBN-10
+    let values: Vec<u32> = digits.chars().map(|c| c.to_digit(10).unwrap_or(10)).collect();
+    let body_is_digits = values.iter().rev().skip(1).all(|v| *v < 10);
+    match values.len() {
+        10 if body_is_digits => values.iter().zip((1..=10).rev()).map(|(v, w)| v * w).sum::<u32>() % 11 == 0,
+        13 if values.iter().all(|v| *v < 10) => {
+            values.iter().enumerate().map(|(i, v)| if i % 2 == 0 { *v } else { v * 3 }).sum::<u32>() % 10 == 0
+        }
+        _ => false,
+    }
+}
+
+// Case-insensitive marker -> canonical kind. Checked in order, so the
+// specific thesis types come before the generic one.
+const KNOWN_KINDS: &[(&str, &str)] = &[
+    (r"lecture[\s-]*notes?|course notes", "Lecture notes"),
+    (r"ph\.?\s?d\.?\s+(?:thesis|dissertation)|doctoral (?:thesis|dissertation)|dissertation", "PhD thesis"),
+    (r"masters?'?\s+thesis|m\.?sc\.?\s+thesis", "Master's thesis"),
+    (r"(?:bachelors?'?|senior|undergraduate|honou?rs)\s+thesis", "Bachelor's thesis"),
+    (r"thesis", "Thesis"),
+    (r"(?:students?'?\s+|instructors?'?\s+)?solutions?\s+manual|solutions to (?:the )?exercises|instructors?'?\s+manual", "Solutions manual"),
+    (r"problem\s+sets?|problem\s+book|homework|exercises", "Problem set"),
+    (r"(?:conference\s+)?proceedings", "Proceedings"),
+    (r"slides", "Slides"),
+];
+
+/// Document kind from markers that are otherwise stripped: `[Lecture notes]`,
+/// `(PhD thesis, MIT)`, `Title - Solutions Manual`. Dash-separated parts must
+/// consist of the marker alone, so "Author - Exercises in Analysis" is not tagged.
+fn extract_kind(s: &str) -> Option<String> {
+    let re_groups = Regex::new(r"\[([^\]]*)\]|\(([^()]*)\)").unwrap();
+    let mut candidates: Vec<(&str, bool)> = re_groups
+        .captures_iter(s)
+        .filter_map(|caps| caps.get(1).or_else(|| caps.get(2)).map(|m| (m.as_str(), false)))
+        .collect();
+    candidates.extend(s.split(" - ").skip(1).map(|part| (part, true)));
+
+    // "Lecture Notes in Mathematics" and friends are series, not kinds
+    let re_series = Regex::new(r"(?i)^\s*lecture notes? in\b").unwrap();
+    candidates
+        .into_iter()
+        .filter(|(c, _)| !re_series.is_match(c))
+        .find_map(|(candidate, whole)| {
+            let end = if whole { r"\s*$" } else { r"\b" };
+            KNOWN_KINDS.iter().find_map(|(pattern, kind)| {
+                let re = Regex::new(&format!(r"(?i)^\s*(?:a\s+|an\s+)?(?:{}){}", pattern, end)).unwrap();
+                re.is_match(candidate).then(|| kind.to_string())
+            })
+        })
+}
+
 fn clean_parentheticals(s: &str, year: Option<u16>) -> String {
     // Smart regex to remove parentheticals containing:
     // 1. Years (with or without publisher)
@@ -300,10 +854,20 @@ fn clean_parentheticals(s: &str, year: Option<u16>) -> String {
         result = re.replace_all(&result, "").to_string();
     }
     
+    // Pattern 1b: Remove a bare known publisher, e.g. what is left of "(Wiley (1999))"
+    let re_publisher = Regex::new(r"\s*\(([^()]+)\)").unwrap();
+    result = re_publisher.replace_all(&result, |caps: &regex::Captures| {
+        if canonical_publisher(&caps[1]).is_some() {
+            String::new()
+        } else {
+            caps[0].to_string()
+        }
+    }).to_string();
+
     // Pattern 2: Remove nested parentheticals with publisher keywords
     // Use a loop to handle nested structures
+    let re = Regex::new(r"\([^()]*(?:\([^()]*\)[^()]*)*\)").unwrap();
     loop {
-        let re = Regex::new(r"\([^()]*(?:\([^()]*\)[^()]*)*\)").unwrap();
         let mut changed = false;
         let new_result = re.replace_all(&result, |caps: &regex::Captures| {
             let content = caps.get(0).map(|m| m.as_str()).unwrap_or("");
@@ -339,8 +903,18 @@ fn clean_parentheticals(s: &str, year: Option<u16>) -> String {
     result.trim().to_string()
 }
 
-fn smart_parse_author_title(s: &str) -> (Option<String>, String) {
+/// Split into (authors, raw title). The title is cleaned by `split_subtitle`.
+/// Splits author and title. The flag is set when a `Name - Name` split could
+/// go either way.
+fn smart_parse_author_title(s: &str, known_authors: &KnownAuthors) -> (Option<String>, String, bool) {
     let s = s.trim();
+
+    // Pattern 0: "Title - تأليف Author" (Arabic/Hebrew "by" in front of the author)
+    let re_rtl_by = Regex::new(r"^(.+?)\s*[-:،]\s*(?:تأليف|بقلم|מאת)\s*:?\s+(.+)$").unwrap();
+    if let Some(caps) = re_rtl_by.captures(s)
+        && is_likely_author(&caps[2]) {
+        return (Some(clean_author_name(&caps[2])), caps[1].to_string(), false);
+    }
     
     // Pattern 1: "Title (Author)" - author at the end in parentheses
     let re_trailing_author = Regex::new(r"^(.+?)\s*\(([^)]+)\)\s*$").unwrap();
@@ -351,21 +925,36 @@ fn smart_parse_author_title(s: &str) -> (Option<String>, String) {
         if is_likely_author(author_part) && !is_publisher_or_series_info(&format!("({})", author_part)) {
             return (
                 Some(clean_author_name(author_part)),
-                clean_title(title_part),
+                title_part.to_string(),
+                false,
             );
         }
     }
     
     // Pattern 2: "Author - Title" or "Author: Title" or "Author -- Title" (dash, double-dash, or colon separator)
-    let re_separator = Regex::new(r"^(.+?)\s*(?:--|[-:])\s+(.+)$").unwrap();
+    let re_separator = Regex::new(r"^(.+?)\s*(--|[-:])\s+(.+)$").unwrap();
     if let Some(caps) = re_separator.captures(s) {
         let author_part = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-        let title_part = caps.get(2).map(|m| m.as_str()).unwrap_or("");
-        
-        if is_likely_author(author_part) && !title_part.is_empty() {
+        let title_part = caps.get(3).map(|m| m.as_str()).unwrap_or("");
+
+        // "Title - Author": a colon always introduces the title, and the
+        // name heuristics only work for Latin script
+        let is_author = |part: &str| is_likely_author(part) || known_authors.contains(part);
+        if &caps[2] != ":" && is_latin_script(s) && is_author(title_part) {
+            let order = author_likeness(author_part, known_authors) - author_likeness(title_part, known_authors);
+            if order < 0 {
+                return (Some(clean_author_name(title_part)), author_part.to_string(), false);
+            }
+            if order == 0 && is_author(author_part) && author_likeness(author_part, known_authors) > 0 {
+                return (Some(clean_author_name(author_part)), title_part.to_string(), true);
+            }
+        }
+
+        if is_author(author_part) && !title_part.is_empty() {
             return (
                 Some(clean_author_name(author_part)),
-                clean_title(title_part),
+                title_part.to_string(),
+                false,
             );
         }
     }
@@ -382,7 +971,8 @@ fn smart_parse_author_title(s: &str) -> (Option<String>, String) {
             let authors = format!("{}, {}", clean_author_name(author1), clean_author_name(author2));
             return (
                 Some(authors),
-                clean_title(title_part),
+                title_part.to_string(),
+                false,
             );
         }
     }
@@ -396,13 +986,14 @@ fn smart_parse_author_title(s: &str) -> (Option<String>, String) {
         if is_likely_author(author_part) && !is_publisher_or_series_info(author_part) {
             return (
                 Some(clean_author_name(author_part)),
-                clean_title(title_part),
+                title_part.to_string(),
+                false,
             );
         }
     }
     
     // Pattern 5: No clear author, treat as title only
-    (None, clean_title(s))
+    (None, s.to_string(), false)
 }
 
 fn is_likely_author(s: &str) -> bool {
@@ -433,6 +1024,11 @@ fn is_likely_author(s: &str) -> bool {
         return false;
     }
 
+    // Arabic/Hebrew names have no capitals to go by, so long phrases are titles
+    if is_rtl(s) && s.split_whitespace().count() > 4 {
+        return false;
+    }
+
     // Check if looks like a name:
     // - Has at least one uppercase Latin letter, OR
     // - Has non-Latin alphabetic characters (CJK, Cyrillic, Arabic, etc.)
@@ -444,6 +1040,80 @@ fn is_likely_author(s: &str) -> bool {
     has_uppercase || has_non_latin
 }
 
+/// How much `s` looks like a list of person names: 1 for short runs of
+/// capitalized words or initials, -1 for lowercase words, digits or long
+/// phrases, 0 when it can't tell (a single word, for instance).
+pub fn name_score(s: &str) -> i32 {
+    let particles = ["van", "von", "de", "der", "den", "di", "du", "da", "la", "le", "y"];
+    s.split([',', '&'])
+        .map(|name| {
+            let words: Vec<&str> = name.split_whitespace().collect();
+            if words.is_empty() {
+                return 0;
+            }
+            if words.len() > 4 || name.chars().any(|c| c.is_ascii_digit()) {
+                return -1;
+            }
+            let lowercase = words.iter().any(|w| {
+                w.chars().next().is_some_and(char::is_lowercase) && !particles.contains(w)
+            });
+            match (lowercase, words.len()) {
+                (true, _) => -1,
+                (false, 1) => 0,
+                (false, _) => 1,
+            }
+        })
+        .min()
+        .unwrap_or(0)
+}
+
+// Words that mark a title rather than a person's name
+const TITLE_WORDS: &[&str] = &[
+    "introduction", "introductory", "algebra", "algebraic", "analysis", "theory", "calculus", "geometry",
+    "topology", "mathematics", "mathematical", "physics", "chemistry", "biology", "economics", "programming",
+    "algorithms", "linear", "functions", "equations", "methods", "principles", "foundations", "fundamentals",
+    "elements", "handbook", "guide", "course", "lectures", "notes", "textbook", "applications", "advanced",
+    "modern", "basic", "essential", "complete", "science", "history", "philosophy", "logic", "probability",
+    "statistics", "numbers", "groups", "manifolds", "spaces", "systems", "learning", "machine", "data",
+    "design", "engineering", "computer", "computing", "quantum", "mechanics", "differential", "integral",
+    "abstract", "complex", "discrete", "applied", "topics", "problems", "solutions", "book", "done", "right",
+    "primer", "companion", "volume", "edition", "chapter", "world", "life", "war", "art",
+];
+
+fn is_latin_script(s: &str) -> bool {
+    s.chars().filter(|c| c.is_alphabetic()).all(|c| c.is_ascii() || ('\u{00C0}'..='\u{024F}').contains(&c))
+}
+
+/// How much `s` reads like an author: `name_score`, minus 2 when it
+/// contains a typical title word, plus 2 when it ends in a known surname.
+fn author_likeness(s: &str, known_authors: &KnownAuthors) -> i32 {
+    let has_title_word = s
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|w| TITLE_WORDS.contains(&w.to_lowercase().as_str()));
+    let mut score = name_score(s);
+    if has_title_word {
+        score -= 2;
+    }
+    if known_authors.contains(s) {
+        score += 2;
+    }
+    score
+}
+
+/// For title-first templates: a plain `A - B` name is parsed as author
+/// first, so swap the two unless `A` is clearly the better name.
+pub fn swap_for_title_first(metadata: &mut ParsedMetadata, filename: &str) {
+    let Some(authors) = metadata.authors.clone() else {
+        return;
+    };
+    if metadata.subtitle.is_some() || !filename.contains(&format!("{} - {}", authors, metadata.title)) {
+        return;
+    }
+    if name_score(&authors) <= name_score(&metadata.title) {
+        metadata.authors = Some(std::mem::replace(&mut metadata.title, authors));
+    }
+}
+
 fn clean_author_name(s: &str) -> String {
     let mut s = s.trim().to_string();
     
@@ -533,6 +1203,12 @@ fn is_publisher_or_series_info(s: &str) -> bool {
             return true;
         }
     }
+
+    // Arabic/Hebrew "publishing house" words ("دار الشروق", "הוצאת כתר")
+    let rtl_publisher_words = ["دار", "منشورات", "مكتبة", "مؤسسة", "הוצאת", "הוצאה"];
+    if s.trim_start_matches('(').split_whitespace().next().is_some_and(|w| rtl_publisher_words.contains(&w)) {
+        return true;
+    }
     
     // Detect hash patterns: 8+ hex chars or 16+ alphanumeric
     if Regex::new(r"[a-f0-9]{8,}").unwrap().is_match(s) && s.len() > 8 {
@@ -590,6 +1266,21 @@ fn is_strict_publisher_info(s: &str) -> bool {
     false
 }
 
+/// Detect a subtitle separator in a raw title: an underscore used as a
+/// colon stand-in ("Algebra_ Chapter 0"), a semicolon, or a real colon.
+/// Both halves are cleaned; without a separator only the title is returned.
+fn split_subtitle(raw: &str) -> (String, Option<String>) {
+    let re = Regex::new(r"^(.+?)(?:_|\s*;|:)\s+(.+)$").unwrap();
+    if let Some(caps) = re.captures(raw.trim()) {
+        let title = clean_title(&caps[1]);
+        let subtitle = clean_title(&caps[2]);
+        if !title.is_empty() && subtitle.chars().filter(|c| c.is_alphanumeric()).count() >= 2 {
+            return (title, Some(subtitle));
+        }
+    }
+    (clean_title(raw), None)
+}
+
 fn clean_title(s: &str) -> String {
     let mut s = s.trim().to_string();
 
@@ -612,13 +1303,12 @@ fn clean_title(s: &str) -> String {
         }
     }
     // Also handle just "-" without spaces if it looks like publisher
-    if let Some(idx) = s.rfind('-') {
-        if idx > 0 && idx < s.len() - 1 {
-             let suffix = &s[idx+1..].trim();
-             // Use stricter check for non-spaced dash to avoid stripping parts of title
-             if is_strict_publisher_info(suffix) {
-                 s = s[..idx].to_string();
-             }
+    if let Some(idx) = s.rfind('-')
+        && idx > 0 && idx < s.len() - 1 {
+        let suffix = &s[idx+1..].trim();
+        // Use stricter check for non-spaced dash to avoid stripping parts of title
+        if is_strict_publisher_info(suffix) {
+            s = s[..idx].to_string();
         }
     }
 
@@ -632,7 +1322,7 @@ fn clean_title(s: &str) -> String {
     s = re_space.replace_all(&s, " ").to_string();
 
     // Remove leading/trailing punctuation
-    s = s.trim_matches(|c: char| c == '-' || c == ':' || c == ',' || c == ';' || c == '.').to_string();
+    s = s.trim_matches(|c: char| matches!(c, '-' | ':' | ',' | ';' | '.' | '،' | '؛')).to_string();
 
     s.trim().to_string()
 }
@@ -708,7 +1398,7 @@ fn clean_orphaned_brackets(s: &str) -> String {
     result.trim().to_string()
 }
 
-fn generate_new_filename(metadata: &ParsedMetadata, extension: &str) -> String {
+pub fn generate_new_filename(metadata: &ParsedMetadata, extension: &str, subtitle_separator: &str) -> String {
     let mut result = String::new();
 
     // Author(s)
@@ -718,7 +1408,33 @@ fn generate_new_filename(metadata: &ParsedMetadata, extension: &str) -> String {
     }
 
     // Title (volume is kept in title if present)
-    result.push_str(&metadata.title);
+    result.push_str(&metadata.full_title(subtitle_separator));
+    result.push_str(&metadata_suffix(metadata));
+    result.push_str(extension);
+    result
+}
+
+/// `--rtl-metadata-first`: "Author - [Series] (Year) Title.ext", so the
+/// Latin metadata doesn't sit between an RTL title and the extension.
+fn generate_metadata_first_filename(metadata: &ParsedMetadata, extension: &str, subtitle_separator: &str) -> String {
+    let mut result = String::new();
+    if let Some(ref authors) = metadata.authors {
+        result.push_str(authors);
+        result.push_str(" - ");
+    }
+    let suffix = metadata_suffix(metadata);
+    if !suffix.is_empty() {
+        result.push_str(suffix.trim_start());
+        result.push(' ');
+    }
+    result.push_str(&metadata.full_title(subtitle_separator));
+    result.push_str(extension);
+    result
+}
+
+/// " [Series] (Year, Edition) [Language]", with empty parts left out.
+fn metadata_suffix(metadata: &ParsedMetadata) -> String {
+    let mut result = String::new();
 
     // Series info in brackets
     if let Some(ref series) = metadata.series {
@@ -739,7 +1455,11 @@ fn generate_new_filename(metadata: &ParsedMetadata, extension: &str) -> String {
         (None, None) => {}
     }
 
-    result.push_str(extension);
+    // Translated copies keep their own name next to the original
+    if let Some(ref language) = metadata.language {
+        result.push_str(&format!(" [{}]", language));
+    }
+
     result
 }
 
@@ -774,17 +1494,93 @@ mod tests {
         assert_eq!(metadata.year, Some(1976));
     }
 
+    #[test]
+    fn test_subtitle_detection() {
+        let metadata = parse_filename("Paolo Aluffi - Algebra_ Chapter 0 (2009).pdf", ".pdf").unwrap();
+        assert_eq!(metadata.title, "Algebra");
+        assert_eq!(metadata.subtitle, Some("Chapter 0".to_string()));
+        assert_eq!(
+            generate_new_filename(&metadata, ".pdf", DEFAULT_SUBTITLE_SEPARATOR),
+            "Paolo Aluffi - Algebra - Chapter 0 (2009).pdf"
+        );
+
+        let metadata = parse_filename("Topology; a first course.pdf", ".pdf").unwrap();
+        assert_eq!(metadata.full_title(", "), "Topology, a first course");
+
+        // Plain underscores between words are not subtitle separators
+        let metadata = parse_filename("Some_Underscored_Name.pdf", ".pdf").unwrap();
+        assert_eq!(metadata.subtitle, None);
+    }
+
+    #[test]
+    fn test_extract_publisher() {
+        let metadata = parse_filename("Serge Lang - Algebra (2002, Springer).pdf", ".pdf").unwrap();
+        assert_eq!(metadata.publisher, Some("Springer".to_string()));
+        assert_eq!(metadata.title, "Algebra");
+
+        let metadata = parse_filename(
+            "Ravi Vakil - The Rising Sea (Cambridge University Press) (2024).pdf",
+            ".pdf",
+        ).unwrap();
+        assert_eq!(metadata.publisher, Some("CUP".to_string()));
+
+        let metadata = parse_filename("Jane Doe - Another Title (2020, Publisher).pdf", ".pdf").unwrap();
+        assert_eq!(metadata.publisher, None);
+    }
+
+    #[test]
+    fn test_extract_kind() {
+        let metadata = parse_filename("John Smith - Algebraic Topology [Lecture notes] (2019).pdf", ".pdf").unwrap();
+        assert_eq!(metadata.kind, Some("Lecture notes".to_string()));
+        assert_eq!(metadata.title, "Algebraic Topology");
+
+        let metadata = parse_filename("Jane Doe - Moduli of Curves (PhD thesis, MIT, 2004).pdf", ".pdf").unwrap();
+        assert_eq!(metadata.kind, Some("PhD thesis".to_string()));
+
+        assert_eq!(extract_kind("Calculus - Student Solutions Manual"), Some("Solutions manual".to_string()));
+        assert_eq!(extract_kind("Title [masters thesis]"), Some("Master's thesis".to_string()));
+        assert_eq!(extract_kind("Lectures on Riemann Surfaces"), None);
+        assert_eq!(extract_kind("John Smith - Exercises in Analysis"), None);
+        assert_eq!(extract_kind("Title (Lecture Notes in Mathematics 1200)"), None);
+    }
+
+    #[test]
+    fn test_extract_identifier() {
+        let isbn = Some("ISBN 9780262046305".to_string());
+        assert_eq!(extract_identifier("Title -- 978-0-262-04630-5 -- Anna's Archive"), isbn);
+        assert_eq!(extract_identifier("Author - Title (2020) [ISBN 9780262046305]"), isbn);
+        assert_eq!(extract_identifier("Title-9780262046305"), isbn);
+        assert_eq!(extract_identifier("Title ISBN 0-387-95385-X"), Some("ISBN 038795385X".to_string()));
+        assert_eq!(extract_identifier("Title-B0F5TFL6ZQ"), Some("ASIN B0F5TFL6ZQ".to_string()));
+        // Wrong check digit, a year, a hash
+        assert_eq!(extract_identifier("Title -- 9780262046306"), None);
+        assert_eq!(extract_identifier("Title (2020)"), None);
+        assert_eq!(extract_identifier("Title -- b3ab25f14db594eb0188171e0dd81250"), None);
+
+        let metadata = parse_filename("Author - Title (2020) [ISBN 9780262046305].pdf", ".pdf").unwrap();
+        assert_eq!(metadata.title, "Title");
+        assert_eq!(metadata.identifier, isbn);
+    }
+
     #[test]
     fn test_generate_new_filename_with_all_fields() {
         let metadata = ParsedMetadata {
             authors: Some("John Smith".to_string()),
             title: "Great Book".to_string(),
+            subtitle: None,
             year: Some(2015),
             series: None,
             edition: None,
             volume: None,
+            publisher: None,
+            kind: None,
+            subject: None,
+            category: None,
+            language: None,
+            identifier: None,
+            ambiguous_order: false,
         };
-        let new_name = generate_new_filename(&metadata, ".pdf");
+        let new_name = generate_new_filename(&metadata, ".pdf", DEFAULT_SUBTITLE_SEPARATOR);
         assert_eq!(new_name, "John Smith - Great Book (2015).pdf");
     }
 
@@ -793,12 +1589,20 @@ mod tests {
         let metadata = ParsedMetadata {
             authors: Some("Jane Doe".to_string()),
             title: "Another Book".to_string(),
+            subtitle: None,
             year: None,
             series: None,
             edition: None,
             volume: None,
+            publisher: None,
+            kind: None,
+            subject: None,
+            category: None,
+            language: None,
+            identifier: None,
+            ambiguous_order: false,
         };
-        let new_name = generate_new_filename(&metadata, ".pdf");
+        let new_name = generate_new_filename(&metadata, ".pdf", DEFAULT_SUBTITLE_SEPARATOR);
         assert_eq!(new_name, "Jane Doe - Another Book.pdf");
     }
 
@@ -911,6 +1715,7 @@ mod tests {
         // No author since series is removed before author detection
         assert_eq!(metadata.title, "Math History A Long-Form Mathematics Textbook");
         assert!(!metadata.title.contains("B0F5TFL6ZQ"));
+        assert_eq!(metadata.identifier.as_deref(), Some("ASIN B0F5TFL6ZQ"));
         assert!(!metadata.title.contains("Series"));
     }
 
@@ -1019,7 +1824,7 @@ mod tests {
             (
                 "(Cambridge Studies in Advanced Mathematics 196) Fabien Durand, Dominique Perrin - Dimension Groups and Dynamical Systems_ Substitutions, Bratteli Diagrams and Cantor Systems-Cambridge University Press.pdf",
                 "Fabien Durand, Dominique Perrin",
-                "Dimension Groups and Dynamical Systems Substitutions, Bratteli Diagrams and Cantor Systems",
+                "Dimension Groups and Dynamical Systems - Substitutions, Bratteli Diagrams and Cantor Systems",
             ),
             (
                 "(CAMBRIDGE STUDIES IN ADVANCED MATHEMATICS 184) Ciprian Demeter - Fourier Restriction, Decoupling, and Applications-Cambridge University Press (2020).pdf",
@@ -1034,14 +1839,14 @@ mod tests {
             (
                 "(Cambridge Studies in Advanced Mathematics 123) Gregory F. Lawler, Vlada Limic - Random walk_ A modern introduction-CUP (2010).pdf",
                 "Gregory F. Lawler, Vlada Limic",
-                "Random walk A modern introduction",
+                "Random walk - A modern introduction",
             ),
         ];
 
         for (filename, expected_author, expected_title) in test_cases {
             let metadata = parse_filename(filename, ".pdf").unwrap();
             assert_eq!(metadata.authors, Some(expected_author.to_string()), "Failed author for {}", filename);
-            assert_eq!(metadata.title, expected_title, "Failed title for {}", filename);
+            assert_eq!(metadata.full_title(DEFAULT_SUBTITLE_SEPARATOR), expected_title, "Failed title for {}", filename);
         }
     }
 
@@ -1186,12 +1991,20 @@ mod tests {
         let metadata = ParsedMetadata {
             authors: Some("Saunders Mac Lane".to_string()),
             title: "Categories for the Working Mathematician".to_string(),
+            subtitle: None,
             year: Some(1978),
             series: Some("GTM 52".to_string()),
             edition: None,
             volume: None,
+            publisher: None,
+            kind: None,
+            subject: None,
+            category: None,
+            language: None,
+            identifier: None,
+            ambiguous_order: false,
         };
-        let new_name = generate_new_filename(&metadata, ".pdf");
+        let new_name = generate_new_filename(&metadata, ".pdf", DEFAULT_SUBTITLE_SEPARATOR);
         assert_eq!(new_name, "Saunders Mac Lane - Categories for the Working Mathematician [GTM 52] (1978).pdf");
     }
 
@@ -1200,12 +2013,20 @@ mod tests {
         let metadata = ParsedMetadata {
             authors: Some("James Munkres".to_string()),
             title: "Topology".to_string(),
+            subtitle: None,
             year: Some(2000),
             series: None,
             edition: Some("2nd ed".to_string()),
             volume: None,
+            publisher: None,
+            kind: None,
+            subject: None,
+            category: None,
+            language: None,
+            identifier: None,
+            ambiguous_order: false,
         };
-        let new_name = generate_new_filename(&metadata, ".pdf");
+        let new_name = generate_new_filename(&metadata, ".pdf", DEFAULT_SUBTITLE_SEPARATOR);
         assert_eq!(new_name, "James Munkres - Topology (2000, 2nd ed).pdf");
     }
 
@@ -1214,12 +2035,20 @@ mod tests {
         let metadata = ParsedMetadata {
             authors: Some("John Lee".to_string()),
             title: "Introduction to Smooth Manifolds".to_string(),
+            subtitle: None,
             year: Some(2012),
             series: Some("GTM 218".to_string()),
             edition: Some("2nd ed".to_string()),
             volume: None,
+            publisher: None,
+            kind: None,
+            subject: None,
+            category: None,
+            language: None,
+            identifier: None,
+            ambiguous_order: false,
         };
-        let new_name = generate_new_filename(&metadata, ".pdf");
+        let new_name = generate_new_filename(&metadata, ".pdf", DEFAULT_SUBTITLE_SEPARATOR);
         assert_eq!(new_name, "John Lee - Introduction to Smooth Manifolds [GTM 218] (2012, 2nd ed).pdf");
     }
 
@@ -1228,12 +2057,20 @@ mod tests {
         let metadata = ParsedMetadata {
             authors: Some("Michael Spivak".to_string()),
             title: "Differential Geometry Vol 2".to_string(),
+            subtitle: None,
             year: Some(1979),
             series: None,
             edition: None,
             volume: Some("Vol 2".to_string()),
+            publisher: None,
+            kind: None,
+            subject: None,
+            category: None,
+            language: None,
+            identifier: None,
+            ambiguous_order: false,
         };
-        let new_name = generate_new_filename(&metadata, ".pdf");
+        let new_name = generate_new_filename(&metadata, ".pdf", DEFAULT_SUBTITLE_SEPARATOR);
         assert_eq!(new_name, "Michael Spivak - Differential Geometry Vol 2 (1979).pdf");
     }
 
@@ -1242,12 +2079,258 @@ mod tests {
         let metadata = ParsedMetadata {
             authors: Some("Author Name".to_string()),
             title: "Book Title Vol 3".to_string(),
+            subtitle: None,
             year: Some(2020),
             series: Some("CSAM 100".to_string()),
             edition: Some("2nd ed".to_string()),
             volume: Some("Vol 3".to_string()),
+            publisher: None,
+            kind: None,
+            subject: None,
+            category: None,
+            language: None,
+            identifier: None,
+            ambiguous_order: false,
         };
-        let new_name = generate_new_filename(&metadata, ".pdf");
+        let new_name = generate_new_filename(&metadata, ".pdf", DEFAULT_SUBTITLE_SEPARATOR);
         assert_eq!(new_name, "Author Name - Book Title Vol 3 [CSAM 100] (2020, 2nd ed).pdf");
     }
+
+    #[test]
+    fn test_normalize_dir_name() {
+        assert_eq!(normalize_dir_name("( Springer GTM ) Algebra stuff [z-lib]"), "Algebra stuff [GTM]");
+        // Already normalized names stay as they are
+        assert_eq!(normalize_dir_name("Algebra stuff [GTM]"), "Algebra stuff [GTM]");
+        assert_eq!(normalize_dir_name("[Graduate Texts in Mathematics] Algebra"), "Algebra [GTM]");
+        assert_eq!(
+            normalize_dir_name("Graduate Texts in Mathematics 52 - Algebraic Geometry"),
+            "Algebraic Geometry [GTM 52]"
+        );
+        assert_eq!(normalize_dir_name("Topology (libgen)"), "Topology");
+        assert_eq!(normalize_dir_name("Homework [Week 3]"), "Homework [Week 3]");
+        assert_eq!(normalize_dir_name("Plain Folder"), "Plain Folder");
+    }
+
+    #[test]
+    fn test_clean_noise_sources_fixpoint() {
+        // Removing the hash exposes the empty `--` separators, which then
+        // leave a trailing dash once the mirror tags are gone
+        assert_eq!(
+            clean_noise_sources("Title (Springer (2nd printing)) -- 9783319110790 -- libgen -- AnnasArchive (1)-3"),
+            "Title (Springer)"
+        );
+    }
+
+    #[test]
+    fn test_ascii_punctuation_cjk_names() {
+        let cases = [
+            ("丘维声 — 高等代数（高等教育出版社，２００３）.pdf", "丘维声 - 高等代数 (2003).pdf"),
+            ("【高清】华罗庚 - 数论导引（科学出版社，1957）.pdf", "华罗庚 - 数论导引 (1957).pdf"),
+            ("小平邦彦 - 解析入門：第一部 (岩波書店, 2003).pdf", "小平邦彦 - 解析入門 - 第一部 (2003).pdf"),
+            ("Serge Lang – “Algebra” (Springer, 2002) -- libgen.pdf", "Serge Lang - \"Algebra\" (2002).pdf"),
+            ("Terence\u{3000}Tao - Analysis\u{00A0}I（2016）.pdf", "Terence Tao - Analysis I (2016).pdf"),
+        ];
+        for (input, expected) in cases {
+            let metadata = parse_filename(&ascii_punctuation(input), ".pdf").unwrap();
+            assert_eq!(generate_new_filename(&metadata, ".pdf", DEFAULT_SUBTITLE_SEPARATOR), expected, "for {}", input);
+        }
+        assert_eq!(ascii_punctuation("《红楼梦》・曹雪芹…"), "《红楼梦》・曹雪芹...");
+    }
+
+    #[test]
+    fn test_translation_language() {
+        let cases = [
+            ("Serge Lang - Algebra (2002) 中文版.pdf", "Serge Lang - Algebra (2002) [zh].pdf"),
+            ("Lang - Algebra - Russian Edition (2002).pdf", "Lang - Algebra (2002) [ru].pdf"),
+            ("Lang - Algebra (in German).pdf", "Lang - Algebra [de].pdf"),
+            ("塞尔 - 数论教程 - 冯克勤 译.pdf", "塞尔 - 数论教程 [zh].pdf"),
+            ("Dostoevsky - Crime and Punishment (translated by Garnett).pdf", "Dostoevsky - Crime and Punishment [en].pdf"),
+            // Already tagged names stay the same
+            ("Lang - Algebra (2002) [zh].pdf", "Lang - Algebra (2002) [zh].pdf"),
+            // Not markers
+            ("An English Translation of the Quran.pdf", "An English Translation of the Quran.pdf"),
+            ("翻译理论.pdf", "翻译理论.pdf"),
+        ];
+        for (input, expected) in cases {
+            let metadata = parse_filename(input, ".pdf").unwrap();
+            assert_eq!(generate_new_filename(&metadata, ".pdf", DEFAULT_SUBTITLE_SEPARATOR), expected, "for {}", input);
+        }
+        assert_eq!(script_language("数论教程"), Some("zh".to_string()));
+        assert_eq!(script_language("ノルウェイの森"), Some("ja".to_string()));
+        assert_eq!(script_language("Преступление и наказание"), Some("ru".to_string()));
+    }
+
+    #[test]
+    fn test_rtl_titles() {
+        let cases = [
+            ("\u{200F}نجيب محفوظ\u{200F} - الثلاثية (دار الشروق، ١٩٥٦).pdf", "نجيب محفوظ - الثلاثية (1956).pdf"),
+            ("الثلاثية - تأليف نجيب محفوظ.pdf", "نجيب محفوظ - الثلاثية.pdf"),
+            ("סיפור על אהבה וחושך - מאת עמוס עוז (2002).pdf", "עמוס עוז - סיפור על אהבה וחושך (2002).pdf"),
+        ];
+        for (input, expected) in cases {
+            let metadata = parse_filename(input, ".pdf").unwrap();
+            assert_eq!(generate_new_filename(&metadata, ".pdf", DEFAULT_SUBTITLE_SEPARATOR), expected, "for {}", input);
+        }
+
+        // Too long to be a name
+        let metadata = parse_filename("مقدمة في علم الجبر الخطي والهندسة التحليلية - الجزء الأول.pdf", ".pdf").unwrap();
+        assert_eq!(metadata.authors, None);
+
+        let metadata = parse_filename("طه حسين، أحمد أمين - الأيام_ الجزء الأول (1929).pdf", ".pdf").unwrap();
+        assert!(is_rtl(&metadata.title));
+        assert_eq!(
+            generate_metadata_first_filename(&metadata, ".pdf", DEFAULT_SUBTITLE_SEPARATOR),
+            "طه حسين، أحمد أمين - (1929) الأيام - الجزء الأول.pdf"
+        );
+        assert!(!is_rtl("Serge Lang - Algebra"));
+    }
+
+    #[test]
+    fn test_title_author_order() {
+        let metadata = parse_filename("Linear Algebra Done Right - Sheldon Axler.pdf", ".pdf").unwrap();
+        assert_eq!(metadata.authors.as_deref(), Some("Sheldon Axler"));
+        assert_eq!(metadata.title, "Linear Algebra Done Right");
+        assert!(!metadata.ambiguous_order);
+
+        let metadata = parse_filename("Topology - James Munkres (2000).pdf", ".pdf").unwrap();
+        assert_eq!(metadata.authors.as_deref(), Some("James Munkres"));
+
+        let metadata = parse_filename("Serge Lang - Algebra.pdf", ".pdf").unwrap();
+        assert_eq!(metadata.authors.as_deref(), Some("Serge Lang"));
+        assert!(!is_low_confidence(&metadata));
+
+        // Both sides could be names
+        let metadata = parse_filename("Gentle Rain - Jane Quux.pdf", ".pdf").unwrap();
+        assert_eq!(metadata.authors.as_deref(), Some("Gentle Rain"));
+        assert!(metadata.ambiguous_order);
+        assert!(is_low_confidence(&metadata));
+
+        // A known surname settles it
+        let metadata = parse_filename("Surely You're Joking - Richard Feynman.pdf", ".pdf").unwrap();
+        assert_eq!(metadata.authors.as_deref(), Some("Richard Feynman"));
+        assert!(!metadata.ambiguous_order);
+        let metadata = parse_filename("algebra - lang.pdf", ".pdf").unwrap();
+        assert_eq!((metadata.authors.as_deref(), metadata.title.as_str()), (Some("lang"), "algebra"));
+    }
+
+    #[test]
+    fn test_folder_hints() {
+        let known = KnownAuthors::builtin();
+        let root = Path::new("/library");
+        let hinted = |path: &str| {
+            let path = Path::new(path);
+            let name = path.file_name().unwrap().to_string_lossy();
+            let mut metadata = parse_filename(&name, ".pdf").unwrap();
+            apply_folder_hints(&mut metadata, path, root, &known);
+            (metadata.authors, metadata.series)
+        };
+        assert_eq!(hinted("/library/GTM/Serre/Linear Representations of Finite Groups.pdf"), (Some("Serre".to_string()), Some("GTM".to_string())));
+        assert_eq!(hinted("/library/Graduate Texts in Mathematics/Algebra.pdf"), (None, Some("GTM".to_string())));
+        // The filename's own author wins
+        assert_eq!(hinted("/library/Serre/Serge Lang - Algebra.pdf"), (Some("Serge Lang".to_string()), None));
+        // Neither a known author nor a series
+        assert_eq!(hinted("/library/Old Stuff/Algebra.pdf"), (None, None));
+        // Folders above the library root are not looked at
+        assert_eq!(hinted("/Serre/Algebra.pdf"), (None, None));
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        fn normalize(name: &str) -> String {
+            let metadata = parse_filename(name, ".pdf").unwrap();
+            generate_new_filename(&metadata, ".pdf", DEFAULT_SUBTITLE_SEPARATOR)
+        }
+
+        // Arbitrary text mixed with the pieces real downloads are made of
+        fn any_stem() -> impl Strategy<Value = String> {
+            let token = prop_oneof![
+                "[A-Za-z]{1,8}",
+                "[A-Z]\\.",
+                "(19|20)[0-9]{2}",
+                "[()\\[\\]_,:;.-]",
+                Just(" - ".to_string()),
+                Just(" -- ".to_string()),
+                Just("libgen".to_string()),
+                Just("Z-Library".to_string()),
+                Just("Anna's Archive".to_string()),
+                Just("(1)".to_string()),
+                Just("-3".to_string()),
+                Just("2nd Edition".to_string()),
+                Just("Vol. 2".to_string()),
+                Just("Springer".to_string()),
+                "[0-9a-f]{32}",
+                "97[89][0-9]{10}",
+                "\\PC{1,3}",
+            ];
+            prop_oneof![
+                proptest::collection::vec(token, 1..12).prop_map(|tokens| tokens.join(" ")),
+                "\\PC{1,40}",
+            ]
+        }
+
+        // "Author - Title_ Subtitle (Publisher, Year) -- hash -- mirror"
+        fn book_stem() -> impl Strategy<Value = String> {
+            let author = prop_oneof!["[A-Z][a-z]{2,8} [A-Z][a-z]{2,10}", "[A-Z]\\. [A-Z]\\. [A-Z][a-z]{3,10}"];
+            let word = prop_oneof!["[A-Z][a-z]{3,9}", "[a-z]{4,9}"];
+            let title = ("[A-Z][a-z]{3,9}", proptest::collection::vec(word, 0..5))
+                .prop_map(|(first, rest)| std::iter::once(first).chain(rest).collect::<Vec<_>>().join(" "));
+            (
+                proptest::collection::vec(author, 0..3),
+                title,
+                proptest::option::of(("[_:]", "[A-Z][a-z]{3,9} [a-z]{4,9}")),
+                proptest::option::of(1950u16..2030),
+                proptest::option::of(prop_oneof![Just("Springer"), Just("Cambridge University Press"), Just("AMS")]),
+                prop_oneof![
+                    Just(""),
+                    Just(" -- libgen"),
+                    Just(" (Z-Library)"),
+                    Just(" (1)"),
+                    Just("-3"),
+                    Just(" -- 0f1e2d3c4b5a69788796a5b4c3d2e1f0 -- Anna's Archive"),
+                ],
+            )
+                .prop_map(|(authors, title, subtitle, year, publisher, noise)| {
+                    let mut stem = String::new();
+                    if !authors.is_empty() {
+                        stem.push_str(&format!("{} - ", authors.join(", ")));
+                    }
+                    stem.push_str(&title);
+                    if let Some((separator, subtitle)) = subtitle {
+                        stem.push_str(&format!("{} {}", separator, subtitle));
+                    }
+                    match (publisher, year) {
+                        (Some(publisher), Some(year)) => stem.push_str(&format!(" ({}, {})", publisher, year)),
+                        (None, Some(year)) => stem.push_str(&format!(" ({})", year)),
+                        (Some(publisher), None) => stem.push_str(&format!(" ({})", publisher)),
+                        (None, None) => {}
+                    }
+                    stem.push_str(noise);
+                    stem
+                })
+        }
+
+        proptest! {
+            // Parsing is slow in debug builds
+            #![proptest_config(ProptestConfig::with_cases(64))]
+
+            #[test]
+            fn normalized_names_are_safe(stem in any_stem()) {
+                // Can't be part of a filename
+                prop_assume!(!stem.contains('/') && !stem.contains('\0') && !stem.trim().is_empty());
+                let name = format!("{}.pdf", stem);
+                let normalized = normalize(&name);
+                prop_assert!(normalized.ends_with(".pdf"));
+                prop_assert!(!normalized.trim_end_matches(".pdf").trim().is_empty(), "empty name for {:?}", name);
+                prop_assert!(!normalized.contains('/'), "{:?} -> {:?}", name, normalized);
+            }
+
+            #[test]
+            fn normalizing_is_idempotent(stem in book_stem()) {
+                let once = normalize(&format!("{}.pdf", stem));
+                prop_assert_eq!(normalize(&once), once.clone(), "for {:?}", stem);
+            }
+        }
+    }
 }
@@ -0,0 +1,52 @@
+use crate::history::{self, RunStats};
+use crate::i18n::t;
+use log::{debug, info};
+use notify_rust::Notification;
+
+const APP_NAME: &str = "ebook-renamer";
+
+/// `--notify`: desktop notification with the run summary, so long runs don't
+/// need the terminal in view.
+pub fn run_finished(stats: &RunStats, todo_count: usize) {
+    send(&t!(if stats.dry_run { "notify-finished-dry-run" } else { "notify-finished" }), &summary(stats, todo_count));
+}
+
+pub fn run_failed(error: &anyhow::Error) {
+    send(&t!("notify-failed"), &format!("{:#}", error));
+}
+
+fn summary(stats: &RunStats, todo_count: usize) -> String {
+    t!(
+        "notify-summary",
+        renamed = stats.renamed,
+        duplicates = stats.duplicates,
+        reclaimed = history::format_bytes(stats.bytes_reclaimed),
+        todo = todo_count,
+        problems = stats.audit_issues.len()
+    )
+}
+
+/// Missing notification daemons (SSH sessions, servers) are not an error.
+fn send(title: &str, body: &str) {
+    debug!("Notification: {}: {}", title, body);
+    if let Err(e) = Notification::new().appname(APP_NAME).summary(title).body(body).show() {
+        info!("Could not show notification: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let stats = RunStats {
+            renamed: 12,
+            duplicates: 3,
+            bytes_reclaimed: 2048,
+            audit_issues: vec!["missing".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(summary(&stats, 4), "Renamed 12 · Duplicates 3 (2.0 KB) · Todo 4 · Problems 1");
+    }
+}
@@ -0,0 +1,299 @@
+use crate::cli::RecordOriginal;
+use crate::rename_order;
+use crate::traversal::{self, Traversal};
+use anyhow::Result;
+use log::{debug, info, warn};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Per-folder record of `--record-original sidecar`: current name to the
+/// name the file had before its first rename.
+pub const SIDECAR_FILE_NAME: &str = ".orignames.json";
+
+#[cfg(unix)]
+const XATTR_NAME: &str = "user.ebook-renamer.original-name";
+
+type Sidecar = BTreeMap<String, String>;
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+fn load_sidecar(dir: &Path) -> Result<Sidecar> {
+    let path = dir.join(SIDECAR_FILE_NAME);
+    if !path.exists() {
+        return Ok(Sidecar::new());
+    }
+    serde_json::from_str(&fs::read_to_string(&path)?)
+        .map_err(|e| crate::error::Error::InvalidFile { path, message: e.to_string() }.into())
+}
+
+fn save_sidecar(dir: &Path, sidecar: &Sidecar) -> Result<()> {
+    let path = dir.join(SIDECAR_FILE_NAME);
+    if sidecar.is_empty() {
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(sidecar)? + "\n")?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn read_xattr(path: &Path) -> Option<String> {
+    xattr::get(path, XATTR_NAME).ok().flatten().and_then(|value| String::from_utf8(value).ok())
+}
+
+#[cfg(not(unix))]
+fn read_xattr(_path: &Path) -> Option<String> {
+    None
+}
+
+/// Remembers the names files had before this tool renamed them. A file
+/// renamed again keeps the name it had before its first rename.
+pub struct Recorder {
+    mode: RecordOriginal,
+    /// Sidecars as they were before this run, by folder
+    before: BTreeMap<PathBuf, Sidecar>,
+    /// Names renamed away from, by folder
+    removed: BTreeMap<PathBuf, BTreeSet<String>>,
+    /// New names and their originals, by folder
+    added: BTreeMap<PathBuf, Sidecar>,
+}
+
+impl Recorder {
+    pub fn new(mode: RecordOriginal) -> Result<Self> {
+        if mode == RecordOriginal::Xattr && cfg!(not(unix)) {
+            return Err(crate::error::Error::Usage("--record-original xattr needs extended attributes (Unix only); use sidecar".to_string()).into());
+        }
+        Ok(Self { mode, before: BTreeMap::new(), removed: BTreeMap::new(), added: BTreeMap::new() })
+    }
+
+    /// Call once `from` has been renamed to `to`.
+    pub fn record(&mut self, from: &Path, to: &Path) -> Result<()> {
+        match self.mode {
+            RecordOriginal::Xattr => self.record_xattr(from, to),
+            RecordOriginal::Sidecar => self.record_sidecar(from, to),
+        }
+    }
+
+    #[cfg(unix)]
+    fn record_xattr(&mut self, from: &Path, to: &Path) -> Result<()> {
+        // Extended attributes move with the file, so an earlier original is still there
+        if read_xattr(to).is_none() {
+            xattr::set(to, XATTR_NAME, file_name(from).as_bytes())?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn record_xattr(&mut self, _from: &Path, _to: &Path) -> Result<()> {
+        unreachable!("rejected in Recorder::new")
+    }
+
+    fn record_sidecar(&mut self, from: &Path, to: &Path) -> Result<()> {
+        let from_dir = from.parent().unwrap_or(Path::new("")).to_path_buf();
+        let to_dir = to.parent().unwrap_or(Path::new("")).to_path_buf();
+        let from_name = file_name(from);
+        // Looked up in the sidecar as the run found it, so a swap (A -> B,
+        // B -> A) doesn't see its own entries
+        if !self.before.contains_key(&from_dir) {
+            self.before.insert(from_dir.clone(), load_sidecar(&from_dir)?);
+        }
+        if !self.before.contains_key(&to_dir) {
+            self.before.insert(to_dir.clone(), load_sidecar(&to_dir)?);
+        }
+        let original = self.before[&from_dir].get(&from_name).cloned().unwrap_or_else(|| from_name.clone());
+        self.removed.entry(from_dir).or_default().insert(from_name);
+        self.added.entry(to_dir).or_default().insert(file_name(to), original);
+        Ok(())
+    }
+
+    /// Write the sidecars of the folders renamed in or out of.
+    pub fn save(&self) -> Result<()> {
+        for (dir, before) in &self.before {
+            let mut sidecar = before.clone();
+            for name in self.removed.get(dir).into_iter().flatten() {
+                sidecar.remove(name);
+            }
+            for (name, original) in self.added.get(dir).into_iter().flatten() {
+                // Back under its original name: nothing left to restore
+                if name != original {
+                    sidecar.insert(name.clone(), original.clone());
+                }
+            }
+            if &sidecar != before {
+                save_sidecar(dir, &sidecar)?;
+                debug!("Saved {} original names to {:?}", sidecar.len(), dir.join(SIDECAR_FILE_NAME));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A file to be given back the name recorded for it, in the folder it is in now.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Restore {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+// Only a name; anything else would move the file elsewhere
+fn is_bare_name(name: &str) -> bool {
+    Path::new(name).file_name().is_some_and(|n| n == name)
+}
+
+/// Files under `root` whose recorded original name (extended attribute or
+/// sidecar entry) differs from their current one, sorted by path.
+pub fn plan(root: &Path, max_depth: usize) -> Result<Vec<Restore>> {
+    let root = root.canonicalize()?;
+    let mut restores: BTreeMap<PathBuf, PathBuf> = BTreeMap::new();
+    let walker = Traversal::new(max_depth)
+        .walker(&root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_type().is_dir() || !traversal::is_excluded_dir(e.path()));
+    for entry in walker.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if entry.file_type().is_dir() {
+            for (name, original) in load_sidecar(path)? {
+                let from = path.join(&name);
+                if from.is_file() && name != original && is_bare_name(&original) {
+                    restores.entry(from).or_insert_with(|| path.join(&original));
+                }
+            }
+        } else if entry.file_type().is_file()
+            && let Some(original) = read_xattr(path)
+            && original != file_name(path)
+            && is_bare_name(&original)
+        {
+            restores.insert(path.to_path_buf(), path.with_file_name(original));
+        }
+    }
+    Ok(restores.into_iter().map(|(from, to)| Restore { from, to }).collect())
+}
+
+/// Rename files back, never replacing an existing file, and forget the
+/// recorded names of restored files. Returns one message per file left alone.
+pub fn restore(restores: &[Restore]) -> Result<Vec<String>> {
+    let pairs: Vec<(&Path, &Path)> = restores.iter().map(|r| (r.from.as_path(), r.to.as_path())).collect();
+    let mut errors = Vec::new();
+    let mut sidecars: BTreeMap<PathBuf, Sidecar> = BTreeMap::new();
+    for (index, result) in rename_order::execute_all(&pairs, || {}) {
+        let Restore { from, to } = &restores[index];
+        match result {
+            Ok(()) => {
+                info!("Restored: {} -> {}", from.display(), file_name(to));
+                forget(to, &mut sidecars)?;
+            }
+            Err(e) => {
+                warn!("Not restored, {}: {}", e, from.display());
+                errors.push(format!("{}: {}", from.display(), e));
+            }
+        }
+    }
+    for (dir, sidecar) in &sidecars {
+        save_sidecar(dir, sidecar)?;
+    }
+    Ok(errors)
+}
+
+/// Drop the record of a file now back under its original name.
+fn forget(restored: &Path, sidecars: &mut BTreeMap<PathBuf, Sidecar>) -> Result<()> {
+    #[cfg(unix)]
+    if read_xattr(restored).is_some()
+        && let Err(e) = xattr::remove(restored, XATTR_NAME)
+    {
+        debug!("Could not remove the original name of {:?}: {}", restored, e);
+    }
+    let dir = restored.parent().unwrap_or(Path::new("")).to_path_buf();
+    if !sidecars.contains_key(&dir) {
+        sidecars.insert(dir.clone(), load_sidecar(&dir)?);
+    }
+    let original = file_name(restored);
+    sidecars.get_mut(&dir).expect("just loaded").retain(|_, o| *o != original);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn rename(recorder: &mut Recorder, from: &Path, to: &Path) -> Result<()> {
+        fs::rename(from, to)?;
+        recorder.record(from, to)
+    }
+
+    #[test]
+    fn test_sidecar_round_trip() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path().canonicalize()?;
+        let p = |name: &str| dir.join(name);
+        fs::write(p("lang_algebra.pdf"), "a")?;
+        fs::write(p("x.pdf"), "x")?;
+        fs::write(p("y.pdf"), "y")?;
+
+        let mut recorder = Recorder::new(RecordOriginal::Sidecar)?;
+        rename(&mut recorder, &p("lang_algebra.pdf"), &p("Lang - Algebra.pdf"))?;
+        // A swap, recorded once both files reached their targets
+        fs::rename(p("x.pdf"), p("tmp.pdf"))?;
+        rename(&mut recorder, &p("y.pdf"), &p("x.pdf"))?;
+        fs::rename(p("tmp.pdf"), p("y.pdf"))?;
+        recorder.record(&p("x.pdf"), &p("y.pdf"))?;
+        recorder.save()?;
+
+        // A later run renames it again: the first name is kept
+        let mut recorder = Recorder::new(RecordOriginal::Sidecar)?;
+        rename(&mut recorder, &p("Lang - Algebra.pdf"), &p("Lang - Algebra (2002).pdf"))?;
+        recorder.save()?;
+        let sidecar = load_sidecar(&dir)?;
+        assert_eq!(sidecar.get("Lang - Algebra (2002).pdf").map(String::as_str), Some("lang_algebra.pdf"));
+        assert_eq!(sidecar.get("y.pdf").map(String::as_str), Some("x.pdf"));
+        assert_eq!(sidecar.len(), 3);
+
+        let restores = plan(&dir, usize::MAX)?;
+        assert_eq!(restores.len(), 3);
+        assert!(restore(&restores)?.is_empty());
+        assert_eq!(fs::read_to_string(p("lang_algebra.pdf"))?, "a");
+        assert_eq!(fs::read_to_string(p("x.pdf"))?, "x");
+        assert_eq!(fs::read_to_string(p("y.pdf"))?, "y");
+        assert!(!p(SIDECAR_FILE_NAME).exists());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_xattr_round_trip() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path().canonicalize()?;
+        fs::write(dir.join("probe"), "")?;
+        if xattr::set(dir.join("probe"), XATTR_NAME, b"probe").is_err() {
+            // Filesystem without user extended attributes
+            return Ok(());
+        }
+        fs::create_dir(dir.join("Math"))?;
+        fs::write(dir.join("Math/lang_algebra.pdf"), "a")?;
+        fs::write(dir.join("Math/Lang - Algebra.pdf"), "taken")?;
+        fs::write(dir.join("serre.pdf"), "s")?;
+
+        let mut recorder = Recorder::new(RecordOriginal::Xattr)?;
+        rename(&mut recorder, &dir.join("Math/lang_algebra.pdf"), &dir.join("Math/Lang - Algebra (2002).pdf"))?;
+        rename(&mut recorder, &dir.join("serre.pdf"), &dir.join("Serre - Linear Representations.pdf"))?;
+        recorder.save()?;
+        fs::rename(dir.join("Math/Lang - Algebra.pdf"), dir.join("Math/lang_algebra.pdf"))?;
+
+        let restores = plan(&dir, usize::MAX)?;
+        assert_eq!(restores.len(), 2);
+        // lang_algebra.pdf is taken by now
+        let errors = restore(&restores)?;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(fs::read_to_string(dir.join("serre.pdf"))?, "s");
+        assert_eq!(read_xattr(&dir.join("serre.pdf")), None);
+        assert!(dir.join("Math/Lang - Algebra (2002).pdf").exists());
+        Ok(())
+    }
+}
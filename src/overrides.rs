@@ -0,0 +1,269 @@
+use crate::duplicates::compute_md5;
+use crate::error::Error;
+use crate::normalizer::ParsedMetadata;
+use crate::scanner::FileInfo;
+use anyhow::Result;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const OVERRIDES_FILE_NAME: &str = ".ebook-renamer-overrides.json";
+
+/// Hand-corrected metadata for one file. Fields left empty are still parsed
+/// from the filename.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Override {
+    /// Only files of this size are hashed when looking up overrides
+    pub size: u64,
+    /// Filename when the override was recorded
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authors: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<u16>,
+    /// e.g. `2nd ed`; only set from Library Genesis records
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edition: Option<String>,
+}
+
+impl Override {
+    pub fn is_empty(&self) -> bool {
+        self.authors.is_none() && self.title.is_none() && self.year.is_none() && self.edition.is_none()
+    }
+
+    /// A corrected title replaces the parsed subtitle as well.
+    pub fn apply(&self, metadata: &mut ParsedMetadata) {
+        if let Some(authors) = &self.authors {
+            metadata.authors = Some(authors.clone());
+        }
+        if let Some(title) = &self.title {
+            metadata.title = title.clone();
+            metadata.subtitle = None;
+        }
+        if let Some(year) = self.year {
+            metadata.year = Some(year);
+        }
+        if let Some(edition) = &self.edition {
+            metadata.edition = Some(edition.clone());
+        }
+    }
+}
+
+/// Overrides keyed by content hash, so they survive renames and moves.
+/// Stored in `<target-dir>/.ebook-renamer-overrides.json`.
+#[derive(Debug, Default)]
+pub struct Overrides {
+    entries: BTreeMap<String, Override>,
+}
+
+impl Overrides {
+    pub fn load(target_dir: &Path) -> Result<Self> {
+        let path = target_dir.join(OVERRIDES_FILE_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        let entries: BTreeMap<String, Override> =
+            serde_json::from_str(&content).map_err(|e| Error::InvalidFile { path: path.clone(), message: format!("invalid overrides file: {}", e) })?;
+        debug!("Loaded {} overrides from {:?}", entries.len(), path);
+        Ok(Self { entries })
+    }
+
+    pub fn save(&self, target_dir: &Path) -> Result<PathBuf> {
+        let path = target_dir.join(OVERRIDES_FILE_NAME);
+        fs::write(&path, serde_json::to_string_pretty(&self.entries)? + "\n")?;
+        Ok(path)
+    }
+
+    /// Record a correction for the file at `path`, merged into any earlier one.
+    pub fn set(&mut self, path: &Path, correction: &Override) -> Result<()> {
+        let hash = compute_md5(path)?;
+        let entry = self.entries.entry(hash).or_default();
+        entry.size = fs::metadata(path)?.len();
+        entry.name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if correction.authors.is_some() {
+            entry.authors = correction.authors.clone();
+        }
+        if correction.title.is_some() {
+            entry.title = correction.title.clone();
+        }
+        if correction.year.is_some() {
+            entry.year = correction.year;
+        }
+        if correction.edition.is_some() {
+            entry.edition = correction.edition.clone();
+        }
+        Ok(())
+    }
+
+    /// Returns whether there was an override for this file's content.
+    pub fn unset(&mut self, path: &Path) -> Result<bool> {
+        Ok(self.entries.remove(&compute_md5(path)?).is_some())
+    }
+
+    /// Content hash and override, sorted by hash.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Override)> {
+        self.entries.iter()
+    }
+
+    /// The override for this file's content, hashing it only if an override
+    /// of the same size exists.
+    pub fn lookup(&self, file_info: &FileInfo) -> Option<&Override> {
+        if !self.entries.values().any(|o| o.size == file_info.size) {
+            return None;
+        }
+        let hash = compute_md5(&file_info.original_path).ok()?;
+        self.entries.get(&hash)
+    }
+}
+
+/// One entry of a `--merge-metadata` file, as written by external tools.
+#[derive(Debug, Deserialize)]
+struct Correction {
+    #[serde(default, alias = "author")]
+    authors: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    year: Option<u16>,
+}
+
+/// Corrections from `--merge-metadata`, keyed by the file's path when the
+/// tool produced them. Applied after the overrides file.
+#[derive(Debug, Default)]
+pub struct MergedMetadata {
+    entries: HashMap<PathBuf, Override>,
+}
+
+impl MergedMetadata {
+    /// A JSON object mapping paths to `{"authors", "title", "year"}`.
+    /// Relative paths are resolved against the target directory.
+    pub fn load(path: &Path, target_dir: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Err(Error::NotFound { what: "Metadata file", path: path.to_path_buf() }.into());
+        }
+        let content = fs::read_to_string(path)?;
+        let corrections: BTreeMap<PathBuf, Correction> = serde_json::from_str(&content)
+            .map_err(|e| Error::InvalidFile { path: path.to_path_buf(), message: format!("invalid metadata file: {}", e) })?;
+        let mut entries = HashMap::new();
+        for (file, correction) in corrections {
+            let Ok(file) = target_dir.join(&file).canonicalize() else {
+                warn!("Metadata for {:?} ignored: no such file", file);
+                continue;
+            };
+            let correction = Override { authors: correction.authors, title: correction.title, year: correction.year, ..Default::default() };
+            if !correction.is_empty() {
+                entries.insert(file, correction);
+            }
+        }
+        debug!("Loaded metadata for {} files from {:?}", entries.len(), path);
+        Ok(Self { entries })
+    }
+
+    pub fn lookup(&self, file_info: &FileInfo) -> Option<&Override> {
+        self.entries.get(&file_info.original_path)
+    }
+
+    /// Add metadata for a file that has none yet; entries from the
+    /// `--merge-metadata` file win.
+    pub fn add_missing(&mut self, path: &Path, correction: Override) {
+        if !correction.is_empty() {
+            self.entries.entry(path.to_path_buf()).or_insert(correction);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_set_save_and_lookup() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let path = tmp_dir.path().join("scan0001.pdf");
+        fs::write(&path, "%PDF-1.4 some content")?;
+
+        let mut overrides = Overrides::default();
+        overrides.set(&path, &Override { authors: Some("Serge Lang".to_string()), ..Default::default() })?;
+        overrides.set(&path, &Override { title: Some("Algebra".to_string()), ..Default::default() })?;
+        overrides.save(tmp_dir.path())?;
+
+        // Renamed since the override was recorded
+        let renamed = tmp_dir.path().join("lang.pdf");
+        fs::rename(&path, &renamed)?;
+        let file_info = FileInfo {
+            original_path: renamed.clone(),
+            original_name: "lang.pdf".to_string(),
+            extension: ".pdf".to_string(),
+            size: fs::metadata(&renamed)?.len(),
+            modified_time: SystemTime::now(),
+            is_failed_download: false,
+            is_too_small: true,
+            is_virtual: false,
+            new_name: None,
+            new_path: renamed,
+            metadata: None,
+        };
+
+        let loaded = Overrides::load(tmp_dir.path())?;
+        let found = loaded.lookup(&file_info).expect("override should be found by content");
+        assert_eq!(found.name, "scan0001.pdf");
+
+        let mut metadata = ParsedMetadata { title: "lang".to_string(), subtitle: Some("x".to_string()), year: Some(2002), ..Default::default() };
+        found.apply(&mut metadata);
+        assert_eq!(metadata.authors.as_deref(), Some("Serge Lang"));
+        assert_eq!((metadata.title.as_str(), metadata.subtitle), ("Algebra", None));
+        assert_eq!(metadata.year, Some(2002));
+
+        let mut loaded = loaded;
+        assert!(loaded.unset(&file_info.original_path)?);
+        assert!(!loaded.unset(&file_info.original_path)?);
+        assert_eq!(loaded.iter().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merged_metadata() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path().canonicalize()?;
+        fs::create_dir(dir.join("Algebra"))?;
+        fs::write(dir.join("Algebra/lang.pdf"), "%PDF-1.4")?;
+        fs::write(dir.join("serre.pdf"), "%PDF-1.4")?;
+        let file = dir.join("corrections.json");
+        fs::write(&file, serde_json::json!({
+            "Algebra/lang.pdf": {"authors": "Serge Lang", "title": "Algebra", "goodreads_id": 1},
+            dir.join("serre.pdf").to_string_lossy(): {"author": "Jean-Pierre Serre", "year": 1973},
+            "missing.pdf": {"title": "Gone"},
+        }).to_string())?;
+
+        let merged = MergedMetadata::load(&file, &dir)?;
+        let file_info = |path: PathBuf| FileInfo {
+            original_name: path.file_name().unwrap().to_string_lossy().to_string(),
+            extension: ".pdf".to_string(),
+            size: 8,
+            modified_time: SystemTime::now(),
+            is_failed_download: false,
+            is_too_small: true,
+            is_virtual: false,
+            new_name: None,
+            new_path: path.clone(),
+            metadata: None,
+            original_path: path,
+        };
+        let lang = merged.lookup(&file_info(dir.join("Algebra/lang.pdf"))).unwrap();
+        assert_eq!((lang.authors.as_deref(), lang.title.as_deref()), (Some("Serge Lang"), Some("Algebra")));
+        let serre = merged.lookup(&file_info(dir.join("serre.pdf"))).unwrap();
+        assert_eq!((serre.authors.as_deref(), serre.year), (Some("Jean-Pierre Serre"), Some(1973)));
+        assert_eq!(merged.entries.len(), 2);
+
+        assert!(MergedMetadata::load(&dir.join("nope.json"), &dir).is_err());
+        Ok(())
+    }
+}
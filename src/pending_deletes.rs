@@ -0,0 +1,145 @@
+use crate::delete_mode::Deleter;
+use anyhow::Result;
+use chrono::Local;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Deletions previewed by `--dry-run-deletes`, run by `--confirm-deletes`.
+pub const PENDING_FILE_NAME: &str = ".ebook-renamer-pending-deletes.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteKind {
+    Duplicate,
+    SmallOrFailed,
+    Sample,
+    /// Empty `.download`/`.crdownload` folder left by recovery
+    DownloadFolder,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingDelete {
+    pub path: PathBuf,
+    pub kind: DeleteKind,
+    /// Size when previewed; the file is kept if it changed since
+    #[serde(default)]
+    pub size: u64,
+    /// For duplicates, the copy that is kept (after this run's renames)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PendingDeletes {
+    pub created: String,
+    pub deletes: Vec<PendingDelete>,
+}
+
+impl PendingDeletes {
+    pub fn new() -> Self {
+        Self { created: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(), deletes: Vec::new() }
+    }
+
+    pub fn add(&mut self, path: &Path, kind: DeleteKind, keep: Option<&Path>) {
+        let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        self.deletes.push(PendingDelete { path: path.to_path_buf(), kind, size, keep: keep.map(Path::to_path_buf) });
+    }
+
+    /// Replaces the plan of an earlier preview; nothing pending removes the file.
+    pub fn save(&self, target_dir: &Path) -> Result<()> {
+        let path = target_dir.join(PENDING_FILE_NAME);
+        if self.deletes.is_empty() {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(target_dir: &Path) -> Result<Option<Self>> {
+        let path = target_dir.join(PENDING_FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    /// Delete everything that still matches the preview. Files that changed
+    /// size, vanished, or whose kept copy is gone are left alone and reported.
+    /// The plan file is removed afterwards.
+    pub fn execute(&self, target_dir: &Path, deleter: &Deleter) -> Result<(Vec<&PendingDelete>, Vec<String>)> {
+        let mut deleted = Vec::new();
+        let mut errors = Vec::new();
+        for delete in &self.deletes {
+            let path = &delete.path;
+            if !path.exists() {
+                errors.push(format!("Already gone: {}", path.display()));
+                continue;
+            }
+            if let Some(keep) = delete.keep.as_ref().filter(|keep| !keep.exists()) {
+                errors.push(format!("Not deleted, kept copy {} is missing: {}", keep.display(), path.display()));
+                continue;
+            }
+            let result = if delete.kind == DeleteKind::DownloadFolder {
+                // Only ever empty folders
+                fs::remove_dir(path)
+            } else if fs::metadata(path).map_or(true, |m| m.len() != delete.size) {
+                errors.push(format!("Not deleted, changed since the preview: {}", path.display()));
+                continue;
+            } else {
+                deleter.remove(path)
+            };
+            match result {
+                Ok(_) => {
+                    info!("Deleted pending {:?}: {}", delete.kind, path.display());
+                    deleted.push(delete);
+                }
+                Err(e) => errors.push(format!("Failed to delete {}: {}", path.display(), e)),
+            }
+        }
+        fs::remove_file(target_dir.join(PENDING_FILE_NAME))?;
+        Ok((deleted, errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::DeleteMode;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_load_and_execute() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path();
+        fs::write(dir.join("Kept.pdf"), "book")?;
+        fs::write(dir.join("Copy.pdf"), "book")?;
+        fs::write(dir.join("Orphan.pdf"), "book")?;
+        fs::write(dir.join("tiny.pdf"), "x")?;
+        fs::create_dir(dir.join("Book.pdf.download"))?;
+
+        let mut pending = PendingDeletes::new();
+        pending.add(&dir.join("Copy.pdf"), DeleteKind::Duplicate, Some(&dir.join("Kept.pdf")));
+        pending.add(&dir.join("Orphan.pdf"), DeleteKind::Duplicate, Some(&dir.join("Gone.pdf")));
+        pending.add(&dir.join("tiny.pdf"), DeleteKind::SmallOrFailed, None);
+        pending.add(&dir.join("Book.pdf.download"), DeleteKind::DownloadFolder, None);
+        pending.save(dir)?;
+
+        // Changed between the preview and the confirmation
+        fs::write(dir.join("tiny.pdf"), "xx")?;
+
+        let loaded = PendingDeletes::load(dir)?.unwrap();
+        assert_eq!(loaded.deletes, pending.deletes);
+        let (deleted, errors) = loaded.execute(dir, &Deleter::new(DeleteMode::Delete, dir, None)?)?;
+        let deleted: Vec<&Path> = deleted.iter().map(|d| d.path.as_path()).collect();
+        assert_eq!(deleted, vec![dir.join("Copy.pdf"), dir.join("Book.pdf.download")]);
+        assert_eq!(errors.len(), 2);
+        assert!(dir.join("Orphan.pdf").exists() && dir.join("tiny.pdf").exists());
+        assert!(PendingDeletes::load(dir)?.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use crate::scanner::FileInfo;
+use log::warn;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Whether the folder holding `path` is marked read-only, as sync clients do
+/// for view-only shared folders. Renaming inside it would fail.
+pub fn is_read_only_location(path: &Path) -> bool {
+    path.parent()
+        .and_then(|dir| fs::metadata(dir).ok())
+        .is_some_and(|m| m.permissions().readonly())
+}
+
+/// Drop planned renames in read-only folders so they are reported instead of
+/// failing the run. Returns the affected files.
+pub fn skip_read_only_renames(files: &mut [FileInfo]) -> Vec<PathBuf> {
+    let mut skipped = Vec::new();
+    for file_info in files.iter_mut() {
+        if file_info.new_name.is_some()
+            && file_info.new_path != file_info.original_path
+            && is_read_only_location(&file_info.original_path)
+        {
+            warn!("Insufficient permission to rename {}", file_info.original_path.display());
+            file_info.new_name = None;
+            file_info.new_path = file_info.original_path.clone();
+            skipped.push(file_info.original_path.clone());
+        }
+    }
+    skipped
+}
+
+pub fn is_permission_error(error: &io::Error) -> bool {
+    matches!(error.kind(), io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_skip_read_only_renames() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let shared = tmp_dir.path().join("shared");
+        fs::create_dir(&shared)?;
+        fs::write(shared.join("book.pdf"), "x")?;
+        fs::write(tmp_dir.path().join("own.pdf"), "x")?;
+
+        let mut files = crate::scanner::Scanner::new(tmp_dir.path(), 2)?.scan()?;
+        for file_info in &mut files {
+            file_info.new_name = Some("Renamed.pdf".to_string());
+            file_info.new_path = file_info.original_path.with_file_name("Renamed.pdf");
+        }
+
+        let original = fs::metadata(&shared)?.permissions();
+        let mut read_only = original.clone();
+        read_only.set_readonly(true);
+        fs::set_permissions(&shared, read_only)?;
+
+        let skipped = skip_read_only_renames(&mut files);
+        fs::set_permissions(&shared, original)?;
+
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].ends_with("shared/book.pdf"));
+        assert_eq!(files.iter().filter(|f| f.new_name.is_some()).count(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,1121 @@
+use crate::cli::{self, Args};
+use crate::download_recovery::DownloadRecovery;
+use crate::i18n::t;
+use crate::overrides::Override;
+use crate::skipped::{SkipList, SkipReason};
+use crate::{
+    annotations, archives, arxiv_versions, audit, authors, catalog, chapters, cloud, collections, config, delete_mode, dir_renamer,
+    dupe_ignore, duplicates, file_kind, fingerprint, history, in_use, known_authors, libgen, normalizer, notify, original_names,
+    overrides, pending_deletes, permissions, placeholders, prune, redownload, rename_order, samples, scanner, simulation, space_map,
+    split_parts, stubs, sync_conflicts, sync_ignore, throttle, todo, variants,
+};
+use anyhow::Result;
+use log::{info, warn};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How far a run has got, for progress displays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Files found by the scan
+    Scanned(usize),
+    /// Files after parsing and normalizing
+    Normalized(usize),
+    /// todo.md entries collected
+    Checked,
+    /// Duplicate groups found
+    Deduplicated(usize),
+}
+
+/// A low-confidence parse offered for correction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewItem {
+    pub path: PathBuf,
+    pub name: String,
+    /// Author, title and year as parsed
+    pub parsed: [String; 3],
+}
+
+/// Corrections typed in the review form; `save` also stores them as overrides.
+#[derive(Debug, Default)]
+pub struct ReviewResult {
+    pub corrections: Vec<(PathBuf, Override)>,
+    pub save: bool,
+}
+
+/// What a front end shows of a run while it happens. Everything the run
+/// found is in the returned [`Plan`].
+pub trait Reporter {
+    /// Receives the scan's progress, if the front end shows it.
+    fn scan_progress(&mut self) -> Option<scanner::ScanProgress> {
+        None
+    }
+
+    fn stage(&mut self, stage: Stage) -> Result<()>;
+
+    /// Something the user may want to know, e.g. how many files were recovered.
+    fn info(&mut self, message: String) -> Result<()>;
+
+    /// A step that failed without stopping the run.
+    fn warn(&mut self, message: String) -> Result<()>;
+
+    /// Corrections for low-confidence parses (`--review`); none by default.
+    fn review(&mut self, _items: Vec<ReviewItem>) -> Result<ReviewResult> {
+        Ok(ReviewResult::default())
+    }
+}
+
+/// Everything a run found and decided, before (and after) it is carried out.
+pub struct Plan {
+    pub dir_renames: Vec<dir_renamer::DirRename>,
+    /// Predicted by a dry run, removed by a live one
+    pub pruned_dirs: Vec<PathBuf>,
+    pub native_docs: Vec<PathBuf>,
+    pub archive_reports: Vec<archives::ArchiveReport>,
+    pub normalize_options: normalizer::NormalizeOptions,
+    pub chapter_groups: Vec<chapters::ChapterGroup>,
+    pub sample_files: Vec<samples::Sample>,
+    /// `--drop-samples` unless it only reports
+    pub drop_samples: Option<cli::SampleAction>,
+    pub part_sets: Vec<split_parts::PartSet>,
+    pub sync_conflicts: Vec<variants::PairedCopy>,
+    pub name_variants: Vec<variants::PairedCopy>,
+    pub html_stubs: Vec<stubs::Stub>,
+    pub todo_list: todo::TodoList,
+    /// (category, file, message) for the JSON output
+    pub todo_items: Vec<(String, String, String)>,
+    /// Small, failed and stub files to delete
+    pub files_to_delete: Vec<PathBuf>,
+    pub duplicate_groups: Vec<Vec<PathBuf>>,
+    pub clean_files: Vec<scanner::FileInfo>,
+    pub version_groups: Vec<arxiv_versions::VersionGroup>,
+    pub annotated_groups: Vec<annotations::AnnotatedGroup>,
+    pub probable_groups: Vec<fingerprint::ProbableGroup>,
+    pub plan_issues: Vec<simulation::Issue>,
+    /// Skipped after a metadata read or hash exceeded `--io-timeout`
+    pub timed_out: Vec<PathBuf>,
+    pub skipped: SkipList,
+    pub run_stats: history::RunStats,
+    pub collection_stats: Vec<collections::CollectionStats>,
+    pub space_map: Option<space_map::SpaceMap>,
+    /// Deletions a `--dry-run-deletes` run saved for `--confirm-deletes`
+    pub pending: Option<pending_deletes::PendingDeletes>,
+    pruner: Option<prune::EmptyDirPruner>,
+}
+
+impl Plan {
+    /// Files that get a new name, from and to.
+    pub fn renames(&self) -> Vec<(PathBuf, PathBuf)> {
+        self.clean_files
+            .iter()
+            .filter(|f| f.new_name.is_some() && f.new_path != f.original_path)
+            .map(|f| (f.original_path.clone(), f.new_path.clone()))
+            .collect()
+    }
+
+    /// Files the run deletes: broken files, extra duplicate copies and dropped samples.
+    pub fn deletions(&self, args: &Args) -> Vec<PathBuf> {
+        let mut deletes = self.files_to_delete.clone();
+        if !args.no_delete {
+            deletes.extend(self.duplicate_groups.iter().flat_map(|group| group.iter().skip(1).cloned()));
+        }
+        if self.drop_samples == Some(cli::SampleAction::Delete) {
+            deletes.extend(self.sample_files.iter().map(|s| s.path.clone()));
+        }
+        deletes
+    }
+}
+
+/// Scan and analyze `args.path` and decide what the run does. Only the steps
+/// that later ones depend on change anything here: download recovery, folder
+/// renames and archive extraction (none of them in a dry run).
+pub fn plan(args: &Args, reporter: &mut impl Reporter) -> Result<Plan> {
+    // Handle --no-recursive: every stage uses the same effective depth
+    let traversal = args.traversal();
+    let effective_max_depth = traversal.max_depth;
+    // Invalid collection patterns fail before anything is moved
+    let collections = collections::Collections::new(&config::Config::load(&args.path)?.collections)?;
+
+    // Recover downloads from .download/.crdownload folders (moves files, so never in read-only mode)
+    let mut recovered = HashSet::new();
+    if args.read_only {
+        info!("Read-only mode: skipping download recovery");
+    } else if args.no_recovery {
+        info!("Download recovery disabled (--no-recovery)");
+    } else if args.files_from.is_some() {
+        info!("Only processing listed files: skipping download recovery");
+    } else {
+        // With --dry-run-deletes the emptied folders are only previewed
+        let recovery = DownloadRecovery::new(&args.path, args.cleanup_downloads && !args.dry_run_deletes)
+            .with_traversal(traversal)
+            .with_min_valid_size(args.min_valid_size.clone());
+        let recovery_result = recovery.recover_downloads()?;
+        // Scanned paths are canonical
+        recovered.extend(recovery_result.extracted_files.iter().filter_map(|p| p.canonicalize().ok()));
+        if !recovery_result.extracted_files.is_empty() {
+            info!("Recovered {} PDFs from download folders", recovery_result.extracted_files.len());
+            reporter.info(t!("recovered-downloads", count = recovery_result.extracted_files.len()))?;
+        }
+        if !recovery_result.errors.is_empty() {
+            info!("Encountered {} errors during download recovery", recovery_result.errors.len());
+        }
+        for error in recovery_result.errors {
+            reporter.warn(error)?;
+        }
+    }
+
+    // Folders and files the sync client leaves out are neither renamed nor scanned
+    let sync_exclusions = sync_ignore::SyncIgnore::load(&args.path, args.sync_exclusions.as_deref())?;
+
+    // Normalize directory names before scanning so file paths reflect the new folders
+    let mut dir_renames = Vec::new();
+    if args.rename_dirs {
+        let dir_renamer = dir_renamer::DirRenamer::new(&args.path, effective_max_depth)?.with_sync_ignore(sync_exclusions.clone());
+        dir_renames = dir_renamer.plan()?;
+        info!("Planned {} directory renames", dir_renames.len());
+        if !args.dry_run {
+            for error in dir_renamer.execute(&dir_renames) {
+                info!("{}", error);
+                reporter.warn(error)?;
+            }
+        }
+    }
+
+    // Remember which directories have content now, so only the ones we empty get pruned
+    let pruner = if args.prune_empty_dirs {
+        Some(prune::EmptyDirPruner::snapshot(&args.path, traversal, &sync_exclusions)?)
+    } else {
+        None
+    };
+
+    let mut scanner = scanner::Scanner::new(&args.path, effective_max_depth)?
+        .with_io_timeout(args.io_timeout_duration())
+        .with_min_valid_size(args.min_valid_size.clone().unwrap_or_default())
+        .with_sync_ignore(sync_exclusions)
+        .with_checkpoints(!args.read_only, args.resume_scan)?;
+    if let Some(progress) = reporter.scan_progress() {
+        scanner = scanner.with_progress(progress);
+    }
+    let merged = match &args.merge_metadata {
+        Some(path) => overrides::MergedMetadata::load(path, &args.path)?,
+        None => overrides::MergedMetadata::default(),
+    };
+    let mut normalize_options = normalizer::NormalizeOptions {
+        aliases: authors::AuthorAliases::load(&args.author_aliases, &args.path)?,
+        template: args.template.clone(),
+        subtitle_separator: args.subtitle_separator.clone(),
+        overrides: overrides::Overrides::load(&args.path)?,
+        merged,
+        ascii_punctuation: args.ascii_punctuation,
+        rtl_metadata_first: args.rtl_metadata_first,
+        known_authors: known_authors::KnownAuthors::load(&args.path)?,
+        folder_hints: if args.folder_hints { Some(args.path.canonicalize()?) } else { None },
+    };
+    // Downloads still named by their Library Genesis MD5 get the catalog's author/title/year
+    let mut lookup = if args.fetch_libgen_metadata && !args.no_normalize {
+        Some(libgen::Lookup::load(&args.path)?.with_api_url(args.libgen_url.as_deref()))
+    } else {
+        None
+    };
+    if args.no_normalize {
+        info!("Filename normalization disabled (--no-normalize)");
+    }
+
+    // Files are parsed and normalized as the scan finds them, so the scan
+    // itself is never held in memory next to the normalized list
+    let mut native_docs = Vec::new();
+    let mut normalized = Vec::new();
+    let mut each = |mut file_info: scanner::FileInfo| {
+        // Google Docs/Dropbox Paper shortcuts (.gdoc, .paper, ...) have no content to rename or dedupe
+        if cloud::is_native_doc(&file_info.extension) {
+            native_docs.push(file_info.original_path);
+            return Ok(());
+        }
+        prepare(args, &mut file_info, lookup.as_mut(), &mut normalize_options)?;
+        normalized.push(file_info);
+        Ok(())
+    };
+    let scanned = match &args.files_from {
+        Some(source) => {
+            let files = scanner.scan_paths(scanner::read_file_list(source)?)?;
+            let count = files.len();
+            files.into_iter().try_for_each(&mut each)?;
+            count
+        }
+        None => scanner.scan_each(&mut each)?,
+    };
+    reporter.stage(Stage::Scanned(scanned))?;
+    if scanner.resumed_files > 0 {
+        reporter.info(t!("scan-resumed", files = scanner.resumed_files))?;
+    }
+    info!("Found {} files to process", scanned);
+
+    // Everything below that ends up neither renamed nor deleted, for --show-skipped
+    let mut skipped = SkipList::default();
+    skipped.add_all(&native_docs, SkipReason::NativeDocument);
+    if !native_docs.is_empty() {
+        info!("Skipping {} native cloud document shortcuts", native_docs.len());
+        reporter.info(t!("skipped-native-docs", count = native_docs.len()))?;
+    }
+
+    // Files still being written are neither renamed nor hashed this run
+    if let Some(settle) = args.settle {
+        let in_use = in_use::find_in_use(&normalized, Duration::from_secs(settle));
+        if !in_use.is_empty() {
+            info!("Deferring {} files that are still being written", in_use.len());
+            let deferred: HashSet<&PathBuf> = in_use.iter().collect();
+            normalized.retain(|f| !deferred.contains(&f.original_path));
+            skipped.add_all(&in_use, SkipReason::InUse);
+        }
+    }
+
+    // Ebooks bundled in .zip/.rar downloads; extracted ones are renamed and deduplicated with the rest
+    let archive_reports = match args.archives {
+        Some(_) => archives::inspect(&normalized, !args.skip_cloud_hash),
+        None => Vec::new(),
+    };
+    if args.archives == Some(cli::ArchiveMode::Extract) && !args.dry_run {
+        let (extracted, errors) = archives::extract(&archive_reports);
+        info!("Extracted {} ebooks from {} archives", extracted.len(), archive_reports.len());
+        for path in &extracted {
+            let mut file_info = scanner.create_file_info(path)?;
+            prepare(args, &mut file_info, lookup.as_mut(), &mut normalize_options)?;
+            normalized.push(file_info);
+        }
+        for error in errors {
+            info!("{}", error);
+            reporter.warn(error)?;
+        }
+    }
+
+    if let Some(lookup) = &lookup {
+        if !args.read_only
+            && let Err(e) = lookup.save()
+        {
+            info!("Failed to save the libgen cache: {}", e);
+        }
+        info!("Found {} files on Library Genesis", lookup.found);
+        if lookup.found > 0 {
+            reporter.info(t!("libgen-found", count = lookup.found))?;
+        }
+        if let Some(error) = &lookup.offline {
+            reporter.warn(t!("libgen-offline", error = error.clone()))?;
+        }
+    }
+    info!("Normalized {} files", normalized.len());
+    reporter.stage(Stage::Normalized(normalized.len()))?;
+
+    // Hand corrections for parses with no author or a broken title
+    if args.review {
+        review(args, &mut normalized, &normalize_options, reporter)?;
+    }
+
+    // Chapter/book-matter PDFs keep their names; they are reported or moved per book instead
+    let chapter_groups = match args.chapter_files {
+        Some(_) => chapters::detect_chapter_files(&normalized),
+        None => Vec::new(),
+    };
+    if !chapter_groups.is_empty() {
+        info!("Detected {} books split into chapter files", chapter_groups.len());
+        chapters::keep_original_names(&mut normalized, &chapter_groups);
+    }
+    // Store samples and previews leave the plan unless they are only reported
+    let sample_files = match args.drop_samples {
+        Some(_) => samples::detect_samples(&normalized),
+        None => Vec::new(),
+    };
+    let drop_samples = args.drop_samples.filter(|action| *action != cli::SampleAction::Report);
+    if drop_samples.is_some() {
+        let sample_paths: HashSet<&Path> = sample_files.iter().map(|s| s.path.as_path()).collect();
+        normalized.retain(|f| !sample_paths.contains(f.original_path.as_path()));
+    }
+    if !sample_files.is_empty() {
+        info!("Detected {} sample files", sample_files.len());
+    }
+    // Books split into part PDFs are reported, or leave the plan to be merged
+    let part_sets = split_parts::detect(&normalized, &normalize_options);
+    if !part_sets.is_empty() {
+        info!("Detected {} books split into parts", part_sets.len());
+    }
+    if args.merge_parts {
+        let part_paths: HashSet<&Path> = part_sets.iter().flat_map(|set| set.parts.iter().map(|p| p.as_path())).collect();
+        normalized.retain(|f| !part_paths.contains(f.original_path.as_path()));
+    }
+
+    // Conflicted copies keep their names; identical ones join their base's duplicate group below
+    let sync_conflicts = if args.sync_conflicts {
+        sync_conflicts::detect(&normalized, !args.skip_cloud_hash && !args.no_dedupe)
+    } else {
+        Vec::new()
+    };
+    if !sync_conflicts.is_empty() {
+        let conflict_paths: HashSet<&Path> = sync_conflicts.iter().map(|c| c.path.as_path()).collect();
+        normalized.retain(|f| !conflict_paths.contains(f.original_path.as_path()));
+        info!("Detected {} sync conflicts", sync_conflicts.len());
+    }
+    // Re-downloaded "(1)"/"copy"/"副本" variants are handled the same way
+    let name_variants = if args.merge_variants {
+        variants::detect(&normalized, !args.skip_cloud_hash && !args.no_dedupe)
+    } else {
+        Vec::new()
+    };
+    if !name_variants.is_empty() {
+        let variant_paths: HashSet<&Path> = name_variants.iter().map(|v| v.path.as_path()).collect();
+        normalized.retain(|f| !variant_paths.contains(f.original_path.as_path()));
+        info!("Detected {} re-downloaded variants", name_variants.len());
+    }
+
+    // Error and captcha pages saved under a book's name are failed downloads
+    let html_stubs = if args.no_integrity_check { Vec::new() } else { stubs::detect(&normalized) };
+    if !html_stubs.is_empty() {
+        let stub_paths: HashSet<&Path> = html_stubs.iter().map(|s| s.path.as_path()).collect();
+        normalized.retain(|f| !stub_paths.contains(f.original_path.as_path()));
+        if !(args.delete_small || args.clean_failed) {
+            skipped.add_all(html_stubs.iter().map(|s| &s.path), SkipReason::HtmlStub);
+        }
+    }
+
+    // Files whose content is another format than their extension says
+    let mut mismatches = if args.no_integrity_check && !args.fix_extensions {
+        Vec::new()
+    } else {
+        file_kind::detect_mismatches(&normalized)
+    };
+    if args.fix_extensions {
+        let found = mismatches.len();
+        mismatches = file_kind::fix_extensions(&mut normalized, mismatches);
+        info!("Fixed the extension of {} files", found - mismatches.len());
+    }
+
+    skipped.add_unprocessable(&normalized, args.delete_small || args.clean_failed);
+    skipped.add_all(chapter_groups.iter().flat_map(|g| g.parts.iter().map(|p| &p.path)), SkipReason::ChapterPart);
+
+    // Handle failed downloads and small files
+    let mut todo_list = todo::TodoList::new(&args.todo_file, &args.path)?;
+    todo_list.set_collections(collections.clone());
+    let mut files_to_delete = Vec::new();
+    let mut todo_items = Vec::new();
+
+    for file_info in &normalized {
+        if file_info.is_failed_download || file_info.is_too_small {
+            if args.delete_small {
+                files_to_delete.push(file_info.original_path.clone());
+                // Remove this file from todo list since we're deleting it
+                todo_list.remove_file_from_todo(&file_info.original_path);
+                continue;
+            }
+            // Log, and with --clean-failed also delete
+            todo_list.add_failed_download(file_info)?;
+            if args.clean_failed {
+                files_to_delete.push(file_info.original_path.clone());
+            }
+            let category = if file_info.is_failed_download { "failed_download" } else { "too_small" };
+            let message = if file_info.is_failed_download {
+                format!("Redownload: {} (Unfinished download)", file_info.original_name)
+            } else {
+                format!("Check and redownload: {} (File too small, only {} bytes)", file_info.original_name, file_info.size)
+            };
+            todo_items.push((category.to_string(), file_info.original_name.clone(), message));
+        } else if let Some(mismatch) = mismatches.iter().find(|m| m.path == file_info.original_path) {
+            todo_list.add_file_issue(file_info, todo::FileIssue::InvalidExtension(mismatch.kind))?;
+            let message = format!("Check file: {} (content is {}, not {})", file_info.original_name, mismatch.kind.name(), file_info.extension);
+            todo_items.push(("invalid_extension".to_string(), file_info.original_name.clone(), message));
+        } else if !args.no_integrity_check {
+            // Analyze file integrity for all other files
+            todo_list.analyze_file_integrity(file_info)?;
+        }
+    }
+
+    for stub in &html_stubs {
+        if args.delete_small {
+            files_to_delete.push(stub.path.clone());
+            todo_list.remove_file_from_todo(&stub.path);
+            continue;
+        }
+        todo_list.add_html_stub(stub);
+        if args.clean_failed {
+            files_to_delete.push(stub.path.clone());
+        }
+        let name = stub.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let message = format!("Redownload: {} (saved a {})", name, stub.describe());
+        todo_items.push(("html_stub".to_string(), name, message));
+    }
+
+    for conflict in sync_conflicts.iter().filter(|c| c.identical != Some(true)) {
+        todo_list.add_sync_conflict(&conflict.path, &conflict.base);
+        skipped.add(&conflict.path, SkipReason::SyncConflict);
+        let name = conflict.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let base = conflict.base.file_name().unwrap_or_default().to_string_lossy();
+        let message = format!("Resolve sync conflict: {} (conflicts with {})", name, base);
+        todo_items.push(("sync_conflict".to_string(), name, message));
+    }
+    for variant in name_variants.iter().filter(|v| v.identical != Some(true)) {
+        todo_list.add_name_conflict(&variant.path, &variant.base);
+        skipped.add(&variant.path, SkipReason::NameConflict);
+        let name = variant.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let base = variant.base.file_name().unwrap_or_default().to_string_lossy();
+        let message = format!("Same name, different content: {} (differs from {})", name, base);
+        todo_items.push(("name_conflict".to_string(), name, message));
+    }
+
+    if drop_samples.is_none() {
+        for sample in &sample_files {
+            todo_list.add_sample(&sample.path, &sample.reason);
+            let name = sample.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let message = format!("Check sample: {} ({})", name, sample.reason);
+            todo_items.push(("sample".to_string(), name, message));
+        }
+    }
+
+    for report in archive_reports.iter().filter(|r| r.is_redundant()) {
+        todo_list.add_redundant_archive(&report.path);
+        let name = report.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let message = format!("Delete archive: {} (all ebooks inside are already in the library)", name);
+        todo_items.push(("redundant_archive".to_string(), name, message));
+    }
+    reporter.stage(Stage::Checked)?;
+
+    let mut run_stats = history::RunStats::new(args.dry_run);
+    run_stats.files_processed = normalized.len();
+    let mut collection_tally = if collections.is_empty() {
+        None
+    } else {
+        Some(collections::CollectionTally::new(&collections, &args.path.canonicalize()?))
+    };
+    if let Some(tally) = &mut collection_tally {
+        tally.add_files(&normalized);
+    }
+    run_stats.small_or_failed = normalized.iter().filter(|f| f.is_failed_download || f.is_too_small).count() + html_stubs.len();
+    let mut file_sizes = file_sizes(&normalized, args.top.is_some());
+    file_sizes.extend(sync_conflicts.iter().chain(&name_variants).map(|c| (c.path.clone(), c.size)));
+    file_sizes.extend(html_stubs.iter().map(|s| (s.path.clone(), s.size)));
+
+    // Detect duplicates (skip if cloud storage mode)
+    let dupe_ignore = dupe_ignore::DupeIgnoreList::load(&args.path)?;
+    let (mut duplicate_groups, mut clean_files, hash_timed_out) = if args.no_dedupe {
+        (Vec::new(), duplicates::skip_detection(normalized), Vec::new())
+    } else {
+        duplicates::detect_duplicates(normalized, args.skip_cloud_hash, args.io_timeout_duration(), &dupe_ignore, &recovered)?
+    };
+    variants::merge_identical(&sync_conflicts, &mut duplicate_groups);
+    variants::merge_identical(&name_variants, &mut duplicate_groups);
+    if args.no_dedupe {
+        info!("Duplicate detection disabled (--no-dedupe)");
+    } else if args.skip_cloud_hash {
+        info!("Skipped duplicate detection (cloud storage mode)");
+    } else {
+        info!("Detected {} duplicate groups", duplicate_groups.len());
+    }
+
+    let version_groups = match args.arxiv_versions {
+        Some(_) => arxiv_versions::detect_versions(&clean_files),
+        None => Vec::new(),
+    };
+    if args.arxiv_versions == Some(cli::ArxivVersions::Newest) {
+        // Older versions differ in size from the kept one, so file_sizes has none of them yet
+        let superseded: HashSet<&PathBuf> = version_groups.iter().flat_map(|g| &g.superseded).collect();
+        file_sizes.extend(clean_files.iter().filter(|f| superseded.contains(&f.original_path)).map(|f| (f.original_path.clone(), f.size)));
+        arxiv_versions::supersede(&version_groups, &mut clean_files, &mut duplicate_groups);
+    }
+    info!("Found {} arXiv papers with superseded versions", version_groups.len());
+
+    // Groups with annotated copies need --delete-annotated-dupes (cloud placeholders can't be read)
+    let (mut duplicate_groups, annotated_groups) = if args.skip_cloud_hash {
+        (duplicate_groups, Vec::new())
+    } else {
+        annotations::split_annotated_groups(duplicate_groups, args.delete_annotated_dupes)
+    };
+    // Sync conflicts, variants and arXiv versions were appended after detection
+    duplicates::sort_groups(&mut duplicate_groups);
+    reporter.stage(Stage::Deduplicated(duplicate_groups.len()))?;
+    for group in &annotated_groups {
+        for path in &group.annotated {
+            todo_list.add_annotated_duplicate(path);
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let message = format!("Confirm duplicate: {} (has annotations, not deleted)", name);
+            todo_items.push(("annotated_duplicate".to_string(), name, message));
+        }
+    }
+    // Re-encoded copies from other mirrors: same pages, different bytes
+    let probable_groups = match args.fingerprint_pdfs {
+        Some(pages) => fingerprint::detect(&clean_files, pages),
+        None => Vec::new(),
+    };
+    for group in &probable_groups {
+        for path in &group.files[1..] {
+            todo_list.add_probable_duplicate(path, &group.files[0]);
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let like = group.files[0].file_name().unwrap_or_default().to_string_lossy();
+            let message = format!("Confirm probable duplicate: {} (same first pages as {}, not deleted)", name, like);
+            todo_items.push(("probable_duplicate".to_string(), name, message));
+        }
+    }
+
+    // Renames in read-only folders (view-only shared folders) are reported, not attempted
+    for path in permissions::skip_read_only_renames(&mut clean_files) {
+        record_permission_denied(&path, &mut todo_list, &mut todo_items);
+        skipped.add(&path, SkipReason::ReadOnlyFolder);
+    }
+
+    // Every plan is applied to a model of the tree first; operations that would fail are dropped
+    let plan_issues = simulation::validate(&mut clean_files, &mut duplicate_groups, &mut files_to_delete);
+    for issue in &plan_issues {
+        if issue.kind == simulation::IssueKind::Overwrite {
+            skipped.add(&issue.path, SkipReason::RenameConflict);
+        }
+    }
+
+    // Files skipped because a metadata read or hash exceeded --io-timeout
+    let timed_out: Vec<PathBuf> = scanner.timed_out.iter().chain(&hash_timed_out).cloned().collect();
+    skipped.add_all(&timed_out, SkipReason::IoTimeout);
+    for path in &timed_out {
+        todo_list.add_timed_out(path);
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let message = format!("Check network storage: {} (IO timeout, skipped)", name);
+        todo_items.push(("io_timeout".to_string(), name, message));
+    }
+    // Broken files whose source URL is known, plus Safari bundles recovery left behind
+    if let Some(format) = args.redownload_list {
+        let mut candidates = todo_list.redownload_candidates();
+        candidates.extend(traversal.download_folders(&args.path));
+        let found = redownload::find_sources(&candidates);
+        info!("Recovered source URLs for {} of {} broken downloads", found.len(), candidates.len());
+        if !args.read_only && !found.is_empty() {
+            let list_path = redownload::write_list(&args.path, &found, format)?;
+            todo_list.set_redownloads(redownload::command(&list_path, format), found);
+            reporter.info(t!("file-written", path = list_path.display().to_string()))?;
+        }
+    }
+
+    // Most of a library is unchanged on a second run; only --show-skipped lists it
+    if args.show_skipped {
+        skipped.add_unchanged(&clean_files, args.no_normalize);
+    }
+
+    run_stats.renamed = clean_files.iter().filter(|f| f.new_name.is_some() && f.new_path != f.original_path).count();
+    let mut reclaimed: Vec<&PathBuf> = files_to_delete.iter().collect();
+    if !args.no_delete {
+        for group in &duplicate_groups {
+            run_stats.duplicates += group.len().saturating_sub(1);
+            reclaimed.extend(group.iter().skip(1));
+        }
+    }
+    run_stats.bytes_reclaimed = reclaimed.iter().filter_map(|p| file_sizes.get(*p)).sum();
+    if drop_samples == Some(cli::SampleAction::Delete) {
+        run_stats.bytes_reclaimed += sample_files.iter().map(|s| s.size).sum::<u64>();
+    }
+    let collection_stats = match collection_tally {
+        Some(mut tally) => {
+            tally.add_renames(&clean_files);
+            if !args.no_delete {
+                tally.add_duplicates(&duplicate_groups);
+            }
+            tally.add_todo_items(&todo_list.item_paths());
+            tally.finish()
+        }
+        None => Vec::new(),
+    };
+    let space_map = args.top.map(|top| space_map::build(&file_sizes, &duplicate_groups, top));
+
+    let mut plan = Plan {
+        dir_renames,
+        pruned_dirs: Vec::new(),
+        native_docs,
+        archive_reports,
+        normalize_options,
+        chapter_groups,
+        sample_files,
+        drop_samples,
+        part_sets,
+        sync_conflicts,
+        name_variants,
+        html_stubs,
+        todo_list,
+        todo_items,
+        files_to_delete,
+        duplicate_groups,
+        clean_files,
+        version_groups,
+        annotated_groups,
+        probable_groups,
+        plan_issues,
+        timed_out,
+        skipped,
+        run_stats,
+        collection_stats,
+        space_map,
+        pending: None,
+        pruner,
+    };
+
+    // Refuse suspiciously large live runs; --dry-run-deletes defers deletions to a reviewed --confirm-deletes
+    if !args.dry_run && !args.force {
+        let deletes = if args.dry_run_deletes { 0 } else { plan.deletions(args).len() };
+        args.limits().check(plan.run_stats.renamed, deletes, plan.run_stats.files_processed)?;
+    }
+
+    if args.dry_run
+        && let Some(pruner) = &plan.pruner
+    {
+        // Renamed files leave their folder too, unless they stay in it
+        let renames = plan.renames();
+        let mut gone: HashSet<PathBuf> = plan.deletions(args).into_iter().collect();
+        gone.extend(renames.iter().map(|(from, _)| from.clone()));
+        let filled: HashSet<PathBuf> = renames.iter().filter_map(|(_, to)| to.parent().map(Path::to_path_buf)).collect();
+        plan.pruned_dirs = pruner.predict(&gone, &filled);
+        plan.run_stats.pruned_dirs = plan.pruned_dirs.len();
+    }
+    Ok(plan)
+}
+
+/// Carry out a live run's plan: renames, deletions (or, with
+/// `--dry-run-deletes`, their preview), sample, part and chapter moves and
+/// pruning, then check that every file ended up where the plan said.
+pub fn execute(args: &Args, plan: &mut Plan, reporter: &mut impl Reporter) -> Result<()> {
+    let mut audit = audit::Audit::default();
+    // Deletions wait for --confirm-deletes
+    let mut pending = args.dry_run_deletes.then(pending_deletes::PendingDeletes::new);
+    let mut renamed_to = HashMap::new();
+    let mut throttle = throttle::Throttle::new(args.throttle, args.batch_size);
+    let mut recorder = args.record_original.map(original_names::Recorder::new).transpose()?;
+    let deleter = delete_mode::Deleter::new(args.delete_mode, &args.path, args.archive_dir.as_deref())?;
+
+    // Execute renames, ordered so that no file is replaced
+    let renames: Vec<&scanner::FileInfo> = plan.clean_files.iter().filter(|f| f.new_name.is_some()).collect();
+    let pairs: Vec<(&Path, &Path)> = renames.iter().map(|f| (f.original_path.as_path(), f.new_path.as_path())).collect();
+    for (index, result) in rename_order::execute_all(&pairs, || throttle.wait()) {
+        let file_info = renames[index];
+        let new_name = file_info.new_name.as_deref().unwrap_or_default();
+        match result {
+            Ok(()) => {
+                info!("Renamed: {} -> {}", file_info.original_name, new_name);
+                audit.renamed(&file_info.original_path, &file_info.new_path);
+                renamed_to.insert(file_info.original_path.clone(), file_info.new_path.clone());
+                if let Some(recorder) = &mut recorder
+                    && let Err(e) = recorder.record(&file_info.original_path, &file_info.new_path)
+                {
+                    warn!("Original name not recorded for {}: {}", new_name, e);
+                    reporter.warn(t!("original-not-recorded", reason = e.to_string(), name = new_name))?;
+                }
+            }
+            Err(e) if permissions::is_permission_error(&e) => {
+                record_permission_denied(&file_info.original_path, &mut plan.todo_list, &mut plan.todo_items);
+                reporter.warn(t!("not-renamed-permission", name = file_info.original_name.clone()))?;
+            }
+            // Taken targets, vanished files, full disks... the other renames go on
+            Err(e) => {
+                warn!("Not renamed, {}: {}", e, file_info.original_name);
+                reporter.warn(t!("not-renamed", reason = e.to_string(), name = file_info.original_name.clone()))?;
+            }
+        }
+    }
+    if let Some(recorder) = &recorder {
+        recorder.save()?;
+    }
+
+    // Delete duplicates
+    if !args.no_delete {
+        for group in plan.duplicate_groups.iter().filter(|g| g.len() > 1) {
+            for path in &group[1..] {
+                if let Some(pending) = &mut pending {
+                    let kept = renamed_to.get(&group[0]).unwrap_or(&group[0]);
+                    pending.add(path, pending_deletes::DeleteKind::Duplicate, Some(kept));
+                    continue;
+                }
+                throttle.wait();
+                if remove(&deleter, path, &mut plan.run_stats, reporter)? {
+                    info!("Deleted duplicate: {}", path.display());
+                    audit.deleted(path, Some(&group[0]));
+                } else {
+                    plan.run_stats.duplicates = plan.run_stats.duplicates.saturating_sub(1);
+                }
+            }
+        }
+    }
+
+    // Delete small/corrupted/failed files if requested
+    for path in plan.files_to_delete.drain(..) {
+        if let Some(pending) = &mut pending {
+            pending.add(&path, pending_deletes::DeleteKind::SmallOrFailed, None);
+            continue;
+        }
+        throttle.wait();
+        if remove(&deleter, &path, &mut plan.run_stats, reporter)? {
+            info!("Deleted small/corrupted/failed file: {}", path.display());
+            audit.deleted(&path, None);
+        }
+    }
+
+    match plan.drop_samples {
+        Some(cli::SampleAction::Delete) => {
+            for sample in &plan.sample_files {
+                if let Some(pending) = &mut pending {
+                    pending.add(&sample.path, pending_deletes::DeleteKind::Sample, None);
+                    continue;
+                }
+                throttle.wait();
+                if remove(&deleter, &sample.path, &mut plan.run_stats, reporter)? {
+                    info!("Deleted sample: {}", sample.path.display());
+                    audit.deleted(&sample.path, None);
+                }
+            }
+        }
+        Some(cli::SampleAction::Quarantine) => {
+            let (moved, errors) = samples::quarantine(&plan.sample_files, &args.path.canonicalize()?);
+            for (from, to) in &moved {
+                audit.renamed(from, to);
+            }
+            for error in errors {
+                info!("{}", error);
+                reporter.warn(error)?;
+            }
+        }
+        _ => {}
+    }
+
+    if args.merge_parts {
+        for set in &plan.part_sets {
+            match split_parts::merge(set, &args.path.canonicalize()?) {
+                Ok(moved) => {
+                    for (from, to) in &moved {
+                        audit.renamed(from, to);
+                    }
+                }
+                Err(e) => {
+                    info!("{}", e);
+                    reporter.warn(e.to_string())?;
+                }
+            }
+        }
+    }
+
+    // Move chapter files last so duplicates among them were already removed
+    let chapter_folders = args.chapter_files == Some(cli::ChapterMode::Folder);
+    if chapter_folders {
+        for error in chapters::move_into_folders(&plan.chapter_groups) {
+            info!("{}", error);
+            reporter.warn(error)?;
+        }
+    }
+
+    // Remove directories emptied by the deletions above
+    if let Some(pruner) = &plan.pruner {
+        plan.pruned_dirs = pruner.prune();
+        plan.run_stats.pruned_dirs = plan.pruned_dirs.len();
+    }
+
+    // Everything else should still be where the scan found it
+    let moved: HashSet<&Path> = if chapter_folders {
+        plan.chapter_groups.iter().flat_map(|g| g.parts.iter().map(|p| p.path.as_path())).collect()
+    } else {
+        Default::default()
+    };
+    for file_info in plan.clean_files.iter().filter(|f| !moved.contains(f.original_path.as_path())) {
+        audit.untouched(&file_info.original_path);
+    }
+    plan.run_stats.audit_issues = audit.verify();
+    for issue in &plan.run_stats.audit_issues {
+        warn!("{}", issue);
+        reporter.warn(issue.clone())?;
+    }
+
+    if let Some(pending) = &mut pending {
+        if args.cleanup_downloads {
+            for folder in args.traversal().download_folders(&args.path) {
+                if std::fs::read_dir(&folder).is_ok_and(|mut entries| entries.next().is_none()) {
+                    pending.add(&folder, pending_deletes::DeleteKind::DownloadFolder, None);
+                }
+            }
+        }
+        pending.save(&args.path)?;
+        // Nothing was deleted yet; --confirm-deletes records that
+        plan.run_stats.duplicates = 0;
+        plan.run_stats.bytes_reclaimed = 0;
+    }
+    plan.pending = pending;
+    Ok(())
+}
+
+/// Write todo.md (unless read-only) and, for live runs, the history entry
+/// and the catalog; then notify.
+pub fn finish(args: &Args, plan: &Plan) -> Result<()> {
+    if !args.read_only {
+        plan.todo_list.write()?;
+        info!("Wrote todo.md");
+    }
+    // Dry runs would skew the trend and the change baseline
+    if !args.dry_run {
+        if let Err(e) = history::record_run(&args.path, &plan.run_stats) {
+            info!("Failed to record run statistics: {}", e);
+        }
+        // The baseline for `changes`, after this run's renames and deletions
+        if let Err(e) = catalog::Catalog::snapshot(&args.path, args.traversal().max_depth).and_then(|c| c.save(&args.path)) {
+            info!("Failed to save the catalog: {}", e);
+        }
+    }
+    if args.notify {
+        notify::run_finished(&plan.run_stats, plan.todo_list.items.len());
+    }
+    Ok(())
+}
+
+/// Delete one file of the plan. A file that cannot be deleted stays, is
+/// reported and leaves the run's stats; the other deletions go on, like
+/// renames do. Returns whether the file is gone.
+fn remove(deleter: &delete_mode::Deleter, path: &Path, run_stats: &mut history::RunStats, reporter: &mut impl Reporter) -> Result<bool> {
+    match deleter.remove(path) {
+        Ok(()) => Ok(true),
+        Err(e) => {
+            warn!("Not deleted, {}: {}", e, path.display());
+            if let Ok(metadata) = std::fs::metadata(path) {
+                run_stats.bytes_reclaimed = run_stats.bytes_reclaimed.saturating_sub(metadata.len());
+            }
+            reporter.warn(t!("not-deleted", reason = e.to_string(), path = path.display().to_string()))?;
+            Ok(false)
+        }
+    }
+}
+
+/// Sizes of the files a plan may delete, for the reclaimed-space stats:
+/// broken files and files that share their size with another (duplicates
+/// always do). With `all`, every file, for the space map.
+fn file_sizes(files: &[scanner::FileInfo], all: bool) -> HashMap<PathBuf, u64> {
+    let mut per_size: HashMap<u64, usize> = HashMap::new();
+    if !all {
+        for file_info in files {
+            *per_size.entry(file_info.size).or_default() += 1;
+        }
+    }
+    files
+        .iter()
+        .filter(|f| all || f.is_failed_download || f.is_too_small || per_size[&f.size] > 1)
+        .map(|f| (f.original_path.clone(), f.size))
+        .collect()
+}
+
+/// The stages that need nothing but the file itself, run on each file as the
+/// scan finds it: placeholder hydration, the Library Genesis lookup and
+/// normalization.
+fn prepare(
+    args: &Args,
+    file_info: &mut scanner::FileInfo,
+    lookup: Option<&mut libgen::Lookup>,
+    options: &mut normalizer::NormalizeOptions,
+) -> Result<()> {
+    if file_info.is_virtual {
+        placeholders::hydrate(std::slice::from_mut(file_info), &args.hydrate);
+    }
+    if let Some(lookup) = lookup {
+        lookup.apply(file_info, &mut options.merged);
+    }
+    if !args.no_normalize {
+        normalizer::normalize_file(file_info, options)?;
+    }
+    Ok(())
+}
+
+/// Offer the low-confidence parses for correction and rename them again with
+/// what the user typed.
+fn review(args: &Args, files: &mut [scanner::FileInfo], options: &normalizer::NormalizeOptions, reporter: &mut impl Reporter) -> Result<()> {
+    let items: Vec<ReviewItem> = files
+        .iter()
+        .filter_map(|f| {
+            let metadata = f.metadata.as_ref().filter(|m| normalizer::is_low_confidence(m))?;
+            Some(ReviewItem {
+                path: f.original_path.clone(),
+                name: f.original_name.clone(),
+                parsed: [
+                    metadata.authors.clone().unwrap_or_default(),
+                    metadata.full_title(&options.subtitle_separator),
+                    metadata.year.map(|y| y.to_string()).unwrap_or_default(),
+                ],
+            })
+        })
+        .collect();
+    if items.is_empty() {
+        return Ok(());
+    }
+    let result = reporter.review(items)?;
+    for (path, correction) in &result.corrections {
+        if let Some(file_info) = files.iter_mut().find(|f| &f.original_path == path)
+            && let Some(mut metadata) = file_info.metadata.clone()
+        {
+            correction.apply(&mut metadata);
+            normalizer::rename_with(file_info, metadata, options);
+        }
+    }
+    reporter.info(t!("review-applied", count = result.corrections.len()))?;
+    if result.save && !result.corrections.is_empty() && !args.read_only {
+        let mut store = overrides::Overrides::load(&args.path)?;
+        for (path, correction) in &result.corrections {
+            store.set(path, correction)?;
+        }
+        let path = store.save(&args.path)?;
+        reporter.info(t!("review-saved", path = path.display().to_string()))?;
+    }
+    Ok(())
+}
+
+fn record_permission_denied(path: &Path, todo_list: &mut todo::TodoList, todo_items: &mut Vec<(String, String, String)>) {
+    todo_list.add_permission_denied(path);
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let message = format!("Check permissions: {} (read-only location, not renamed)", name);
+    todo_items.push(("insufficient_permission".to_string(), name, message));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[derive(Default)]
+    struct Recorder {
+        stages: Vec<Stage>,
+        warnings: Vec<String>,
+    }
+
+    impl Reporter for Recorder {
+        fn stage(&mut self, stage: Stage) -> Result<()> {
+            self.stages.push(stage);
+            Ok(())
+        }
+
+        fn info(&mut self, _message: String) -> Result<()> {
+            Ok(())
+        }
+
+        fn warn(&mut self, message: String) -> Result<()> {
+            self.warnings.push(message);
+            Ok(())
+        }
+    }
+
+    fn library() -> Result<TempDir> {
+        let tmp_dir = TempDir::new()?;
+        let content = format!("%PDF-1.4\n{}\n%%EOF\n", "x".repeat(4096));
+        fs::write(tmp_dir.path().join("Serge Lang - Algebra (2002).pdf"), &content)?;
+        fs::write(tmp_dir.path().join("Lang - Algebra.pdf"), &content)?;
+        fs::write(tmp_dir.path().join("tiny.pdf"), "x")?;
+        Ok(tmp_dir)
+    }
+
+    fn args(root: &Path, flags: &[&str]) -> Args {
+        Args::try_parse_from(["ebook-renamer"].iter().chain(flags).chain([&root.to_str().unwrap()])).unwrap()
+    }
+
+    #[test]
+    fn test_live_run_deletes_duplicates_and_small_files() -> Result<()> {
+        let tmp_dir = library()?;
+        let root = tmp_dir.path();
+        let args = args(root, &["--delete-small"]);
+        let mut reporter = Recorder::default();
+
+        let mut plan = plan(&args, &mut reporter)?;
+        assert_eq!(reporter.stages, [Stage::Scanned(3), Stage::Normalized(3), Stage::Checked, Stage::Deduplicated(1)]);
+        assert_eq!(plan.deletions(&args).len(), 2);
+        execute(&args, &mut plan, &mut reporter)?;
+        finish(&args, &plan)?;
+
+        assert!(reporter.warnings.is_empty(), "{:?}", reporter.warnings);
+        assert!(plan.run_stats.audit_issues.is_empty());
+        assert!(!root.join("tiny.pdf").exists());
+        let pdfs = fs::read_dir(root)?.filter_map(|e| e.ok()).filter(|e| e.path().extension().is_some_and(|x| x == "pdf")).count();
+        assert_eq!(pdfs, 1);
+        assert!(root.join(history::HISTORY_FILE_NAME).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_deletion_does_not_stop_the_run() -> Result<()> {
+        let tmp_dir = library()?;
+        let root = tmp_dir.path();
+        let args = args(root, &["--delete-small"]);
+        let mut reporter = Recorder::default();
+
+        let mut plan = plan(&args, &mut reporter)?;
+        // Gone before its turn, e.g. deleted by the sync client
+        let duplicate = plan.duplicate_groups[0][1].clone();
+        fs::remove_file(&duplicate)?;
+        execute(&args, &mut plan, &mut reporter)?;
+
+        assert_eq!(reporter.warnings.len(), 1, "{:?}", reporter.warnings);
+        assert!(reporter.warnings[0].contains(&duplicate.display().to_string()));
+        assert!(!root.join("tiny.pdf").exists());
+        assert_eq!(plan.run_stats.duplicates, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unwritable_archive_keeps_every_file() -> Result<()> {
+        let tmp_dir = library()?;
+        let root = tmp_dir.path();
+        let archive = TempDir::new()?;
+        let blocked = archive.path().join("not-a-folder");
+        fs::write(&blocked, "")?;
+        let args = args(root, &["--delete-small", "--delete-mode", "archive", "--archive-dir", blocked.to_str().unwrap()]);
+        let mut reporter = Recorder::default();
+
+        let mut plan = plan(&args, &mut reporter)?;
+        execute(&args, &mut plan, &mut reporter)?;
+
+        // Both deletions were tried and reported, and both files are still there
+        assert_eq!(reporter.warnings.len(), 2, "{:?}", reporter.warnings);
+        assert!(reporter.warnings.iter().all(|w| w.contains("not archived to")));
+        assert!(root.join("tiny.pdf").exists());
+        assert_eq!(fs::read_dir(root)?.filter_map(|e| e.ok()).filter(|e| e.path().extension().is_some_and(|x| x == "pdf")).count(), 3);
+        assert_eq!(plan.run_stats.duplicates, 0);
+        assert_eq!(plan.run_stats.bytes_reclaimed, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dry_run_changes_nothing() -> Result<()> {
+        let tmp_dir = library()?;
+        let root = tmp_dir.path();
+        let args = args(root, &["--dry-run", "--delete-small"]);
+
+        let plan = plan(&args, &mut Recorder::default())?;
+        finish(&args, &plan)?;
+
+        assert_eq!(plan.deletions(&args).len(), 2);
+        assert!(root.join("tiny.pdf").exists());
+        assert!(root.join("todo.md").exists());
+        assert!(!root.join(history::HISTORY_FILE_NAME).exists());
+        Ok(())
+    }
+
+    /// Every path under `root` with its size and modification time.
+    fn tree(root: &Path) -> Vec<(PathBuf, u64, std::time::SystemTime)> {
+        walkdir::WalkDir::new(root).sort_by_file_name().into_iter().filter_map(|e| e.ok())
+            .map(|e| {
+                let metadata = e.metadata().unwrap();
+                (e.path().to_path_buf(), metadata.len(), metadata.modified().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_read_only_run_writes_nothing() -> Result<()> {
+        let tmp_dir = library()?;
+        let root = tmp_dir.path();
+        let download = root.join("Topology.pdf.download");
+        fs::create_dir(&download)?;
+        fs::write(download.join("Topology.pdf"), format!("%PDF-1.4\n{}\n%%EOF\n", "y".repeat(4096)))?;
+        fs::create_dir(root.join("old"))?;
+        fs::write(root.join("old").join("empty.pdf"), "")?;
+        let before = tree(root);
+        // process_library makes --read-only imply --dry-run
+        let args = args(root, &["--read-only", "--dry-run", "--delete-small", "--clean-failed", "--cleanup-downloads", "--prune-empty-dirs"]);
+
+        let plan = plan(&args, &mut Recorder::default())?;
+        finish(&args, &plan)?;
+
+        assert_eq!(plan.deletions(&args).len(), 3);
+        assert_eq!(tree(root), before);
+        assert!(!root.join("todo.md").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use crate::scanner::FileInfo;
+use log::info;
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+
+/// Whether a file is a cloud placeholder: listed with its full size, but its
+/// content is only downloaded when something reads it.
+///
+/// OneDrive Files On-Demand and other Cloud Files API providers keep a cloud
+/// reparse point on hydrated files too, so only the recall attributes count.
+#[cfg(windows)]
+pub fn is_placeholder(metadata: &Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x40000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x400000;
+    metadata.file_attributes() & (FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_OPEN | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS) != 0
+}
+
+/// APFS marks evicted iCloud Drive and File Provider (`~/Library/CloudStorage`)
+/// files as dataless.
+#[cfg(target_os = "macos")]
+pub fn is_placeholder(metadata: &Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    const SF_DATALESS: u32 = 0x40000000;
+    metadata.st_flags() & SF_DATALESS != 0
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn is_placeholder(_metadata: &Metadata) -> bool {
+    false
+}
+
+pub fn is_placeholder_path(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|metadata| is_placeholder(&metadata))
+}
+
+/// Treat placeholders under `paths` (files or folders) as local, so they are
+/// hashed and checked like any other file. Reading them makes the sync
+/// client download them.
+pub fn hydrate(files: &mut [FileInfo], paths: &[PathBuf]) {
+    let roots: Vec<PathBuf> = paths.iter().map(|p| p.canonicalize().unwrap_or_else(|_| p.clone())).collect();
+    for file_info in files.iter_mut().filter(|f| f.is_virtual) {
+        if roots.iter().any(|root| file_info.original_path.starts_with(root)) {
+            info!("Downloading placeholder to verify it: {:?}", file_info.original_path);
+            file_info.is_virtual = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hydrate() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path();
+        fs::create_dir(dir.join("Verify"))?;
+        fs::write(dir.join("Verify/a.pdf"), "x")?;
+        fs::write(dir.join("b.pdf"), "x")?;
+        fs::write(dir.join("c.pdf"), "x")?;
+
+        let mut files = crate::scanner::Scanner::new(dir, 2)?.scan()?;
+        for file_info in &mut files {
+            file_info.is_virtual = true;
+        }
+        hydrate(&mut files, &[dir.join("Verify"), dir.join("c.pdf")]);
+        let mut local: Vec<String> =
+            files.iter().filter(|f| !f.is_virtual).map(|f| f.original_name.clone()).collect();
+        local.sort();
+        assert_eq!(local, vec!["a.pdf", "c.pdf"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use log::{debug, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Added to the process's nice value by `--nice`.
+pub const NICE_INCREMENT: i32 = 10;
+
+/// Pause after each hashed file in `--nice` mode, so other programs get the disk.
+pub const FILE_PAUSE: Duration = Duration::from_millis(50);
+
+static LOW_IMPACT: AtomicBool = AtomicBool::new(false);
+
+/// Run at low CPU and disk priority for the rest of the process: nice +10
+/// and the idle I/O class on Linux, the background band (CPU, disk and
+/// network throttled) on macOS. Hashing then uses a single thread and
+/// pauses between files (see [`pause`]).
+///
+/// Must be called before any worker thread is started: on Linux both
+/// priorities are per thread and only inherited by threads created later.
+/// Failing to lower a priority is logged, never fatal.
+pub fn lower() {
+    LOW_IMPACT.store(true, Ordering::Relaxed);
+    lower_cpu();
+    lower_io();
+}
+
+/// Whether `--nice` is in effect.
+pub fn is_low_impact() -> bool {
+    LOW_IMPACT.load(Ordering::Relaxed)
+}
+
+/// Sleep for `FILE_PAUSE` in `--nice` mode; no-op otherwise.
+pub fn pause() {
+    if is_low_impact() {
+        thread::sleep(FILE_PAUSE);
+    }
+}
+
+#[cfg(unix)]
+fn lower_cpu() {
+    // nice() returns the new nice value; -1 is only a valid value when
+    // starting from -11, which unprivileged runs never do
+    let result = unsafe { libc::nice(NICE_INCREMENT) };
+    if result == -1 {
+        warn!("Could not lower CPU priority: {}", std::io::Error::last_os_error());
+    } else {
+        debug!("CPU priority lowered to nice {}", result);
+    }
+}
+
+#[cfg(not(unix))]
+fn lower_cpu() {
+    debug!("Lowering CPU priority is not supported on this platform");
+}
+
+#[cfg(target_os = "linux")]
+fn lower_io() {
+    // ioprio_set(IOPRIO_WHO_PROCESS, self, IOPRIO_PRIO_VALUE(IOPRIO_CLASS_IDLE, 0))
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+    let result = unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT) };
+    if result == -1 {
+        warn!("Could not lower I/O priority: {}", std::io::Error::last_os_error());
+    } else {
+        debug!("I/O priority set to idle");
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn lower_io() {
+    // The background band throttles disk and network I/O as well as CPU
+    let result = unsafe { libc::setpriority(libc::PRIO_DARWIN_PROCESS, 0, libc::PRIO_DARWIN_BG) };
+    if result == -1 {
+        warn!("Could not switch to background priority: {}", std::io::Error::last_os_error());
+    } else {
+        debug!("Running in the background priority band");
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn lower_io() {
+    debug!("Lowering I/O priority is not supported on this platform");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_only_in_low_impact_mode() {
+        let start = std::time::Instant::now();
+        pause();
+        assert!(start.elapsed() < FILE_PAUSE);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::cli::Args;
+use crate::error::Error;
+use anyhow::Result;
+use log::{debug, warn};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `$XDG_CONFIG_HOME/ebook-renamer/profiles.json`, falling back to
+/// `~/.config` (`%APPDATA%` on Windows).
+pub fn default_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(config_home.join("ebook-renamer").join("profiles.json"))
+}
+
+/// Settings for every library under `root`. Flags given on the command
+/// line and the library's own `.ebook-renamer.json` take precedence; a
+/// flag set to `true` here turns it on.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub name: Option<String>,
+    /// `~/` is the home directory
+    pub root: PathBuf,
+    pub template: Option<String>,
+    pub subtitle_separator: Option<String>,
+    pub no_dedupe: bool,
+    pub no_delete: bool,
+    pub rename_only: bool,
+    pub delete_small: bool,
+    pub clean_failed: bool,
+    pub cleanup_downloads: bool,
+    pub prune_empty_dirs: bool,
+}
+
+impl Profile {
+    pub fn label(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.root.display().to_string())
+    }
+
+    fn deletes(&self) -> bool {
+        self.delete_small || self.clean_failed || self.cleanup_downloads || self.prune_empty_dirs
+    }
+
+    /// Fill in what the command line left unset. Deleting flags and
+    /// `rename_only` exclude each other: deleting flags on the command line
+    /// of a rename-only library are a usage error unless `--force` is given,
+    /// a profile's deleting flags are dropped with `--rename-only`.
+    pub fn apply(&self, args: &mut Args) -> Result<()> {
+        if args.template.is_none() {
+            args.template = self.template.clone();
+        }
+        if args.subtitle_separator == crate::normalizer::DEFAULT_SUBTITLE_SEPARATOR
+            && let Some(separator) = &self.subtitle_separator {
+            args.subtitle_separator = separator.clone();
+        }
+        args.no_dedupe |= self.no_dedupe;
+        args.no_delete |= self.no_delete;
+
+        let args_delete = args.delete_small || args.clean_failed || args.cleanup_downloads || args.prune_empty_dirs || args.drop_samples.is_some();
+        if self.rename_only && args_delete && !args.force {
+            return Err(Error::Usage(format!(
+                "Profile {} is rename-only; pass --force to delete files in it anyway",
+                self.label()
+            ))
+            .into());
+        } else if self.rename_only && args_delete {
+            warn!("Profile {}: rename_only ignored with --force", self.label());
+        } else {
+            args.rename_only |= self.rename_only;
+        }
+        if self.deletes() && args.rename_only {
+            warn!("Profile {}: deleting flags ignored with --rename-only", self.label());
+            return Ok(());
+        }
+        args.delete_small |= self.delete_small;
+        args.clean_failed |= self.clean_failed;
+        args.cleanup_downloads |= self.cleanup_downloads;
+        args.prune_empty_dirs |= self.prune_empty_dirs;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profiles {
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+}
+
+impl Profiles {
+    /// The given file, or the default one if it exists.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) if !path.exists() => return Err(Error::NotFound { what: "Profiles file", path: path.to_path_buf() }.into()),
+            Some(path) => path.to_path_buf(),
+            None => match default_path().filter(|p| p.exists()) {
+                Some(path) => path,
+                None => return Ok(Self::default()),
+            },
+        };
+        let content = fs::read_to_string(&path)?;
+        let profiles = serde_json::from_str(&content)
+            .map_err(|e| Error::InvalidFile { path: path.clone(), message: format!("invalid profiles: {}", e) })?;
+        debug!("Loaded profiles from {:?}", path);
+        Ok(profiles)
+    }
+
+    /// The profile with the deepest root that contains `target`.
+    pub fn select(&self, target: &Path) -> Option<&Profile> {
+        let target = target.canonicalize().unwrap_or_else(|_| target.to_path_buf());
+        self.profiles
+            .iter()
+            .filter_map(|profile| {
+                let root = expand_home(&profile.root);
+                let root = root.canonicalize().unwrap_or(root);
+                target.starts_with(&root).then(|| (root.components().count(), profile))
+            })
+            .max_by_key(|(depth, _)| *depth)
+            .map(|(_, profile)| profile)
+    }
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))) {
+        (Ok(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_select_and_apply() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let downloads = tmp_dir.path().join("Downloads");
+        let books = tmp_dir.path().join("Books");
+        let papers = books.join("Papers");
+        fs::create_dir_all(&downloads)?;
+        fs::create_dir_all(&papers)?;
+        let file = tmp_dir.path().join("profiles.json");
+        fs::write(&file, serde_json::json!({
+            "profiles": [
+                {"name": "downloads", "root": downloads, "clean_failed": true, "prune_empty_dirs": true},
+                {"name": "library", "root": books, "rename_only": true, "template": "{authors} - {title}"},
+                {"name": "papers", "root": papers, "no_dedupe": true},
+            ]
+        }).to_string())?;
+        let profiles = Profiles::load(Some(&file))?;
+
+        let select = |path: &Path| profiles.select(path).map(Profile::label);
+        assert_eq!(select(&downloads.join("x")), Some("downloads".to_string()));
+        assert_eq!(select(&papers), Some("papers".to_string()));
+        assert_eq!(select(tmp_dir.path()), None);
+
+        let parse = |flags: &[&str]| Args::try_parse_from(["ebook-renamer"].iter().chain(flags)).unwrap();
+        let mut args = parse(&["--template", "{title}"]);
+        profiles.select(&books).unwrap().apply(&mut args)?;
+        assert!(args.rename_only);
+        assert_eq!(args.template.as_deref(), Some("{title}"));
+
+        let mut args = parse(&["--rename-only"]);
+        profiles.select(&downloads).unwrap().apply(&mut args)?;
+        assert!(!args.clean_failed && !args.prune_empty_dirs);
+
+        // Deleting in a rename-only library needs --force
+        let mut args = parse(&["--delete-small"]);
+        let err = profiles.select(&books).unwrap().apply(&mut args).unwrap_err();
+        assert!(matches!(err.downcast_ref::<Error>(), Some(Error::Usage(_))));
+        let mut args = parse(&["--clean-failed", "--force"]);
+        profiles.select(&books).unwrap().apply(&mut args)?;
+        assert!(!args.rename_only && args.clean_failed);
+
+        fs::write(&file, r#"{"profiles": [{"root": "/", "delete_everything": true}]}"#)?;
+        assert!(Profiles::load(Some(&file)).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+use crate::sync_ignore::SyncIgnore;
+use crate::traversal::{self, Traversal};
+use anyhow::Result;
+use log::{debug, info, warn};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directories removed by `--prune-empty-dirs`, relative to the target
+/// directory, until `restore-names` creates them again.
+pub const JOURNAL_FILE_NAME: &str = ".ebook-renamer-pruned-dirs.json";
+
+/// Remembers which directories had content before the run so that only
+/// directories emptied by our own operations are removed afterwards.
+pub struct EmptyDirPruner {
+    root: PathBuf,
+    non_empty_dirs: Vec<PathBuf>,
+}
+
+impl EmptyDirPruner {
+    /// Folders the scan skips (hidden, system, download and sync-excluded
+    /// ones) are never pruned.
+    pub fn snapshot(root: &Path, traversal: Traversal, sync_ignore: &SyncIgnore) -> Result<Self> {
+        let root = root.canonicalize()?;
+        let mut non_empty_dirs = Vec::new();
+
+        let walker = traversal.walker(&root).min_depth(1).into_iter().filter_entry(|e| {
+            !e.file_type().is_dir()
+                || !(traversal::is_excluded_dir(e.path())
+                    || sync_ignore.is_excluded(e.path().strip_prefix(&root).unwrap_or(e.path()), true))
+        });
+        for entry in walker.filter_map(|e| e.ok()) {
+            if entry.file_type().is_dir() && !is_dir_empty(entry.path()) {
+                non_empty_dirs.push(entry.path().to_path_buf());
+            }
+        }
+
+        // Deepest first so parents are checked after their children
+        non_empty_dirs.sort_by(|a, b| {
+            b.components().count().cmp(&a.components().count()).then_with(|| a.cmp(b))
+        });
+
+        debug!("Snapshot recorded {} non-empty directories", non_empty_dirs.len());
+        Ok(EmptyDirPruner { root, non_empty_dirs })
+    }
+
+    /// Directories that would become empty once `gone` files are deleted or
+    /// moved away (dry-run). Directories in `filled` receive renamed files and
+    /// are kept.
+    pub fn predict(&self, gone: &HashSet<PathBuf>, filled: &HashSet<PathBuf>) -> Vec<PathBuf> {
+        let mut emptied: HashSet<PathBuf> = HashSet::new();
+        let mut result = Vec::new();
+
+        for dir in &self.non_empty_dirs {
+            if filled.contains(dir) {
+                continue;
+            }
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            let all_gone = entries
+                .filter_map(|e| e.ok())
+                .all(|e| {
+                    let path = e.path();
+                    gone.contains(&path) || emptied.contains(&path)
+                });
+            if all_gone {
+                emptied.insert(dir.clone());
+                result.push(dir.clone());
+            }
+        }
+
+        result
+    }
+
+    /// Remove directories that were non-empty at snapshot time and are empty
+    /// now, and add them to the journal.
+    pub fn prune(&self) -> Vec<PathBuf> {
+        let mut removed = Vec::new();
+
+        for dir in &self.non_empty_dirs {
+            if dir.is_dir() && is_dir_empty(dir) {
+                match fs::remove_dir(dir) {
+                    Ok(_) => {
+                        info!("Removed empty directory: {:?}", dir);
+                        removed.push(dir.clone());
+                    }
+                    Err(e) => debug!("Failed to remove directory {:?}: {}", dir, e),
+                }
+            }
+        }
+
+        if !removed.is_empty()
+            && let Err(e) = record(&self.root, &removed)
+        {
+            warn!("Pruned directories not journaled: {}", e);
+        }
+        removed
+    }
+}
+
+fn load_journal(root: &Path) -> Result<Vec<String>> {
+    let path = root.join(JOURNAL_FILE_NAME);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&fs::read_to_string(&path)?)
+        .map_err(|e| crate::error::Error::InvalidFile { path, message: e.to_string() }.into())
+}
+
+fn record(root: &Path, removed: &[PathBuf]) -> Result<()> {
+    let mut journal = load_journal(root)?;
+    for dir in removed {
+        let relative = dir.strip_prefix(root).unwrap_or(dir).to_string_lossy().to_string();
+        if !journal.contains(&relative) {
+            journal.push(relative);
+        }
+    }
+    let path = root.join(JOURNAL_FILE_NAME);
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(&journal)? + "\n")?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// Journaled directories that do not exist (again), parents first.
+pub fn pruned_dirs(root: &Path) -> Result<Vec<PathBuf>> {
+    let root = root.canonicalize()?;
+    let mut dirs: Vec<PathBuf> = load_journal(&root)?.iter().map(|d| root.join(d)).filter(|d| !d.exists()).collect();
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// Create the journaled directories again and clear the journal.
+pub fn restore_pruned(root: &Path) -> Result<Vec<PathBuf>> {
+    let dirs = pruned_dirs(root)?;
+    for dir in &dirs {
+        fs::create_dir_all(dir)?;
+        info!("Restored pruned directory: {:?}", dir);
+    }
+    let path = root.canonicalize()?.join(JOURNAL_FILE_NAME);
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(dirs)
+}
+
+fn is_dir_empty(path: &Path) -> bool {
+    fs::read_dir(path)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_prune_only_dirs_emptied_during_run() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let emptied = tmp_dir.path().join("emptied");
+        let already_empty = tmp_dir.path().join("already_empty");
+        fs::create_dir(&emptied)?;
+        fs::create_dir(&already_empty)?;
+        fs::write(emptied.join("dup.pdf"), "content")?;
+
+        let pruner = EmptyDirPruner::snapshot(tmp_dir.path(), Traversal::new(usize::MAX), &SyncIgnore::default())?;
+        fs::remove_file(emptied.join("dup.pdf"))?;
+        let removed = pruner.prune();
+
+        assert_eq!(removed.len(), 1);
+        assert!(!emptied.exists());
+        assert!(already_empty.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_cascades_to_parents() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let nested = tmp_dir.path().join("a").join("b");
+        fs::create_dir_all(&nested)?;
+        fs::write(nested.join("dup.pdf"), "content")?;
+
+        let pruner = EmptyDirPruner::snapshot(tmp_dir.path(), Traversal::new(usize::MAX), &SyncIgnore::default())?;
+        fs::remove_file(nested.join("dup.pdf"))?;
+        let removed = pruner.prune();
+
+        assert_eq!(removed.len(), 2);
+        assert!(!tmp_dir.path().join("a").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_without_touching_disk() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        let nested = root.join("a").join("b");
+        fs::create_dir_all(&nested)?;
+        fs::write(nested.join("dup.pdf"), "content")?;
+        fs::write(root.join("a").join("keep.pdf"), "content")?;
+
+        let pruner = EmptyDirPruner::snapshot(&root, Traversal::new(usize::MAX), &SyncIgnore::default())?;
+        let deleted: HashSet<PathBuf> = [nested.join("dup.pdf")].into_iter().collect();
+        let predicted = pruner.predict(&deleted, &HashSet::new());
+
+        assert_eq!(predicted, vec![nested.clone()]);
+        assert!(nested.join("dup.pdf").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_counts_files_renamed_away() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        for dir in ["moved", "renamed_in_place"] {
+            fs::create_dir(root.join(dir))?;
+            fs::write(root.join(dir).join("book.pdf"), "content")?;
+        }
+
+        let pruner = EmptyDirPruner::snapshot(&root, Traversal::new(usize::MAX), &SyncIgnore::default())?;
+        // One file moves up to the root, the other is renamed inside its folder
+        let gone: HashSet<PathBuf> = [root.join("moved/book.pdf"), root.join("renamed_in_place/book.pdf")].into_iter().collect();
+        let filled: HashSet<PathBuf> = [root.clone(), root.join("renamed_in_place")].into_iter().collect();
+
+        assert_eq!(pruner.predict(&gone, &filled), vec![root.join("moved")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_skipped_folders_and_journal() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        for dir in ["a/b", ".hidden", "Unsynced"] {
+            fs::create_dir_all(root.join(dir))?;
+            fs::write(root.join(dir).join("dup.pdf"), "content")?;
+        }
+        fs::write(root.join(".stignore"), "Unsynced\n")?;
+
+        let pruner = EmptyDirPruner::snapshot(&root, Traversal::new(usize::MAX), &SyncIgnore::load(&root, None)?)?;
+        for dir in ["a/b", ".hidden", "Unsynced"] {
+            fs::remove_file(root.join(dir).join("dup.pdf"))?;
+        }
+        assert_eq!(pruner.prune(), vec![root.join("a/b"), root.join("a")]);
+        assert!(root.join(".hidden").exists() && root.join("Unsynced").exists());
+
+        assert_eq!(pruned_dirs(&root)?, vec![root.join("a"), root.join("a/b")]);
+        restore_pruned(&root)?;
+        assert!(root.join("a/b").is_dir());
+        assert!(pruned_dirs(&root)?.is_empty());
+        Ok(())
+    }
+}
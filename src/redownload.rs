@@ -0,0 +1,152 @@
+use crate::cli::RedownloadFormat;
+use anyhow::Result;
+use log::debug;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const LIST_FILE_NAME: &str = "redownload.txt";
+
+/// A broken file whose source URL could be recovered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redownload {
+    pub path: PathBuf,
+    pub url: String,
+    /// Name the download should be saved as (without `.download`/`.crdownload`)
+    pub file_name: String,
+}
+
+/// Look up source URLs for the given files or Safari download bundles;
+/// files without a recoverable source are left out.
+pub fn find_sources(paths: &[PathBuf]) -> Vec<Redownload> {
+    let mut found: Vec<Redownload> = paths
+        .iter()
+        .filter_map(|path| {
+            let file_name = target_name(path)?;
+            let url = source_url(path, &file_name)?;
+            debug!("Recovered source of {:?}: {}", path, url);
+            Some(Redownload { path: path.clone(), url, file_name })
+        })
+        .collect();
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+    found.dedup_by(|a, b| a.path == b.path);
+    found
+}
+
+fn target_name(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    Some(name.trim_end_matches(".crdownload").trim_end_matches(".download").to_string())
+}
+
+// Browser metadata first, then a `.url` shortcut saved next to the file, then the arXiv ID
+fn source_url(path: &Path, file_name: &str) -> Option<String> {
+    if path.is_dir() {
+        return safari_bundle_url(path);
+    }
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let shortcuts = [
+        parent.join(format!("{}.url", path.file_name()?.to_str()?)),
+        parent.join(format!("{}.url", file_name)),
+    ];
+    shortcuts.iter().find_map(|shortcut| shortcut_url(shortcut)).or_else(|| arxiv_url(file_name))
+}
+
+/// Safari keeps the source in `Info.plist` inside the `.download` bundle.
+/// Only XML property lists are read.
+fn safari_bundle_url(bundle: &Path) -> Option<String> {
+    let plist = fs::read_to_string(bundle.join("Info.plist")).ok()?;
+    let re = Regex::new(r"<key>DownloadEntryURL</key>\s*<string>([^<]+)</string>").unwrap();
+    let url = re.captures(&plist)?.get(1)?.as_str();
+    Some(url.replace("&amp;", "&").trim().to_string())
+}
+
+/// Internet shortcut (`[InternetShortcut]` / `URL=...`).
+fn shortcut_url(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("URL="))
+        .map(|url| url.trim().to_string())
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+}
+
+/// New-style arXiv IDs at the start of the name, e.g. `2301.12345v2.pdf`
+/// or `arXiv-2012.08669v1.tar.gz` (sources come from the e-print endpoint).
+fn arxiv_url(file_name: &str) -> Option<String> {
+    let re = Regex::new(r"(?i)^(?:arxiv[-_:. ]?)?(\d{4}\.\d{4,5}(?:v\d+)?)(?:[^\d]|$)").unwrap();
+    let id = re.captures(file_name)?.get(1)?.as_str();
+    if file_name.ends_with(".tar.gz") {
+        Some(format!("https://arxiv.org/e-print/{}", id))
+    } else {
+        Some(format!("https://arxiv.org/pdf/{}", id))
+    }
+}
+
+/// Input file for `aria2c -i` (with target folder and name per URL) or
+/// `wget -i` (one URL per line).
+pub fn render_list(entries: &[Redownload], format: RedownloadFormat) -> String {
+    let mut list = String::new();
+    for entry in entries {
+        list.push_str(&entry.url);
+        list.push('\n');
+        if format == RedownloadFormat::Aria2 {
+            let dir = entry.path.parent().unwrap_or(Path::new("."));
+            list.push_str(&format!("  dir={}\n  out={}\n", dir.display(), entry.file_name));
+        }
+    }
+    list
+}
+
+/// The shell command that works through the list.
+pub fn command(list_path: &Path, format: RedownloadFormat) -> String {
+    match format {
+        RedownloadFormat::Aria2 => format!("aria2c -i \"{}\"", list_path.display()),
+        RedownloadFormat::Wget => format!("wget --content-disposition -i \"{}\"", list_path.display()),
+    }
+}
+
+pub fn write_list(target_dir: &Path, entries: &[Redownload], format: RedownloadFormat) -> Result<PathBuf> {
+    let list_path = target_dir.join(LIST_FILE_NAME);
+    fs::write(&list_path, render_list(entries, format))?;
+    Ok(list_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_sources() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path();
+
+        let arxiv = root.join("2301.12345v2.pdf.crdownload");
+        fs::write(&arxiv, "partial")?;
+        let shortcut = root.join("Lang - Algebra.pdf.download");
+        fs::write(&shortcut, "partial")?;
+        fs::write(root.join("Lang - Algebra.pdf.url"), "[InternetShortcut]\r\nURL=https://example.org/lang.pdf?a=1\r\n")?;
+        let bundle = root.join("munkres.pdf.download");
+        fs::create_dir(&bundle)?;
+        fs::write(
+            bundle.join("Info.plist"),
+            "<dict>\n<key>DownloadEntryURL</key>\n<string>https://example.org/get?id=1&amp;f=pdf</string>\n</dict>",
+        )?;
+        let unknown = root.join("Unknown Book.pdf");
+        fs::write(&unknown, "broken")?;
+
+        let found = find_sources(&[unknown, arxiv.clone(), shortcut.clone(), bundle.clone()]);
+
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].url, "https://arxiv.org/pdf/2301.12345v2");
+        assert_eq!(found[0].file_name, "2301.12345v2.pdf");
+        assert_eq!(found[1].url, "https://example.org/lang.pdf?a=1");
+        assert_eq!(found[2].url, "https://example.org/get?id=1&f=pdf");
+        assert_eq!(found[2].file_name, "munkres.pdf");
+
+        let aria2 = render_list(&found[..1], RedownloadFormat::Aria2);
+        assert_eq!(aria2, format!("https://arxiv.org/pdf/2301.12345v2\n  dir={}\n  out=2301.12345v2.pdf\n", root.display()));
+        assert_eq!(render_list(&found[..1], RedownloadFormat::Wget), "https://arxiv.org/pdf/2301.12345v2\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use log::{debug, warn};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// One `fs::rename` of the run. A rename that is part of a cycle takes two
+/// steps: aside to a temporary name, then to its target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenameStep {
+    /// Index into the renames passed to `order`
+    pub index: usize,
+    pub from: PathBuf,
+    pub to: PathBuf,
+    /// Whether the file reaches its target with this step
+    pub is_final: bool,
+}
+
+/// Order renames so that no target is taken when its turn comes: a file
+/// moves away before another takes its name (`B -> C` before `A -> B`), and
+/// cycles (`A -> B`, `B -> A`) are broken by moving one file aside first.
+/// Otherwise the given order is kept.
+pub fn order<P: AsRef<Path>>(renames: &[(P, P)]) -> Vec<RenameStep> {
+    let by_source: HashMap<&Path, usize> = renames.iter().enumerate().map(|(i, (from, _))| (from.as_ref(), i)).collect();
+    // The rename that has to vacate this one's target first
+    let blocker = |i: usize| {
+        let (from, to) = (renames[i].0.as_ref(), renames[i].1.as_ref());
+        by_source.get(to).copied().filter(|_| from != to)
+    };
+    let step = |i: usize| RenameStep { index: i, from: renames[i].0.as_ref().to_path_buf(), to: renames[i].1.as_ref().to_path_buf(), is_final: true };
+
+    let mut steps = Vec::with_capacity(renames.len());
+    let mut done = HashSet::new();
+    for start in 0..renames.len() {
+        let mut path: Vec<usize> = Vec::new();
+        let mut current = Some(start);
+        while let Some(i) = current.filter(|i| !done.contains(i)) {
+            if let Some(position) = path.iter().position(|&p| p == i) {
+                // `path[position..]` is a cycle: move its first rename aside,
+                // let the others follow it around, then finish it
+                let first = path[position];
+                let (from, to) = (renames[first].0.as_ref(), renames[first].1.as_ref());
+                let temp = temporary_name(from);
+                debug!("Breaking rename cycle at {:?} via {:?}", from, temp);
+                steps.push(RenameStep { index: first, from: from.to_path_buf(), to: temp.clone(), is_final: false });
+                for &i in path[position + 1..].iter().rev() {
+                    steps.push(step(i));
+                }
+                steps.push(RenameStep { index: first, from: temp, to: to.to_path_buf(), is_final: true });
+                done.extend(path.drain(position..));
+                break;
+            }
+            path.push(i);
+            current = blocker(i);
+        }
+        for &i in path.iter().rev() {
+            steps.push(step(i));
+        }
+        done.extend(path);
+    }
+    steps
+}
+
+/// A hidden name next to `path` that is not taken.
+fn temporary_name(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    (0..)
+        .map(|n| match n {
+            0 => path.with_file_name(format!(".{}.ebook-renamer-tmp", name)),
+            n => path.with_file_name(format!(".{}.ebook-renamer-tmp{}", name, n)),
+        })
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded range")
+}
+
+/// Perform one step. Never replaces an existing file: a taken target fails
+/// with `AlreadyExists`. A file that fails on its way back from a temporary
+/// name is returned to `original`, or the error says where it was left.
+pub fn execute(step: &RenameStep, original: &Path) -> io::Result<()> {
+    let result = if step.to.exists() && !is_same_file(&step.from, &step.to) {
+        Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", step.to.display())))
+    } else {
+        // Templates with a `/` rename into subfolders
+        step.to.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::rename(&step.from, &step.to))
+    };
+    match result {
+        Err(e) if step.from != original && (original.exists() || fs::rename(&step.from, original).is_err()) => {
+            warn!("Left {} under its temporary name {}", original.display(), step.from.display());
+            Err(io::Error::new(e.kind(), format!("{}, left under its temporary name {}", e, step.from.display())))
+        }
+        result => result,
+    }
+}
+
+/// Perform the steps of `order(renames)`, calling `before` ahead of each.
+/// A failed step never stops the others; a file that could not be moved
+/// aside stays where it is. Returns each rename's outcome in step order.
+pub fn execute_all<P: AsRef<Path>>(renames: &[(P, P)], mut before: impl FnMut()) -> Vec<(usize, io::Result<()>)> {
+    let mut outcomes = Vec::with_capacity(renames.len());
+    let mut failed = HashSet::new();
+    for step in order(renames) {
+        if failed.contains(&step.index) {
+            continue;
+        }
+        before();
+        match execute(&step, renames[step.index].0.as_ref()) {
+            Ok(()) if !step.is_final => debug!("Moved aside: {} -> {}", step.from.display(), step.to.display()),
+            Ok(()) => outcomes.push((step.index, Ok(()))),
+            Err(e) => {
+                failed.insert(step.index);
+                outcomes.push((step.index, Err(e)));
+            }
+        }
+    }
+    outcomes
+}
+
+/// Case-only renames on case-insensitive filesystems see their own target.
+#[cfg(unix)]
+pub fn is_same_file(from: &Path, to: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::metadata(from), fs::metadata(to)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn is_same_file(from: &Path, to: &Path) -> bool {
+    from.parent() == to.parent() && from.to_string_lossy().to_lowercase() == to.to_string_lossy().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_order_and_execute() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path();
+        let p = |name: &str| dir.join(name);
+        for name in ["a", "b", "c", "x", "y"] {
+            fs::write(p(name), name)?;
+        }
+        let renames = vec![
+            // Chain: b has to become c before a can become b
+            (p("a"), p("b")),
+            (p("b"), p("c")),
+            (p("c"), p("d")),
+            // Cycle
+            (p("x"), p("y")),
+            (p("y"), p("x")),
+        ];
+        let steps = order(&renames);
+        let indices: Vec<(usize, bool)> = steps.iter().map(|s| (s.index, s.is_final)).collect();
+        assert_eq!(indices, vec![(2, true), (1, true), (0, true), (3, false), (4, true), (3, true)]);
+
+        for step in &steps {
+            execute(step, &renames[step.index].0)?;
+        }
+        for (name, content) in [("b", "a"), ("c", "b"), ("d", "c"), ("x", "y"), ("y", "x")] {
+            assert_eq!(fs::read_to_string(p(name))?, content);
+        }
+        assert!(!p("a").exists());
+
+        // Never replaces a file
+        fs::write(p("e"), "e")?;
+        let step = RenameStep { index: 0, from: p("b"), to: p("e"), is_final: true };
+        assert_eq!(execute(&step, &p("b")).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_all_continues_after_failures() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path();
+        let p = |name: &str| dir.join(name);
+        for name in ["y", "m", "taken"] {
+            fs::write(p(name), name)?;
+        }
+        let renames = vec![
+            // Cycle whose first file is gone: it can't be moved aside
+            (p("x"), p("y")),
+            (p("y"), p("x")),
+            (p("m"), p("taken")),
+            (p("m2"), p("n")),
+            (p("y2"), p("z")),
+        ];
+        fs::write(p("y2"), "y2")?;
+        let mut calls = 0;
+        let outcomes = execute_all(&renames, || calls += 1);
+
+        let failed: Vec<usize> = outcomes.iter().filter(|(_, r)| r.is_err()).map(|(i, _)| *i).collect();
+        assert_eq!(failed, vec![0, 2, 3]);
+        assert_eq!(outcomes.len(), renames.len());
+        assert_eq!(calls, 5);
+        assert_eq!(fs::read_to_string(p("x"))?, "y");
+        assert_eq!(fs::read_to_string(p("m"))?, "m");
+        assert_eq!(fs::read_to_string(p("z"))?, "y2");
+        // No file is left under a temporary name
+        assert!(fs::read_dir(dir)?.all(|e| !e.unwrap().file_name().to_string_lossy().contains("ebook-renamer-tmp")));
+        Ok(())
+    }
+}
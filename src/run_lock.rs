@@ -0,0 +1,75 @@
+use crate::error::Error;
+use anyhow::Result;
+use log::{debug, info};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const LOCK_FILE_NAME: &str = ".ebook-renamer.lock";
+
+/// Advisory lock on a library so two runs (e.g. cron and a manual run)
+/// don't race on renames and todo.md. Released when dropped. The lock file
+/// itself is left in place; deleting it would let a waiting run lock a
+/// file nobody else can see.
+pub struct RunLock {
+    _file: File,
+}
+
+impl RunLock {
+    /// Take the lock, or fail with a message naming the other run. With
+    /// `wait`, block until the other run finishes instead.
+    pub fn acquire(target_dir: &Path, wait: bool) -> Result<Self> {
+        if !target_dir.is_dir() {
+            return Err(Error::NotADirectory(target_dir.to_path_buf()).into());
+        }
+        let path = lock_path(target_dir);
+        let mut file = OpenOptions::new().create(true).truncate(false).read(true).write(true).open(&path)?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) if wait => {
+                info!("Waiting for another run to release {:?}", path);
+                file.lock()?;
+            }
+            Err(TryLockError::WouldBlock) => {
+                let holder = fs::read_to_string(&path).unwrap_or_default();
+                let holder = holder.trim();
+                return Err(Error::Locked(format!(
+                    "Another ebook-renamer run{} is already working on {}. Wait for it to finish or pass --wait-for-lock",
+                    if holder.is_empty() { String::new() } else { format!(" (pid {})", holder) },
+                    target_dir.display()
+                ))
+                .into());
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+        debug!("Acquired run lock {:?}", path);
+        Ok(Self { _file: file })
+    }
+}
+
+fn lock_path(target_dir: &Path) -> PathBuf {
+    target_dir.join(LOCK_FILE_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_second_run_is_refused_until_released() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+
+        let lock = RunLock::acquire(tmp_dir.path(), false)?;
+        let err = RunLock::acquire(tmp_dir.path(), false).err().expect("second run must be refused");
+        assert!(err.to_string().contains(&format!("pid {}", std::process::id())));
+
+        drop(lock);
+        assert!(RunLock::acquire(tmp_dir.path(), false).is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use crate::chapters::count_pdf_pages;
+use crate::scanner::FileInfo;
+use log::{debug, info};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Quarantined samples go here, hidden so later scans skip them.
+pub const QUARANTINE_DIR: &str = ".ebook-renamer-samples";
+
+// A plain "sample" in the name is only trusted for short PDFs
+const MAX_SAMPLE_PAGES: usize = 40;
+
+// "(Sample)", "[Free Preview]", "{excerpt}"
+static RE_MARKED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)[(\[{]\s*(?:free\s+|kindle\s+|google\s+play\s+)?(?:sample|preview|excerpt)\s*[)\]}]").unwrap()
+});
+// "Title - Sample", "title_preview"
+static RE_SUFFIX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)[\s_-](?:free[\s_]+)?(?:sample|preview|excerpt)$").unwrap());
+static RE_WORD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\b(?:sample|preview)\b").unwrap());
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub path: PathBuf,
+    pub size: u64,
+    pub reason: String,
+}
+
+/// Files that look like store samples or previews. Broken files are left to
+/// the todo list.
+pub fn detect_samples(files: &[FileInfo]) -> Vec<Sample> {
+    let mut samples = Vec::new();
+    for file_info in files {
+        if file_info.is_failed_download || file_info.is_too_small {
+            continue;
+        }
+        let stem = file_info.original_name.strip_suffix(&file_info.extension).unwrap_or(&file_info.original_name);
+        let reason = if let Some(marker) = RE_MARKED.find(stem).or_else(|| RE_SUFFIX.find(stem)) {
+            Some(format!("\"{}\" in the name", marker.as_str().trim_matches(|c: char| c == '_' || c == '-' || c.is_whitespace())))
+        } else if RE_WORD.is_match(stem) && file_info.extension.eq_ignore_ascii_case(".pdf") {
+            count_pdf_pages(&file_info.original_path)
+                .filter(|pages| *pages <= MAX_SAMPLE_PAGES)
+                .map(|pages| format!("\"sample\" or \"preview\" in the name and only {} pages", pages))
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            debug!("Sample: {} ({})", file_info.original_name, reason);
+            samples.push(Sample { path: file_info.original_path.clone(), size: file_info.size, reason });
+        }
+    }
+    samples
+}
+
+/// Move samples under `QUARANTINE_DIR`, keeping their folder structure.
+/// Existing files are never overwritten. Returns the moves and any errors.
+pub fn quarantine(samples: &[Sample], root: &Path) -> (Vec<(PathBuf, PathBuf)>, Vec<String>) {
+    let mut moved = Vec::new();
+    let mut errors = Vec::new();
+    for sample in samples {
+        let relative = sample.path.strip_prefix(root).unwrap_or(&sample.path);
+        let target = root.join(QUARANTINE_DIR).join(relative);
+        if target.exists() {
+            errors.push(format!("Not quarantined, {} already exists", target.display()));
+            continue;
+        }
+        let result = target.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::rename(&sample.path, &target));
+        match result {
+            Ok(_) => {
+                info!("Quarantined sample {:?} -> {:?}", sample.path, target);
+                moved.push((sample.path.clone(), target));
+            }
+            Err(e) => errors.push(format!("Failed to quarantine {}: {}", sample.path.display(), e)),
+        }
+    }
+    (moved, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    fn pdf_with_pages(pages: usize) -> Vec<u8> {
+        let mut content = b"%PDF-1.4\n".to_vec();
+        for i in 0..pages {
+            content.extend(format!("{} 0 obj << /Type /Page >> endobj\n", i + 1).as_bytes());
+        }
+        content.resize(content.len().max(2048), b' ');
+        content
+    }
+
+    #[test]
+    fn test_detect_and_quarantine_samples() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        fs::create_dir(root.join("Math"))?;
+        fs::write(root.join("Math/Jane Quux - Algebra (Sample).epub"), vec![b'x'; 2048])?;
+        fs::write(root.join("Topology_preview.pdf"), pdf_with_pages(200))?;
+        fs::write(root.join("Sample Surveys Preview Edition.pdf"), pdf_with_pages(12))?;
+        fs::write(root.join("Sampling Theory.pdf"), pdf_with_pages(5))?;
+        fs::write(root.join("Survey Sampling Sample Chapters.pdf"), pdf_with_pages(300))?;
+
+        let files = Scanner::new(&root, 2)?.scan()?;
+        let mut samples = detect_samples(&files);
+        samples.sort_by(|a, b| a.path.cmp(&b.path));
+        let found: Vec<(String, &str)> = samples
+            .iter()
+            .map(|s| (s.path.strip_prefix(&root).unwrap().to_string_lossy().to_string(), s.reason.as_str()))
+            .collect();
+        assert_eq!(found, vec![
+            ("Math/Jane Quux - Algebra (Sample).epub".to_string(), "\"(Sample)\" in the name"),
+            ("Sample Surveys Preview Edition.pdf".to_string(), "\"sample\" or \"preview\" in the name and only 12 pages"),
+            ("Topology_preview.pdf".to_string(), "\"preview\" in the name"),
+        ]);
+
+        let (moved, errors) = quarantine(&samples, &root);
+        assert!(errors.is_empty());
+        assert_eq!(moved[0].1, root.join(QUARANTINE_DIR).join("Math/Jane Quux - Algebra (Sample).epub"));
+        assert!(moved.iter().all(|(from, to)| !from.exists() && to.exists()));
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::Result;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Progress of an interrupted scan, read back by `--resume-scan`.
+pub const CHECKPOINT_FILE_NAME: &str = ".ebook-renamer-scan-checkpoint.json";
+
+/// How often a running scan writes its checkpoint. Scans that finish sooner
+/// never write one.
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Older checkpoints are ignored: too much may have changed on disk since.
+pub const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A file found in a completed folder, with what the scanner read from disk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+    #[serde(default)]
+    pub is_virtual: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanCheckpoint {
+    pub saved: SystemTime,
+    pub root: PathBuf,
+    pub max_depth: usize,
+    /// Folders whose whole subtree was scanned; none is inside another
+    pub completed: BTreeSet<PathBuf>,
+    /// The files under `completed`
+    pub files: Vec<CheckpointFile>,
+}
+
+impl ScanCheckpoint {
+    pub fn new(root: &Path, max_depth: usize) -> Self {
+        Self {
+            saved: SystemTime::now(),
+            root: root.to_path_buf(),
+            max_depth,
+            completed: BTreeSet::new(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Record a folder whose subtree is done; folders already recorded
+    /// inside it are folded into it.
+    pub fn complete(&mut self, dir: &Path) {
+        let inside: Vec<PathBuf> = self.completed.range(dir.to_path_buf()..).take_while(|d| d.starts_with(dir)).cloned().collect();
+        for d in inside {
+            self.completed.remove(&d);
+        }
+        self.completed.insert(dir.to_path_buf());
+    }
+
+    /// Whether `path` lies in a completed folder.
+    pub fn covers(&self, path: &Path) -> bool {
+        path.ancestors().skip(1).any(|dir| self.completed.contains(dir))
+    }
+
+    /// The checkpoint of a scan of `root` with the same depth, if one was
+    /// saved within `MAX_AGE`. Others are ignored (and logged).
+    pub fn load(root: &Path, max_depth: usize) -> Result<Option<Self>> {
+        let path = root.join(CHECKPOINT_FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let checkpoint: Self = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        if checkpoint.root != root || checkpoint.max_depth != max_depth {
+            info!("Ignoring scan checkpoint {:?}: it is for another root or depth", path);
+            return Ok(None);
+        }
+        let age = checkpoint.saved.elapsed().unwrap_or(Duration::ZERO);
+        if age > MAX_AGE {
+            info!("Ignoring scan checkpoint {:?}: saved {} hours ago", path, age.as_secs() / 3600);
+            return Ok(None);
+        }
+        debug!("Loaded scan checkpoint with {} folders and {} files", checkpoint.completed.len(), checkpoint.files.len());
+        Ok(Some(checkpoint))
+    }
+
+    /// Write the checkpoint, keeping only files under completed folders.
+    pub fn save(&mut self) -> Result<()> {
+        self.saved = SystemTime::now();
+        let files = std::mem::take(&mut self.files);
+        self.files = files.into_iter().filter(|f| self.covers(&f.path)).collect();
+        // Write then rename, so an interruption never leaves half a checkpoint
+        let path = self.root.join(CHECKPOINT_FILE_NAME);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string(self)?)?;
+        fs::rename(&tmp, &path)?;
+        debug!("Saved scan checkpoint: {} folders, {} files", self.completed.len(), self.files.len());
+        Ok(())
+    }
+
+    /// Remove the checkpoint of `root` once a scan finished.
+    pub fn remove(root: &Path) -> Result<()> {
+        let path = root.join(CHECKPOINT_FILE_NAME);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_complete_folds_nested_folders() {
+        let mut checkpoint = ScanCheckpoint::new(Path::new("/lib"), usize::MAX);
+        checkpoint.complete(Path::new("/lib/Math/Algebra"));
+        checkpoint.complete(Path::new("/lib/Math/Analysis"));
+        checkpoint.complete(Path::new("/lib/Mathematica"));
+        checkpoint.complete(Path::new("/lib/Math"));
+
+        let completed: Vec<&Path> = checkpoint.completed.iter().map(PathBuf::as_path).collect();
+        assert_eq!(completed, vec![Path::new("/lib/Math"), Path::new("/lib/Mathematica")]);
+        assert!(checkpoint.covers(Path::new("/lib/Math/Algebra/Lang.pdf")));
+        assert!(!checkpoint.covers(Path::new("/lib/Lang.pdf")));
+    }
+
+    #[test]
+    fn test_save_load_and_remove() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path();
+        let mut checkpoint = ScanCheckpoint::new(root, usize::MAX);
+        checkpoint.complete(&root.join("Done"));
+        for name in ["Done/a.pdf", "Open/b.pdf"] {
+            checkpoint.files.push(CheckpointFile {
+                path: root.join(name),
+                size: 2048,
+                modified: SystemTime::UNIX_EPOCH,
+                is_virtual: false,
+            });
+        }
+        checkpoint.save()?;
+
+        let loaded = ScanCheckpoint::load(root, usize::MAX)?.unwrap();
+        assert_eq!(loaded, checkpoint);
+        assert_eq!(loaded.files.len(), 1);
+        assert!(ScanCheckpoint::load(root, 1)?.is_none());
+
+        ScanCheckpoint::remove(root)?;
+        assert!(ScanCheckpoint::load(root, usize::MAX)?.is_none());
+        Ok(())
+    }
+}
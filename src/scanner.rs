@@ -1,8 +1,17 @@
+use crate::error::Error;
+use crate::io_timeout::{self, run_with_timeout};
+use crate::normalizer::ParsedMetadata;
+use crate::placeholders;
+use crate::scan_checkpoint::{CheckpointFile, ScanCheckpoint};
+use crate::sync_ignore::SyncIgnore;
+use crate::traversal::{self, Traversal};
 use anyhow::{anyhow, Result};
-use log::debug;
+use log::{debug, info, warn};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime};
 
 #[derive(Debug, Clone)]
 pub struct FileInfo {
@@ -13,57 +22,323 @@ pub struct FileInfo {
     pub modified_time: std::time::SystemTime,
     pub is_failed_download: bool,
     pub is_too_small: bool,
+    /// Cloud placeholder whose content is not on disk; never read, so the
+    /// sync client doesn't download it
+    pub is_virtual: bool,
     pub new_name: Option<String>,
     pub new_path: PathBuf,
+    /// Parsed filename fields, set by the normalizer
+    pub metadata: Option<ParsedMetadata>,
 }
 
+/// Per-extension size below which a file counts as a broken download.
+/// Extensions without a threshold are never too small.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinValidSize {
+    thresholds: HashMap<String, u64>,
+}
+
+impl Default for MinValidSize {
+    // txt files can be small
+    fn default() -> Self {
+        Self { thresholds: HashMap::from([(".pdf".to_string(), 1024), (".epub".to_string(), 1024)]) }
+    }
+}
+
+impl MinValidSize {
+    pub fn threshold(&self, extension: &str) -> u64 {
+        self.thresholds.get(&extension.to_lowercase()).copied().unwrap_or(0)
+    }
+
+    pub fn is_too_small(&self, extension: &str, size: u64) -> bool {
+        size < self.threshold(extension)
+    }
+}
+
+/// `pdf=10KB,epub=5KB,txt=0`; extensions not listed keep their default.
+impl FromStr for MinValidSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut sizes = Self::default();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (extension, size) = part.split_once('=').ok_or_else(|| format!("expected EXT=SIZE, got '{}'", part))?;
+            let extension = extension.trim().trim_start_matches('.').to_lowercase();
+            if extension.is_empty() {
+                return Err(format!("missing extension in '{}'", part));
+            }
+            sizes.thresholds.insert(format!(".{}", extension), parse_size(size.trim())?);
+        }
+        Ok(sizes)
+    }
+}
+
+/// Bytes, or a number with a B/KB/MB suffix (powers of 1024).
+fn parse_size(s: &str) -> std::result::Result<u64, String> {
+    let upper = s.to_uppercase();
+    let (number, multiplier) = if let Some(n) = upper.strip_suffix("MB") {
+        (n, 1024 * 1024)
+    } else if let Some(n) = upper.strip_suffix("KB") {
+        (n, 1024)
+    } else {
+        (upper.strip_suffix('B').unwrap_or(&upper), 1)
+    };
+    number.trim().parse::<u64>().map(|n| n * multiplier).map_err(|_| format!("invalid size '{}'", s))
+}
+
+// Report scan progress every this many directory entries
+const PROGRESS_INTERVAL: usize = 500;
+
+/// Called with the number of entries walked so far and the current folder.
+pub type ScanProgress = Box<dyn FnMut(usize, &Path) + Send>;
+
 pub struct Scanner {
     root_path: PathBuf,
-    max_depth: usize,
+    traversal: Traversal,
+    io_timeout: Option<Duration>,
+    min_valid_size: MinValidSize,
+    progress: Option<ScanProgress>,
+    /// Written periodically while walking, when checkpoints are saved
+    checkpoint: Option<ScanCheckpoint>,
+    /// Loaded by `--resume-scan`
+    resume_from: Option<ScanCheckpoint>,
+    /// Folders and files the sync client leaves out
+    sync_ignore: SyncIgnore,
+    /// Files whose metadata could not be read within `io_timeout`
+    pub timed_out: Vec<PathBuf>,
+    /// Files taken from the checkpoint instead of walking their folders
+    pub resumed_files: usize,
 }
 
 impl Scanner {
     pub fn new(path: &Path, max_depth: usize) -> Result<Self> {
         let root_path = path.canonicalize()?;
         if !root_path.is_dir() {
-            return Err(anyhow!("Path is not a directory: {:?}", path));
+            return Err(Error::NotADirectory(path.to_path_buf()).into());
         }
         Ok(Scanner {
             root_path,
-            max_depth,
+            traversal: Traversal::new(max_depth),
+            io_timeout: None,
+            min_valid_size: MinValidSize::default(),
+            progress: None,
+            checkpoint: None,
+            resume_from: None,
+            sync_ignore: SyncIgnore::default(),
+            timed_out: Vec::new(),
+            resumed_files: 0,
         })
     }
 
+    pub fn with_io_timeout(mut self, io_timeout: Option<Duration>) -> Self {
+        self.io_timeout = io_timeout;
+        self
+    }
+
+    pub fn with_min_valid_size(mut self, min_valid_size: MinValidSize) -> Self {
+        self.min_valid_size = min_valid_size;
+        self
+    }
+
+    /// Leave out what the sync client does not sync (`--sync-exclusions`):
+    /// excluded folders are not walked.
+    pub fn with_sync_ignore(mut self, sync_ignore: SyncIgnore) -> Self {
+        self.sync_ignore = sync_ignore;
+        self
+    }
+
+    /// Report progress while walking slow trees (network or cloud-synced folders).
+    pub fn with_progress(mut self, progress: ScanProgress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Save a checkpoint every `SAVE_INTERVAL` while walking (`save`), and
+    /// skip the folders an earlier interrupted scan completed (`resume`).
+    /// The checkpoint is removed once a scan finishes.
+    pub fn with_checkpoints(mut self, save: bool, resume: bool) -> Result<Self> {
+        let max_depth = self.traversal.max_depth;
+        if resume {
+            self.resume_from = ScanCheckpoint::load(&self.root_path, max_depth)?;
+            if self.resume_from.is_none() {
+                info!("No scan checkpoint to resume from, scanning from the start");
+            }
+        }
+        if save {
+            self.checkpoint = Some(ScanCheckpoint::new(&self.root_path, max_depth));
+        }
+        Ok(self)
+    }
+
     pub fn scan(&mut self) -> Result<Vec<FileInfo>> {
         let mut files = Vec::new();
+        self.scan_each(|file_info| {
+            files.push(file_info);
+            Ok(())
+        })?;
+        Ok(files)
+    }
+
+    /// Walk the tree and hand each file to `each` as soon as it is found,
+    /// so a caller can process a large library without holding the whole
+    /// scan first. Returns how many files were found.
+    pub fn scan_each(&mut self, mut each: impl FnMut(FileInfo) -> Result<()>) -> Result<usize> {
+        let mut found = 0;
 
-        for entry in WalkDir::new(&self.root_path)
-            .max_depth(self.max_depth)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
+        // Files of completed folders by folder, taken when the walk reaches the folder
+        let mut resumed: HashMap<PathBuf, Vec<CheckpointFile>> = HashMap::new();
+        if let Some(checkpoint) = self.resume_from.take() {
+            info!("Resuming scan: {} folders and {} files from the checkpoint", checkpoint.completed.len(), checkpoint.files.len());
+            for file in checkpoint.files {
+                if let Some(dir) = file.path.ancestors().find(|dir| checkpoint.completed.contains(*dir)) {
+                    resumed.entry(dir.to_path_buf()).or_default().push(file);
+                }
+            }
+            for dir in checkpoint.completed {
+                resumed.entry(dir).or_default();
+            }
+        }
+
+        // Folders being walked, innermost last; a folder is complete once
+        // the walk leaves it
+        let mut open_dirs: Vec<(usize, PathBuf)> = Vec::new();
+        let mut last_save = Instant::now();
+
+        let mut walker = self.traversal.walker(&self.root_path).into_iter();
+        let mut walked = 0;
+        while let Some(entry) = walker.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            walked += 1;
             let path = entry.path();
+            if let Some(progress) = &mut self.progress
+                && walked % PROGRESS_INTERVAL == 0
+            {
+                progress(walked, path.parent().unwrap_or(path));
+            }
+
+            if let Some(checkpoint) = &mut self.checkpoint {
+                while open_dirs.last().is_some_and(|(depth, _)| *depth >= entry.depth()) {
+                    let (_, dir) = open_dirs.pop().unwrap();
+                    checkpoint.complete(&dir);
+                }
+                if last_save.elapsed() >= crate::scan_checkpoint::SAVE_INTERVAL {
+                    if let Err(e) = checkpoint.save() {
+                        warn!("Could not save scan checkpoint: {}", e);
+                    }
+                    last_save = Instant::now();
+                }
+            }
+
+            if self.is_sync_excluded(path, entry.file_type().is_dir()) {
+                debug!("Excluded from sync: {:?}", path);
+                if entry.file_type().is_dir() {
+                    walker.skip_current_dir();
+                }
+                continue;
+            }
+
+            if entry.file_type().is_dir() {
+                if let Some(done) = resumed.remove(path) {
+                    walker.skip_current_dir();
+                    for file in done {
+                        let file_info = self.file_info(&file.path, file.size, file.modified, file.is_virtual)?;
+                        self.resumed_files += 1;
+                        self.found(file_info, &mut each)?;
+                        found += 1;
+                    }
+                    if let Some(checkpoint) = &mut self.checkpoint {
+                        checkpoint.complete(path);
+                    }
+                } else if self.checkpoint.is_some() {
+                    open_dirs.push((entry.depth(), path.to_path_buf()));
+                }
+                continue;
+            }
 
-            // Skip directories, hidden files, and system directories
-            if path.is_dir() || self.should_skip(path) {
+            // Skip hidden files and files in system directories
+            if self.should_skip(path) {
                 continue;
             }
 
             // Check for interesting extensions
-            if let Ok(file_info) = self.create_file_info(path) {
-                files.push(file_info);
+            match self.create_file_info(path) {
+                Ok(file_info) => {
+                    self.found(file_info, &mut each)?;
+                    found += 1;
+                }
+                Err(e) if io_timeout::is_timeout(&e) => {
+                    warn!("Skipping {:?}: {}", path, e);
+                    self.timed_out.push(path.to_path_buf());
+                }
+                Err(_) => {}
             }
         }
 
-        debug!("Scanner found {} files", files.len());
+        if self.checkpoint.is_some() {
+            ScanCheckpoint::remove(&self.root_path)?;
+        }
+
+        debug!("Scanner found {} files", found);
+        Ok(found)
+    }
+
+    /// Record a found file in the checkpoint and pass it on.
+    fn found(&mut self, file_info: FileInfo, each: &mut impl FnMut(FileInfo) -> Result<()>) -> Result<()> {
+        if let Some(checkpoint) = &mut self.checkpoint {
+            checkpoint.files.push(checkpoint_file(&file_info));
+        }
+        each(file_info)
+    }
+
+    /// Scan the given files instead of walking the tree. Paths that are not
+    /// files under the root are left out with a warning.
+    pub fn scan_paths(&mut self, paths: Vec<PathBuf>) -> Result<Vec<FileInfo>> {
+        let mut files = Vec::new();
+        for path in paths {
+            let path = match path.canonicalize() {
+                Ok(path) if self.is_sync_excluded(&path, false) => {
+                    debug!("Excluded from sync: {:?}", path);
+                    continue;
+                }
+                Ok(path) if path.is_file() && path.starts_with(&self.root_path) => path,
+                Ok(_) => {
+                    warn!("Skipping {:?}: not a file under {:?}", path, self.root_path);
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Skipping {:?}: {}", path, e);
+                    continue;
+                }
+            };
+            match self.create_file_info(&path) {
+                Ok(file_info) => files.push(file_info),
+                Err(e) if io_timeout::is_timeout(&e) => {
+                    warn!("Skipping {:?}: {}", path, e);
+                    self.timed_out.push(path);
+                }
+                Err(_) => {}
+            }
+        }
+        // A path listed twice is still one file
+        files.sort_by(|a, b| a.original_path.cmp(&b.original_path));
+        files.dedup_by(|a, b| a.original_path == b.original_path);
+        debug!("Scanner took {} files from the list", files.len());
         Ok(files)
     }
 
-    fn create_file_info(&self, path: &Path) -> Result<FileInfo> {
-        let metadata = fs::metadata(path)?;
-        let size = metadata.len();
-        let modified_time = metadata.modified()?;
+    pub fn create_file_info(&self, path: &Path) -> Result<FileInfo> {
+        let owned_path = path.to_path_buf();
+        let metadata = run_with_timeout(self.io_timeout, move || Ok(fs::metadata(owned_path)?))?;
+        let is_virtual = placeholders::is_placeholder(&metadata);
+        if is_virtual {
+            debug!("Cloud placeholder, content not downloaded: {:?}", path);
+        }
+        self.file_info(path, metadata.len(), metadata.modified()?, is_virtual)
+    }
 
+    fn file_info(&self, path: &Path, size: u64, modified_time: SystemTime, is_virtual: bool) -> Result<FileInfo> {
         let original_name = path
             .file_name()
             .and_then(|n| n.to_str())
@@ -85,9 +360,7 @@ impl Scanner {
         };
 
         let is_failed_download = original_name.ends_with(".download") || original_name.ends_with(".crdownload");
-        // Only check size for PDF and EPUB files (txt files can be small)
-        let is_ebook = extension == ".pdf" || extension == ".epub";
-        let is_too_small = !is_failed_download && is_ebook && size < 1024; // Less than 1KB
+        let is_too_small = !is_failed_download && self.min_valid_size.is_too_small(&extension, size);
 
         Ok(FileInfo {
             original_path: path.to_path_buf(),
@@ -97,26 +370,32 @@ impl Scanner {
             modified_time,
             is_failed_download,
             is_too_small,
+            is_virtual,
             new_name: None,
             new_path: path.to_path_buf(),
+            metadata: None,
         })
     }
 
+    fn is_sync_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        path.strip_prefix(&self.root_path).is_ok_and(|relative| self.sync_ignore.is_excluded(relative, is_dir))
+    }
+
+    // Only the entry itself is skipped, not its subtree (see docs/spec.md)
     fn should_skip(&self, path: &Path) -> bool {
         if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-            // Skip hidden files/folders
-            if filename.starts_with('.') {
+            // Hidden files/folders and known system directories
+            if traversal::is_hidden_or_system(filename) {
                 return true;
             }
 
-            // Skip download folders only (not files) - they're handled by download_recovery module
-            if path.is_dir() && (filename.ends_with(".download") || filename.ends_with(".crdownload")) {
+            // Our own re-download list is not a book
+            if filename == crate::redownload::LIST_FILE_NAME {
                 return true;
             }
 
-            // Skip known system directories
-            let skip_dirs = ["Xcode", "node_modules", ".git", "__pycache__"];
-            if skip_dirs.iter().any(|d| filename == *d) {
+            // Skip download folders only (not files) - they're handled by download_recovery module
+            if path.is_dir() && traversal::is_download_folder_name(filename) {
                 return true;
             }
         }
@@ -125,11 +404,126 @@ impl Scanner {
     }
 }
 
+fn checkpoint_file(file_info: &FileInfo) -> CheckpointFile {
+    CheckpointFile {
+        path: file_info.original_path.clone(),
+        size: file_info.size,
+        modified: file_info.modified_time,
+        is_virtual: file_info.is_virtual,
+    }
+}
+
+/// Newline-separated paths from a file, or from stdin for `-`. Relative
+/// paths are taken from the current directory, like `find` prints them.
+pub fn read_file_list(source: &Path) -> Result<Vec<PathBuf>> {
+    let content = if source == Path::new("-") {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        fs::read_to_string(source).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => Error::NotFound { what: "File list", path: source.to_path_buf() }.into(),
+            _ => anyhow::Error::from(e),
+        })?
+    };
+    Ok(content
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.trim().is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_scan_reports_progress() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        for i in 0..PROGRESS_INTERVAL {
+            fs::write(tmp_dir.path().join(format!("book{}.txt", i)), "x")?;
+        }
+
+        let reports = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let mut scanner = Scanner::new(tmp_dir.path(), 1)?
+            .with_progress(Box::new(move |walked, _| sink.lock().unwrap().push(walked)));
+        scanner.scan()?;
+
+        // The root directory itself counts as the first entry
+        assert_eq!(*reports.lock().unwrap(), vec![PROGRESS_INTERVAL]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_each_hands_over_every_file() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        fs::create_dir(root.join("Shelf"))?;
+        fs::write(root.join("a.pdf"), "x")?;
+        fs::write(root.join("Shelf/b.epub"), "x")?;
+
+        let mut names = Vec::new();
+        let found = Scanner::new(&root, usize::MAX)?.with_checkpoints(true, false)?.scan_each(|file_info| {
+            names.push(file_info.original_name);
+            Ok(())
+        })?;
+        names.sort();
+        assert_eq!(found, 2);
+        assert_eq!(names, vec!["a.pdf", "b.epub"]);
+        assert!(!root.join(crate::scan_checkpoint::CHECKPOINT_FILE_NAME).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_leaves_out_sync_exclusions() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        fs::create_dir_all(root.join("Archive/Old"))?;
+        fs::write(root.join("Archive/Old/a.pdf"), "x")?;
+        fs::write(root.join("b.pdf"), "x")?;
+        fs::write(root.join(".sync-exclude.lst"), "Archive/\n")?;
+
+        let sync_ignore = SyncIgnore::load(&root, None)?;
+        let files = Scanner::new(&root, usize::MAX)?.with_sync_ignore(sync_ignore.clone()).scan()?;
+        assert_eq!(files.iter().map(|f| f.original_name.as_str()).collect::<Vec<_>>(), vec!["b.pdf"]);
+        let listed = Scanner::new(&root, usize::MAX)?
+            .with_sync_ignore(sync_ignore)
+            .scan_paths(vec![root.join("Archive/Old/a.pdf"), root.join("b.pdf")])?;
+        assert_eq!(listed.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_resume_skips_completed_folders() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        fs::create_dir_all(root.join("Done"))?;
+        fs::create_dir_all(root.join("Open"))?;
+        fs::write(root.join("Done/walked.pdf"), "x")?;
+        fs::write(root.join("Open/b.pdf"), "x")?;
+
+        // Only in the checkpoint, so it proves Done/ was not walked again
+        let mut checkpoint = ScanCheckpoint::new(&root, usize::MAX);
+        checkpoint.complete(&root.join("Done"));
+        checkpoint.files.push(CheckpointFile {
+            path: root.join("Done/from-checkpoint.pdf"),
+            size: 4096,
+            modified: SystemTime::UNIX_EPOCH,
+            is_virtual: false,
+        });
+        checkpoint.save()?;
+
+        let mut scanner = Scanner::new(&root, usize::MAX)?.with_checkpoints(true, true)?;
+        let mut names: Vec<String> = scanner.scan()?.into_iter().map(|f| f.original_name).collect();
+        names.sort();
+        assert_eq!(names, vec!["b.pdf", "from-checkpoint.pdf"]);
+        assert_eq!(scanner.resumed_files, 1);
+        // A finished scan leaves nothing to resume
+        assert!(ScanCheckpoint::load(&root, usize::MAX)?.is_none());
+        Ok(())
+    }
+
     #[test]
     fn test_scanner_creates_correct_file_info() {
         let tmp_dir = TempDir::new().unwrap();
@@ -184,5 +578,55 @@ mod tests {
 
         assert!(file_info.is_too_small);
     }
-}
 
+    #[test]
+    fn test_min_valid_size_per_extension() -> Result<()> {
+        let sizes: MinValidSize = "pdf=10KB, .EPUB=5kb,txt=100".parse().unwrap();
+        assert_eq!(sizes.threshold(".pdf"), 10 * 1024);
+        assert_eq!(sizes.threshold(".epub"), 5 * 1024);
+        assert_eq!(sizes.threshold(".djvu"), 0);
+        assert!("pdf".parse::<MinValidSize>().is_err());
+        assert!("pdf=ten".parse::<MinValidSize>().is_err());
+
+        let tmp_dir = TempDir::new()?;
+        fs::write(tmp_dir.path().join("notes.txt"), "x")?;
+        fs::write(tmp_dir.path().join("sample.epub"), vec![b'x'; 2048])?;
+        let scanner = Scanner::new(tmp_dir.path(), 1)?.with_min_valid_size(sizes);
+        assert!(scanner.create_file_info(&tmp_dir.path().join("notes.txt"))?.is_too_small);
+        assert!(scanner.create_file_info(&tmp_dir.path().join("sample.epub"))?.is_too_small);
+
+        fs::write(tmp_dir.path().join("tiny.pdf"), "x")?;
+        let scanner = Scanner::new(tmp_dir.path(), 1)?.with_min_valid_size("pdf=0".parse().unwrap());
+        assert!(!scanner.create_file_info(&tmp_dir.path().join("tiny.pdf"))?.is_too_small);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_paths_from_list() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let library = tmp_dir.path().join("library");
+        fs::create_dir_all(library.join("sub"))?;
+        fs::write(library.join("a.pdf"), "x")?;
+        fs::write(library.join("sub").join("b.epub"), "x")?;
+        fs::write(tmp_dir.path().join("outside.pdf"), "x")?;
+        let list = tmp_dir.path().join("list.txt");
+        let lines = [
+            library.join("a.pdf"),
+            library.join("sub").join("b.epub"),
+            library.join("sub").join("..").join("a.pdf"),
+            library.join("sub"),
+            library.join("missing.pdf"),
+            tmp_dir.path().join("outside.pdf"),
+        ];
+        let content: Vec<String> = lines.iter().map(|p| p.display().to_string()).collect();
+        fs::write(&list, content.join("\r\n") + "\n\n")?;
+
+        let paths = read_file_list(&list)?;
+        assert_eq!(paths.len(), 6);
+        let files = Scanner::new(&library, 1)?.scan_paths(paths)?;
+        let names: Vec<&str> = files.iter().map(|f| f.original_name.as_str()).collect();
+        // Depth limits only apply to walking
+        assert_eq!(names, vec!["a.pdf", "b.epub"]);
+        Ok(())
+    }
+}
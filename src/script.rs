@@ -0,0 +1,194 @@
+use crate::rename_order::{self, RenameStep};
+use anyhow::Result;
+use chrono::Local;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Posix,
+    PowerShell,
+}
+
+impl Shell {
+    fn extension(self) -> &'static str {
+        match self {
+            Shell::Posix => "sh",
+            Shell::PowerShell => "ps1",
+        }
+    }
+
+    fn quote(self, path: &Path) -> String {
+        let path = path.to_string_lossy();
+        match self {
+            Shell::Posix => format!("'{}'", path.replace('\'', r"'\''")),
+            Shell::PowerShell => format!("'{}'", path.replace('\'', "''")),
+        }
+    }
+
+    /// Stops at the first failure and never moves onto an existing file,
+    /// unless only the case of the name changes.
+    fn header(self) -> &'static str {
+        match self {
+            Shell::Posix => concat!(
+                "#!/bin/sh\n",
+                "set -e\n",
+                "move() {\n",
+                "    if [ -e \"$2\" ] && ! [ \"$1\" -ef \"$2\" ]; then\n",
+                "        echo \"ebook-renamer: $2 already exists\" >&2\n",
+                "        exit 1\n",
+                "    fi\n",
+                "    mv -- \"$1\" \"$2\"\n",
+                "}\n",
+            ),
+            Shell::PowerShell => concat!(
+                "$ErrorActionPreference = 'Stop'\n",
+                "function Move-Book($From, $To) {\n",
+                "    if ((Test-Path -LiteralPath $To) -and ($From -ne $To)) { throw \"ebook-renamer: $To already exists\" }\n",
+                "    Move-Item -LiteralPath $From -Destination $To\n",
+                "}\n",
+            ),
+        }
+    }
+
+    fn mkdir(self, dir: &Path) -> String {
+        match self {
+            Shell::Posix => format!("mkdir -p -- {}\n", self.quote(dir)),
+            Shell::PowerShell => format!("New-Item -ItemType Directory -Force -Path {} | Out-Null\n", self.quote(dir)),
+        }
+    }
+
+    /// Only removes the folder if it is empty.
+    fn rmdir(self, dir: &Path) -> String {
+        match self {
+            Shell::Posix => format!("rmdir -- {} 2>/dev/null || true\n", self.quote(dir)),
+            Shell::PowerShell => format!(
+                "if (-not (Get-ChildItem -LiteralPath {0} -Force)) {{ Remove-Item -LiteralPath {0} }}\n",
+                self.quote(dir)
+            ),
+        }
+    }
+
+    fn mv(self, from: &Path, to: &Path) -> String {
+        match self {
+            Shell::Posix => format!("move {} {}\n", self.quote(from), self.quote(to)),
+            Shell::PowerShell => format!("Move-Book {} {}\n", self.quote(from), self.quote(to)),
+        }
+    }
+
+    fn rm(self, path: &Path) -> String {
+        match self {
+            Shell::Posix => format!("rm -- {}\n", self.quote(path)),
+            Shell::PowerShell => format!("Remove-Item -LiteralPath {}\n", self.quote(path)),
+        }
+    }
+}
+
+/// The plan as a script: renames in the order the tool runs them, then
+/// deletions.
+pub fn render(shell: Shell, renames: &[(PathBuf, PathBuf)], deletes: &[PathBuf]) -> String {
+    let steps = rename_order::order(renames);
+    let mut script = format!("{}# Generated by ebook-renamer on {}\n", shell.header(), Local::now().format("%Y-%m-%d %H:%M:%S"));
+    script.push_str(&created_dirs(&steps).iter().map(|dir| shell.mkdir(dir)).collect::<String>());
+    for step in &steps {
+        script.push_str(&shell.mv(&step.from, &step.to));
+    }
+    for path in deletes {
+        script.push_str(&shell.rm(path));
+    }
+    script
+}
+
+/// Renames run backwards. Deleted files cannot be brought back; they are
+/// listed as comments.
+pub fn render_undo(shell: Shell, renames: &[(PathBuf, PathBuf)], deletes: &[PathBuf]) -> String {
+    let steps = rename_order::order(renames);
+    let mut script = format!("{}# Undoes the script generated by ebook-renamer on {}\n", shell.header(), Local::now().format("%Y-%m-%d %H:%M:%S"));
+    for step in steps.iter().rev() {
+        script.push_str(&shell.mv(&step.to, &step.from));
+    }
+    for dir in created_dirs(&steps).iter().rev() {
+        script.push_str(&shell.rmdir(dir));
+    }
+    for path in deletes {
+        script.push_str(&format!("# Deleted, cannot be restored: {}\n", path.display()));
+    }
+    script
+}
+
+/// Folders the renames move files into, e.g. for templates with a `/`.
+fn created_dirs(steps: &[RenameStep]) -> BTreeSet<PathBuf> {
+    steps
+        .iter()
+        .filter_map(|step| Some((step.from.parent()?, step.to.parent()?)))
+        .filter(|(from, to)| from != to && !to.exists())
+        .map(|(_, to)| to.to_path_buf())
+        .collect()
+}
+
+/// Write `path` and its PowerShell variant next to it, plus `undo.sh` and
+/// `undo.ps1` in the same folder. Returns the files written.
+pub fn write(path: &Path, renames: &[(PathBuf, PathBuf)], deletes: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    for shell in [Shell::Posix, Shell::PowerShell] {
+        let script = path.with_extension(shell.extension());
+        let undo = path.with_file_name(format!("undo.{}", shell.extension()));
+        fs::write(&script, render(shell, renames, deletes))?;
+        fs::write(&undo, render_undo(shell, renames, deletes))?;
+        written.extend([script, undo]);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        for script in written.iter().filter(|p| p.extension().is_some_and(|e| e == "sh")) {
+            fs::set_permissions(script, fs::Permissions::from_mode(0o755))?;
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path();
+        let p = |name: &str| dir.join(name);
+        for name in ["a.pdf", "b.pdf", "Lang's Algebra.pdf", "dupe.pdf"] {
+            fs::write(p(name), name)?;
+        }
+        let renames = vec![
+            (p("a.pdf"), p("b.pdf")),
+            (p("b.pdf"), p("a.pdf")),
+            (p("Lang's Algebra.pdf"), p("Lang/Algebra.pdf")),
+        ];
+        let deletes = vec![p("dupe.pdf")];
+
+        let script = render(Shell::Posix, &renames, &deletes);
+        assert!(script.contains(&format!("mkdir -p -- '{}'\n", p("Lang").display())));
+        assert!(script.contains(&format!("move '{}' ", dir.join("Lang'\\''s Algebra.pdf").display())));
+        let powershell = render(Shell::PowerShell, &renames, &deletes);
+        assert!(powershell.contains(&format!("Remove-Item -LiteralPath '{}'\n", p("dupe.pdf").display())));
+        assert!(powershell.contains("Lang''s Algebra.pdf"));
+
+        #[cfg(unix)]
+        {
+            let written = write(&p("rename.sh"), &renames, &deletes)?;
+            assert_eq!(written.len(), 4);
+            let run = |script: &str| std::process::Command::new("sh").arg(p(script)).status().map(|s| s.success());
+            assert!(run("rename.sh")?);
+            assert_eq!(fs::read_to_string(p("a.pdf"))?, "b.pdf");
+            assert_eq!(fs::read_to_string(p("Lang/Algebra.pdf"))?, "Lang's Algebra.pdf");
+            assert!(!p("dupe.pdf").exists());
+            assert!(run("undo.sh")?);
+            assert_eq!(fs::read_to_string(p("a.pdf"))?, "a.pdf");
+            assert_eq!(fs::read_to_string(p("Lang's Algebra.pdf"))?, "Lang's Algebra.pdf");
+            assert!(!p("Lang").exists());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// Comparison key for names and titles: lowercase, without diacritics,
+/// apostrophes or quotes, other punctuation collapsed to single spaces. So
+/// "Galois' Théorie", "Galois’ Theorie" and "galois theorie" compare equal.
+///
+/// Only for comparing; displayed and generated names keep their spelling.
+pub fn fold(s: &str) -> String {
+    let mut folded = String::with_capacity(s.len());
+    // NFKD splits "é" into "e" + accent and maps full-width letters to ASCII
+    for c in s.nfkd().filter(|c| !is_combining_mark(*c)).flat_map(char::to_lowercase) {
+        match c {
+            '\'' | '’' | '‘' | 'ʼ' | '`' | '´' | '"' | '“' | '”' | '„' | '«' | '»' => {}
+            'ß' => folded.push_str("ss"),
+            'æ' => folded.push_str("ae"),
+            'œ' => folded.push_str("oe"),
+            'þ' => folded.push_str("th"),
+            'ø' => folded.push('o'),
+            'ł' => folded.push('l'),
+            'đ' | 'ð' => folded.push('d'),
+            'ı' => folded.push('i'),
+            c if c.is_alphanumeric() => folded.push(c),
+            _ => folded.push(' '),
+        }
+    }
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold("Galois' Théorie"), "galois theorie");
+        assert_eq!(fold("Galois’ Theorie"), "galois theorie");
+        assert_eq!(fold("Galois Theorie"), "galois theorie");
+        assert_eq!(fold("Paul Erdős"), "paul erdos");
+        assert_eq!(fold("Gel'fand, I. M."), "gelfand i m");
+        assert_eq!(fold("Łojasiewicz — Straße"), "lojasiewicz strasse");
+        assert_eq!(fold("Ｆｕｌｌ　Ｗｉｄｔｈ"), "full width");
+        assert_eq!(fold("代数学 (第二版)"), "代数学 第二版");
+    }
+}
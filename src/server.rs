@@ -0,0 +1,446 @@
+use crate::error::Error;
+use crate::i18n::t;
+use crate::run_lock::RunLock;
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Rename,
+    /// A duplicate copy; `keep` is the copy that stays
+    DeleteDuplicate,
+    /// A failed download, too-small or corrupted file
+    Delete,
+}
+
+/// One step of a plan, as offered for approval.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Operation {
+    pub id: usize,
+    pub kind: OperationKind,
+    /// Relative to the target directory, like in the JSON output
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep: Option<String>,
+    pub approved: bool,
+}
+
+struct Plan {
+    root: PathBuf,
+    operations: Vec<Operation>,
+    /// The dry run's JSON output, for everything that is not an operation
+    output: Value,
+}
+
+impl Plan {
+    /// Operations from the dry run's `renames`, `duplicate_deletes` and
+    /// `small_or_corrupted_deletes`, none approved yet.
+    fn from_output(root: PathBuf, output: Value) -> Self {
+        let mut operations = Vec::new();
+        let mut push = |kind, path: &Value, to: Option<&Value>, keep: Option<&Value>| {
+            let text = |v: &Value| v.as_str().unwrap_or_default().to_string();
+            operations.push(Operation {
+                id: operations.len() + 1,
+                kind,
+                path: text(path),
+                to: to.map(text),
+                keep: keep.map(text),
+                approved: false,
+            });
+        };
+        let list = |key: &str| output.get(key).and_then(Value::as_array).cloned().unwrap_or_default();
+        for group in list("duplicate_deletes") {
+            for path in group["delete"].as_array().into_iter().flatten() {
+                push(OperationKind::DeleteDuplicate, path, None, Some(&group["keep"]));
+            }
+        }
+        for delete in list("small_or_corrupted_deletes") {
+            push(OperationKind::Delete, &delete["path"], None, None);
+        }
+        for rename in list("renames").iter().filter(|r| r["from"] != r["to"]) {
+            push(OperationKind::Rename, &rename["from"], Some(&rename["to"]), None);
+        }
+        Self { root, operations, output }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({ "root": self.root, "operations": self.operations, "plan": self.output })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(SERVER_ERROR, format!("{:#}", e))
+    }
+}
+
+/// One client connection's state: the plan of the last scan.
+#[derive(Default)]
+pub struct Session {
+    plan: Option<Plan>,
+    shutdown: bool,
+}
+
+impl Session {
+    /// Serve requests, one JSON object per line, until the client
+    /// disconnects or calls `shutdown`. `scan` streams `progress` and `log`
+    /// notifications before its response.
+    pub fn serve(&mut self, reader: impl Read, mut writer: impl Write) -> Result<()> {
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle(&line, &mut |notification| {
+                // A client that stopped reading only loses notifications
+                let _ = writeln!(writer, "{}", notification).and_then(|_| writer.flush());
+            }) {
+                writeln!(writer, "{}", response)?;
+                writer.flush()?;
+            }
+            if self.shutdown {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// The response to one request line; `None` for notifications (no `id`).
+    fn handle(&mut self, line: &str, notify: &mut dyn FnMut(Value)) -> Option<Value> {
+        let request: Request = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string()))),
+        };
+        debug!("RPC {}", request.method);
+        let result = self.dispatch(&request.method, &request.params, notify);
+        let id = request.id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => error_response(id, e),
+        })
+    }
+
+    fn dispatch(&mut self, method: &str, params: &Value, notify: &mut dyn FnMut(Value)) -> Result<Value, RpcError> {
+        match method {
+            "scan" => {
+                let path = params["path"].as_str().ok_or_else(|| RpcError::new(INVALID_PARAMS, "scan needs a path"))?;
+                let args: Vec<String> = match &params["args"] {
+                    Value::Null => Vec::new(),
+                    args => serde_json::from_value(args.clone()).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?,
+                };
+                let root = Path::new(path).canonicalize().map_err(|e| RpcError::new(INVALID_PARAMS, format!("{}: {}", path, e)))?;
+                let output = dry_run(&root, &args, notify)?;
+                let plan = Plan::from_output(root, output);
+                let result = plan.to_json();
+                self.plan = Some(plan);
+                Ok(result)
+            }
+            "plan" => Ok(self.plan()?.to_json()),
+            "approve" | "reject" => {
+                let ids: Option<Vec<usize>> = match &params["ids"] {
+                    Value::Null => None,
+                    ids => Some(serde_json::from_value(ids.clone()).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?),
+                };
+                let approved = method == "approve";
+                let plan = self.plan_mut()?;
+                for operation in &mut plan.operations {
+                    if ids.as_ref().is_none_or(|ids| ids.contains(&operation.id)) {
+                        operation.approved = approved;
+                    }
+                }
+                Ok(json!({ "approved": plan.operations.iter().filter(|o| o.approved).count() }))
+            }
+            "execute" => {
+                let plan = self.plan.take().ok_or_else(no_plan)?;
+                let results = execute(&plan)?;
+                Ok(json!({ "results": results }))
+            }
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("Unknown method {}", method))),
+        }
+    }
+
+    fn plan(&self) -> Result<&Plan, RpcError> {
+        self.plan.as_ref().ok_or_else(no_plan)
+    }
+
+    fn plan_mut(&mut self) -> Result<&mut Plan, RpcError> {
+        self.plan.as_mut().ok_or_else(no_plan)
+    }
+}
+
+fn no_plan() -> RpcError {
+    RpcError::new(SERVER_ERROR, "No plan: call scan first")
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": error.code, "message": error.message } })
+}
+
+/// Plan with the normal pipeline in a `--read-only --json` child, so a scan
+/// sees exactly the flags and config a command-line run would. Progress and
+/// log lines from its stderr are passed on as notifications.
+fn dry_run(root: &Path, args: &[String], notify: &mut dyn FnMut(Value)) -> Result<Value> {
+    let mut child = Command::new(std::env::current_exe()?)
+        .args(args)
+        .args(["--read-only", "--json", "--progress-json"])
+        .arg(root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdout = child.stdout.take().ok_or_else(|| anyhow!("No stdout from the scan"))?;
+    let reader = std::thread::spawn(move || -> std::io::Result<String> {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output)?;
+        Ok(output)
+    });
+    let stderr = child.stderr.take().ok_or_else(|| anyhow!("No stderr from the scan"))?;
+    for line in BufReader::new(stderr).lines() {
+        let line = line?;
+        match serde_json::from_str::<Value>(&line) {
+            Ok(progress) if progress.get("walked").is_some() => {
+                notify(json!({ "jsonrpc": "2.0", "method": "progress", "params": progress }))
+            }
+            _ => notify(json!({ "jsonrpc": "2.0", "method": "log", "params": { "message": line } })),
+        }
+    }
+    let status = child.wait()?;
+    let output = reader.join().map_err(|_| anyhow!("Scan output reader panicked"))??;
+    let output: Value = serde_json::from_str(&output).map_err(|e| anyhow!("Unreadable scan output: {}", e))?;
+    if !status.success() {
+        let message = output["error"].as_str().unwrap_or("scan failed");
+        return Err(anyhow!("{}", message));
+    }
+    Ok(output)
+}
+
+#[derive(Debug, Serialize)]
+struct OperationResult {
+    id: usize,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Run the approved operations: deletions first, while every kept copy
+/// still has the path the plan names, then renames. Each operation is
+/// checked against the disk first and skipped with an error if the plan no
+/// longer fits.
+fn execute(plan: &Plan) -> Result<Vec<OperationResult>> {
+    let _lock = RunLock::acquire(&plan.root, false)?;
+    let mut approved: Vec<&Operation> = plan.operations.iter().filter(|o| o.approved).collect();
+    approved.sort_by_key(|o| o.kind == OperationKind::Rename);
+
+    let mut results = Vec::new();
+    for operation in approved {
+        let result = execute_one(&plan.root, operation);
+        match &result {
+            Ok(()) => info!("Executed {:?} {}", operation.kind, operation.path),
+            Err(e) => warn!("Skipped {:?} {}: {}", operation.kind, operation.path, e),
+        }
+        results.push(OperationResult { id: operation.id, ok: result.is_ok(), error: result.err().map(|e| e.to_string()) });
+    }
+    Ok(results)
+}
+
+fn execute_one(root: &Path, operation: &Operation) -> Result<()> {
+    let path = root.join(&operation.path);
+    if !path.is_file() {
+        return Err(anyhow!("{} is gone", operation.path));
+    }
+    match operation.kind {
+        OperationKind::Rename => {
+            let to = operation.to.as_deref().ok_or_else(|| anyhow!("rename without a target"))?;
+            let target = root.join(to);
+            // A case-only rename on a case-insensitive disk finds the file itself
+            if target.exists() && !same_file(&path, &target) {
+                return Err(anyhow!("{} already exists", to));
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(&path, &target)?;
+        }
+        OperationKind::DeleteDuplicate => {
+            let keep = operation.keep.as_deref().ok_or_else(|| anyhow!("duplicate without a kept copy"))?;
+            if !root.join(keep).is_file() {
+                return Err(anyhow!("kept copy {} is missing", keep));
+            }
+            fs::remove_file(&path)?;
+        }
+        OperationKind::Delete => fs::remove_file(&path)?,
+    }
+    Ok(())
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Accept connections one at a time on a Unix socket (`socket`) or on
+/// 127.0.0.1:`port`, until a client calls `shutdown`.
+pub fn run(socket: Option<&Path>, port: Option<u16>) -> Result<()> {
+    match (socket, port) {
+        (Some(socket), _) => serve_unix(socket),
+        (None, Some(port)) => {
+            let listener = std::net::TcpListener::bind(("127.0.0.1", port))?;
+            info!("Listening on {}", listener.local_addr()?);
+            eprintln!("{}", t!("server-listening", address = listener.local_addr()?.to_string()));
+            for stream in listener.incoming() {
+                let stream = stream?;
+                let mut session = Session::default();
+                if let Err(e) = session.serve(stream.try_clone()?, stream) {
+                    warn!("Connection ended: {}", e);
+                }
+                if session.shutdown {
+                    break;
+                }
+            }
+            Ok(())
+        }
+        (None, None) => Err(Error::Usage("serve needs --socket or --port".to_string()).into()),
+    }
+}
+
+#[cfg(unix)]
+fn serve_unix(socket: &Path) -> Result<()> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    // A socket file nobody answers on is left over from a crashed server
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(Error::Usage(format!("{} is in use by another server", socket.display())).into());
+        }
+        fs::remove_file(socket)?;
+    }
+    let listener = UnixListener::bind(socket)?;
+    info!("Listening on {}", socket.display());
+    eprintln!("{}", t!("server-listening", address = socket.display().to_string()));
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let mut session = Session::default();
+        if let Err(e) = session.serve(stream.try_clone()?, stream) {
+            warn!("Connection ended: {}", e);
+        }
+        if session.shutdown {
+            break;
+        }
+    }
+    fs::remove_file(socket)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve_unix(_socket: &Path) -> Result<()> {
+    Err(Error::Usage("Unix sockets are not available on this platform, use --port".to_string()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn session_with_plan(root: &Path) -> Session {
+        let output = json!({
+            "renames": [{"from": "lang.pdf", "to": "Math/Lang - Algebra.pdf", "reason": "normalized"}],
+            "duplicate_deletes": [{"keep": "lang.pdf", "delete": ["copy.pdf"]}],
+            "small_or_corrupted_deletes": [{"path": "tiny.pdf", "issue": "deleted"}],
+            "todo_items": []
+        });
+        Session { plan: Some(Plan::from_output(root.to_path_buf(), output)), shutdown: false }
+    }
+
+    fn call(session: &mut Session, request: &str) -> Value {
+        session.handle(request, &mut |_| {}).unwrap()
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        let mut session = Session::default();
+        assert_eq!(call(&mut session, "{not json")["error"]["code"], PARSE_ERROR);
+        assert_eq!(call(&mut session, r#"{"jsonrpc":"2.0","id":1,"method":"frobnicate"}"#)["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(call(&mut session, r#"{"jsonrpc":"2.0","id":2,"method":"plan"}"#)["error"]["code"], SERVER_ERROR);
+        assert_eq!(call(&mut session, r#"{"jsonrpc":"2.0","id":3,"method":"scan","params":{}}"#)["error"]["code"], INVALID_PARAMS);
+        // Notifications get no response
+        assert!(session.handle(r#"{"jsonrpc":"2.0","method":"plan"}"#, &mut |_| {}).is_none());
+    }
+
+    #[test]
+    fn test_approve_and_execute() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        for name in ["lang.pdf", "copy.pdf", "tiny.pdf"] {
+            fs::write(root.join(name), name)?;
+        }
+        let mut session = session_with_plan(&root);
+
+        let plan = call(&mut session, r#"{"jsonrpc":"2.0","id":1,"method":"plan"}"#);
+        let kinds: Vec<&str> = plan["result"]["operations"].as_array().unwrap().iter().map(|o| o["kind"].as_str().unwrap()).collect();
+        assert_eq!(kinds, vec!["delete_duplicate", "delete", "rename"]);
+
+        let approved = call(&mut session, r#"{"jsonrpc":"2.0","id":2,"method":"approve"}"#);
+        assert_eq!(approved["result"]["approved"], 3);
+        let approved = call(&mut session, r#"{"jsonrpc":"2.0","id":3,"method":"reject","params":{"ids":[2]}}"#);
+        assert_eq!(approved["result"]["approved"], 2);
+
+        let executed = call(&mut session, r#"{"jsonrpc":"2.0","id":4,"method":"execute"}"#);
+        let oks: Vec<bool> = executed["result"]["results"].as_array().unwrap().iter().map(|r| r["ok"].as_bool().unwrap()).collect();
+        assert_eq!(oks, vec![true, true]);
+        assert!(!root.join("copy.pdf").exists());
+        assert!(root.join("tiny.pdf").exists());
+        assert!(root.join("Math/Lang - Algebra.pdf").exists());
+        // The plan is used up
+        assert_eq!(call(&mut session, r#"{"jsonrpc":"2.0","id":5,"method":"execute"}"#)["error"]["code"], SERVER_ERROR);
+        Ok(())
+    }
+
+    #[test]
+    fn test_serve_until_shutdown() -> Result<()> {
+        let input = "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"shutdown\"}\n{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"plan\"}\n";
+        let mut output = Vec::new();
+        let mut session = Session::default();
+        session.serve(input.as_bytes(), &mut output)?;
+        let lines: Vec<Value> = String::from_utf8(output)?.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines, vec![json!({"jsonrpc": "2.0", "id": 1, "result": null})]);
+        Ok(())
+    }
+}
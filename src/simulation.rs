@@ -0,0 +1,253 @@
+use crate::rename_order;
+use crate::scanner::FileInfo;
+use log::warn;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files that belong to a book by name (`Book.pdf.url`, `Book.opf`, `Book.jpg`)
+/// and stay behind when it is renamed or deleted.
+const SIDECAR_EXTENSIONS: &[&str] = &["url", "webloc", "opf", "jpg", "jpeg", "png", "nfo", "md5"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    /// The rename target exists at that point of the run
+    Overwrite,
+    /// The file is gone by the time the operation runs
+    MissingSource,
+    /// The duplicate's kept copy is gone by the time it is deleted
+    KeptCopyMissing,
+    /// A sidecar file would be left without its book
+    OrphanedSidecar,
+}
+
+impl IssueKind {
+    pub fn code(self) -> &'static str {
+        match self {
+            IssueKind::Overwrite => "overwrite",
+            IssueKind::MissingSource => "missing_source",
+            IssueKind::KeptCopyMissing => "kept_copy_missing",
+            IssueKind::OrphanedSidecar => "orphaned_sidecar",
+        }
+    }
+
+    /// Whether the operation is dropped from the plan.
+    pub fn is_blocking(self) -> bool {
+        self != IssueKind::OrphanedSidecar
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub kind: IssueKind,
+    /// The file the dropped or flagged operation was for
+    pub path: PathBuf,
+    /// Rename target, kept copy or sidecar
+    pub other: Option<PathBuf>,
+}
+
+impl Issue {
+    pub fn message(&self) -> String {
+        let other = self.other.as_deref().map(|p| p.display().to_string()).unwrap_or_default();
+        match self.kind {
+            IssueKind::Overwrite => format!("{} -> {}: target already exists, not renamed", self.path.display(), other),
+            IssueKind::MissingSource => format!("{}: no longer there when its turn comes, skipped", self.path.display()),
+            IssueKind::KeptCopyMissing => format!("{}: kept copy {} would be gone, not deleted", self.path.display(), other),
+            IssueKind::OrphanedSidecar => format!("{}: {} stays behind", self.path.display(), other),
+        }
+    }
+}
+
+/// The tree as the plan leaves it: paths the plan touched so far on top of
+/// the real filesystem, which is only read.
+#[derive(Debug, Default)]
+struct Model {
+    overlay: HashMap<PathBuf, bool>,
+    /// Where each original path ended up
+    moved: HashMap<PathBuf, PathBuf>,
+}
+
+impl Model {
+    fn exists(&self, path: &Path) -> bool {
+        self.overlay.get(path).copied().unwrap_or_else(|| path.exists())
+    }
+
+    fn rename(&mut self, from: &Path, to: &Path) {
+        self.overlay.insert(from.to_path_buf(), false);
+        self.overlay.insert(to.to_path_buf(), true);
+    }
+
+    fn remove(&mut self, path: &Path) {
+        self.overlay.insert(path.to_path_buf(), false);
+    }
+
+    fn is_untouched(&self, path: &Path) -> bool {
+        !self.overlay.contains_key(path)
+    }
+
+    fn current(&self, original: &Path) -> PathBuf {
+        self.moved.get(original).cloned().unwrap_or_else(|| original.to_path_buf())
+    }
+}
+
+/// Apply renames in the order `rename_order` runs them, then deletions, to
+/// the model and report what would go wrong. `deletes` pairs each file with
+/// the duplicate it is a copy of.
+pub fn simulate<P: AsRef<Path>>(renames: &[(P, P)], deletes: &[(P, Option<P>)]) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut model = Model::default();
+
+    let mut failed = HashSet::new();
+    // Only kept copies are looked up after a move
+    let kept: HashSet<&Path> = deletes.iter().filter_map(|(_, keep)| keep.as_ref().map(|keep| keep.as_ref())).collect();
+    for step in rename_order::order(renames) {
+        if failed.contains(&step.index) {
+            continue;
+        }
+        let (original, target) = (renames[step.index].0.as_ref(), renames[step.index].1.as_ref());
+        let kind = if !model.exists(&step.from) {
+            Some(IssueKind::MissingSource)
+        } else if model.exists(&step.to) && !(model.is_untouched(&step.to) && rename_order::is_same_file(&step.from, &step.to)) {
+            Some(IssueKind::Overwrite)
+        } else {
+            None
+        };
+        match kind {
+            Some(kind) => {
+                issues.push(Issue { kind, path: original.to_path_buf(), other: Some(target.to_path_buf()) });
+                failed.insert(step.index);
+            }
+            None => {
+                model.rename(&step.from, &step.to);
+                if step.is_final && kept.contains(original) {
+                    model.moved.insert(original.to_path_buf(), step.to.clone());
+                }
+            }
+        }
+    }
+
+    for (path, keep) in deletes {
+        let path = path.as_ref();
+        let keep = keep.as_ref().map(|keep| model.current(keep.as_ref()));
+        if !model.exists(path) {
+            issues.push(Issue { kind: IssueKind::MissingSource, path: path.to_path_buf(), other: None });
+        } else if let Some(keep) = keep.filter(|keep| !model.exists(keep) || keep == path) {
+            issues.push(Issue { kind: IssueKind::KeptCopyMissing, path: path.to_path_buf(), other: Some(keep) });
+        } else {
+            model.remove(path);
+        }
+    }
+
+    let touched = renames.iter().map(|(from, _)| from.as_ref()).chain(deletes.iter().map(|(path, _)| path.as_ref()));
+    for path in touched.filter(|p| !model.exists(p)) {
+        for sidecar in sidecars(path) {
+            issues.push(Issue { kind: IssueKind::OrphanedSidecar, path: path.to_path_buf(), other: Some(sidecar) });
+        }
+    }
+    issues
+}
+
+/// Run `simulate` on the plan and drop every operation that would fail,
+/// until the rest goes through cleanly. Returns all issues found.
+pub fn validate(files: &mut [FileInfo], duplicate_groups: &mut Vec<Vec<PathBuf>>, files_to_delete: &mut Vec<PathBuf>) -> Vec<Issue> {
+    let mut found = Vec::new();
+    loop {
+        // Borrowed, the plan is not copied on every pass
+        let renames: Vec<(&Path, &Path)> = files
+            .iter()
+            .filter(|f| f.new_name.is_some() && f.new_path != f.original_path)
+            .map(|f| (f.original_path.as_path(), f.new_path.as_path()))
+            .collect();
+        let deletes: Vec<(&Path, Option<&Path>)> = duplicate_groups
+            .iter()
+            .flat_map(|group| group.iter().skip(1).map(|path| (path.as_path(), Some(group[0].as_path()))))
+            .chain(files_to_delete.iter().map(|path| (path.as_path(), None)))
+            .collect();
+
+        let (blocking, other): (Vec<Issue>, Vec<Issue>) =
+            simulate(&renames, &deletes).into_iter().partition(|issue| issue.kind.is_blocking());
+        if blocking.is_empty() {
+            found.extend(other);
+            return found;
+        }
+        for issue in &blocking {
+            warn!("Plan check: {}", issue.message());
+            let dropped_rename = files
+                .iter_mut()
+                .find(|f| f.original_path == issue.path && f.new_path != f.original_path && issue.kind != IssueKind::KeptCopyMissing);
+            if let Some(file_info) = dropped_rename {
+                file_info.new_name = None;
+                file_info.new_path = file_info.original_path.clone();
+                continue;
+            }
+            for group in duplicate_groups.iter_mut() {
+                if let Some(idx) = group.iter().skip(1).position(|p| *p == issue.path) {
+                    group.remove(idx + 1);
+                }
+            }
+            files_to_delete.retain(|p| *p != issue.path);
+        }
+        duplicate_groups.retain(|group| group.len() > 1);
+        found.extend(blocking);
+    }
+}
+
+fn sidecars(book: &Path) -> Vec<PathBuf> {
+    let (Some(parent), Some(name), Some(stem)) = (book.parent(), book.file_name(), book.file_stem()) else {
+        return Vec::new();
+    };
+    let (name, stem) = (name.to_string_lossy(), stem.to_string_lossy());
+    let Ok(entries) = fs::read_dir(parent) else {
+        return Vec::new();
+    };
+    let mut found: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let Some(extension) = path.extension().map(|e| e.to_string_lossy().to_lowercase()) else {
+                return false;
+            };
+            let sidecar_stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            SIDECAR_EXTENSIONS.contains(&extension.as_str()) && (sidecar_stem == name || sidecar_stem == stem)
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_simulate() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path();
+        for name in ["a.pdf", "b.pdf", "c.pdf", "d.pdf", "e.pdf", "Taken.pdf", "e.pdf.url"] {
+            fs::write(dir.join(name), "x")?;
+        }
+        let p = |name: &str| dir.join(name);
+        let renames = vec![
+            (p("a.pdf"), p("b.pdf")),
+            (p("b.pdf"), p("a.pdf")),
+            (p("c.pdf"), p("Taken.pdf")),
+            (p("d.pdf"), p("New.pdf")),
+            (p("e.pdf"), p("E.pdf")),
+        ];
+        let deletes = vec![(p("Taken.pdf"), Some(p("d.pdf"))), (p("gone.pdf"), None)];
+
+        let issues: Vec<(IssueKind, String)> = simulate(&renames, &deletes)
+            .iter()
+            .map(|i| (i.kind, i.path.file_name().unwrap().to_string_lossy().to_string()))
+            .collect();
+        // The a/b swap goes through with a temporary name
+        assert_eq!(issues, vec![
+            (IssueKind::Overwrite, "c.pdf".to_string()),
+            (IssueKind::MissingSource, "gone.pdf".to_string()),
+            (IssueKind::OrphanedSidecar, "e.pdf".to_string()),
+        ]);
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+use crate::duplicates;
+use crate::i18n::t;
+use crate::sync_conflicts;
+use crate::scanner::FileInfo;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Why a scanned file gets no rename and no deletion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// Not a `.pdf`, `.epub` or `.txt`
+    UnsupportedExtension,
+    /// Google Docs/Dropbox Paper shortcut
+    NativeDocument,
+    /// `.download`/`.crdownload` left on the todo list
+    FailedDownload,
+    /// Under `--min-valid-size` (1KB for ebooks) and left on the todo list
+    TooSmall,
+    /// Chapter or book-matter part of a split book
+    ChapterPart,
+    /// The folder is read-only
+    ReadOnlyFolder,
+    /// Metadata read or hash exceeded `--io-timeout`
+    IoTimeout,
+    /// `--no-normalize`
+    NormalizeDisabled,
+    /// The name is already what the normalizer would produce
+    AlreadyNormalized,
+    /// `.tmp`/`.partial` file a sync client is still writing
+    SyncTemporary,
+    /// Conflicted copy whose content differs from its base file
+    SyncConflict,
+    /// Changed during `--settle` or open for writing elsewhere
+    InUse,
+    /// `(1)`/`copy`/`副本` variant whose content differs from its base file
+    NameConflict,
+    /// The plan check found the rename would overwrite a file
+    RenameConflict,
+    /// A web page saved in place of the download, left on the todo list
+    HtmlStub,
+}
+
+impl SkipReason {
+    /// Stable code used in JSON output.
+    pub fn code(self) -> &'static str {
+        match self {
+            SkipReason::UnsupportedExtension => "unsupported_extension",
+            SkipReason::NativeDocument => "native_document",
+            SkipReason::FailedDownload => "failed_download",
+            SkipReason::TooSmall => "too_small",
+            SkipReason::ChapterPart => "chapter_part",
+            SkipReason::ReadOnlyFolder => "read_only_folder",
+            SkipReason::IoTimeout => "io_timeout",
+            SkipReason::NormalizeDisabled => "normalize_disabled",
+            SkipReason::AlreadyNormalized => "already_normalized",
+            SkipReason::SyncTemporary => "sync_temporary",
+            SkipReason::SyncConflict => "sync_conflict",
+            SkipReason::InUse => "in_use",
+            SkipReason::NameConflict => "name_conflict",
+            SkipReason::RenameConflict => "rename_conflict",
+            SkipReason::HtmlStub => "html_stub",
+        }
+    }
+
+    /// Shown in the console and TUI, in the output language.
+    pub fn description(self) -> String {
+        t!(&format!("skip-{}", self.code().replace('_', "-")))
+    }
+}
+
+/// Skipped files and the first reason recorded for each, in path order.
+#[derive(Debug, Default)]
+pub struct SkipList {
+    entries: BTreeMap<PathBuf, SkipReason>,
+}
+
+impl SkipList {
+    /// Earlier stages win: a file is only reported with the first reason.
+    pub fn add(&mut self, path: &Path, reason: SkipReason) {
+        self.entries.entry(path.to_path_buf()).or_insert(reason);
+    }
+
+    pub fn add_all<'a>(&mut self, paths: impl IntoIterator<Item = &'a PathBuf>, reason: SkipReason) {
+        for path in paths {
+            self.add(path, reason);
+        }
+    }
+
+    /// Failed downloads and small files that stay on disk, and files duplicate
+    /// detection drops for their extension.
+    pub fn add_unprocessable(&mut self, files: &[FileInfo], deleting_broken: bool) {
+        for file_info in files {
+            let reason = if file_info.is_failed_download {
+                SkipReason::FailedDownload
+            } else if file_info.is_too_small {
+                SkipReason::TooSmall
+            } else if sync_conflicts::is_temporary(file_info) {
+                SkipReason::SyncTemporary
+            } else if !duplicates::is_allowed_extension(&file_info.extension) {
+                SkipReason::UnsupportedExtension
+            } else {
+                continue;
+            };
+            // Broken files that get deleted are not skipped
+            if deleting_broken && matches!(reason, SkipReason::FailedDownload | SkipReason::TooSmall) {
+                continue;
+            }
+            self.add(&file_info.original_path, reason);
+        }
+    }
+
+    /// Files left without a name change once the plan is final.
+    pub fn add_unchanged(&mut self, files: &[FileInfo], normalize_disabled: bool) {
+        for file_info in files {
+            let renamed = file_info.new_name.is_some() && file_info.new_path != file_info.original_path;
+            if renamed || file_info.is_failed_download || file_info.is_too_small {
+                continue;
+            }
+            let reason = if normalize_disabled && file_info.new_name.is_none() {
+                SkipReason::NormalizeDisabled
+            } else {
+                SkipReason::AlreadyNormalized
+            };
+            self.add(&file_info.original_path, reason);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&PathBuf, SkipReason)> {
+        self.entries.iter().map(|(path, reason)| (path, *reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+    use anyhow::Result;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_skip_reasons() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        fs::write(tmp_dir.path().join("cover.jpg"), vec![b'x'; 2048])?;
+        fs::write(tmp_dir.path().join("tiny.pdf"), "x")?;
+        fs::write(tmp_dir.path().join("Jane Quux - Algebra (2002).pdf"), vec![b'x'; 2048])?;
+        fs::write(tmp_dir.path().join("messy_name_2002.pdf"), vec![b'x'; 2048])?;
+        let mut files = Scanner::new(tmp_dir.path(), 1)?.scan()?;
+        for file_info in &mut files {
+            if file_info.original_name.starts_with("messy") {
+                file_info.new_name = Some("Messy Name (2002).pdf".to_string());
+                file_info.new_path = file_info.original_path.with_file_name("Messy Name (2002).pdf");
+            }
+        }
+
+        let mut skipped = SkipList::default();
+        skipped.add_unprocessable(&files, false);
+        skipped.add_unchanged(&files, false);
+        let reasons: Vec<(String, &str)> = skipped
+            .iter()
+            .map(|(path, reason)| (path.file_name().unwrap().to_string_lossy().to_string(), reason.code()))
+            .collect();
+        assert_eq!(reasons, vec![
+            ("Jane Quux - Algebra (2002).pdf".to_string(), "already_normalized"),
+            ("cover.jpg".to_string(), "unsupported_extension"),
+            ("tiny.pdf".to_string(), "too_small"),
+        ]);
+
+        // Deleted with --delete-small, so not skipped
+        let mut deleting = SkipList::default();
+        deleting.add_unprocessable(&files, true);
+        assert_eq!(deleting.len(), 1);
+        Ok(())
+    }
+}
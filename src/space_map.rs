@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A duplicate group ranked by the bytes deleting its extra copies frees.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateSpace {
+    pub keep: PathBuf,
+    pub copies: usize,
+    pub reclaimable: u64,
+}
+
+/// A folder ranked by the size of the files directly inside it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectorySpace {
+    pub path: PathBuf,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Where the space goes, for `--top N`.
+#[derive(Debug, Default)]
+pub struct SpaceMap {
+    pub largest_files: Vec<(PathBuf, u64)>,
+    pub duplicate_groups: Vec<DuplicateSpace>,
+    pub directories: Vec<DirectorySpace>,
+}
+
+/// The `top` largest entries of each kind. Ties are broken by path so the
+/// output is stable between runs.
+pub fn build(sizes: &HashMap<PathBuf, u64>, duplicate_groups: &[Vec<PathBuf>], top: usize) -> SpaceMap {
+    let mut largest_files: Vec<(PathBuf, u64)> = sizes.iter().map(|(path, size)| (path.clone(), *size)).collect();
+    largest_files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    largest_files.truncate(top);
+
+    let mut groups: Vec<DuplicateSpace> = duplicate_groups
+        .iter()
+        .filter(|group| group.len() > 1)
+        .map(|group| DuplicateSpace {
+            keep: group[0].clone(),
+            copies: group.len() - 1,
+            reclaimable: group.iter().skip(1).filter_map(|p| sizes.get(p)).sum(),
+        })
+        .collect();
+    groups.sort_by(|a, b| b.reclaimable.cmp(&a.reclaimable).then_with(|| a.keep.cmp(&b.keep)));
+    groups.truncate(top);
+
+    let mut by_dir: HashMap<&Path, (usize, u64)> = HashMap::new();
+    for (path, size) in sizes {
+        let entry = by_dir.entry(path.parent().unwrap_or(Path::new(""))).or_default();
+        entry.0 += 1;
+        entry.1 += size;
+    }
+    let mut directories: Vec<DirectorySpace> = by_dir
+        .into_iter()
+        .map(|(path, (files, bytes))| DirectorySpace { path: path.to_path_buf(), files, bytes })
+        .collect();
+    directories.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+    directories.truncate(top);
+
+    SpaceMap { largest_files, duplicate_groups: groups, directories }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_ranks_by_size() {
+        let root = PathBuf::from("/library");
+        let sizes: HashMap<PathBuf, u64> = [
+            ("Big.pdf", 900),
+            ("Small.pdf", 100),
+            ("Math/Algebra.pdf", 500),
+            ("Math/Algebra (1).pdf", 500),
+            ("Math/Topology.epub", 300),
+            ("Math/Topology copy.epub", 300),
+            ("Math/Topology copy 2.epub", 300),
+        ]
+        .into_iter()
+        .map(|(name, size)| (root.join(name), size))
+        .collect();
+        let groups = vec![
+            vec![root.join("Math/Algebra.pdf"), root.join("Math/Algebra (1).pdf")],
+            vec![root.join("Math/Topology.epub"), root.join("Math/Topology copy.epub"), root.join("Math/Topology copy 2.epub")],
+        ];
+
+        let map = build(&sizes, &groups, 2);
+        assert_eq!(map.largest_files, vec![(root.join("Big.pdf"), 900), (root.join("Math/Algebra (1).pdf"), 500)]);
+        assert_eq!(map.duplicate_groups[0], DuplicateSpace { keep: root.join("Math/Topology.epub"), copies: 2, reclaimable: 600 });
+        assert_eq!(map.duplicate_groups[1].reclaimable, 500);
+        assert_eq!(map.directories, vec![
+            DirectorySpace { path: root.join("Math"), files: 5, bytes: 1900 },
+            DirectorySpace { path: root.clone(), files: 2, bytes: 1000 },
+        ]);
+    }
+}
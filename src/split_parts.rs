@@ -0,0 +1,297 @@
+use crate::normalizer::{self, NormalizeOptions};
+use crate::scanner::FileInfo;
+use anyhow::{anyhow, Result};
+use log::{debug, info};
+use lopdf::{dictionary, Document, Object, ObjectId};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Parts of merged sets go here, hidden so later scans skip them.
+pub const QUARANTINE_DIR: &str = ".ebook-renamer-parts";
+
+// "Title part1", "Title - Part 2 of 3", "Title_pt.3", "Title (Teil 1)"
+static RE_PART: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(.+?)(?:[\s._-]+[(\[]?|[(\[])\s*(?:part|pt|teil)[\s._-]*(\d{1,2})(?:\s*(?:of|/)\s*(\d{1,2}))?\s*[)\]]?$").unwrap()
+});
+// "Title (1 of 3)", "Title [2/3]"
+static RE_OF: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^(.+?)[\s._-]*[(\[]\s*(\d{1,2})\s*(?:of|/)\s*(\d{1,2})\s*[)\]]$").unwrap());
+// "Title pp. 1-120", "Title (pages 121-240)"
+static RE_PAGES: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(.+?)(?:[\s._-]+[(\[]?|[(\[])\s*(?:pp?\.?|pages?)[\s._]*(\d{1,5})\s*[-–]\s*(\d{1,5})\s*[)\]]?$").unwrap()
+});
+
+// Files with the same folder and lowercased base name: the base as first
+// seen, and each file's marker
+type Candidates<'a> = BTreeMap<(PathBuf, String), (String, Vec<(Marker, &'a FileInfo)>)>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Marker {
+    Part { number: u32, total: Option<u32> },
+    Pages { first: u32, last: u32 },
+}
+
+fn marker(stem: &str) -> Option<(String, Marker)> {
+    let number = |caps: &regex::Captures, i| caps.get(i).and_then(|m| m.as_str().parse().ok());
+    if let Some(caps) = RE_PAGES.captures(stem) {
+        let (first, last) = (number(&caps, 2)?, number(&caps, 3)?);
+        return (first <= last).then(|| (base(&caps[1]), Marker::Pages { first, last }));
+    }
+    let caps = RE_PART.captures(stem).or_else(|| RE_OF.captures(stem))?;
+    Some((base(&caps[1]), Marker::Part { number: number(&caps, 2)?, total: number(&caps, 3) }))
+}
+
+fn base(name: &str) -> String {
+    name.trim_end_matches(|c: char| c.is_whitespace() || "-_.,".contains(c)).to_string()
+}
+
+/// PDFs that are parts of one book, in reading order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartSet {
+    pub parts: Vec<PathBuf>,
+    /// The name, without the part markers, the merged PDF gets
+    pub merged_name: String,
+}
+
+impl PartSet {
+    pub fn merged_path(&self) -> PathBuf {
+        self.parts[0].with_file_name(&self.merged_name)
+    }
+}
+
+/// Sets of PDFs in one folder whose names differ only in a part number
+/// (numbered from 1 without gaps, agreeing on the total where given) or
+/// in page ranges that start at 1 and continue each other.
+pub fn detect(files: &[FileInfo], options: &NormalizeOptions) -> Vec<PartSet> {
+    let mut candidates: Candidates = BTreeMap::new();
+    for file in files {
+        if !file.extension.eq_ignore_ascii_case(".pdf") || file.is_failed_download || file.is_too_small {
+            continue;
+        }
+        let stem = file.original_name.strip_suffix(&file.extension).unwrap_or(&file.original_name);
+        if let Some((base, marker)) = marker(stem) {
+            let dir = file.original_path.parent().unwrap_or(Path::new("")).to_path_buf();
+            candidates.entry((dir, base.to_lowercase())).or_insert_with(|| (base, Vec::new())).1.push((marker, file));
+        }
+    }
+
+    let mut sets = Vec::new();
+    for (base, mut parts) in candidates.into_values() {
+        if parts.len() < 2 || !is_sequence(&mut parts) {
+            continue;
+        }
+        let name = format!("{}.pdf", base);
+        let merged_name = normalizer::normalized_name(&name, ".pdf", options).unwrap_or(name);
+        debug!("Split into {} parts: {}", parts.len(), merged_name);
+        sets.push(PartSet { parts: parts.iter().map(|(_, f)| f.original_path.clone()).collect(), merged_name });
+    }
+    sets
+}
+
+/// Sort the parts into reading order and check they form a complete set.
+fn is_sequence(parts: &mut [(Marker, &FileInfo)]) -> bool {
+    parts.sort_by_key(|(marker, _)| match marker {
+        Marker::Part { number, .. } => *number,
+        Marker::Pages { first, .. } => *first,
+    });
+    let count = parts.len() as u32;
+    let mut next_page = 1;
+    for (i, (marker, _)) in parts.iter().enumerate() {
+        match *marker {
+            Marker::Part { number, total } => {
+                if number != i as u32 + 1 || total.is_some_and(|t| t != count) {
+                    return false;
+                }
+            }
+            Marker::Pages { first, last } => {
+                if first != next_page {
+                    return false;
+                }
+                next_page = last + 1;
+            }
+        }
+    }
+    // All part numbers or all page ranges
+    parts.iter().all(|(m, _)| matches!(m, Marker::Part { .. })) || parts.iter().all(|(m, _)| matches!(m, Marker::Pages { .. }))
+}
+
+/// Write the parts as one PDF at `set.merged_path()` and move the parts under
+/// `QUARANTINE_DIR`, keeping their folder structure. Nothing is overwritten:
+/// an existing merged file or quarantined part fails the whole set before
+/// anything is written. Returns the parts' moves.
+pub fn merge(set: &PartSet, root: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let target = set.merged_path();
+    if target.exists() {
+        return Err(anyhow!("Not merged, {} already exists", target.display()));
+    }
+    let moves: Vec<(PathBuf, PathBuf)> = set
+        .parts
+        .iter()
+        .map(|part| (part.clone(), root.join(QUARANTINE_DIR).join(part.strip_prefix(root).unwrap_or(part))))
+        .collect();
+    if let Some((_, existing)) = moves.iter().find(|(_, to)| to.exists()) {
+        return Err(anyhow!("Not merged, {} already exists", existing.display()));
+    }
+
+    let mut merged = merge_pdfs(&set.parts)?;
+    // Write then rename, so an interruption never leaves half a book
+    let tmp = target.with_extension("pdf.tmp");
+    merged.save(&tmp).map_err(|e| anyhow!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, &target)?;
+    info!("Merged {} parts into {:?}", set.parts.len(), target);
+
+    for (from, to) in &moves {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(from, to)?;
+        info!("Quarantined part {:?} -> {:?}", from, to);
+    }
+    Ok(moves)
+}
+
+// Attributes a page may inherit from its page tree; the merged tree is new,
+// so they are copied onto the pages themselves
+const INHERITED: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// The pages of every part, in order, under one new page tree. Outlines and
+/// other document-level structures of the parts are dropped.
+fn merge_pdfs(parts: &[PathBuf]) -> Result<Document> {
+    let mut merged = Document::with_version("1.5");
+    let pages_id = merged.new_object_id();
+    let mut kids: Vec<Object> = Vec::new();
+
+    for part in parts {
+        let mut doc = Document::load(part).map_err(|e| anyhow!("{}: {}", part.display(), e))?;
+        if doc.is_encrypted() {
+            return Err(anyhow!("{} is encrypted", part.display()));
+        }
+        doc.renumber_objects_with(merged.max_id + 1);
+        let page_ids: Vec<ObjectId> = doc.get_pages().into_values().collect();
+        for &page_id in &page_ids {
+            let inherited = inherited_attributes(&doc, page_id);
+            let page = doc.get_dictionary_mut(page_id)?;
+            for (key, value) in inherited {
+                page.set(key, value);
+            }
+            page.set("Parent", pages_id);
+            kids.push(page_id.into());
+        }
+        for (id, object) in doc.objects {
+            if !matches!(object.type_name(), Ok(b"Catalog" | b"Pages")) {
+                merged.objects.insert(id, object);
+            }
+        }
+        merged.max_id = merged.max_id.max(doc.max_id);
+    }
+
+    let count = kids.len() as i64;
+    merged.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => count }));
+    let catalog_id = merged.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    merged.trailer.set("Root", catalog_id);
+    Ok(merged)
+}
+
+fn inherited_attributes(doc: &Document, page_id: ObjectId) -> Vec<(&'static [u8], Object)> {
+    let Ok(page) = doc.get_dictionary(page_id) else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    for key in INHERITED.into_iter().filter(|key| !page.has(key)) {
+        let mut parent = page.get(b"Parent").and_then(Object::as_reference).ok();
+        while let Some(dict) = parent.and_then(|id| doc.get_dictionary(id).ok()) {
+            if let Ok(value) = dict.get(key) {
+                found.push((key, value.clone()));
+                break;
+            }
+            parent = dict.get(b"Parent").and_then(Object::as_reference).ok();
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+    use tempfile::TempDir;
+
+    fn pdf(pages: usize) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let kids: Vec<Object> = (0..pages).map(|_| doc.add_object(dictionary! { "Type" => "Page", "Parent" => pages_id }).into()).collect();
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => pages as i64,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+        // Above the too-small threshold
+        doc.add_object(lopdf::Stream::new(dictionary! {}, vec![b' '; 2048]));
+        let mut content = Vec::new();
+        doc.save_to(&mut content).unwrap();
+        content
+    }
+
+    #[test]
+    fn test_markers() {
+        let part = |n, total| Some(Marker::Part { number: n, total });
+        assert_eq!(marker("Algebra part1").map(|m| m.1), part(1, None));
+        assert_eq!(marker("Algebra - Part 2 of 3").map(|m| m.1), part(2, Some(3)));
+        assert_eq!(marker("Algebra_pt.3").map(|m| m.1), part(3, None));
+        assert_eq!(marker("Algebra (1 of 2)").map(|m| m.1), part(1, Some(2)));
+        assert_eq!(marker("Algebra pp. 121-240").map(|m| m.1), Some(Marker::Pages { first: 121, last: 240 }));
+        assert_eq!(marker("Algebra pp. 1-120").unwrap().0, "Algebra");
+        assert_eq!(marker("Departures 2"), None);
+        assert_eq!(marker("Concept3"), None);
+    }
+
+    #[test]
+    fn test_detect_and_merge() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        fs::write(root.join("Lang - Algebra part1.pdf"), pdf(2))?;
+        fs::write(root.join("Lang - Algebra part2.pdf"), pdf(3))?;
+        // Gaps and single parts are not sets
+        fs::write(root.join("Rudin - Analysis part1.pdf"), pdf(1))?;
+        fs::write(root.join("Rudin - Analysis part3.pdf"), pdf(1))?;
+        fs::write(root.join("Serre - Arithmetic pp. 1-40.pdf"), pdf(1))?;
+
+        let files = Scanner::new(&root, usize::MAX)?.scan()?;
+        let options = NormalizeOptions {
+            aliases: crate::authors::AuthorAliases::builtin(),
+            template: None,
+            subtitle_separator: normalizer::DEFAULT_SUBTITLE_SEPARATOR.to_string(),
+            overrides: crate::overrides::Overrides::default(),
+            merged: crate::overrides::MergedMetadata::default(),
+            ascii_punctuation: false,
+            rtl_metadata_first: false,
+            known_authors: crate::known_authors::KnownAuthors::builtin(),
+            folder_hints: None,
+        };
+        let sets = detect(&files, &options);
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].parts, vec![root.join("Lang - Algebra part1.pdf"), root.join("Lang - Algebra part2.pdf")]);
+        assert_eq!(sets[0].merged_name, "Lang - Algebra.pdf");
+
+        let moves = merge(&sets[0], &root)?;
+        assert_eq!(moves.len(), 2);
+        assert_eq!(Document::load(root.join("Lang - Algebra.pdf"))?.get_pages().len(), 5);
+        assert!(root.join(QUARANTINE_DIR).join("Lang - Algebra part2.pdf").exists());
+        assert!(!root.join("Lang - Algebra part1.pdf").exists());
+
+        // Never overwrites
+        fs::write(root.join("Lang - Algebra part1.pdf"), pdf(1))?;
+        fs::write(root.join("Lang - Algebra part2.pdf"), pdf(1))?;
+        assert!(merge(&sets[0], &root).is_err());
+        Ok(())
+    }
+}
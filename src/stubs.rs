@@ -0,0 +1,156 @@
+use crate::file_kind::{self, FileKind};
+use crate::scanner::FileInfo;
+use log::info;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+// Mirror pages name themselves and their captcha well within this
+const READ_BYTES: u64 = 64 * 1024;
+
+const CAPTCHA_MARKERS: &[&str] = &["captcha", "cf-challenge", "challenge-platform", "ddos-guard", "are you a robot", "verify you are human"];
+const ERROR_MARKERS: &[&str] = &[
+    "error", "not found", "404", "403", "forbidden", "too many requests", "limit reached", "unavailable", "expired",
+];
+// Marker in the page, and the name the todo item shows
+const MIRRORS: &[(&str, &str)] = &[
+    ("libgen", "Library Genesis"),
+    ("library genesis", "Library Genesis"),
+    ("library.lol", "Library Genesis"),
+    ("annas-archive", "Anna's Archive"),
+    ("anna's archive", "Anna's Archive"),
+    ("z-lib", "Z-Library"),
+    ("zlibrary", "Z-Library"),
+    ("1lib.", "Z-Library"),
+    ("b-ok.", "Z-Library"),
+    ("sci-hub", "Sci-Hub"),
+    ("pdfdrive", "PDF Drive"),
+    ("archive.org", "Internet Archive"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StubKind {
+    Captcha,
+    ErrorPage,
+    /// Some other web page
+    Page,
+}
+
+impl StubKind {
+    fn label(self) -> &'static str {
+        match self {
+            StubKind::Captcha => "captcha page",
+            StubKind::ErrorPage => "error page",
+            StubKind::Page => "web page",
+        }
+    }
+}
+
+/// A failed download: the mirror's web page saved under the book's name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stub {
+    pub path: PathBuf,
+    pub size: u64,
+    pub kind: StubKind,
+    /// The site the page came from, when it names a known mirror
+    pub mirror: Option<String>,
+}
+
+impl Stub {
+    /// "captcha page from Library Genesis"
+    pub fn describe(&self) -> String {
+        match &self.mirror {
+            Some(mirror) => format!("{} from {}", self.kind.label(), mirror),
+            None => self.kind.label().to_string(),
+        }
+    }
+}
+
+/// Whether the file is a stub: HTML behind an ebook extension, or a file
+/// under the minimum size that is no known format and reads like an error
+/// or captcha message. Placeholders and failed downloads are not read.
+pub fn check(file_info: &FileInfo) -> Option<Stub> {
+    if file_info.is_virtual || file_info.is_failed_download || !file_kind::is_known_extension(&file_info.extension) {
+        return None;
+    }
+    let extension = file_info.extension.to_lowercase();
+    if FileKind::Html.extensions().contains(&extension.as_str()) {
+        return None;
+    }
+    let mut file = File::open(&file_info.original_path).ok()?;
+    let mut bytes = Vec::new();
+    file.by_ref().take(file_kind::SNIFF_BYTES).read_to_end(&mut bytes).ok()?;
+    let sniffed = file_kind::sniff_bytes(&bytes);
+    let is_html = sniffed == Some(FileKind::Html);
+    if !(is_html || file_info.is_too_small && sniffed.is_none()) {
+        return None;
+    }
+    file.take(READ_BYTES).read_to_end(&mut bytes).ok()?;
+    let text = String::from_utf8_lossy(&bytes).to_lowercase();
+
+    let has = |markers: &[&str]| markers.iter().any(|marker| text.contains(marker));
+    let kind = if has(CAPTCHA_MARKERS) {
+        StubKind::Captcha
+    } else if has(ERROR_MARKERS) {
+        StubKind::ErrorPage
+    } else if is_html {
+        StubKind::Page
+    } else {
+        // Just a small file
+        return None;
+    };
+    let mirror = MIRRORS
+        .iter()
+        .filter_map(|(marker, name)| text.find(marker).map(|position| (position, *name)))
+        .min()
+        .map(|(_, name)| name.to_string());
+    Some(Stub { path: file_info.original_path.clone(), size: file_info.size, kind, mirror })
+}
+
+pub fn detect(files: &[FileInfo]) -> Vec<Stub> {
+    let stubs: Vec<Stub> = files.iter().filter_map(check).collect();
+    if !stubs.is_empty() {
+        info!("Detected {} saved web pages instead of downloads", stubs.len());
+    }
+    stubs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+    use anyhow::Result;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detect() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path();
+        let padding = " ".repeat(2000);
+        fs::write(
+            dir.join("Captcha.pdf"),
+            format!("<!DOCTYPE html><title>libgen.li</title><div class=\"g-recaptcha\"></div>{}", padding),
+        )?;
+        fs::write(dir.join("Gone.epub"), "<html><body><h1>404 Not Found</h1></body></html>")?;
+        fs::write(dir.join("Limit.pdf"), "{\"error\": \"Daily limit reached\"}")?;
+        fs::write(dir.join("Page.pdf"), format!("<html><body>Welcome</body></html>{}", padding))?;
+        fs::write(dir.join("Tiny.pdf"), "%PDF-1.4 error")?;
+        fs::write(dir.join("Real.pdf"), format!("%PDF-1.4\n{}", padding))?;
+        fs::write(dir.join("index.html"), "<html>error</html>")?;
+
+        let files = Scanner::new(dir, 1)?.scan()?;
+        let mut stubs: Vec<(String, StubKind, Option<String>)> = detect(&files)
+            .into_iter()
+            .map(|s| (s.path.file_name().unwrap().to_string_lossy().to_string(), s.kind, s.mirror))
+            .collect();
+        stubs.sort();
+        assert_eq!(stubs, vec![
+            ("Captcha.pdf".to_string(), StubKind::Captcha, Some("Library Genesis".to_string())),
+            ("Gone.epub".to_string(), StubKind::ErrorPage, None),
+            ("Limit.pdf".to_string(), StubKind::ErrorPage, None),
+            ("Page.pdf".to_string(), StubKind::Page, None),
+        ]);
+        Ok(())
+    }
+}
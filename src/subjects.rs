@@ -0,0 +1,143 @@
+use regex::bytes::Regex;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::LazyLock;
+
+// The Info dictionary sits near the start (linearized files) or the end
+const SEARCH_BYTES: u64 = 64 * 1024;
+
+static RE_PDF_KEYWORDS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"/Keywords\s*(\((?:\\.|[^\\)])*\)|<[0-9A-Fa-f\s]*>)").unwrap());
+// The stamp arXiv puts on the first page: "arXiv:2301.12345v2 [math.AG] 3 Jan 2023"
+static RE_ARXIV_STAMP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"arXiv:\s*(?:\d{4}\.\d{4,5}|[a-z-]+(?:\.[A-Z]{2})?/\d{7})(?:v\d+)?\s*\[([a-z-]+(?:\.[A-Za-z-]{2,})?)\]").unwrap()
+});
+static RE_EPUB_ROOTFILE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"full-path="([^"]+)""#).unwrap());
+static RE_EPUB_SUBJECT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<dc:subject[^>]*>\s*([^<]+?)\s*</dc:subject>").unwrap());
+// Old-style IDs on disk: "math.AG_0501234v3.pdf", "hep-th_9901001.pdf"
+static RE_OLD_ARXIV_NAME: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"(?:^|[^A-Za-z-])((?:math|math-ph|hep-th|hep-ph|hep-ex|hep-lat|gr-qc|quant-ph|cond-mat|astro-ph|nucl-th|nucl-ex|physics|nlin|q-bio|cs|stat)(?:\.[A-Za-z-]{2,})?)_\d{7}(?:v\d+)?(?:[^\d]|$)").unwrap()
+});
+
+/// The first keyword of a PDF's Keywords field or an EPUB's `dc:subject`,
+/// and the arXiv category of a paper, for `{subject}` and `{category}`.
+pub fn read_subjects(path: &Path, extension: &str) -> (Option<String>, Option<String>) {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let category = RE_OLD_ARXIV_NAME.captures(&name).map(|caps| caps[1].to_string());
+    match extension.to_lowercase().as_str() {
+        ".pdf" => {
+            let Ok(bytes) = read_head_and_tail(path) else {
+                return (None, category);
+            };
+            let subject = RE_PDF_KEYWORDS
+                .captures(&bytes)
+                .map(|caps| decode_pdf_string(&caps[1]))
+                .and_then(|keywords| first_keyword(&keywords));
+            let category = category.or_else(|| {
+                RE_ARXIV_STAMP.captures(&bytes).map(|caps| String::from_utf8_lossy(&caps[1]).to_string())
+            });
+            (subject, category)
+        }
+        ".epub" => (epub_subject(path), category),
+        _ => (None, category),
+    }
+}
+
+fn read_head_and_tail(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let mut bytes = Vec::new();
+    file.by_ref().take(SEARCH_BYTES).read_to_end(&mut bytes)?;
+    let len = file.metadata()?.len();
+    if len > SEARCH_BYTES {
+        file.seek(SeekFrom::Start(len.saturating_sub(SEARCH_BYTES).max(SEARCH_BYTES)))?;
+        file.read_to_end(&mut bytes)?;
+    }
+    Ok(bytes)
+}
+
+/// `(literal)` or `<hex>` string, UTF-16 when it starts with a BOM.
+fn decode_pdf_string(raw: &[u8]) -> String {
+    let inner = &raw[1..raw.len() - 1];
+    let bytes: Vec<u8> = if raw[0] == b'<' {
+        let hex: Vec<u8> = inner.iter().copied().filter(u8::is_ascii_hexdigit).collect();
+        hex.chunks(2)
+            .filter_map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+            .collect()
+    } else {
+        let mut unescaped = Vec::with_capacity(inner.len());
+        let mut iter = inner.iter();
+        while let Some(&b) = iter.next() {
+            match (b, iter.clone().next()) {
+                (b'\\', Some(b'n' | b'r' | b't')) => {
+                    iter.next();
+                    unescaped.push(b' ');
+                }
+                (b'\\', Some(&next)) => {
+                    iter.next();
+                    unescaped.push(next);
+                }
+                _ => unescaped.push(b),
+            }
+        }
+        unescaped
+    };
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = utf16.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+        return String::from_utf16_lossy(&units);
+    }
+    match String::from_utf8(bytes) {
+        Ok(s) => s,
+        // PDFDocEncoding is close enough to Latin-1 for keywords
+        Err(e) => e.into_bytes().iter().map(|&b| b as char).collect(),
+    }
+}
+
+fn first_keyword(keywords: &str) -> Option<String> {
+    keywords.split([',', ';', '\n']).map(str::trim).find(|k| !k.is_empty()).map(str::to_string)
+}
+
+fn epub_subject(path: &Path) -> Option<String> {
+    let mut zip = zip::ZipArchive::new(File::open(path).ok()?).ok()?;
+    let mut container = Vec::new();
+    zip.by_name("META-INF/container.xml").ok()?.read_to_end(&mut container).ok()?;
+    let rootfile = String::from_utf8_lossy(&RE_EPUB_ROOTFILE.captures(&container)?[1]).to_string();
+    let mut opf = Vec::new();
+    zip.by_name(&rootfile).ok()?.read_to_end(&mut opf).ok()?;
+    let subject = String::from_utf8_lossy(&RE_EPUB_SUBJECT.captures(&opf)?[1]).replace("&amp;", "&");
+    first_keyword(&subject)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::fs;
+    use std::io::Write;
+    use tempfile::TempDir;
+    use zip::write::SimpleFileOptions;
+
+    #[test]
+    fn test_read_subjects() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path();
+        fs::write(dir.join("Algebra.pdf"), b"%PDF-1.4\n1 0 obj << /Title (Algebra) /Keywords (Commutative algebra\\, rings; modules) >> endobj\n")?;
+        fs::write(dir.join("2301.12345v2.pdf"), b"%PDF-1.4\nBT (arXiv:2301.12345v2  [math.AG]  3 Jan 2023) Tj ET\n")?;
+        fs::write(dir.join("math.AG_0501234v3.pdf"), b"%PDF-1.4\n/Keywords <FEFF00540061007400650020>\n")?;
+
+        let mut zip = zip::ZipWriter::new(File::create(dir.join("Topology.epub"))?);
+        zip.start_file("META-INF/container.xml", SimpleFileOptions::default())?;
+        zip.write_all(br#"<rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>"#)?;
+        zip.start_file("OEBPS/content.opf", SimpleFileOptions::default())?;
+        zip.write_all(b"<metadata><dc:subject>Topology &amp; Geometry</dc:subject><dc:subject>Math</dc:subject></metadata>")?;
+        zip.finish()?;
+
+        let read = |name: &str, ext: &str| read_subjects(&dir.join(name), ext);
+        assert_eq!(read("Algebra.pdf", ".pdf"), (Some("Commutative algebra".to_string()), None));
+        assert_eq!(read("2301.12345v2.pdf", ".pdf"), (None, Some("math.AG".to_string())));
+        assert_eq!(read("math.AG_0501234v3.pdf", ".pdf"), (Some("Tate".to_string()), Some("math.AG".to_string())));
+        assert_eq!(read("Topology.epub", ".epub"), (Some("Topology & Geometry".to_string()), None));
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use crate::scanner::FileInfo;
+use crate::variants::{self, PairedCopy};
+use regex::Regex;
+use std::sync::LazyLock;
+
+// Dropbox and Nextcloud: "Book (Jane's conflicted copy 2024-01-31).pdf",
+// "Book (Case Conflict).pdf", "Book (Selective Sync Conflict).pdf"
+static RE_CONFLICTED_COPY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(.+?) \((?:[^()]*conflicted copy[^()]*|case conflict(?: \d+)?|selective sync conflict(?: \d+)?)\)$").unwrap()
+});
+// Syncthing: "Book.sync-conflict-20240131-101500-ABCDEFG"
+static RE_SYNCTHING: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(.+)\.sync-conflict-\d{8}-\d{6}-[A-Z0-9]{7}$").unwrap());
+// OneDrive appends the computer name: "Book-DESKTOP-4F3G2H1". Only trusted
+// when the base file exists next to it.
+static RE_ONEDRIVE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(.+)-([A-Z][A-Z0-9]*(?:-[A-Z0-9]+)*)$").unwrap());
+
+/// Extensions and prefixes sync clients use for files still being written.
+const TEMPORARY_EXTENSIONS: &[&str] = &[".tmp", ".partial", ".part"];
+
+pub fn is_temporary(file_info: &FileInfo) -> bool {
+    file_info.original_name.starts_with("~$")
+        || TEMPORARY_EXTENSIONS.contains(&file_info.extension.to_lowercase().as_str())
+}
+
+/// The name the conflicted copy was made from, if the name has a marker.
+fn base_name(original_name: &str, extension: &str) -> Option<String> {
+    let stem = original_name.strip_suffix(extension)?;
+    [&*RE_CONFLICTED_COPY, &*RE_SYNCTHING, &*RE_ONEDRIVE]
+        .iter()
+        .find_map(|re| re.captures(stem))
+        .map(|caps| format!("{}{}", &caps[1], extension))
+}
+
+/// Pair conflicted copies with their base file in the same folder.
+pub fn detect(files: &[FileInfo], hash: bool) -> Vec<PairedCopy> {
+    variants::pair_with_base(files, hash, base_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+    use crate::variants::merge_identical;
+    use anyhow::Result;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_base_name() {
+        assert_eq!(base_name("Algebra (Jane's conflicted copy 2024-01-31).pdf", ".pdf").as_deref(), Some("Algebra.pdf"));
+        assert_eq!(base_name("Algebra (conflicted copy 2024-01-31 101500).pdf", ".pdf").as_deref(), Some("Algebra.pdf"));
+        assert_eq!(base_name("Algebra (Case Conflict).epub", ".epub").as_deref(), Some("Algebra.epub"));
+        assert_eq!(base_name("Algebra.sync-conflict-20240131-101500-ABCDEFG.pdf", ".pdf").as_deref(), Some("Algebra.pdf"));
+        assert_eq!(base_name("Algebra-DESKTOP-4F3G2H1.pdf", ".pdf").as_deref(), Some("Algebra.pdf"));
+        assert_eq!(base_name("Algebra (2nd Edition).pdf", ".pdf"), None);
+    }
+
+    #[test]
+    fn test_detect_and_merge() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path();
+        fs::write(dir.join("Algebra.pdf"), vec![b'a'; 2048])?;
+        fs::write(dir.join("Algebra (Jane's conflicted copy 2024-01-31).pdf"), vec![b'a'; 2048])?;
+        fs::write(dir.join("Algebra-LAPTOP-7.pdf"), vec![b'b'; 2048])?;
+        // No base file, so not a conflict
+        fs::write(dir.join("Topology-DESKTOP-4F3G2H1.pdf"), vec![b'c'; 2048])?;
+
+        let files = Scanner::new(dir, 1)?.scan()?;
+        let conflicts = detect(&files, true);
+        let found: Vec<(String, Option<bool>)> = conflicts
+            .iter()
+            .map(|c| (c.path.file_name().unwrap().to_string_lossy().to_string(), c.identical))
+            .collect();
+        assert_eq!(found, vec![
+            ("Algebra (Jane's conflicted copy 2024-01-31).pdf".to_string(), Some(true)),
+            ("Algebra-LAPTOP-7.pdf".to_string(), Some(false)),
+        ]);
+        assert!(detect(&files, false)[0].identical.is_none());
+
+        let mut groups = vec![vec![conflicts[0].base.clone(), dir.canonicalize()?.join("Algebra copy.pdf")]];
+        merge_identical(&conflicts, &mut groups);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0][2], conflicts[0].path);
+        Ok(())
+    }
+}
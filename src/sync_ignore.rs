@@ -0,0 +1,157 @@
+use crate::collections::glob_to_regex;
+use crate::error::Error;
+use anyhow::Result;
+use log::info;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Exclusion lists sync clients keep in the synced folder itself: Syncthing
+/// and the Nextcloud/ownCloud desktop client.
+pub const PROVIDER_FILES: &[&str] = &[".stignore", ".sync-exclude.lst"];
+
+// Heading printed by `dropbox exclude list` before the excluded folders
+const DROPBOX_HEADING: &str = "Excluded:";
+
+#[derive(Debug, Clone)]
+struct Rule {
+    re: Regex,
+    negated: bool,
+    dir_only: bool,
+}
+
+/// Paths the sync client does not sync, from gitignore-style rules: the
+/// provider's own files in the target directory and an exported list.
+/// The last matching rule wins; nothing inside an excluded folder is included
+/// again.
+#[derive(Debug, Clone, Default)]
+pub struct SyncIgnore {
+    rules: Vec<Rule>,
+}
+
+impl SyncIgnore {
+    /// The provider files found in `root`, then `export` (`--sync-exclusions`).
+    pub fn load(root: &Path, export: Option<&Path>) -> Result<Self> {
+        let root = root.canonicalize()?;
+        let mut sources: Vec<PathBuf> = PROVIDER_FILES.iter().map(|name| root.join(name)).filter(|p| p.is_file()).collect();
+        if let Some(export) = export {
+            if !export.is_file() {
+                return Err(Error::NotFound { what: "Sync exclusion list", path: export.to_path_buf() }.into());
+            }
+            sources.push(export.to_path_buf());
+        }
+        let mut ignore = Self::default();
+        for source in sources {
+            let before = ignore.rules.len();
+            ignore.parse(&root, &fs::read_to_string(&source)?).map_err(|message| Error::InvalidFile { path: source.clone(), message })?;
+            info!("Loaded {} sync exclusion rules from {:?}", ignore.rules.len() - before, source);
+        }
+        Ok(ignore)
+    }
+
+    fn parse(&mut self, root: &Path, text: &str) -> std::result::Result<(), String> {
+        for line in text.lines().map(str::trim) {
+            // `#` for gitignore and Nextcloud, `//` for Syncthing
+            if line.is_empty() || line.starts_with('#') || line.starts_with("//") || line == DROPBOX_HEADING {
+                continue;
+            }
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            // Syncthing flags: (?i) case-insensitive, (?d) deletable
+            let mut pattern = line;
+            let mut case_insensitive = false;
+            while let Some(rest) = pattern.strip_prefix("(?i)").or_else(|| pattern.strip_prefix("(?d)")) {
+                case_insensitive |= pattern.starts_with("(?i)");
+                pattern = rest;
+            }
+            // Nextcloud marks patterns it may delete with a leading `]`
+            let pattern = pattern.trim_start_matches(']');
+
+            // Full paths (as exported by Dropbox) under the target directory;
+            // any other leading `/` anchors the pattern there, as in gitignore
+            let pattern = match Path::new(pattern).strip_prefix(root) {
+                Ok(relative) => format!("/{}", relative.to_string_lossy()),
+                Err(_) => pattern.to_string(),
+            }
+            .replace('\\', "/");
+            let dir_only = pattern.ends_with('/');
+            let pattern = pattern.trim_end_matches('/');
+            if pattern.is_empty() {
+                continue;
+            }
+            // A pattern with a slash is anchored at the target directory; one
+            // without matches a name at any depth
+            let glob = match pattern.strip_prefix('/') {
+                Some(anchored) => anchored.to_string(),
+                None if pattern.contains('/') => pattern.to_string(),
+                None => format!("**/{}", pattern),
+            };
+            let re = format!("{}{}", if case_insensitive { "(?i)" } else { "" }, glob_to_regex(&glob));
+            let re = Regex::new(&re).map_err(|e| format!("invalid pattern {:?}: {}", line, e))?;
+            self.rules.push(Rule { re, negated, dir_only });
+        }
+        Ok(())
+    }
+
+    /// Whether a path relative to the target directory, or any folder it is
+    /// in, is excluded from sync.
+    pub fn is_excluded(&self, relative: &Path, is_dir: bool) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+        let mut ancestors: Vec<&Path> = relative.ancestors().filter(|a| !a.as_os_str().is_empty()).collect();
+        ancestors.reverse();
+        let last = ancestors.len().saturating_sub(1);
+        ancestors.iter().enumerate().any(|(i, path)| self.matches(path, i < last || is_dir))
+    }
+
+    fn matches(&self, relative: &Path, is_dir: bool) -> bool {
+        let path = relative.to_string_lossy().replace('\\', "/");
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.re.is_match(&path))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rules() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        fs::write(root.join(".stignore"), "// Syncthing\n(?i)*.TMP\nArchive/\n/Papers/Old\n!Archive/keep.pdf\n")?;
+        let export = root.join("excluded.txt");
+        fs::write(&export, format!("Excluded:\n{}\n/elsewhere/Photos\n", root.join("Math/Scans").display()))?;
+
+        let ignore = SyncIgnore::load(&root, Some(&export))?;
+        assert!(ignore.is_excluded(Path::new("notes.tmp"), false));
+        assert!(ignore.is_excluded(Path::new("Math/draft.tmp"), false));
+        assert!(ignore.is_excluded(Path::new("Archive"), true));
+        // Not re-included: its folder is excluded
+        assert!(ignore.is_excluded(Path::new("Archive/keep.pdf"), false));
+        assert!(ignore.is_excluded(Path::new("Math/Archive/a.pdf"), false));
+        assert!(ignore.is_excluded(Path::new("Papers/Old/a.pdf"), false));
+        assert!(ignore.is_excluded(Path::new("Math/Scans/a.pdf"), false));
+
+        assert!(!ignore.is_excluded(Path::new("Books/Papers/Old/a.pdf"), false));
+        // `Archive/` only matches folders
+        assert!(!ignore.is_excluded(Path::new("Archive"), false));
+        assert!(!ignore.is_excluded(Path::new("Math/Lang - Algebra.pdf"), false));
+        assert!(!ignore.is_excluded(Path::new("Photos/a.pdf"), false));
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_export() {
+        let tmp_dir = TempDir::new().unwrap();
+        assert!(SyncIgnore::load(tmp_dir.path(), Some(&tmp_dir.path().join("missing.txt"))).is_err());
+        assert!(SyncIgnore::load(tmp_dir.path(), None).unwrap().rules.is_empty());
+    }
+}
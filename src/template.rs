@@ -0,0 +1,232 @@
+use crate::error::Error;
+use crate::normalizer::ParsedMetadata;
+use anyhow::Result;
+use regex::Regex;
+
+/// Placeholders accepted in `--template` (`{author}` is an alias of `{authors}`).
+pub const FIELDS: &[&str] = &[
+    "authors", "author", "title", "subtitle", "year", "series", "edition", "volume", "publisher", "kind",
+    "subject", "category", "language", "isbn", "asin",
+];
+
+/// Reject unknown placeholders and unbalanced braces before any file is touched.
+pub fn validate(template: &str) -> Result<()> {
+    if template.matches('{').count() != template.matches('}').count() {
+        return Err(Error::Usage(format!("Unbalanced braces in template: {}", template)).into());
+    }
+    let re = Regex::new(r"\{([^{}]*)\}").unwrap();
+    for caps in re.captures_iter(template) {
+        let field = &caps[1];
+        if !FIELDS.contains(&field) {
+            return Err(Error::Usage(format!(
+                "Unknown template field {{{}}} (available: {})",
+                field,
+                FIELDS.iter().map(|f| format!("{{{}}}", f)).collect::<Vec<_>>().join(", ")
+            ))
+            .into());
+        }
+    }
+    if !template.rsplit('/').next().unwrap_or_default().contains("{title}") {
+        return Err(Error::Usage("Template must contain {title} after the last /".to_string()).into());
+    }
+    Ok(())
+}
+
+/// Whether rendering needs `{subject}`/`{category}`, which are read from
+/// the file itself.
+pub fn uses_subjects(template: &str) -> bool {
+    template.contains("{subject}") || template.contains("{category}")
+}
+
+/// Whether the title comes before the authors (`{title} - {authors}`).
+pub fn is_title_first(template: &str) -> bool {
+    let authors = template.find("{authors}").or_else(|| template.find("{author}"));
+    match (template.find("{title}"), authors) {
+        (Some(title), Some(authors)) => title < authors,
+        _ => false,
+    }
+}
+
+/// Render a filename stem (without extension). Missing fields expand to
+/// nothing and the brackets/separators around them are cleaned up, so
+/// `{authors} - {title} ({year}, {publisher})` degrades gracefully.
+///
+/// `{title}` includes the subtitle unless the template places `{subtitle}` itself.
+///
+/// A `/` in the template starts a subfolder (`{category}/{authors} - {title}`);
+/// folders that render empty are left out. Slashes in field values are not
+/// folder separators and become `-`.
+pub fn render(template: &str, metadata: &ParsedMetadata, subtitle_separator: &str) -> String {
+    let re = Regex::new(r"\{([^{}]*)\}").unwrap();
+    let has_subtitle_field = template.contains("{subtitle}");
+    let parts: Vec<String> = template
+        .split('/')
+        .map(|part| {
+            let rendered = re.replace_all(part, |caps: &regex::Captures| {
+                let value = match &caps[1] {
+                    "title" if !has_subtitle_field => metadata.full_title(subtitle_separator),
+                    field => field_value(metadata, field).unwrap_or_default(),
+                };
+                value.replace('/', "-")
+            });
+            tidy(&rendered)
+        })
+        .collect();
+    let (name, folders) = parts.split_last().expect("split yields at least one part");
+    folders
+        .iter()
+        .filter(|folder| !folder.is_empty() && folder.as_str() != "." && folder.as_str() != "..")
+        .chain(std::iter::once(name))
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn field_value(metadata: &ParsedMetadata, field: &str) -> Option<String> {
+    match field {
+        "authors" | "author" => metadata.authors.clone(),
+        "title" => Some(metadata.title.clone()),
+        "subtitle" => metadata.subtitle.clone(),
+        "year" => metadata.year.map(|y| y.to_string()),
+        "series" => metadata.series.clone(),
+        "edition" => metadata.edition.clone(),
+        "volume" => metadata.volume.clone(),
+        "publisher" => metadata.publisher.clone(),
+        "kind" => metadata.kind.clone(),
+        "subject" => metadata.subject.clone(),
+        "category" => metadata.category.clone(),
+        "language" => metadata.language.clone(),
+        "isbn" => metadata.identifier.as_deref().and_then(|id| id.strip_prefix("ISBN ")).map(str::to_string),
+        "asin" => metadata.identifier.as_deref().and_then(|id| id.strip_prefix("ASIN ")).map(str::to_string),
+        _ => None,
+    }
+}
+
+fn tidy(s: &str) -> String {
+    let mut result = s.to_string();
+
+    // Separators left dangling inside brackets by empty fields: "(, 2nd ed)" / "(2020, )"
+    result = Regex::new(r"([(\[])\s*[,;]\s*").unwrap().replace_all(&result, "$1").to_string();
+    result = Regex::new(r"\s*[,;]\s*([)\]])").unwrap().replace_all(&result, "$1").to_string();
+    result = Regex::new(r",\s*,").unwrap().replace_all(&result, ",").to_string();
+
+    // Labels left without their identifier: "[ISBN ]"
+    result = Regex::new(r"(?i)[(\[]\s*(?:ISBN|ASIN)[:\s]*[)\]]").unwrap().replace_all(&result, "").to_string();
+
+    // Brackets that ended up empty
+    result = Regex::new(r"\(\s*\)|\[\s*\]").unwrap().replace_all(&result, "").to_string();
+
+    // Doubled separators from an empty field between two dashes
+    result = Regex::new(r"\s+-\s+-\s+").unwrap().replace_all(&result, " - ").to_string();
+    result = Regex::new(r"\s+").unwrap().replace_all(&result, " ").to_string();
+
+    result
+        .trim_matches(|c: char| c.is_whitespace() || matches!(c, '-' | ',' | ';' | ':' | '_'))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> ParsedMetadata {
+        ParsedMetadata {
+            authors: Some("Serge Lang".to_string()),
+            title: "Algebra".to_string(),
+            subtitle: None,
+            year: Some(2002),
+            series: Some("GTM 211".to_string()),
+            edition: None,
+            volume: None,
+            publisher: Some("Springer".to_string()),
+            kind: None,
+            subject: None,
+            category: None,
+            language: None,
+            identifier: None,
+            ambiguous_order: false,
+        }
+    }
+
+    #[test]
+    fn test_render_all_fields() {
+        let name = render("{authors} - {title} [{series}] ({year}, {publisher})", &metadata(), " - ");
+        assert_eq!(name, "Serge Lang - Algebra [GTM 211] (2002, Springer)");
+    }
+
+    #[test]
+    fn test_render_cleans_up_missing_fields() {
+        let mut meta = metadata();
+        meta.authors = None;
+        meta.series = None;
+        meta.year = None;
+        let name = render("{authors} - {title} [{series}] ({year}, {publisher})", &meta, " - ");
+        assert_eq!(name, "Algebra (Springer)");
+
+        meta.publisher = None;
+        let name = render("{publisher} - {authors} - {title} ({edition})", &meta, " - ");
+        assert_eq!(name, "Algebra");
+    }
+
+    #[test]
+    fn test_render_subtitle() {
+        let mut meta = metadata();
+        meta.title = "Topology".to_string();
+        meta.subtitle = Some("A First Course".to_string());
+
+        assert_eq!(render("{title} ({year})", &meta, " - "), "Topology - A First Course (2002)");
+        assert_eq!(render("{title}. {subtitle}", &meta, " - "), "Topology. A First Course");
+    }
+
+    #[test]
+    fn test_render_kind() {
+        let mut meta = metadata();
+        let template = "{authors} - {title} [{kind}] ({year})";
+        assert_eq!(render(template, &meta, " - "), "Serge Lang - Algebra (2002)");
+
+        meta.kind = Some("Lecture notes".to_string());
+        assert_eq!(render(template, &meta, " - "), "Serge Lang - Algebra [Lecture notes] (2002)");
+    }
+
+    #[test]
+    fn test_render_identifier() {
+        let mut meta = metadata();
+        let template = "{authors} - {title} ({year}) [ISBN {isbn}]";
+        assert_eq!(render(template, &meta, " - "), "Serge Lang - Algebra (2002)");
+
+        meta.identifier = Some("ISBN 9780387953854".to_string());
+        assert_eq!(render(template, &meta, " - "), "Serge Lang - Algebra (2002) [ISBN 9780387953854]");
+        assert_eq!(render("{title} [{asin}]", &meta, " - "), "Algebra");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("{author} - {title} ({publisher})").is_ok());
+        assert!(validate("{authors} - {titel}").is_err());
+        assert!(validate("{authors} - {title").is_err());
+        assert!(validate("{authors} ({year})").is_err());
+        assert!(validate("{category}/{authors} - {title}").is_ok());
+        assert!(validate("{title}/{authors}").is_err());
+    }
+
+    #[test]
+    fn test_render_folders() {
+        let mut meta = metadata();
+        let template = "{category}/{subject}/{authors} - {title}";
+        assert_eq!(render(template, &meta, " - "), "Serge Lang - Algebra");
+
+        meta.category = Some("math.AG".to_string());
+        meta.subject = Some("Rings/Modules".to_string());
+        assert_eq!(render(template, &meta, " - "), "math.AG/Rings-Modules/Serge Lang - Algebra");
+
+        meta.category = Some("..".to_string());
+        assert_eq!(render(template, &meta, " - "), "Rings-Modules/Serge Lang - Algebra");
+    }
+
+    #[test]
+    fn test_is_title_first() {
+        assert!(is_title_first("{title} - {author} ({year})"));
+        assert!(!is_title_first("{authors} - {title}"));
+        assert!(!is_title_first("{title} ({year})"));
+    }
+}
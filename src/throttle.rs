@@ -0,0 +1,64 @@
+use log::info;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Pause after each `--batch-size` operations so the sync client can catch up.
+pub const BATCH_PAUSE: Duration = Duration::from_secs(10);
+
+/// Spaces out renames and deletions, e.g. for cloud-synced folders where a
+/// burst of thousands of changes overwhelms the sync client.
+#[derive(Debug)]
+pub struct Throttle {
+    interval: Option<Duration>,
+    batch_size: Option<usize>,
+    batch_pause: Duration,
+    operations: usize,
+    last: Option<Instant>,
+}
+
+impl Throttle {
+    pub fn new(ops_per_second: Option<f64>, batch_size: Option<usize>) -> Self {
+        Self {
+            interval: ops_per_second.map(|rate| Duration::from_secs_f64(1.0 / rate)),
+            batch_size: batch_size.filter(|size| *size > 0),
+            batch_pause: BATCH_PAUSE,
+            operations: 0,
+            last: None,
+        }
+    }
+
+    /// Call before each operation; sleeps as needed.
+    pub fn wait(&mut self) {
+        if let Some(batch_size) = self.batch_size
+            && self.operations > 0
+            && self.operations.is_multiple_of(batch_size)
+        {
+            info!("Pausing {:?} after {} operations", self.batch_pause, self.operations);
+            thread::sleep(self.batch_pause);
+        } else if let (Some(interval), Some(last)) = (self.interval, self.last) {
+            let elapsed = last.elapsed();
+            if elapsed < interval {
+                thread::sleep(interval - elapsed);
+            }
+        }
+        self.operations += 1;
+        self.last = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_spaces_operations() {
+        let mut throttle = Throttle::new(Some(50.0), Some(3));
+        throttle.batch_pause = Duration::from_millis(100);
+        let start = Instant::now();
+        for _ in 0..4 {
+            throttle.wait();
+        }
+        // Two 20ms gaps, then the pause before the fourth operation
+        assert!(start.elapsed() >= Duration::from_millis(140));
+    }
+}
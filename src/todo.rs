@@ -1,31 +1,69 @@
+use crate::collections::Collections;
+use crate::file_kind::FileKind;
 use crate::scanner::FileInfo;
-use anyhow::Result;
+use crate::redownload::Redownload;
+use crate::stubs::{Stub, StubKind};
+use anyhow::{anyhow, Result};
 use chrono::Local;
 use log::debug;
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone)]
 pub enum FileIssue {
     FailedDownload,
     TooSmall,
     CorruptedPdf,
-    #[allow(dead_code)]
-    InvalidExtension,
+    /// The content is this kind, which the extension doesn't match
+    InvalidExtension(FileKind),
     ReadError,
 }
 
+// Machine-readable copy of the items, kept in an HTML comment at the end of todo.md
+const ENTRIES_START: &str = "<!-- ebook-renamer:items";
+const ENTRIES_END: &str = "-->";
+
+/// One todo item with a stable identity, so removing a file's items never
+/// touches items of other files that merely share a word with it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TodoEntry {
+    /// Relative to the target directory
+    pub path: String,
+    /// Same categories as the JSON `todo_items`
+    pub category: String,
+    /// The item text shown in todo.md
+    pub reason: String,
+    pub first_seen: String,
+}
+
+#[derive(Clone, Copy)]
+enum Section {
+    FailedDownloads,
+    SmallFiles,
+    CorruptedFiles,
+    HtmlStubs,
+    OtherIssues,
+}
+
 pub struct TodoList {
     pub items: Vec<String>,
     pub todo_file_path: PathBuf,
     pub failed_downloads: Vec<String>,
     pub small_files: Vec<String>,
     pub corrupted_files: Vec<String>,
+    pub html_stubs: Vec<String>,
     pub other_issues: Vec<String>,
+    pub entries: Vec<TodoEntry>,
+    root: PathBuf,
+    /// Command for the re-download list and the files it covers
+    redownload: Option<(String, Vec<Redownload>)>,
+    /// Splits todo.md into one part per collection
+    collections: Option<Collections>,
 }
 
 impl TodoList {
-    pub fn new(custom_path: &Option<PathBuf>, target_dir: &PathBuf) -> Result<Self> {
+    pub fn new(custom_path: &Option<PathBuf>, target_dir: &Path) -> Result<Self> {
         let todo_file_path = if let Some(path) = custom_path {
             path.clone()
         } else {
@@ -34,10 +72,11 @@ impl TodoList {
 
         // Try to read existing todo.md to avoid duplicates
         let mut existing_items = Vec::new();
-        if todo_file_path.exists() {
-            if let Ok(content) = fs::read_to_string(&todo_file_path) {
-                existing_items = extract_items_from_md(&content);
-            }
+        let mut entries = Vec::new();
+        if todo_file_path.exists()
+            && let Ok(content) = fs::read_to_string(&todo_file_path) {
+            existing_items = extract_items_from_md(&content);
+            entries = extract_entries_from_md(&content);
         }
 
         Ok(TodoList {
@@ -46,10 +85,73 @@ impl TodoList {
             failed_downloads: Vec::new(),
             small_files: Vec::new(),
             corrupted_files: Vec::new(),
+            html_stubs: Vec::new(),
             other_issues: Vec::new(),
+            entries,
+            // Scanned paths are canonical
+            root: target_dir.canonicalize().unwrap_or_else(|_| target_dir.to_path_buf()),
+            redownload: None,
+            collections: None,
         })
     }
 
+    /// Files with a download, size or corruption issue that may be worth re-downloading.
+    pub fn redownload_candidates(&self) -> Vec<PathBuf> {
+        self.entries
+            .iter()
+            .filter(|e| matches!(e.category.as_str(), "failed_download" | "too_small" | "corrupted_pdf" | "html_stub"))
+            .filter(|e| self.items.contains(&e.reason))
+            .map(|e| self.root.join(&e.path))
+            .collect()
+    }
+
+    /// List the re-downloadable files and the command to fetch them in todo.md.
+    pub fn set_redownloads(&mut self, command: String, entries: Vec<Redownload>) {
+        self.redownload = Some((command, entries)).filter(|(_, entries)| !entries.is_empty());
+    }
+
+    /// Group todo.md by collection.
+    pub fn set_collections(&mut self, collections: Collections) {
+        self.collections = Some(collections);
+    }
+
+    /// Paths (relative to the target directory) of the items in todo.md.
+    pub fn item_paths(&self) -> Vec<&str> {
+        self.entries.iter().filter(|e| self.items.contains(&e.reason)).map(|e| e.path.as_str()).collect()
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root).unwrap_or(path).to_string_lossy().to_string()
+    }
+
+    /// Track `item` for `path`, keeping the first-seen time of an existing
+    /// entry, and list it under `section` unless todo.md already has it.
+    fn record(&mut self, path: &Path, category: &str, item: String, section: Section) {
+        let relative = self.relative(path);
+        match self.entries.iter_mut().find(|e| e.path == relative && e.category == category) {
+            Some(entry) => entry.reason = item.clone(),
+            None => self.entries.push(TodoEntry {
+                path: relative,
+                category: category.to_string(),
+                reason: item.clone(),
+                first_seen: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            }),
+        }
+
+        if !self.items.contains(&item) {
+            let list = match section {
+                Section::FailedDownloads => &mut self.failed_downloads,
+                Section::SmallFiles => &mut self.small_files,
+                Section::CorruptedFiles => &mut self.corrupted_files,
+                Section::HtmlStubs => &mut self.html_stubs,
+                Section::OtherIssues => &mut self.other_issues,
+            };
+            list.push(item.clone());
+            debug!("Added to todo: {}", item);
+            self.items.push(item);
+        }
+    }
+
     pub fn add_file_issue(&mut self, file_info: &FileInfo, issue: FileIssue) -> Result<()> {
         let item = match issue {
             FileIssue::FailedDownload => {
@@ -67,10 +169,10 @@ impl TodoList {
                     file_info.original_name
                 )
             }
-            FileIssue::InvalidExtension => {
+            FileIssue::InvalidExtension(kind) => {
                 format!(
-                    "检查文件: {} (扩展名异常: {})",
-                    file_info.original_name, file_info.extension
+                    "检查文件: {} (内容为 {}，扩展名为 {})",
+                    file_info.original_name, kind.name(), file_info.extension
                 )
             }
             FileIssue::ReadError => {
@@ -81,17 +183,14 @@ impl TodoList {
             }
         };
 
-        if !self.items.contains(&item) {
-            let item_clone = item.clone();
-            match issue {
-                FileIssue::FailedDownload => self.failed_downloads.push(item_clone.clone()),
-                FileIssue::TooSmall => self.small_files.push(item_clone.clone()),
-                FileIssue::CorruptedPdf => self.corrupted_files.push(item_clone.clone()),
-                FileIssue::InvalidExtension | FileIssue::ReadError => self.other_issues.push(item_clone.clone()),
-            }
-            self.items.push(item_clone);
-            debug!("Added to todo: {}", item);
-        }
+        let (category, section) = match issue {
+            FileIssue::FailedDownload => ("failed_download", Section::FailedDownloads),
+            FileIssue::TooSmall => ("too_small", Section::SmallFiles),
+            FileIssue::CorruptedPdf => ("corrupted_pdf", Section::CorruptedFiles),
+            FileIssue::InvalidExtension(_) => ("invalid_extension", Section::OtherIssues),
+            FileIssue::ReadError => ("read_error", Section::OtherIssues),
+        };
+        self.record(&file_info.original_path, category, item, section);
 
         Ok(())
     }
@@ -107,21 +206,20 @@ impl TodoList {
     }
 
     pub fn analyze_file_integrity(&mut self, file_info: &FileInfo) -> Result<()> {
-        // Skip if already marked as failed or too small
-        if file_info.is_failed_download || file_info.is_too_small {
+        // Skip if already marked as failed or too small; placeholders would be downloaded
+        if file_info.is_failed_download || file_info.is_too_small || file_info.is_virtual {
             return Ok(());
         }
 
         // Check PDF integrity for PDF files
-        if file_info.extension.to_lowercase() == ".pdf" {
-            if let Err(_) = validate_pdf_header(&file_info.original_path) {
-                self.add_file_issue(file_info, FileIssue::CorruptedPdf)?;
-                return Ok(());
-            }
+        if file_info.extension.to_lowercase() == ".pdf"
+            && let Err(_) = validate_pdf_header(&file_info.original_path) {
+            self.add_file_issue(file_info, FileIssue::CorruptedPdf)?;
+            return Ok(());
         }
 
         // Check file readability
-        if let Err(_) = fs::metadata(&file_info.original_path) {
+        if fs::metadata(&file_info.original_path).is_err() {
             self.add_file_issue(file_info, FileIssue::ReadError)?;
             return Ok(());
         }
@@ -129,37 +227,161 @@ impl TodoList {
         Ok(())
     }
 
-    pub fn remove_file_from_todo(&mut self, filename: &str) {
-        // Remove items that contain this filename from all lists
-        let filename_lower = filename.to_lowercase();
-        self.items.retain(|item| !item.to_lowercase().contains(&filename_lower));
-        self.failed_downloads.retain(|item| !item.to_lowercase().contains(&filename_lower));
-        self.small_files.retain(|item| !item.to_lowercase().contains(&filename_lower));
-        self.corrupted_files.retain(|item| !item.to_lowercase().contains(&filename_lower));
-        self.other_issues.retain(|item| !item.to_lowercase().contains(&filename_lower));
-        debug!("Removed {} from todo list", filename);
+    /// Record a web page saved in place of a download.
+    pub fn add_html_stub(&mut self, stub: &Stub) {
+        let name = stub.path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let page = match stub.kind {
+            StubKind::Captcha => "验证码页面",
+            StubKind::ErrorPage => "错误页面",
+            StubKind::Page => "网页",
+        };
+        let item = match &stub.mirror {
+            Some(mirror) => format!("重新下载: {} (保存的是 {} 的{})", name, mirror, page),
+            None => format!("重新下载: {} (保存的是{})", name, page),
+        };
+        self.record(&stub.path, "html_stub", item, Section::HtmlStubs);
+    }
+
+    /// Record a file that was skipped because reading it timed out.
+    pub fn add_timed_out(&mut self, path: &Path) {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let item = format!("检查网络存储: {} (读取超时，已跳过)", name);
+        self.record(path, "io_timeout", item, Section::OtherIssues);
+    }
+
+    /// Record an annotated copy whose duplicate group was left for confirmation.
+    pub fn add_annotated_duplicate(&mut self, path: &Path) {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let item = format!("确认重复文件: {} (含批注，未删除)", name);
+        self.record(path, "annotated_duplicate", item, Section::OtherIssues);
+    }
+
+    /// Record a PDF whose first pages match another one's (`--fingerprint-pdfs`).
+    pub fn add_probable_duplicate(&mut self, path: &Path, like: &Path) {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let like = like.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let item = format!("确认疑似重复: {} (前几页与 {} 相同，未删除)", name, like);
+        self.record(path, "probable_duplicate", item, Section::OtherIssues);
+    }
+
+    /// Record a file whose rename was skipped because its folder is read-only.
+    pub fn add_permission_denied(&mut self, path: &Path) {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let item = format!("检查权限: {} (只读位置，未重命名)", name);
+        self.record(path, "insufficient_permission", item, Section::OtherIssues);
+    }
+
+    /// Record an archive whose ebooks are all in the library already.
+    pub fn add_redundant_archive(&mut self, path: &Path) {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let item = format!("删除压缩包: {} (其中的电子书已在库中)", name);
+        self.record(path, "redundant_archive", item, Section::OtherIssues);
+    }
+
+    /// Record a store sample or preview found with `--drop-samples=report`.
+    pub fn add_sample(&mut self, path: &Path, reason: &str) {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let item = format!("检查试读本: {} ({})", name, reason);
+        self.record(path, "sample", item, Section::OtherIssues);
+    }
+
+    /// Record a conflicted copy that was not deleted as a duplicate of its base file.
+    pub fn add_sync_conflict(&mut self, path: &Path, base: &Path) {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let base = base.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let item = format!("解决同步冲突: {} (与 {} 冲突)", name, base);
+        self.record(path, "sync_conflict", item, Section::OtherIssues);
+    }
+
+    pub fn add_name_conflict(&mut self, path: &Path, base: &Path) {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let base = base.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let item = format!("同名不同内容: {} (与 {} 内容不同)", name, base);
+        self.record(path, "name_conflict", item, Section::OtherIssues);
+    }
+
+    /// Remove every item recorded for exactly this file.
+    pub fn remove_file_from_todo(&mut self, path: &Path) {
+        let relative = self.relative(path);
+        let (removed, kept): (Vec<TodoEntry>, Vec<TodoEntry>) =
+            std::mem::take(&mut self.entries).into_iter().partition(|e| e.path == relative);
+        self.entries = kept;
+
+        // Items from a todo.md written before entries were tracked have no
+        // identity; match their exact "<action>: <name> (<reason>)" form instead
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let legacy = format!(": {} (", name);
+        let tracked: Vec<String> = self.entries.iter().map(|e| e.reason.clone()).collect();
+        let is_removed = |item: &String| {
+            removed.iter().any(|e| &e.reason == item) || (!tracked.contains(item) && item.contains(&legacy))
+        };
+
+        self.items.retain(|item| !is_removed(item));
+        self.failed_downloads.retain(|item| !is_removed(item));
+        self.small_files.retain(|item| !is_removed(item));
+        self.corrupted_files.retain(|item| !is_removed(item));
+        self.html_stubs.retain(|item| !is_removed(item));
+        self.other_issues.retain(|item| !is_removed(item));
+        debug!("Removed {} from todo list", relative);
     }
 
     pub fn write(&self) -> Result<()> {
-        let content = generate_todo_md(
-            &self.failed_downloads,
-            &self.small_files,
-            &self.corrupted_files,
-            &self.other_issues,
-            self.items.iter().filter(|item| {
-                !self.failed_downloads.contains(item) 
-                && !self.small_files.contains(item)
-                && !self.corrupted_files.contains(item)
-                && !self.other_issues.contains(item)
-            }),
-        );
+        let sections = Sections {
+            failed_downloads: self.failed_downloads.iter().collect(),
+            small_files: self.small_files.iter().collect(),
+            corrupted_files: self.corrupted_files.iter().collect(),
+            html_stubs: self.html_stubs.iter().collect(),
+            other_issues: self.other_issues.iter().collect(),
+            other_items: self
+                .items
+                .iter()
+                .filter(|item| {
+                    !self.failed_downloads.contains(item)
+                        && !self.small_files.contains(item)
+                        && !self.corrupted_files.contains(item)
+                        && !self.html_stubs.contains(item)
+                        && !self.other_issues.contains(item)
+                })
+                .collect(),
+        };
+        let content = match self.collections.as_ref().filter(|c| !c.is_empty()) {
+            Some(collections) => {
+                // Items from before entries were tracked have no path and count as outside
+                let collection_of = |item: &String| {
+                    self.entries.iter().find(|e| &e.reason == item).and_then(|e| collections.of(Path::new(&e.path)))
+                };
+                let groups = sections.by_collection(collection_of, collections.names().collect());
+                generate_todo_md(&groups, true, self.redownload.as_ref())
+            }
+            None => generate_todo_md(&[(None, sections)], false, self.redownload.as_ref()),
+        };
+
+        let entries: Vec<&TodoEntry> = self.entries.iter().filter(|e| self.items.contains(&e.reason)).collect();
+        let content = if entries.is_empty() {
+            content
+        } else {
+            format!("{}\n{}\n{}\n{}\n", content, ENTRIES_START, serde_json::to_string_pretty(&entries)?, ENTRIES_END)
+        };
 
-        fs::write(&self.todo_file_path, content)?;
+        write_atomic(&self.todo_file_path, &content)?;
         debug!("Wrote todo.md to {:?}", self.todo_file_path);
         Ok(())
     }
 }
 
+/// Write to a temp file next to `path` and rename it into place, so an
+/// interrupted run or a concurrent reader never sees a half-written file.
+fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let file_name = path.file_name().ok_or_else(|| anyhow!("Invalid todo file path: {:?}", path))?;
+    let tmp_path = path.with_file_name(format!(".{}.{}.tmp", file_name.to_string_lossy(), std::process::id()));
+    fs::write(&tmp_path, content)?;
+    if let Err(e) = fs::rename(&tmp_path, path) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
 fn extract_items_from_md(content: &str) -> Vec<String> {
     // Skip generic checklist items
     let skip_patterns = [
@@ -184,7 +406,19 @@ fn extract_items_from_md(content: &str) -> Vec<String> {
         .collect()
 }
 
-fn validate_pdf_header(path: &PathBuf) -> Result<()> {
+fn extract_entries_from_md(content: &str) -> Vec<TodoEntry> {
+    let Some(start) = content.find(ENTRIES_START) else {
+        return Vec::new();
+    };
+    let block = &content[start + ENTRIES_START.len()..];
+    let block = block.find(ENTRIES_END).map_or(block, |end| &block[..end]);
+    serde_json::from_str(block).unwrap_or_else(|e| {
+        debug!("Ignoring unreadable todo entries block: {}", e);
+        Vec::new()
+    })
+}
+
+pub fn validate_pdf_header(path: &Path) -> Result<()> {
     use std::io::Read;
     
     let mut file = fs::File::open(path)?;
@@ -199,62 +433,111 @@ fn validate_pdf_header(path: &PathBuf) -> Result<()> {
     Ok(())
 }
 
-fn generate_todo_md<'a>(
-    failed_downloads: &[String],
-    small_files: &[String],
-    corrupted_files: &[String],
-    other_issues: &[String],
-    other_items: impl Iterator<Item = &'a String>,
-) -> String {
-    let mut md = String::new();
-
-    md.push_str("# 需要检查的任务\n\n");
-    md.push_str(&format!("更新时间: {}\n\n", Local::now().format("%Y-%m-%d %H:%M:%S")));
+/// The items of todo.md by section, for the whole library or one collection.
+#[derive(Default)]
+struct Sections<'a> {
+    failed_downloads: Vec<&'a String>,
+    small_files: Vec<&'a String>,
+    corrupted_files: Vec<&'a String>,
+    html_stubs: Vec<&'a String>,
+    other_issues: Vec<&'a String>,
+    other_items: Vec<&'a String>,
+}
 
-    if !failed_downloads.is_empty() {
-        md.push_str("## 🔄 未完成下载文件（.download）\n\n");
-        for item in failed_downloads {
-            md.push_str(&format!("- [ ] {}\n", item));
-        }
-        md.push('\n');
+impl Sections<'_> {
+    fn is_empty(&self) -> bool {
+        self.failed_downloads.is_empty()
+            && self.small_files.is_empty()
+            && self.corrupted_files.is_empty()
+            && self.html_stubs.is_empty()
+            && self.other_issues.is_empty()
+            && self.other_items.is_empty()
     }
 
-    if !small_files.is_empty() {
-        md.push_str("## 📁 异常小文件（< 1KB）\n\n");
-        for item in small_files {
-            md.push_str(&format!("- [ ] {}\n", item));
+    /// Split into one set per collection (in collection order), then the
+    /// items outside every collection, skipping empty ones.
+    fn by_collection<'s>(self, collection_of: impl Fn(&String) -> Option<&'s str>, names: Vec<&'s str>) -> Vec<(Option<&'s str>, Self)> {
+        let mut groups: Vec<(Option<&str>, Sections)> = names.iter().map(|name| (Some(*name), Sections::default())).collect();
+        groups.push((None, Sections::default()));
+        let group_of = |item: &String| {
+            let collection = collection_of(item);
+            names.iter().position(|name| Some(*name) == collection).unwrap_or(names.len())
+        };
+        for item in self.failed_downloads {
+            groups[group_of(item)].1.failed_downloads.push(item);
         }
-        md.push('\n');
+        for item in self.small_files {
+            groups[group_of(item)].1.small_files.push(item);
+        }
+        for item in self.corrupted_files {
+            groups[group_of(item)].1.corrupted_files.push(item);
+        }
+        for item in self.html_stubs {
+            groups[group_of(item)].1.html_stubs.push(item);
+        }
+        for item in self.other_issues {
+            groups[group_of(item)].1.other_issues.push(item);
+        }
+        for item in self.other_items {
+            groups[group_of(item)].1.other_items.push(item);
+        }
+        groups.retain(|(_, sections)| !sections.is_empty());
+        groups
     }
 
-    if !corrupted_files.is_empty() {
-        md.push_str("## 🚨 损坏的PDF文件\n\n");
-        for item in corrupted_files {
-            md.push_str(&format!("- [ ] {}\n", item));
+    /// Append the non-empty sections with headings of `level` (`##`, `###`).
+    fn push_to(&self, md: &mut String, level: &str) {
+        for (heading, items) in [
+            ("🔄 未完成下载文件（.download）", &self.failed_downloads),
+            ("📁 异常小文件（< 1KB）", &self.small_files),
+            ("🚨 损坏的PDF文件", &self.corrupted_files),
+            ("🌐 下载失败的网页（需重新下载）", &self.html_stubs),
+            ("⚠️ 其他文件问题", &self.other_issues),
+            ("📋 其他需要处理的文件", &self.other_items),
+        ] {
+            if items.is_empty() {
+                continue;
+            }
+            md.push_str(&format!("{} {}\n\n", level, heading));
+            for item in items {
+                md.push_str(&format!("- [ ] {}\n", item));
+            }
+            md.push('\n');
         }
-        md.push('\n');
     }
+}
+
+/// With collections, each collection gets its own `##` heading and the
+/// sections move one level down.
+fn generate_todo_md(groups: &[(Option<&str>, Sections)], by_collection: bool, redownload: Option<&(String, Vec<Redownload>)>) -> String {
+    let mut md = String::new();
 
-    if !other_issues.is_empty() {
-        md.push_str("## ⚠️ 其他文件问题\n\n");
-        for item in other_issues {
-            md.push_str(&format!("- [ ] {}\n", item));
+    md.push_str("# 需要检查的任务\n\n");
+    md.push_str(&format!("更新时间: {}\n\n", Local::now().format("%Y-%m-%d %H:%M:%S")));
+
+    for (collection, sections) in groups {
+        if by_collection {
+            match collection {
+                Some(name) => md.push_str(&format!("## 📚 {}\n\n", name)),
+                None => md.push_str("## 📂 未归入合集的文件\n\n"),
+            }
+            sections.push_to(&mut md, "###");
+        } else {
+            sections.push_to(&mut md, "##");
         }
-        md.push('\n');
     }
 
-    let other_vec: Vec<&String> = other_items.collect();
-    let has_other_items = !other_vec.is_empty();
-    
-    if has_other_items {
-        md.push_str("## 📋 其他需要处理的文件\n\n");
-        for item in &other_vec {
-            md.push_str(&format!("- [ ] {}\n", item));
+    // Plain bullets, not checkboxes, so they aren't read back as items
+    if let Some((command, entries)) = redownload {
+        md.push_str("## 🔁 可重新下载的文件\n\n");
+        md.push_str(&format!("运行以下命令重新下载：\n\n```\n{}\n```\n\n", command));
+        for entry in entries {
+            md.push_str(&format!("- {} ← {}\n", entry.file_name, entry.url));
         }
         md.push('\n');
     }
 
-    if failed_downloads.is_empty() && small_files.is_empty() && corrupted_files.is_empty() && other_issues.is_empty() && !has_other_items {
+    if groups.iter().all(|(_, sections)| sections.is_empty()) {
         md.push_str("✅ 所有文件已检查完毕，无需处理的问题。\n\n");
     }
 
@@ -296,7 +579,12 @@ Other text
             failed_downloads: vec!["Failed download item".to_string()],
             small_files: vec!["Small file item".to_string()],
             corrupted_files: Vec::new(),
+            html_stubs: Vec::new(),
             other_issues: Vec::new(),
+            entries: Vec::new(),
+            root: tmp_dir.path().to_path_buf(),
+            redownload: None,
+            collections: None,
         };
 
         todo_list.write()?;
@@ -305,14 +593,39 @@ Other text
         let content = fs::read_to_string(&todo_path)?;
         assert!(content.contains("Failed download item"));
         assert!(content.contains("Small file item"));
+        // Written through a temp file that is renamed into place
+        assert_eq!(fs::read_dir(tmp_dir.path())?.count(), 1);
 
         Ok(())
     }
 
+    #[test]
+    fn test_write_groups_items_by_collection() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        let mut todo_list = TodoList::new(&None, &root)?;
+        let config = serde_json::from_str(r#"{"papers": "arXiv/**", "textbooks": "Math/**"}"#)?;
+        todo_list.set_collections(Collections::new(&config)?);
+        todo_list.add_timed_out(&root.join("Math/Lang.pdf"));
+        todo_list.add_timed_out(&root.join("Misc/notes.pdf"));
+        todo_list.add_annotated_duplicate(&root.join("Math/Rudin.pdf"));
+        todo_list.write()?;
+
+        let content = fs::read_to_string(root.join("todo.md"))?;
+        let textbooks = content.find("## 📚 textbooks").unwrap();
+        let outside = content.find("## 📂 未归入合集的文件").unwrap();
+        assert!(!content.contains("## 📚 papers"));
+        assert!(textbooks < content.find("Lang.pdf").unwrap() && content.find("Rudin.pdf").unwrap() < outside);
+        assert!(outside < content.find("notes.pdf").unwrap());
+        assert!(content.contains("### ⚠️ 其他文件问题"));
+        assert_eq!(todo_list.item_paths(), vec!["Math/Lang.pdf", "Misc/notes.pdf", "Math/Rudin.pdf"]);
+        Ok(())
+    }
+
     #[test]
     fn test_add_failed_download() -> Result<()> {
         let tmp_dir = TempDir::new()?;
-        let mut todo_list = TodoList::new(&None, &tmp_dir.path().to_path_buf())?;
+        let mut todo_list = TodoList::new(&None, tmp_dir.path())?;
 
         let file_info = FileInfo {
             original_path: tmp_dir.path().join("fail.download"),
@@ -322,8 +635,10 @@ Other text
             modified_time: std::time::SystemTime::now(),
             is_failed_download: true,
             is_too_small: false,
+            is_virtual: false,
             new_name: None,
             new_path: tmp_dir.path().join("fail.download"),
+            metadata: None,
         };
 
         todo_list.add_failed_download(&file_info)?;
@@ -337,14 +652,14 @@ Other text
     #[test]
     fn test_remove_file_from_todo() -> Result<()> {
         let tmp_dir = TempDir::new()?;
-        let mut todo_list = TodoList::new(&None, &tmp_dir.path().to_path_buf())?;
+        let mut todo_list = TodoList::new(&None, tmp_dir.path())?;
 
-        // Add item manually to internal lists
+        // Add item manually to internal lists, as if loaded from an older todo.md
         let item = "重新下载: test_file.pdf (未完成下载)".to_string();
         todo_list.failed_downloads.push(item.clone());
         todo_list.items.push(item);
 
-        todo_list.remove_file_from_todo("test_file.pdf");
+        todo_list.remove_file_from_todo(&tmp_dir.path().join("test_file.pdf"));
 
         assert!(todo_list.failed_downloads.is_empty());
         assert!(todo_list.items.is_empty());
@@ -352,6 +667,30 @@ Other text
         Ok(())
     }
 
+    #[test]
+    fn test_remove_is_exact_and_entries_round_trip() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path().canonicalize()?;
+        let mut todo_list = TodoList::new(&None, &root)?;
+
+        // "Algebra.pdf" is a substring of the other names
+        todo_list.add_timed_out(&root.join("Algebra.pdf"));
+        todo_list.add_timed_out(&root.join("Linear Algebra.pdf"));
+        todo_list.add_permission_denied(&root.join("shared/Algebra.pdf"));
+        todo_list.write()?;
+
+        let mut reloaded = TodoList::new(&None, &root)?;
+        assert_eq!(reloaded.entries, todo_list.entries);
+        assert_eq!(reloaded.entries[0].category, "io_timeout");
+
+        reloaded.remove_file_from_todo(&root.join("Algebra.pdf"));
+        assert_eq!(reloaded.items.len(), 2);
+        assert_eq!(reloaded.entries.len(), 2);
+        assert!(reloaded.entries.iter().all(|e| e.path != "Algebra.pdf"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_analyze_file_integrity_corrupted_pdf() -> Result<()> {
         let tmp_dir = TempDir::new()?;
@@ -359,7 +698,7 @@ Other text
         // Write invalid header
         fs::write(&pdf_path, "NOT PDF content")?;
 
-        let mut todo_list = TodoList::new(&None, &tmp_dir.path().to_path_buf())?;
+        let mut todo_list = TodoList::new(&None, tmp_dir.path())?;
 
         let file_info = FileInfo {
             original_path: pdf_path.clone(),
@@ -369,8 +708,10 @@ Other text
             modified_time: std::time::SystemTime::now(),
             is_failed_download: false,
             is_too_small: false,
+            is_virtual: false,
             new_name: None,
             new_path: pdf_path,
+            metadata: None,
         };
 
         todo_list.analyze_file_integrity(&file_info)?;
@@ -388,7 +729,7 @@ Other text
         // Write valid header
         fs::write(&pdf_path, "%PDF-1.4 content")?;
 
-        let mut todo_list = TodoList::new(&None, &tmp_dir.path().to_path_buf())?;
+        let mut todo_list = TodoList::new(&None, tmp_dir.path())?;
 
         let file_info = FileInfo {
             original_path: pdf_path.clone(),
@@ -398,8 +739,10 @@ Other text
             modified_time: std::time::SystemTime::now(),
             is_failed_download: false,
             is_too_small: false,
+            is_virtual: false,
             new_name: None,
             new_path: pdf_path,
+            metadata: None,
         };
 
         todo_list.analyze_file_integrity(&file_info)?;
@@ -0,0 +1,91 @@
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+// Skipped at any level, besides hidden folders
+const SYSTEM_DIRS: &[&str] = &["Xcode", "node_modules", ".git", "__pycache__"];
+
+/// Depth and exclusion rules shared by the scanner, download recovery and
+/// directory renames, so `--max-depth`/`--no-recursive` mean the same everywhere.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Traversal {
+    pub max_depth: usize,
+}
+
+impl Traversal {
+    pub fn new(max_depth: usize) -> Self {
+        Self { max_depth }
+    }
+
+    /// `--no-recursive` limits every stage to the top-level directory.
+    pub fn effective(max_depth: usize, no_recursive: bool) -> Self {
+        Self::new(if no_recursive { 1 } else { max_depth })
+    }
+
+    pub fn walker(&self, root: &Path) -> WalkDir {
+        WalkDir::new(root).max_depth(self.max_depth)
+    }
+
+    /// `.download`/`.crdownload` folders within reach. Their contents and
+    /// hidden/system folders are not descended into.
+    pub fn download_folders(&self, root: &Path) -> Vec<PathBuf> {
+        let mut folders = Vec::new();
+        let mut walker = self.walker(root).min_depth(1).sort_by_file_name().into_iter();
+        while let Some(entry) = walker.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            if !entry.file_type().is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy();
+            if is_download_folder_name(&name) {
+                folders.push(entry.path().to_path_buf());
+                walker.skip_current_dir();
+            } else if is_hidden_or_system(&name) {
+                walker.skip_current_dir();
+            }
+        }
+        folders
+    }
+}
+
+pub fn is_download_folder_name(name: &str) -> bool {
+    name.ends_with(".download") || name.ends_with(".crdownload")
+}
+
+pub fn is_hidden_or_system(name: &str) -> bool {
+    name.starts_with('.') || SYSTEM_DIRS.contains(&name)
+}
+
+/// Folders no stage renames or descends into for renaming: hidden, system
+/// and download folders (the latter belong to download recovery).
+pub fn is_excluded_dir(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|name| is_hidden_or_system(name) || is_download_folder_name(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_download_folders_respect_depth() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let root = tmp_dir.path();
+        fs::create_dir_all(root.join("top.pdf.download"))?;
+        fs::create_dir_all(root.join("Algebra/nested.pdf.crdownload/inner.pdf.download"))?;
+        fs::create_dir_all(root.join(".cache/hidden.pdf.download"))?;
+        fs::write(root.join("file.pdf.download"), "not a folder")?;
+
+        let top_level = Traversal::effective(usize::MAX, true).download_folders(root);
+        assert_eq!(top_level, vec![root.join("top.pdf.download")]);
+
+        let all = Traversal::new(usize::MAX).download_folders(root);
+        assert_eq!(all, vec![root.join("Algebra/nested.pdf.crdownload"), root.join("top.pdf.download")]);
+        Ok(())
+    }
+}
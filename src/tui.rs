@@ -1,6 +1,6 @@
 use anyhow::Result;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -19,22 +19,109 @@ use std::{
     time::{Duration, Instant},
 };
 
-use crate::cli::Args;
-use crate::{duplicates, normalizer, scanner, todo, download_recovery};
+use crate::cli::{Args, GroupBy};
+use crate::{delete_mode, dir_groups, history, name_diff, notify, scanner};
+use crate::overrides::Override;
+use crate::pipeline::{self, ReviewItem, ReviewResult, Stage};
+use crate::skipped::SkipReason;
+use crate::cloud::HashMode;
+use crate::i18n::t;
 
 #[derive(Debug, Clone)]
 pub enum AppEvent {
-    ScanComplete(Vec<crate::scanner::FileInfo>),
-    NormalizeComplete(Vec<crate::scanner::FileInfo>),
+    // Counts only: cloning whole file lists per event is too costly on large libraries
+    ScanProgress(usize, String),
+    ScanComplete(usize),
+    NormalizeComplete(usize),
     CheckComplete,
-    DuplicatesComplete(Vec<Vec<std::path::PathBuf>>),
+    DuplicatesComplete(usize),
+    /// Dry-run rename: original name, new name
+    RenamePreview(String, String),
+    /// Dry-run plan grouped by folder (`--group-by dir`)
+    DirPlans(Vec<dir_groups::DirPlan>),
+    /// Low-confidence parses to correct (`--review`); the worker waits for a `ReviewResult`
+    Review(Vec<ReviewItem>),
+    Log(String),
     Error(String),
     Done,
 }
 
+// Message ids of the field labels
+const REVIEW_FIELDS: [&str; 3] = ["tui-field-author", "tui-field-title", "tui-field-year"];
+
+struct ReviewForm {
+    items: Vec<ReviewItem>,
+    values: Vec<[String; 3]>,
+    row: usize,
+    field: usize,
+    save: bool,
+}
+
+impl ReviewForm {
+    fn new(items: Vec<ReviewItem>) -> Self {
+        let values = items.iter().map(|item| item.parsed.clone()).collect();
+        Self { items, values, row: 0, field: 0, save: false }
+    }
+
+    fn next_row(&mut self) {
+        if self.row + 1 < self.items.len() {
+            self.row += 1;
+        }
+    }
+
+    fn previous_row(&mut self) {
+        self.row = self.row.saturating_sub(1);
+    }
+
+    fn next_field(&mut self) {
+        self.field = (self.field + 1) % REVIEW_FIELDS.len();
+    }
+
+    fn previous_field(&mut self) {
+        self.field = (self.field + REVIEW_FIELDS.len() - 1) % REVIEW_FIELDS.len();
+    }
+
+    fn type_char(&mut self, c: char) {
+        if let Some(values) = self.values.get_mut(self.row) {
+            values[self.field].push(c);
+        }
+    }
+
+    fn backspace(&mut self) {
+        if let Some(values) = self.values.get_mut(self.row) {
+            values[self.field].pop();
+        }
+    }
+
+    /// Only edited, non-empty fields become corrections.
+    fn result(&self) -> ReviewResult {
+        let corrections = self
+            .items
+            .iter()
+            .zip(&self.values)
+            .filter_map(|(item, values)| {
+                let changed = |i: usize| Some(values[i].trim().to_string()).filter(|v| !v.is_empty() && *v != item.parsed[i].trim());
+                let correction = Override {
+                    authors: changed(0),
+                    title: changed(1),
+                    year: changed(2).and_then(|y| y.parse().ok()),
+                    ..Default::default()
+                };
+                (!correction.is_empty()).then(|| (item.path.clone(), correction))
+            })
+            .collect();
+        ReviewResult { corrections, save: self.save }
+    }
+}
+
 struct App {
     title: String,
     logs: Vec<String>,
+    renames: Vec<(String, String)>,
+    dir_plans: Vec<dir_groups::DirPlan>,
+    expanded: Vec<bool>,
+    selected: usize,
+    review: Option<ReviewForm>,
     progress: f64,
     state: String,
     done: bool,
@@ -43,16 +130,37 @@ struct App {
 impl App {
     fn new() -> App {
         App {
-            title: "Ebook Renamer".to_string(),
-            logs: vec!["Starting...".to_string()],
+            title: t!("tui-title"),
+            logs: vec![t!("tui-starting")],
+            renames: Vec::new(),
+            dir_plans: Vec::new(),
+            expanded: Vec::new(),
+            selected: 0,
+            review: None,
             progress: 0.0,
-            state: "Initializing".to_string(),
+            state: t!("tui-initializing"),
             done: false,
         }
     }
+
+    fn select_next(&mut self) {
+        if self.selected + 1 < self.dir_plans.len() {
+            self.selected += 1;
+        }
+    }
+
+    fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    fn toggle_selected(&mut self) {
+        if let Some(expanded) = self.expanded.get_mut(self.selected) {
+            *expanded = !*expanded;
+        }
+    }
 }
 
-pub fn run(args: Args) -> Result<()> {
+pub fn run(args: Args, hash_mode: HashMode) -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -66,10 +174,15 @@ pub fn run(args: Args) -> Result<()> {
     // Channel for events
     let (tx, rx) = mpsc::channel();
     let tx_worker = tx.clone();
+    let (review_tx, review_rx) = mpsc::channel();
 
     // Spawn worker thread
     thread::spawn(move || {
-        if let Err(e) = run_process(args, tx_worker.clone()) {
+        let notify = args.notify;
+        if let Err(e) = run_process(args, hash_mode, tx_worker.clone(), review_rx) {
+            if notify {
+                notify::run_failed(&e);
+            }
             let _ = tx_worker.send(AppEvent::Error(e.to_string()));
         }
     });
@@ -85,45 +198,88 @@ pub fn run(args: Args) -> Result<()> {
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
 
-        if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if let KeyCode::Char('q') = key.code {
-                    break;
+        if crossterm::event::poll(timeout)?
+            && let Event::Key(key) = event::read()? {
+            if let Some(form) = app.review.as_mut() {
+                match key.code {
+                    KeyCode::Enter => {
+                        let _ = review_tx.send(form.result());
+                        app.review = None;
+                    }
+                    KeyCode::Esc => {
+                        let _ = review_tx.send(ReviewResult::default());
+                        app.review = None;
+                    }
+                    KeyCode::Down => form.next_row(),
+                    KeyCode::Up => form.previous_row(),
+                    KeyCode::Tab => form.next_field(),
+                    KeyCode::BackTab => form.previous_field(),
+                    KeyCode::Backspace => form.backspace(),
+                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => form.save = !form.save,
+                    KeyCode::Char(c) => form.type_char(c),
+                    _ => {}
+                }
+            } else {
+                match key.code {
+                    KeyCode::Char('q') => break,
+                    KeyCode::Down => app.select_next(),
+                    KeyCode::Up => app.select_previous(),
+                    KeyCode::Enter | KeyCode::Char(' ') => app.toggle_selected(),
+                    _ => {}
                 }
             }
         }
 
         if last_tick.elapsed() >= tick_rate {
-            if let Ok(event) = rx.try_recv() {
+            // Drain everything queued since the last tick so progress events don't pile up
+            while let Ok(event) = rx.try_recv() {
                 match event {
-                    AppEvent::ScanComplete(files) => {
-                        app.logs.push(format!("Found {} files", files.len()));
+                    AppEvent::ScanProgress(walked, folder) => {
+                        app.state = t!("tui-scanning", walked = walked, folder = folder);
+                    }
+                    AppEvent::ScanComplete(count) => {
+                        app.logs.push(t!("tui-found-files", count = count));
                         app.progress = 0.2;
-                        app.state = "Normalizing...".to_string();
+                        app.state = t!("tui-normalizing");
                     }
-                    AppEvent::NormalizeComplete(files) => {
-                        app.logs.push(format!("Normalized {} files", files.len()));
+                    AppEvent::NormalizeComplete(count) => {
+                        app.logs.push(t!("tui-normalized", count = count));
                         app.progress = 0.4;
-                        app.state = "Checking Integrity...".to_string();
+                        app.state = t!("tui-checking-integrity");
                     }
                     AppEvent::CheckComplete => {
-                        app.logs.push("Integrity check complete".to_string());
+                        app.logs.push(t!("tui-integrity-done"));
                         app.progress = 0.6;
-                        app.state = "Detecting Duplicates...".to_string();
+                        app.state = t!("tui-detecting-duplicates");
                     }
-                    AppEvent::DuplicatesComplete(groups) => {
-                        app.logs.push(format!("Detected {} duplicate groups", groups.len()));
+                    AppEvent::DuplicatesComplete(count) => {
+                        app.logs.push(t!("tui-duplicate-groups", count = count));
                         app.progress = 0.8;
-                        app.state = "Executing...".to_string();
+                        app.state = t!("tui-executing");
+                    }
+                    AppEvent::RenamePreview(old, new) => {
+                        app.renames.push((old, new));
+                    }
+                    AppEvent::DirPlans(plans) => {
+                        app.expanded = vec![false; plans.len()];
+                        app.selected = 0;
+                        app.dir_plans = plans;
+                    }
+                    AppEvent::Review(items) => {
+                        app.state = t!("tui-reviewing", count = items.len());
+                        app.review = Some(ReviewForm::new(items));
+                    }
+                    AppEvent::Log(msg) => {
+                        app.logs.push(msg);
                     }
                     AppEvent::Error(msg) => {
-                        app.logs.push(format!("Error: {}", msg));
-                        app.state = "Error".to_string();
+                        app.logs.push(format!("{}: {}", t!("tui-error"), msg));
+                        app.state = t!("tui-error");
                     }
                     AppEvent::Done => {
-                        app.logs.push("Done!".to_string());
+                        app.logs.push(t!("tui-done"));
                         app.progress = 1.0;
-                        app.state = "Completed".to_string();
+                        app.state = t!("tui-completed");
                         app.done = true;
                     }
                 }
@@ -148,71 +304,169 @@ pub fn run(args: Args) -> Result<()> {
     Ok(())
 }
 
-fn run_process(mut args: Args, tx: mpsc::Sender<AppEvent>) -> Result<()> {
-    // Auto-detect cloud storage and enable skip_cloud_hash if not explicitly set
-    if !args.skip_cloud_hash {
-        if let Some(provider) = crate::cloud::is_cloud_storage_path(&args.path) {
-            args.skip_cloud_hash = true;
-            // Send log message about cloud mode
-            let msg = format!("⚠️  Detected {} - using metadata-only mode", provider.name());
-            tx.send(AppEvent::Error(msg))?;
+fn run_process(args: Args, hash_mode: HashMode, tx: mpsc::Sender<AppEvent>, review_rx: mpsc::Receiver<ReviewResult>) -> Result<()> {
+    if let HashMode::Detected(provider) = hash_mode {
+        tx.send(AppEvent::Log(t!("tui-cloud-mode", provider = provider.name())))?;
+    }
+
+    let mut reporter = TuiReporter { tx: tx.clone(), review_rx };
+    let mut plan = pipeline::plan(&args, &mut reporter)?;
+    log_plan(&args, &plan, &tx)?;
+
+    if args.dry_run {
+        // Folder names end in `/` to tell them from files
+        for dir_rename in &plan.dir_renames {
+            let name = |path: &std::path::Path| format!("{}/", path.file_name().unwrap_or_default().to_string_lossy());
+            tx.send(AppEvent::RenamePreview(name(&dir_rename.from), name(&dir_rename.to)))?;
+        }
+        if args.group_by == Some(GroupBy::Dir) {
+            // Scanned paths are canonical, so show folders relative to the canonical root
+            let root = args.path.canonicalize().unwrap_or_else(|_| args.path.clone());
+            let mut plans = dir_groups::group_by_dir(&plan.clean_files, &plan.duplicate_groups, &plan.files_to_delete);
+            for plan in &mut plans {
+                plan.dir = plan.dir.strip_prefix(&root).unwrap_or(&plan.dir).to_path_buf();
+            }
+            tx.send(AppEvent::DirPlans(plans))?;
+        } else {
+            for file_info in &plan.clean_files {
+                if let Some(new_name) = &file_info.new_name
+                    && file_info.new_path != file_info.original_path {
+                    tx.send(AppEvent::RenamePreview(file_info.original_name.clone(), new_name.clone()))?;
+                }
+            }
+        }
+        for dir in &plan.pruned_dirs {
+            tx.send(AppEvent::Log(format!("{} {}", t!("label-remove-dir"), dir.display())))?;
+        }
+    } else {
+        if !plan.deletions(&args).is_empty() {
+            let effect = delete_mode::DeleteEffect::new(args.delete_mode, &args.path.canonicalize()?, args.archive_dir.as_deref());
+            tx.send(AppEvent::Log(effect.describe()))?;
+        }
+        pipeline::execute(&args, &mut plan, &mut reporter)?;
+        if !plan.pruned_dirs.is_empty() {
+            tx.send(AppEvent::Log(t!("tui-dirs-removed", count = plan.pruned_dirs.len())))?;
+        }
+        if let Some(pending) = &plan.pending
+            && !pending.deletes.is_empty()
+        {
+            tx.send(AppEvent::Log(t!("tui-deletions-previewed", count = pending.deletes.len())))?;
         }
     }
 
-    // 1. Recovery
-    let recovery = download_recovery::DownloadRecovery::new(&args.path, args.cleanup_downloads);
-    let _ = recovery.recover_downloads(); // Ignore errors for now or log them
+    pipeline::finish(&args, &plan)?;
+    tx.send(AppEvent::Done)?;
+    Ok(())
+}
 
-    // 2. Scan
-    let effective_max_depth = if args.no_recursive { 1 } else { args.max_depth };
-    let mut scanner = scanner::Scanner::new(&args.path, effective_max_depth)?;
-    let files = scanner.scan()?;
-    tx.send(AppEvent::ScanComplete(files.clone()))?;
+/// Stages, notes and the review form of the pipeline, as events for the UI thread.
+struct TuiReporter {
+    tx: mpsc::Sender<AppEvent>,
+    review_rx: mpsc::Receiver<ReviewResult>,
+}
 
-    // 3. Normalize
-    let normalized = normalizer::normalize_files(files)?;
-    tx.send(AppEvent::NormalizeComplete(normalized.clone()))?;
+impl pipeline::Reporter for TuiReporter {
+    fn scan_progress(&mut self) -> Option<scanner::ScanProgress> {
+        let tx = self.tx.clone();
+        Some(Box::new(move |walked, folder| {
+            let _ = tx.send(AppEvent::ScanProgress(walked, folder.display().to_string()));
+        }))
+    }
 
-    // 4. Todo / Check
-    let mut todo_list = todo::TodoList::new(&args.todo_file, &args.path)?;
-    // ... (Simplified logic for TUI demo, ideally copy full logic)
-    for file_info in &normalized {
-        if !file_info.is_failed_download && !file_info.is_too_small {
-             todo_list.analyze_file_integrity(file_info)?;
-        }
+    fn stage(&mut self, stage: Stage) -> Result<()> {
+        let event = match stage {
+            Stage::Scanned(count) => AppEvent::ScanComplete(count),
+            Stage::Normalized(count) => AppEvent::NormalizeComplete(count),
+            Stage::Checked => AppEvent::CheckComplete,
+            Stage::Deduplicated(count) => AppEvent::DuplicatesComplete(count),
+        };
+        Ok(self.tx.send(event)?)
     }
-    tx.send(AppEvent::CheckComplete)?;
 
-    // 5. Duplicates
-    let (duplicate_groups, clean_files) = duplicates::detect_duplicates(normalized, args.skip_cloud_hash)?;
-    tx.send(AppEvent::DuplicatesComplete(duplicate_groups.clone()))?;
+    fn info(&mut self, message: String) -> Result<()> {
+        Ok(self.tx.send(AppEvent::Log(message))?)
+    }
 
-    // 6. Execute
-    if !args.dry_run {
-        // Execute renames
-        for file_info in &clean_files {
-            if let Some(ref _new_name) = file_info.new_name {
-                std::fs::rename(&file_info.original_path, &file_info.new_path)?;
-            }
+    fn warn(&mut self, message: String) -> Result<()> {
+        Ok(self.tx.send(AppEvent::Log(message))?)
+    }
+
+    fn review(&mut self, items: Vec<ReviewItem>) -> Result<ReviewResult> {
+        self.tx.send(AppEvent::Review(items))?;
+        Ok(self.review_rx.recv()?)
+    }
+}
+
+/// What the plan found besides renames and duplicates, as log lines.
+fn log_plan(args: &Args, plan: &pipeline::Plan, tx: &mpsc::Sender<AppEvent>) -> Result<()> {
+    for (path, reason) in plan.skipped.iter() {
+        match reason {
+            SkipReason::InUse => tx.send(AppEvent::Log(t!("tui-in-use", path = path.display().to_string())))?,
+            SkipReason::ReadOnlyFolder => tx.send(AppEvent::Log(t!("not-renamed-permission", name = path.display().to_string())))?,
+            _ => {}
         }
-        // Delete duplicates
-        if !args.no_delete {
-            for group in &duplicate_groups {
-                if group.len() > 1 {
-                    for (idx, path) in group.iter().enumerate() {
-                        if idx > 0 {
-                            std::fs::remove_file(path)?;
-                        }
-                    }
-                }
-            }
+    }
+    for report in &plan.archive_reports {
+        let duplicates = report.payloads.iter().filter(|p| p.duplicate_of.is_some()).count();
+        let summary = match &report.error {
+            Some(error) => error.clone(),
+            None => t!("tui-archive-contents", count = report.payloads.len(), duplicates = duplicates),
+        };
+        tx.send(AppEvent::Log(t!("tui-archive", path = report.path.display().to_string(), summary = summary)))?;
+    }
+    for sample in &plan.sample_files {
+        tx.send(AppEvent::Log(t!("tui-sample", reason = sample.reason.clone(), path = sample.path.display().to_string())))?;
+    }
+    for set in &plan.part_sets {
+        tx.send(AppEvent::Log(t!("tui-split-parts", count = set.parts.len(), path = set.merged_path().display().to_string())))?;
+    }
+    for conflict in plan.sync_conflicts.iter().filter(|c| c.identical != Some(true)) {
+        tx.send(AppEvent::Log(t!("tui-sync-conflict", base = conflict.base.display().to_string(), path = conflict.path.display().to_string())))?;
+    }
+    for variant in plan.name_variants.iter().filter(|v| v.identical != Some(true)) {
+        tx.send(AppEvent::Log(t!("tui-name-variant", base = variant.base.display().to_string(), path = variant.path.display().to_string())))?;
+    }
+    for stub in &plan.html_stubs {
+        tx.send(AppEvent::Log(t!("tui-html-stub", kind = stub.describe(), path = stub.path.display().to_string())))?;
+    }
+    for group in &plan.chapter_groups {
+        tx.send(AppEvent::Log(t!("tui-chapter-files", book = group.book_key.clone(), count = group.parts.len())))?;
+    }
+    for group in &plan.version_groups {
+        tx.send(AppEvent::Log(t!("tui-arxiv-versions", id = group.id.clone(), count = group.superseded.len(), path = group.newest.display().to_string())))?;
+    }
+    for group in &plan.annotated_groups {
+        tx.send(AppEvent::Log(t!("tui-annotated-group", path = group.files[0].display().to_string())))?;
+    }
+    for group in &plan.probable_groups {
+        tx.send(AppEvent::Log(t!("tui-probable-duplicates", basis = group.basis.code(), files = group.files.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", "))))?;
+    }
+    for path in &plan.timed_out {
+        tx.send(AppEvent::Log(t!("tui-io-timeout", path = path.display().to_string())))?;
+    }
+    for issue in &plan.plan_issues {
+        tx.send(AppEvent::Log(t!("tui-plan-check", code = issue.kind.code(), message = issue.message())))?;
+    }
+    if args.show_skipped {
+        for (path, reason) in plan.skipped.iter() {
+            tx.send(AppEvent::Log(t!("tui-skipped", reason = reason.description(), path = path.display().to_string())))?;
+        }
+    }
+    for s in &plan.collection_stats {
+        let name = if s.name.is_empty() { t!("collection-none") } else { s.name.clone() };
+        tx.send(AppEvent::Log(t!("collection-stats", name = name, files = s.files, renamed = s.renamed, duplicates = s.duplicates, todo = s.todo_items)))?;
+    }
+    if let Some(map) = &plan.space_map {
+        for (path, bytes) in &map.largest_files {
+            tx.send(AppEvent::Log(t!("tui-large-file", bytes = history::format_bytes(*bytes), path = path.display().to_string())))?;
+        }
+        for group in &map.duplicate_groups {
+            tx.send(AppEvent::Log(t!("tui-large-duplicates", bytes = history::format_bytes(group.reclaimable), path = group.keep.display().to_string())))?;
+        }
+        for dir in &map.directories {
+            tx.send(AppEvent::Log(t!("tui-large-folder", bytes = history::format_bytes(dir.bytes), count = dir.files, path = dir.path.display().to_string())))?;
         }
     }
-    
-    // Write todo
-    todo_list.write()?;
-
-    tx.send(AppEvent::Done)?;
     Ok(())
 }
 
@@ -230,25 +484,37 @@ fn ui(f: &mut ratatui::Frame, app: &App) {
         )
         .split(f.area());
 
+    // Dry-run previews share the bottom area with the logs
+    let (renames_area, logs_area) = if app.renames.is_empty() && app.dir_plans.is_empty() && app.review.is_none() {
+        (None, chunks[2])
+    } else {
+        let bottom = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+            .split(chunks[2]);
+        (Some(bottom[0]), bottom[1])
+    };
+
     let title = Paragraph::new(app.title.as_str())
         .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
-        .block(Block::default().borders(Borders::ALL).title("Status"));
+        .block(Block::default().borders(Borders::ALL).title(t!("tui-status")));
     f.render_widget(title, chunks[0]);
 
     let gauge = Gauge::default()
-        .block(Block::default().borders(Borders::ALL).title("Progress"))
+        .block(Block::default().borders(Borders::ALL).title(t!("tui-progress")))
         .gauge_style(Style::default().fg(Color::Green))
         .ratio(app.progress)
         .label(format!("{:.0}%", app.progress * 100.0));
     f.render_widget(gauge, chunks[1]);
 
+    let (error, done) = (t!("tui-error"), t!("tui-done"));
     let logs: Vec<ListItem> = app.logs
         .iter()
         .rev()
         .map(|m| {
-            let style = if m.starts_with("Error") {
+            let style = if m.starts_with(&error) {
                 Style::default().fg(Color::Red)
-            } else if m.starts_with("Done") {
+            } else if m.starts_with(&done) {
                 Style::default().fg(Color::Green)
             } else {
                 Style::default()
@@ -258,8 +524,88 @@ fn ui(f: &mut ratatui::Frame, app: &App) {
         .collect();
     
     let logs_list = List::new(logs)
-        .block(Block::default().borders(Borders::ALL).title("Logs"));
-    f.render_widget(logs_list, chunks[2]);
+        .block(Block::default().borders(Borders::ALL).title(t!("tui-logs")));
+    f.render_widget(logs_list, logs_area);
+
+    if let Some(area) = renames_area
+        && let Some(form) = &app.review {
+        let mut items = Vec::new();
+        for (idx, (item, values)) in form.items.iter().zip(&form.values).enumerate() {
+            let mut style = Style::default().add_modifier(Modifier::BOLD);
+            if idx == form.row {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            items.push(ListItem::new(Line::from(Span::styled(item.name.clone(), style))));
+            let mut fields = vec![Span::raw(" ")];
+            for (field, (label, value)) in REVIEW_FIELDS.iter().zip(values).enumerate() {
+                let value_style = if idx == form.row && field == form.field {
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default().fg(Color::Cyan)
+                };
+                fields.push(Span::styled(format!(" {}: ", t!(label)), Style::default().fg(Color::DarkGray)));
+                fields.push(Span::styled(format!("[{}]", value), value_style));
+            }
+            items.push(ListItem::new(Line::from(fields)));
+        }
+        let title = t!("tui-review", count = form.items.len(), save = t!(if form.save { "tui-on" } else { "tui-off" }));
+        f.render_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(title)), area);
+    } else if let Some(area) = renames_area
+        && !app.dir_plans.is_empty() {
+        let mut items = Vec::new();
+        for (idx, plan) in app.dir_plans.iter().enumerate() {
+            let expanded = app.expanded.get(idx).copied().unwrap_or(false);
+            let mut style = Style::default().add_modifier(Modifier::BOLD);
+            if idx == app.selected {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            let dir = plan.dir.display().to_string();
+            items.push(ListItem::new(Line::from(vec![
+                Span::styled(format!("{} {}", if expanded { "▾" } else { "▸" }, if dir.is_empty() { "." } else { &dir }), style),
+                Span::styled(
+                    format!(" {}", t!("dir-plan-counts", renames = plan.renames.len(), duplicates = plan.duplicates.len(), deletions = plan.deletions.len())),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])));
+            if !expanded {
+                continue;
+            }
+            for (old, new) in &plan.renames {
+                let mut line = diff_line(old, new);
+                line.spans.insert(0, Span::raw("  "));
+                items.push(ListItem::new(line));
+            }
+            for path in plan.duplicates.iter().chain(&plan.deletions) {
+                let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                items.push(ListItem::new(Line::from(Span::styled(format!("  {}", t!("tui-delete", name = name)), Style::default().fg(Color::Red)))));
+            }
+        }
+        let plans_list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(t!("tui-plan-by-folder")));
+        f.render_widget(plans_list, area);
+    } else if let Some(area) = renames_area {
+        let renames: Vec<ListItem> = app.renames.iter().map(|(old, new)| ListItem::new(diff_line(old, new))).collect();
+        let renames_list = List::new(renames)
+            .block(Block::default().borders(Borders::ALL).title(t!("tui-renames", count = app.renames.len())));
+        f.render_widget(renames_list, area);
+    }
+}
+
+/// Old name with removed parts in red, new name with added parts in green.
+fn diff_line<'a>(old: &str, new: &str) -> Line<'a> {
+    let (old_segments, new_segments) = name_diff::diff_names(old, new);
+    let span = |segment: name_diff::Segment| {
+        let style = match segment.change {
+            name_diff::Change::Same => Style::default(),
+            name_diff::Change::Removed => Style::default().fg(Color::Red).add_modifier(Modifier::CROSSED_OUT),
+            name_diff::Change::Added => Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        };
+        Span::styled(segment.text, style)
+    };
+    let mut spans: Vec<Span> = old_segments.into_iter().map(span).collect();
+    spans.push(Span::styled(" → ", Style::default().fg(Color::Blue)));
+    spans.extend(new_segments.into_iter().map(span));
+    Line::from(spans)
 }
 
 #[cfg(test)]
@@ -268,6 +614,7 @@ mod tests {
     use ratatui::backend::TestBackend;
     use ratatui::Terminal;
     use ratatui::buffer::Buffer;
+    use std::path::PathBuf;
 
     #[test]
     fn test_ui_render_rich_text() {
@@ -292,7 +639,7 @@ mod tests {
         println!("Buffer content:");
         for y in 0..buffer.area.height {
             let line_str = (0..buffer.area.width)
-                .map(|x| buffer.get(x, y).symbol())
+                .map(|x| buffer[(x, y)].symbol())
                 .collect::<String>();
             println!("{:2}: {}", y, line_str);
         }
@@ -317,11 +664,84 @@ mod tests {
         assert_line_style(buffer, "Starting...", Color::Reset);
     }
 
+    #[test]
+    fn test_ui_renders_rename_diff() {
+        let mut app = App::new();
+        app.renames.push(("Algebra (z-lib.org).pdf".to_string(), "Algebra (2002).pdf".to_string()));
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|f| ui(f, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+
+        assert_area_contains_str(buffer, "Renames (1)");
+        assert_line_style(buffer, "z-lib.org", Color::Red);
+        assert_line_style(buffer, "2002", Color::Green);
+    }
+
+    #[test]
+    fn test_ui_renders_collapsible_dir_plans() {
+        let mut app = App::new();
+        app.dir_plans = vec![
+            dir_groups::DirPlan {
+                dir: "Algebra".into(),
+                renames: vec![("lang.pdf".to_string(), "Lang - Algebra.pdf".to_string())],
+                ..Default::default()
+            },
+            dir_groups::DirPlan {
+                dir: "Topology".into(),
+                deletions: vec!["Topology/broken.pdf".into()],
+                ..Default::default()
+            },
+        ];
+        app.expanded = vec![false, false];
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|f| ui(f, &app)).unwrap();
+        assert_area_contains_str(terminal.backend().buffer(), "Algebra (1 renames, 0 duplicates, 0 deletions)");
+        let collapsed = terminal.backend().buffer().clone();
+
+        app.select_next();
+        app.toggle_selected();
+        terminal.draw(|f| ui(f, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        assert_area_contains_str(buffer, "▾ Topology");
+        assert_line_style(buffer, "delete broken.pdf", Color::Red);
+        assert_ne!(&collapsed, buffer);
+    }
+
+    #[test]
+    fn test_review_form_collects_edits() {
+        let mut app = App::new();
+        app.review = Some(ReviewForm::new(vec![
+            ReviewItem { path: "/lib/scan0001.pdf".into(), name: "scan0001.pdf".to_string(), parsed: [String::new(), "scan0001".to_string(), String::new()] },
+            ReviewItem { path: "/lib/2301.12345v2.pdf".into(), name: "2301.12345v2.pdf".to_string(), parsed: [String::new(), "2301.12345v2".to_string(), String::new()] },
+        ]));
+        let form = app.review.as_mut().unwrap();
+        "Lang".chars().for_each(|c| form.type_char(c));
+        form.next_field();
+        (0..8).for_each(|_| form.backspace());
+        "Algebra".chars().for_each(|c| form.type_char(c));
+        form.previous_field();
+        form.previous_field();
+        "2002".chars().for_each(|c| form.type_char(c));
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| ui(f, &app)).unwrap();
+        assert_area_contains_str(terminal.backend().buffer(), "Author: [Lang] Title: [Algebra] Year: [2002]");
+
+        let result = app.review.as_ref().unwrap().result();
+        assert_eq!(result.corrections.len(), 1);
+        let (path, correction) = &result.corrections[0];
+        assert_eq!(path, &PathBuf::from("/lib/scan0001.pdf"));
+        assert_eq!((correction.authors.as_deref(), correction.title.as_deref(), correction.year), (Some("Lang"), Some("Algebra"), Some(2002)));
+        assert!(!result.save);
+    }
+
     fn assert_area_contains_str(buffer: &Buffer, s: &str) {
         let mut found = false;
         for y in 0..buffer.area.height {
             let line_str = (0..buffer.area.width)
-                .map(|x| buffer.get(x, y).symbol())
+                .map(|x| buffer[(x, y)].symbol())
                 .collect::<String>();
             if line_str.contains(s) {
                 found = true;
@@ -336,11 +756,12 @@ mod tests {
         let mut found = false;
         for y in 0..buffer.area.height {
             let line_len = buffer.area.width;
-            let line_cells: Vec<_> = (0..line_len).map(|x| buffer.get(x, y)).collect();
+            let line_cells: Vec<_> = (0..line_len).map(|x| &buffer[(x, y)]).collect();
             let line_str: String = line_cells.iter().map(|c| c.symbol()).collect();
 
             if let Some(idx) = line_str.find(text) {
-                let cell = line_cells[idx];
+                // Borders and arrows are multi-byte, so convert the byte offset to a cell index
+                let cell = line_cells[line_str[..idx].chars().count()];
                 assert_eq!(cell.fg, expected_fg, "Text '{}' at y={} has wrong color. Expected {:?}, got {:?}", text, y, expected_fg, cell.fg);
                 found = true;
                 break;
@@ -0,0 +1,134 @@
+use crate::duplicates::compute_md5;
+use crate::scanner::FileInfo;
+use log::debug;
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+// Browser and file manager re-downloads: "Book (1)", "Book(2)",
+// "Book - Copy", "Book - Copy (2)", "Book copy 2", "Book - 副本", "Book 副本 (2)"
+static RE_VARIANT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(.+?)(?: ?\(\d{1,3}\)|(?: -)? copy(?: \(\d{1,3}\)| \d{1,3})?|(?: -)? ?副本(?: ?\(\d{1,3}\))?)$").unwrap()
+});
+
+/// A copy of `base` in the same folder, recognized by its name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairedCopy {
+    pub path: PathBuf,
+    pub base: PathBuf,
+    pub size: u64,
+    /// Same content as the base; `None` when it could not be compared
+    pub identical: Option<bool>,
+}
+
+/// The name a re-downloaded variant was made from, if the name has a marker.
+fn base_name(original_name: &str, extension: &str) -> Option<String> {
+    let stem = original_name.strip_suffix(extension)?;
+    RE_VARIANT.captures(stem).map(|caps| format!("{}{}", caps[1].trim_end(), extension))
+}
+
+/// Pair `X (1).pdf`, `X - Copy.pdf`, `X 副本.pdf` and the like with `X.pdf`.
+pub fn detect(files: &[FileInfo], hash: bool) -> Vec<PairedCopy> {
+    pair_with_base(files, hash, base_name)
+}
+
+/// Pair files whose name `base_name` maps to another file in the same folder.
+/// Content is compared with MD5 when `hash` is set, otherwise only sizes are.
+pub fn pair_with_base(files: &[FileInfo], hash: bool, base_name: fn(&str, &str) -> Option<String>) -> Vec<PairedCopy> {
+    let by_path: HashMap<&Path, &FileInfo> = files
+        .iter()
+        .filter(|f| !f.is_failed_download && !f.is_too_small)
+        .map(|f| (f.original_path.as_path(), f))
+        .collect();
+
+    let mut copies = Vec::new();
+    for file_info in by_path.values() {
+        let Some(name) = base_name(&file_info.original_name, &file_info.extension) else {
+            continue;
+        };
+        let base_path = file_info.original_path.with_file_name(&name);
+        let Some(base) = by_path.get(base_path.as_path()) else {
+            continue;
+        };
+        let identical = if base.size != file_info.size {
+            Some(false)
+        } else if hash && !base.is_virtual && !file_info.is_virtual {
+            match (compute_md5(&base.original_path), compute_md5(&file_info.original_path)) {
+                (Ok(a), Ok(b)) => Some(a == b),
+                _ => None,
+            }
+        } else {
+            None
+        };
+        debug!("Copy {:?} of {:?} (identical: {:?})", file_info.original_path, base_path, identical);
+        copies.push(PairedCopy {
+            path: file_info.original_path.clone(),
+            base: base_path,
+            size: file_info.size,
+            identical,
+        });
+    }
+    copies.sort_by(|a, b| a.path.cmp(&b.path));
+    copies
+}
+
+/// Delete identical copies as duplicates of their base, joining the base's
+/// group if it already has one so the kept file stays the group's first.
+pub fn merge_identical(copies: &[PairedCopy], duplicate_groups: &mut Vec<Vec<PathBuf>>) {
+    for copy in copies.iter().filter(|c| c.identical == Some(true)) {
+        match duplicate_groups.iter_mut().find(|group| group.contains(&copy.base)) {
+            Some(group) => group.push(copy.path.clone()),
+            None => duplicate_groups.push(vec![copy.base.clone(), copy.path.clone()]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+    use anyhow::Result;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_base_name() {
+        assert_eq!(base_name("Algebra (1).pdf", ".pdf").as_deref(), Some("Algebra.pdf"));
+        assert_eq!(base_name("Algebra(2).pdf", ".pdf").as_deref(), Some("Algebra.pdf"));
+        assert_eq!(base_name("Algebra - Copy.pdf", ".pdf").as_deref(), Some("Algebra.pdf"));
+        assert_eq!(base_name("Algebra - Copy (2).epub", ".epub").as_deref(), Some("Algebra.epub"));
+        assert_eq!(base_name("Algebra copy 2.pdf", ".pdf").as_deref(), Some("Algebra.pdf"));
+        assert_eq!(base_name("代数 - 副本.pdf", ".pdf").as_deref(), Some("代数.pdf"));
+        assert_eq!(base_name("代数 副本 (2).pdf", ".pdf").as_deref(), Some("代数.pdf"));
+        assert_eq!(base_name("Algebra (2nd Edition).pdf", ".pdf"), None);
+        assert_eq!(base_name("Algebra (2024).pdf", ".pdf"), None);
+    }
+
+    #[test]
+    fn test_detect_variants() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let dir = tmp_dir.path();
+        fs::write(dir.join("Algebra.pdf"), vec![b'a'; 2048])?;
+        fs::write(dir.join("Algebra (1).pdf"), vec![b'a'; 2048])?;
+        fs::write(dir.join("Algebra - Copy.pdf"), vec![b'b'; 2048])?;
+        // No base file, so nothing to pair with
+        fs::write(dir.join("Topology (1).pdf"), vec![b'c'; 2048])?;
+
+        let files = Scanner::new(dir, 1)?.scan()?;
+        let variants = detect(&files, true);
+        let found: Vec<(String, Option<bool>)> = variants
+            .iter()
+            .map(|v| (v.path.file_name().unwrap().to_string_lossy().to_string(), v.identical))
+            .collect();
+        assert_eq!(found, vec![
+            ("Algebra (1).pdf".to_string(), Some(true)),
+            ("Algebra - Copy.pdf".to_string(), Some(false)),
+        ]);
+
+        let mut groups = Vec::new();
+        merge_identical(&variants, &mut groups);
+        assert_eq!(groups, vec![vec![variants[0].base.clone(), variants[0].path.clone()]]);
+        Ok(())
+    }
+}
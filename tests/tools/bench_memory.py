@@ -0,0 +1,112 @@
+#!/usr/bin/env python3
+"""
+Memory/time benchmark for the Rust ebook-renamer on a synthetic library.
+Generates N files with noisy names (plus some duplicates), runs one or more
+binaries in --dry-run --json mode and reports peak RSS and wall time, so a
+build from before and after a change can be compared side by side.
+
+Example:
+    python3 tests/tools/bench_memory.py --files 500000 \
+        --binary /tmp/before/ebook_renamer --binary target/release/ebook_renamer
+"""
+
+import argparse
+import platform
+import random
+import shutil
+import subprocess
+import sys
+import tempfile
+import time
+from pathlib import Path
+
+AUTHORS = ["Knuth", "Tao", "Serre", "Hatcher", "Rudin", "Lang", "Atiyah", "Milnor"]
+NOISE = [" (Z-Library)", " [libgen]", " (anna's archive)", " - 3rd Edition", " (1)", ""]
+
+
+def generate_library(root: Path, count: int, duplicate_ratio: float) -> None:
+    """Create `count` files spread over nested directories."""
+    rng = random.Random(42)
+    for i in range(count):
+        folder = root / f"shelf{i % 100:03d}" / f"box{i % 7}"
+        folder.mkdir(parents=True, exist_ok=True)
+        name = f"Title {i} - {rng.choice(AUTHORS)} ({1950 + i % 70}){rng.choice(NOISE)}.pdf"
+        # Mostly unique sizes; a fraction share content to exercise hashing.
+        # Every file is above the 1 KB PDF threshold, or it would be a broken download.
+        if rng.random() < duplicate_ratio:
+            content = b"%PDF-1.4 duplicate body " * 64
+        else:
+            content = b"%PDF-1.4 " + str(i).encode() * 200 + b" " * 1024
+        (folder / name).write_bytes(content)
+
+
+# Runs in a fresh interpreter so RUSAGE_CHILDREN only covers one binary
+MEASURE_SCRIPT = """
+import resource, subprocess, sys
+result = subprocess.run(sys.argv[1:], stdout=subprocess.DEVNULL, stderr=subprocess.PIPE)
+if result.returncode != 0:
+    sys.stderr.buffer.write(result.stderr)
+    sys.exit(result.returncode)
+print(resource.getrusage(resource.RUSAGE_CHILDREN).ru_maxrss)
+"""
+
+
+def measure(binary: Path, target: Path) -> tuple[float, int]:
+    """Run the binary once and return (seconds, peak RSS in KB)."""
+    start = time.monotonic()
+    result = subprocess.run(
+        [sys.executable, "-c", MEASURE_SCRIPT,
+         str(binary), "--dry-run", "--json", "--read-only", str(target)],
+        capture_output=True,
+        text=True,
+    )
+    elapsed = time.monotonic() - start
+
+    if result.returncode != 0:
+        print(f"{binary} failed:\n{result.stderr}")
+        sys.exit(1)
+
+    # ru_maxrss is KB on Linux, bytes on macOS
+    max_rss = int(result.stdout.strip())
+    if platform.system() == "Darwin":
+        max_rss //= 1024
+    return elapsed, max_rss
+
+
+def main():
+    parser = argparse.ArgumentParser(description="Benchmark memory usage on a synthetic library")
+    parser.add_argument("--files", type=int, default=100000, help="Number of files to generate")
+    parser.add_argument("--duplicate-ratio", type=float, default=0.05, help="Fraction of files sharing content")
+    parser.add_argument(
+        "--binary",
+        action="append",
+        help="Binary to benchmark (repeat to compare builds); defaults to target/release/ebook_renamer",
+    )
+    parser.add_argument("--keep", action="store_true", help="Keep the generated library")
+    args = parser.parse_args()
+
+    project_root = Path(__file__).parent.parent.parent
+    binaries = [Path(b) for b in (args.binary or [project_root / "target" / "release" / "ebook_renamer"])]
+    for binary in binaries:
+        if not binary.exists():
+            print(f"Binary not found: {binary} (build with `cargo build --release`)")
+            sys.exit(1)
+
+    target = Path(tempfile.mkdtemp(prefix="ebook-bench-"))
+    try:
+        print(f"Generating {args.files} files in {target}...")
+        generate_library(target, args.files, args.duplicate_ratio)
+
+        print(f"\n{'Binary':<50} {'Time (s)':>10} {'Peak RSS (MB)':>15}")
+        for binary in binaries:
+            elapsed, rss_kb = measure(binary, target)
+            print(f"{str(binary):<50} {elapsed:>10.2f} {rss_kb / 1024:>15.1f}")
+    finally:
+        if args.keep:
+            print(f"\nLibrary kept at: {target}")
+        else:
+            shutil.rmtree(target)
+
+
+if __name__ == "__main__":
+    main()